@@ -3,10 +3,14 @@
 
 #![deny(unsafe_code, unused_qualifications, trivial_casts, missing_docs)]
 #![deny(clippy::all, clippy::pedantic, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
 
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{parse_macro_input, AttributeArgs, Fields, ItemStruct, Meta, NestedMeta, Path};
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, AttributeArgs, Data, DeriveInput, Fields, ItemStruct, Lit, Meta, NestedMeta,
+    Path, Variant,
+};
 
 /// Implements a newtype to use the [`eventually::aggregate::Root`] instance with
 /// user-defined [`eventually::aggregate::Aggregate`] types.
@@ -81,3 +85,159 @@ pub fn aggregate_root(args: TokenStream, item: TokenStream) -> TokenStream {
 
     result.into()
 }
+
+/// Derives the [`eventually::message::Message`] and [`eventually::message::Registry`]
+/// traits for a Domain Event enum, together with a [`TryFrom`] implementation
+/// from a `(name, payload)` pair, where the payload is any self-describing, owned
+/// `serde::Deserializer`, such as `serde_json::Value`.
+///
+/// Each variant of the enum is given a stable name, which defaults to the variant
+/// identifier and can be overridden using the `#[event(name = "...")]` attribute.
+/// The names are used by [`Message::name`][eventually::message::Message::name],
+/// and to select the variant to deserialize the payload into when converting
+/// from a `(name, payload)` pair.
+///
+/// The payload of a unit variant is ignored, the payload of a newtype variant is
+/// the inner value, while the payload of a tuple or struct variant are its fields,
+/// deserialized as a tuple or a struct respectively. The latter requires the
+/// `serde` crate with the `derive` feature enabled.
+///
+/// Generic enums are not supported.
+///
+/// # Panics
+///
+/// This method will panic if used on a type that is not an enum, or with
+/// a malformed `#[event]` attribute.
+#[proc_macro_derive(Event, attributes(event))]
+pub fn derive_event(item: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(item as DeriveInput);
+    let item_ident = &item.ident;
+
+    if !item.generics.params.is_empty() {
+        return syn::Error::new_spanned(&item.generics, "generic event enums are not supported")
+            .to_compile_error()
+            .into();
+    }
+
+    let Data::Enum(data) = &item.data else {
+        panic!("the Event derive macro can only be used on enums");
+    };
+
+    let names: Vec<String> = data.variants.iter().map(event_name).collect();
+
+    let name_arms = data.variants.iter().zip(&names).map(|(variant, name)| {
+        let variant_ident = &variant.ident;
+
+        match &variant.fields {
+            Fields::Unit => quote! { Self::#variant_ident => #name },
+            Fields::Unnamed(_) => quote! { Self::#variant_ident(..) => #name },
+            Fields::Named(_) => quote! { Self::#variant_ident { .. } => #name },
+        }
+    });
+
+    let try_from_arms = data.variants.iter().zip(&names).map(|(variant, name)| {
+        let deserialize = deserialize_variant(variant);
+        quote! { #name => #deserialize }
+    });
+
+    let result = quote! {
+        impl eventually::message::Message for #item_ident {
+            fn name(&self) -> &'static str {
+                match self {
+                    #(#name_arms,)*
+                }
+            }
+        }
+
+        impl eventually::message::Registry for #item_ident {
+            const NAMES: &'static [&'static str] = &[#(#names),*];
+        }
+
+        impl<'name, D> TryFrom<(&'name str, D)> for #item_ident
+        where
+            D: for<'de> ::serde::Deserializer<'de>,
+        {
+            type Error = <D as ::serde::Deserializer<'static>>::Error;
+
+            fn try_from((name, payload): (&'name str, D)) -> Result<Self, Self::Error> {
+                match name {
+                    #(#try_from_arms,)*
+                    _ => Err(<Self::Error as ::serde::de::Error>::unknown_variant(
+                        name,
+                        <Self as eventually::message::Registry>::NAMES,
+                    )),
+                }
+            }
+        }
+    };
+
+    result.into()
+}
+
+fn event_name(variant: &Variant) -> String {
+    variant
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("event"))
+        .flat_map(|attr| match attr.parse_meta() {
+            Ok(Meta::List(list)) => list.nested,
+            _ => panic!("the event attribute must be in the form #[event(name = \"...\")]"),
+        })
+        .find_map(|meta| match meta {
+            NestedMeta::Meta(Meta::NameValue(name_value)) if name_value.path.is_ident("name") => {
+                match name_value.lit {
+                    Lit::Str(name) => Some(name.value()),
+                    _ => panic!("the event name must be a string literal"),
+                }
+            },
+            _ => None,
+        })
+        .unwrap_or_else(|| variant.ident.to_string())
+}
+
+fn deserialize_variant(variant: &Variant) -> proc_macro2::TokenStream {
+    let variant_ident = &variant.ident;
+
+    match &variant.fields {
+        Fields::Unit => quote! { Ok(Self::#variant_ident) },
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+            let ty = &fields.unnamed[0].ty;
+            quote! { <#ty as ::serde::Deserialize>::deserialize(payload).map(Self::#variant_ident) }
+        },
+        Fields::Unnamed(fields) => {
+            let types = fields.unnamed.iter().map(|field| &field.ty);
+            let bindings: Vec<_> = (0..fields.unnamed.len())
+                .map(|i| format_ident!("field_{}", i))
+                .collect();
+
+            quote! {{
+                #[derive(::serde::Deserialize)]
+                struct Payload(#(#types),*);
+
+                <Payload as ::serde::Deserialize>::deserialize(payload)
+                    .map(|Payload(#(#bindings),*)| Self::#variant_ident(#(#bindings),*))
+            }}
+        },
+        Fields::Named(fields) => {
+            let fields_ident: Vec<_> = fields.named.iter().map(|field| &field.ident).collect();
+            let helper_fields = fields.named.iter().map(|field| {
+                let serde_attrs = field
+                    .attrs
+                    .iter()
+                    .filter(|attr| attr.path.is_ident("serde"));
+                let ident = &field.ident;
+                let ty = &field.ty;
+
+                quote! { #(#serde_attrs)* #ident: #ty }
+            });
+
+            quote! {{
+                #[derive(::serde::Deserialize)]
+                struct Payload { #(#helper_fields),* }
+
+                <Payload as ::serde::Deserialize>::deserialize(payload)
+                    .map(|Payload { #(#fields_ident),* }| Self::#variant_ident { #(#fields_ident),* })
+            }}
+        },
+    }
+}
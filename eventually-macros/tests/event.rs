@@ -0,0 +1,73 @@
+use eventually::message::{Message, Registry};
+use eventually_macros::Event;
+use serde_json::json;
+
+#[derive(Debug, PartialEq, Event)]
+enum OrderEvent {
+    #[event(name = "OrderWasCreated")]
+    WasCreated {
+        id: String,
+        items: Vec<String>,
+    },
+    ItemWasAdded(String),
+    PriceWasChanged(String, u32),
+    WasCancelled,
+}
+
+#[test]
+fn event_names_are_derived_from_the_variants() {
+    assert_eq!(
+        &[
+            "OrderWasCreated",
+            "ItemWasAdded",
+            "PriceWasChanged",
+            "WasCancelled"
+        ],
+        OrderEvent::NAMES
+    );
+
+    assert_eq!(
+        "OrderWasCreated",
+        OrderEvent::WasCreated {
+            id: "order-1".to_owned(),
+            items: vec![]
+        }
+        .name()
+    );
+    assert_eq!("WasCancelled", OrderEvent::WasCancelled.name());
+    assert!(OrderEvent::is_registered("ItemWasAdded"));
+    assert!(!OrderEvent::is_registered("WasCreated"));
+}
+
+#[test]
+fn events_can_be_converted_from_name_and_payload() {
+    let event = OrderEvent::try_from((
+        "OrderWasCreated",
+        json!({ "id": "order-1", "items": ["item-1"] }),
+    ))
+    .expect("event should be deserialized");
+
+    assert_eq!(
+        OrderEvent::WasCreated {
+            id: "order-1".to_owned(),
+            items: vec!["item-1".to_owned()]
+        },
+        event
+    );
+
+    let event = OrderEvent::try_from(("ItemWasAdded", json!("item-2")))
+        .expect("event should be deserialized");
+    assert_eq!(OrderEvent::ItemWasAdded("item-2".to_owned()), event);
+
+    let event = OrderEvent::try_from(("PriceWasChanged", json!(["item-2", 42])))
+        .expect("event should be deserialized");
+    assert_eq!(OrderEvent::PriceWasChanged("item-2".to_owned(), 42), event);
+
+    let event =
+        OrderEvent::try_from(("WasCancelled", json!(null))).expect("event should be deserialized");
+    assert_eq!(OrderEvent::WasCancelled, event);
+
+    let error = OrderEvent::try_from(("WasCreated", json!({})))
+        .expect_err("unknown event names should fail");
+    assert!(error.to_string().contains("unknown variant `WasCreated`"));
+}
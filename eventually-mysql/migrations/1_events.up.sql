@@ -0,0 +1,17 @@
+CREATE TABLE event_streams (
+    event_stream_id  VARCHAR(255) NOT NULL PRIMARY KEY,
+    `version`        BIGINT       NOT NULL CHECK (`version` > 0)
+);
+
+CREATE TABLE events (
+    -- Global sequence of all the appended Domain Events, in commit order per Event Stream.
+    global_position  BIGINT       NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    event_stream_id  VARCHAR(255) NOT NULL,
+    `type`           VARCHAR(255) NOT NULL,
+    `version`        BIGINT       NOT NULL CHECK (`version` > 0),
+    `event`          LONGBLOB     NOT NULL,
+    metadata         JSON,
+
+    UNIQUE KEY events_stream_version (event_stream_id, `version`),
+    FOREIGN KEY (event_stream_id) REFERENCES event_streams (event_stream_id) ON DELETE CASCADE
+);
@@ -0,0 +1,547 @@
+//! This module contains the implementation of the [`eventually::event::Store`] trait,
+//! to work specifically with `MySQL` and `MariaDB` databases.
+//!
+//! Check out the [Store] type for more information.
+
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::string::ToString;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::Utc;
+use eventually::error::{Kind, Retryable};
+use eventually::event::store::Streamer;
+use eventually::message::{Message, Metadata};
+use eventually::version::Version;
+use eventually::{event, serde, version};
+use futures::future::ready;
+use futures::{StreamExt, TryStreamExt};
+use sqlx::mysql::MySqlRow;
+use sqlx::{MySql, MySqlPool, Row, Transaction};
+
+/// All possible errors returned by [`Store`] during an [`event::store::Streamer::stream`] call.
+#[derive(Debug, thiserror::Error)]
+pub enum StreamError {
+    /// Error returned when the Domain Event could not be deserialized
+    /// using the configured [`serde::Serde`] implementation.
+    #[error("failed to deserialize event from database: {0}")]
+    DeserializeEvent(#[source] anyhow::Error),
+    /// Error returned when a column could not be read from a result row.
+    #[error("failed to get column '{name}' from result row: {error}")]
+    ReadColumn {
+        /// The name of the column that could not be read.
+        name: &'static str,
+        /// The underlying error returned by the database driver.
+        #[source]
+        error: sqlx::Error,
+    },
+    /// Error returned when the database has returned an error.
+    #[error("db returned an error: {0}")]
+    Database(#[source] sqlx::Error),
+}
+
+impl StreamError {
+    /// Returns the [Kind] of the error.
+    #[must_use]
+    pub fn kind(&self) -> Kind {
+        match self {
+            StreamError::DeserializeEvent(_) | StreamError::ReadColumn { .. } => {
+                Kind::Serialization
+            },
+            StreamError::Database(err) => crate::error_kind(err),
+        }
+    }
+}
+
+impl Retryable for StreamError {
+    fn is_transient(&self) -> bool {
+        self.kind().is_transient()
+    }
+}
+
+pub use eventually::message::RECORDED_AT_KEY;
+
+/// [Metadata] key set automatically on each appended Domain Event,
+/// containing the new version of the Event Stream after the append.
+///
+/// Any value set by the caller under this key is overridden.
+pub const RECORDED_WITH_NEW_VERSION_KEY: &str = "Recorded-With-New-Version";
+
+/// Maximum number of Domain Events fetched by each poll of a subscription.
+const SUBSCRIPTION_PAGE_SIZE: usize = 256;
+
+async fn append_domain_events<Evt>(
+    tx: &mut Transaction<'_, MySql>,
+    serde: &impl serde::Serializer<Evt>,
+    event_stream_id: &str,
+    new_version: i64,
+    events: Vec<event::Envelope<Evt>>,
+) -> anyhow::Result<()>
+where
+    Evt: Message,
+{
+    #[allow(clippy::cast_possible_wrap)]
+    let current_event_stream_version = new_version - (events.len() as i64);
+    let recorded_at = Utc::now().to_rfc3339();
+
+    for (i, event) in events.into_iter().enumerate() {
+        #[allow(clippy::cast_possible_wrap)]
+        let event_version = current_event_stream_version + (i as i64) + 1;
+
+        let event_type = event.message.name();
+        let mut metadata = event.metadata;
+        let serialized_event = serde.serialize(event.message).map_err(|err| {
+            crate::Error::Serialization(anyhow!("failed to serialize event message: {err}"))
+        })?;
+
+        metadata.insert(RECORDED_AT_KEY.to_owned(), recorded_at.clone());
+        metadata.insert(
+            RECORDED_WITH_NEW_VERSION_KEY.to_owned(),
+            new_version.to_string(),
+        );
+
+        sqlx::query(
+            r"INSERT INTO events (event_stream_id, `type`, `version`, `event`, metadata) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(event_stream_id)
+        .bind(event_type)
+        .bind(event_version)
+        .bind(serialized_event)
+        .bind(sqlx::types::Json(metadata))
+        .execute(&mut **tx)
+        .await
+        .map_err(|err| crate::classify_error(&err, "failed to insert domain event"))?;
+    }
+
+    Ok(())
+}
+
+/// Implements the [`eventually::event::Store`] trait for
+/// `MySQL` and `MariaDB` databases.
+///
+/// Each appended Domain Event is also assigned a position in the global,
+/// auto-incrementing sequence of the `events` table.
+#[derive(Debug, Clone)]
+pub struct Store<Id, Evt, Serde>
+where
+    Id: ToString + Clone,
+    Serde: serde::Serde<Evt>,
+{
+    pool: MySqlPool,
+    serde: Serde,
+    id_type: PhantomData<Id>,
+    evt_type: PhantomData<Evt>,
+}
+
+impl<Id, Evt, Serde> Store<Id, Evt, Serde>
+where
+    Id: ToString + Clone,
+    Serde: serde::Serde<Evt>,
+{
+    /// Runs the latest migrations necessary for the implementation to work,
+    /// then returns a new [`Store`] instance.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the migrations fail to run.
+    pub async fn new(pool: MySqlPool, serde: Serde) -> Result<Self, sqlx::migrate::MigrateError> {
+        Self::builder(pool, serde).build().await
+    }
+
+    /// Returns a [`StoreBuilder`] to configure a new [`Store`] instance.
+    pub fn builder(pool: MySqlPool, serde: Serde) -> StoreBuilder<Id, Evt, Serde> {
+        StoreBuilder {
+            pool,
+            serde,
+            run_migrations: true,
+            id_type: PhantomData,
+            evt_type: PhantomData,
+        }
+    }
+}
+
+/// Builder type used to configure and create a new [`Store`] instance.
+///
+/// Use [`Store::builder`] to create a new builder.
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct StoreBuilder<Id, Evt, Serde>
+where
+    Id: ToString + Clone,
+    Serde: serde::Serde<Evt>,
+{
+    pool: MySqlPool,
+    serde: Serde,
+    run_migrations: bool,
+    id_type: PhantomData<Id>,
+    evt_type: PhantomData<Evt>,
+}
+
+impl<Id, Evt, Serde> StoreBuilder<Id, Evt, Serde>
+where
+    Id: ToString + Clone,
+    Serde: serde::Serde<Evt>,
+{
+    /// Specifies whether the latest migrations should be run when building
+    /// the [`Store`] instance. Defaults to `true`.
+    pub fn run_migrations(mut self, run_migrations: bool) -> Self {
+        self.run_migrations = run_migrations;
+        self
+    }
+
+    /// Builds the new [`Store`] instance, running the latest migrations
+    /// necessary for the implementation to work, unless disabled.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the migrations fail to run.
+    pub async fn build(self) -> Result<Store<Id, Evt, Serde>, sqlx::migrate::MigrateError> {
+        if self.run_migrations {
+            // Make sure the latest migrations are used before using the Store instance.
+            crate::MIGRATIONS.run(&self.pool).await?;
+        }
+
+        Ok(Store {
+            pool: self.pool,
+            serde: self.serde,
+            id_type: PhantomData,
+            evt_type: PhantomData,
+        })
+    }
+}
+
+fn try_get_column<T>(row: &MySqlRow, name: &'static str) -> Result<T, StreamError>
+where
+    for<'a> T: sqlx::Type<MySql> + sqlx::Decode<'a, MySql>,
+{
+    row.try_get(name)
+        .map_err(|err| StreamError::ReadColumn { name, error: err })
+}
+
+impl<Id, Evt, Serde> Store<Id, Evt, Serde>
+where
+    Id: ToString + Clone + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt> + Send + Sync,
+{
+    fn event_row_to_persisted_event(
+        &self,
+        stream_id: Id,
+        row: &MySqlRow,
+    ) -> Result<event::Persisted<Id, Evt>, StreamError> {
+        let version_column: i64 = try_get_column(row, "version")?;
+        let event_column: Vec<u8> = try_get_column(row, "event")?;
+        let metadata_column: sqlx::types::Json<Metadata> = try_get_column(row, "metadata")?;
+
+        let deserialized_event = self
+            .serde
+            .deserialize(&event_column)
+            .map_err(StreamError::DeserializeEvent)?;
+
+        #[allow(clippy::cast_sign_loss)]
+        Ok(event::Persisted {
+            stream_id,
+            version: version_column as Version,
+            event: event::Envelope {
+                message: deserialized_event,
+                metadata: metadata_column.0,
+            },
+        })
+    }
+
+    /// Returns the Domain Events of an Event Stream starting from the specified version,
+    /// up to the specified limit.
+    async fn stream_page(
+        &self,
+        id: &Id,
+        from_version: Version,
+        limit: usize,
+    ) -> Result<Vec<event::Persisted<Id, Evt>>, StreamError> {
+        let rows = sqlx::query(
+            r"SELECT `version`, `event`, metadata
+               FROM events
+               WHERE event_stream_id = ? AND `version` >= ?
+               ORDER BY `version`
+               LIMIT ?",
+        )
+        .bind(id.to_string())
+        .bind(i64::try_from(from_version).unwrap_or(i64::MAX))
+        .bind(u64::try_from(limit).unwrap_or(u64::MAX))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(StreamError::Database)?;
+
+        rows.iter()
+            .map(|row| self.event_row_to_persisted_event(id.clone(), row))
+            .collect()
+    }
+
+    /// Subscribes to an Event Stream, returning its Domain Events starting from
+    /// the specified version, followed by any Domain Event appended afterwards.
+    ///
+    /// New Domain Events are discovered by polling the database with the
+    /// specified interval. The returned stream never ends: errors are yielded
+    /// and polling continues, so the caller can decide whether to stop.
+    pub fn subscribe(
+        &self,
+        id: &Id,
+        select: event::VersionSelect,
+        poll_interval: Duration,
+    ) -> event::Stream<'_, Id, Evt, StreamError> {
+        let from_version = match select {
+            event::VersionSelect::All => 0,
+            event::VersionSelect::From(v) => v,
+        };
+
+        let id = id.clone();
+
+        futures::stream::unfold(
+            (from_version, VecDeque::new()),
+            move |(mut from_version, mut buffer)| {
+                let id = id.clone();
+
+                async move {
+                    loop {
+                        if let Some(event) = buffer.pop_front() {
+                            return Some((Ok(event), (from_version, buffer)));
+                        }
+
+                        let result = self
+                            .stream_page(&id, from_version, SUBSCRIPTION_PAGE_SIZE)
+                            .await;
+
+                        match result {
+                            Err(err) => return Some((Err(err), (from_version, buffer))),
+                            Ok(events) => match events.last() {
+                                None => tokio::time::sleep(poll_interval).await,
+                                Some(last) => {
+                                    from_version = last.version + 1;
+                                    buffer.extend(events);
+                                },
+                            },
+                        }
+                    }
+                }
+            },
+        )
+        .boxed()
+    }
+
+    /// Increments the version of the Event Stream by the specified number of
+    /// Domain Events, creating it if it does not exist yet, and returns the new version.
+    ///
+    /// The upsert never fails if a concurrent append creates the Event Stream first:
+    /// it waits for the concurrent transaction instead, then increments its version.
+    async fn increment_version(
+        tx: &mut Transaction<'_, MySql>,
+        id: &str,
+        events_len: i64,
+    ) -> Result<i64, event::store::AppendError> {
+        sqlx::query(
+            r"INSERT INTO event_streams (event_stream_id, `version`) VALUES (?, ?)
+               ON DUPLICATE KEY UPDATE `version` = `version` + ?",
+        )
+        .bind(id)
+        .bind(events_len)
+        .bind(events_len)
+        .execute(&mut **tx)
+        .await
+        .map_err(|err| crate::classify_error(&err, "failed to upsert new event stream version"))?;
+
+        #[allow(clippy::cast_possible_wrap)]
+        Ok(Self::current_version(tx, id).await? as i64)
+    }
+
+    /// Increments the version of the Event Stream by the specified number of
+    /// Domain Events, only if its current version is the expected one,
+    /// and returns the new version.
+    async fn increment_expected_version(
+        tx: &mut Transaction<'_, MySql>,
+        id: &str,
+        expected: Version,
+        events_len: i64,
+    ) -> Result<i64, event::store::AppendError> {
+        // Lock the Event Stream row, if any, so that concurrent appends
+        // to the same Event Stream are serialized.
+        let current_version = Self::current_version(tx, id).await?;
+
+        if current_version != expected {
+            return Err(event::store::AppendError::Conflict(
+                version::ConflictError {
+                    expected,
+                    actual: current_version,
+                },
+            ));
+        }
+
+        #[allow(clippy::cast_possible_wrap)]
+        let new_version = current_version as i64 + events_len;
+
+        let query = if current_version == 0 {
+            "INSERT INTO event_streams (`version`, event_stream_id) VALUES (?, ?)"
+        } else {
+            "UPDATE event_streams SET `version` = ? WHERE event_stream_id = ?"
+        };
+
+        let result = sqlx::query(query)
+            .bind(new_version)
+            .bind(id)
+            .execute(&mut **tx)
+            .await;
+
+        match result {
+            // The Event Stream has been created by a concurrent append,
+            // after the current version has been checked.
+            Err(err) if crate::error_number(&err) == Some(crate::DUPLICATE_ENTRY) => Err(
+                event::store::AppendError::Conflict(version::ConflictError {
+                    expected,
+                    actual: Self::current_version(tx, id).await?,
+                }),
+            ),
+            Err(err) => {
+                Err(crate::classify_error(&err, "failed to upsert new event stream version").into())
+            },
+            Ok(_) => Ok(new_version),
+        }
+    }
+
+    /// Returns the current version of the Event Stream, locking its row
+    /// until the end of the transaction.
+    async fn current_version(
+        tx: &mut Transaction<'_, MySql>,
+        id: &str,
+    ) -> Result<Version, event::store::AppendError> {
+        let version: Option<i64> = sqlx::query_scalar(
+            "SELECT `version` FROM event_streams WHERE event_stream_id = ? FOR UPDATE",
+        )
+        .bind(id)
+        .fetch_optional(&mut **tx)
+        .await
+        .map_err(|err| crate::classify_error(&err, "failed to fetch the event stream version"))?;
+
+        #[allow(clippy::cast_sign_loss)]
+        Ok(version.map_or(0, |v| v as Version))
+    }
+}
+
+#[async_trait]
+impl<Id, Evt, Serde> Streamer<Id, Evt> for Store<Id, Evt, Serde>
+where
+    Id: ToString + Clone + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt> + Send + Sync,
+{
+    type Error = StreamError;
+
+    fn stream(
+        &self,
+        id: &Id,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, Id, Evt, Self::Error> {
+        #[allow(clippy::cast_possible_wrap)]
+        let from_version: i64 = match select {
+            event::VersionSelect::All => 0,
+            event::VersionSelect::From(v) => v as i64,
+        };
+
+        let query = sqlx::query(
+            r"SELECT `version`, `event`, metadata
+               FROM events
+               WHERE event_stream_id = ? AND `version` >= ?
+               ORDER BY `version`",
+        );
+
+        let id = id.clone();
+
+        query
+            .bind(id.to_string())
+            .bind(from_version)
+            .fetch(&self.pool)
+            .map_err(StreamError::Database)
+            .and_then(move |row| ready(self.event_row_to_persisted_event(id.clone(), &row)))
+            .boxed()
+    }
+
+    fn read_from<'a>(
+        &'a self,
+        id: &Id,
+        from_version: Version,
+        batch_size: usize,
+    ) -> event::Stream<'a, Id, Evt, Self::Error>
+    where
+        Id: 'a,
+        Evt: 'a,
+    {
+        assert!(batch_size > 0, "batch size must be greater than zero");
+
+        let id = id.clone();
+
+        event::store::paged(from_version, batch_size, move |from_version| {
+            let id = id.clone();
+
+            async move { self.stream_page(&id, from_version, batch_size).await }
+        })
+    }
+
+    async fn head_version(&self, id: &Id) -> Result<Option<Version>, Self::Error> {
+        let version: Option<i64> =
+            sqlx::query_scalar("SELECT `version` FROM event_streams WHERE event_stream_id = ?")
+                .bind(id.to_string())
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(StreamError::Database)?;
+
+        #[allow(clippy::cast_sign_loss)]
+        Ok(version.map(|v| v as Version))
+    }
+}
+
+#[async_trait]
+impl<Id, Evt, Serde> event::store::Appender<Id, Evt> for Store<Id, Evt, Serde>
+where
+    Id: ToString + Clone + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt> + Send + Sync,
+{
+    async fn append(
+        &self,
+        id: Id,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Evt>>,
+    ) -> Result<Version, event::store::AppendError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|err| crate::classify_error(&err, "failed to begin transaction"))?;
+
+        let string_id = id.to_string();
+
+        #[allow(clippy::cast_possible_wrap)]
+        let events_len = events.len() as i64;
+
+        let new_version = match version_check {
+            version::Check::Any => Self::increment_version(&mut tx, &string_id, events_len).await?,
+            version::Check::MustBe(expected) => {
+                Self::increment_expected_version(
+                    &mut tx,
+                    &string_id,
+                    expected.version(),
+                    events_len,
+                )
+                .await?
+            },
+        };
+
+        append_domain_events(&mut tx, &self.serde, &string_id, new_version, events).await?;
+
+        tx.commit()
+            .await
+            .map_err(|err| crate::classify_error(&err, "failed to commit transaction"))?;
+
+        #[allow(clippy::cast_sign_loss)]
+        Ok(new_version as Version)
+    }
+}
@@ -0,0 +1,92 @@
+//! `eventually-mysql` contains implementations of traits from the [eventually] crate
+//! that are specific for `MySQL` and `MariaDB` databases.
+//!
+//! Check out the [`event::Store`] implementation to know more.
+
+#![deny(unsafe_code, unused_qualifications, trivial_casts)]
+#![deny(clippy::all, clippy::pedantic, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+#![warn(missing_docs)]
+
+pub mod event;
+
+pub(crate) static MIGRATIONS: sqlx::migrate::Migrator = sqlx::migrate!("./migrations");
+
+use anyhow::anyhow;
+use eventually::error::Kind;
+use sqlx::mysql::MySqlDatabaseError;
+
+/// Framework error type used to classify the errors returned by the database,
+/// which never carries a domain error.
+pub(crate) type Error = eventually::error::Error;
+
+/// `ER_DUP_ENTRY`: a unique key constraint has been violated.
+pub(crate) const DUPLICATE_ENTRY: u16 = 1062;
+
+/// Returns the `MySQL` error number of the specified [`sqlx::Error`], if any.
+pub(crate) fn error_number(err: &sqlx::Error) -> Option<u16> {
+    err.as_database_error()
+        .and_then(|db_err| db_err.try_downcast_ref::<MySqlDatabaseError>())
+        .map(MySqlDatabaseError::number)
+}
+
+/// Returns the [`eventually::error::Kind`] that best describes the specified [`sqlx::Error`].
+pub(crate) fn error_kind(err: &sqlx::Error) -> Kind {
+    match err {
+        sqlx::Error::PoolTimedOut => Kind::Timeout,
+        sqlx::Error::Io(_)
+        | sqlx::Error::Tls(_)
+        | sqlx::Error::PoolClosed
+        | sqlx::Error::WorkerCrashed => Kind::Connection,
+        sqlx::Error::Decode(_) | sqlx::Error::ColumnDecode { .. } => Kind::Serialization,
+        sqlx::Error::RowNotFound => Kind::NotFound,
+        sqlx::Error::Database(_) => match error_number(err) {
+            // ER_LOCK_DEADLOCK: the transaction has been rolled back, and can be retried.
+            Some(1213) => Kind::Conflict,
+            // ER_LOCK_WAIT_TIMEOUT and ER_QUERY_TIMEOUT.
+            Some(1205 | 3024) => Kind::Timeout,
+            _ => Kind::Internal,
+        },
+        _ => Kind::Internal,
+    }
+}
+
+/// Wraps a [`sqlx::Error`] into an [`anyhow::Error`] with the specified context message,
+/// preserving its [`eventually::error::Kind`] classification.
+pub(crate) fn classify_error(err: &sqlx::Error, context: &str) -> anyhow::Error {
+    wrap_error(error_kind(err), anyhow!("{context}: {err}"))
+}
+
+/// Wraps the specified [`anyhow::Error`] into the framework [Error] variant
+/// matching the specified [`eventually::error::Kind`], if any.
+fn wrap_error(kind: Kind, err: anyhow::Error) -> anyhow::Error {
+    match kind {
+        Kind::Timeout => Error::Timeout(err).into(),
+        Kind::Conflict => Error::Contention(err).into(),
+        Kind::Connection => Error::Connection(err).into(),
+        Kind::Serialization => Error::Serialization(err).into(),
+        _ => err,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use eventually::error::Retryable;
+
+    use super::*;
+
+    #[test]
+    fn deadlocks_are_transient_conflicts() {
+        let err = wrap_error(Kind::Conflict, anyhow!("failed to commit transaction"));
+
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::Contention(_))
+        ));
+        assert!(err.is_transient());
+
+        let err = wrap_error(Kind::Internal, anyhow!("failed to insert domain event"));
+
+        assert!(!err.is_transient());
+    }
+}
@@ -0,0 +1,249 @@
+use std::time::Duration;
+
+use ::serde::{Deserialize, Serialize};
+use eventually::error::Retryable;
+use eventually::event::store::{AppendError, Appender, Streamer};
+use eventually::event::{Persisted, VersionSelect};
+use eventually::message::Message;
+use eventually::{serde, version};
+use eventually_mysql::event;
+use futures::future::join_all;
+use futures::{StreamExt, TryStreamExt};
+use rand::Rng;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+enum TestDomainEvent {
+    WasCreated { name: String },
+    WasDeleted,
+}
+
+impl Message for TestDomainEvent {
+    fn name(&self) -> &'static str {
+        match self {
+            TestDomainEvent::WasCreated { .. } => "TestDomainSomethingWasCreated",
+            TestDomainEvent::WasDeleted => "TestDomainSomethingWasDeleted",
+        }
+    }
+}
+
+type Store = event::Store<String, TestDomainEvent, serde::Json<TestDomainEvent>>;
+
+/// Returns a new [Store] connected to the database at `MYSQL_DATABASE_URL`.
+///
+/// The tests using it are ignored by default, as they need a `MySQL` server:
+/// run them with `cargo test -p eventually-mysql -- --ignored`.
+async fn new_event_store() -> Store {
+    let url =
+        std::env::var("MYSQL_DATABASE_URL").expect("the env var MYSQL_DATABASE_URL is required");
+
+    let pool = sqlx::MySqlPool::connect(&url)
+        .await
+        .expect("connection to the database should work");
+
+    event::Store::new(pool, serde::Json::default())
+        .await
+        .expect("the event store should be created")
+}
+
+fn created(name: &str) -> eventually::event::Envelope<TestDomainEvent> {
+    TestDomainEvent::WasCreated {
+        name: name.to_owned(),
+    }
+    .into()
+}
+
+#[tokio::test]
+#[ignore = "requires a MySQL server at MYSQL_DATABASE_URL"]
+async fn it_appends_and_streams_events_with_version_checks() {
+    let event_store = new_event_store().await;
+
+    let id = format!("test-event-stream-{}", rand::thread_rng().gen::<u64>());
+
+    let err = event_store
+        .append(
+            id.clone(),
+            version::Check::must_be(1),
+            vec![created("John Dee")],
+        )
+        .await
+        .expect_err("the event stream should not exist yet");
+
+    assert!(matches!(
+        err,
+        AppendError::Conflict(version::ConflictError {
+            expected: 1,
+            actual: 0
+        })
+    ));
+
+    let new_version = event_store
+        .append(
+            id.clone(),
+            version::Check::must_be(0),
+            vec![created("John Dee"), TestDomainEvent::WasDeleted.into()],
+        )
+        .await
+        .expect("the events should be appended");
+
+    assert_eq!(2, new_version);
+
+    let err = event_store
+        .append(
+            id.clone(),
+            version::Check::must_be(0),
+            vec![created("John Dee")],
+        )
+        .await
+        .expect_err("the event stream already exists");
+
+    assert!(matches!(
+        err,
+        AppendError::Conflict(version::ConflictError {
+            expected: 0,
+            actual: 2
+        })
+    ));
+
+    let new_version = event_store
+        .append(id.clone(), version::Check::Any, vec![created("Jane Doe")])
+        .await
+        .expect("the event should be appended");
+
+    assert_eq!(3, new_version);
+
+    let events: Vec<_> = event_store
+        .stream(&id, VersionSelect::From(2))
+        .try_collect()
+        .await
+        .expect("the events should be streamed");
+
+    assert_eq!(
+        vec![
+            Persisted {
+                stream_id: id.clone(),
+                version: 2,
+                event: TestDomainEvent::WasDeleted.into(),
+            },
+            Persisted {
+                stream_id: id.clone(),
+                version: 3,
+                event: created("Jane Doe"),
+            },
+        ],
+        events
+    );
+
+    assert!(events[0]
+        .event
+        .metadata
+        .contains_key(event::RECORDED_AT_KEY));
+
+    let head_version = event_store
+        .head_version(&id)
+        .await
+        .expect("the head version should be returned");
+
+    assert_eq!(Some(3), head_version);
+}
+
+#[tokio::test]
+#[ignore = "requires a MySQL server at MYSQL_DATABASE_URL"]
+async fn concurrent_appends_to_a_new_event_stream_only_conflict_on_version_checks() {
+    const CONCURRENT_APPENDS: usize = 8;
+
+    let event_store = new_event_store().await;
+
+    let id = format!("test-event-stream-{}", rand::thread_rng().gen::<u64>());
+
+    let results = join_all((0..CONCURRENT_APPENDS).map(|i| {
+        event_store.append(
+            id.clone(),
+            version::Check::Any,
+            vec![created(&format!("John Dee {i}"))],
+        )
+    }))
+    .await;
+
+    let mut new_versions = results
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .expect("appends with no version check should never conflict");
+
+    new_versions.sort_unstable();
+
+    assert_eq!(
+        (1..=CONCURRENT_APPENDS as u64).collect::<Vec<_>>(),
+        new_versions
+    );
+
+    let id = format!("test-event-stream-{}", rand::thread_rng().gen::<u64>());
+
+    let results = join_all((0..CONCURRENT_APPENDS).map(|i| {
+        event_store.append(
+            id.clone(),
+            version::Check::empty(),
+            vec![created(&format!("John Dee {i}"))],
+        )
+    }))
+    .await;
+
+    let (appended, failed): (Vec<_>, Vec<_>) = results.into_iter().partition(Result::is_ok);
+
+    assert_eq!(
+        1,
+        appended.len(),
+        "only one append should create the stream"
+    );
+
+    for err in failed.into_iter().filter_map(Result::err) {
+        match err {
+            AppendError::Conflict(conflict) => assert_eq!(
+                version::ConflictError {
+                    expected: 0,
+                    actual: 1
+                },
+                conflict
+            ),
+            err => assert!(err.is_transient(), "unexpected non-transient error: {err}"),
+        }
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires a MySQL server at MYSQL_DATABASE_URL"]
+async fn it_reads_and_subscribes_to_event_streams_in_pages() {
+    const EVENTS: usize = 600;
+
+    let event_store = new_event_store().await;
+
+    let id = format!("test-event-stream-{}", rand::thread_rng().gen::<u64>());
+
+    event_store
+        .append(
+            id.clone(),
+            version::Check::empty(),
+            (0..EVENTS).map(|i| created(&i.to_string())).collect(),
+        )
+        .await
+        .expect("the events should be appended");
+
+    let read: Vec<_> = event_store
+        .read_from(&id, 3, 7)
+        .map_ok(|evt| evt.version)
+        .try_collect()
+        .await
+        .expect("the events should be read");
+
+    assert_eq!((3..=EVENTS as u64).collect::<Vec<_>>(), read);
+
+    // The subscription catches up over more than one poll.
+    let subscribed: Vec<_> = event_store
+        .subscribe(&id, VersionSelect::All, Duration::from_millis(10))
+        .take(EVENTS)
+        .map_ok(|evt| evt.version)
+        .try_collect()
+        .await
+        .expect("the events should be returned");
+
+    assert_eq!((1..=EVENTS as u64).collect::<Vec<_>>(), subscribed);
+}
@@ -0,0 +1,5 @@
+CREATE TABLE effects (
+    "key"        TEXT        NOT NULL PRIMARY KEY,
+    "status"     TEXT        NOT NULL CHECK ("status" IN ('pending', 'completed')),
+    recorded_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
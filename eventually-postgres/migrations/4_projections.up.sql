@@ -0,0 +1,8 @@
+CREATE TABLE projection_checkpoints (
+    projection       TEXT        NOT NULL,
+    event_stream_id  TEXT        NOT NULL,
+    "version"        INTEGER     NOT NULL CHECK ("version" > 0),
+    updated_at       TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (projection, event_stream_id)
+);
@@ -0,0 +1,5 @@
+CREATE TABLE event_stream_metadata (
+    event_stream_id  TEXT        NOT NULL PRIMARY KEY,
+    metadata         JSONB       NOT NULL,
+    updated_at       TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
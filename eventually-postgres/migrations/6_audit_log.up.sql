@@ -0,0 +1,9 @@
+CREATE TABLE audit_log (
+    audit_stream_id  TEXT    NOT NULL,
+    "version"        INTEGER NOT NULL CHECK ("version" > 0),
+    "type"           TEXT    NOT NULL,
+    operation        JSONB   NOT NULL,
+    metadata         JSONB   NOT NULL,
+
+    PRIMARY KEY (audit_stream_id, "version")
+);
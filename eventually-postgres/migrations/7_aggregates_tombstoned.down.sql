@@ -0,0 +1 @@
+ALTER TABLE aggregates DROP COLUMN tombstoned;
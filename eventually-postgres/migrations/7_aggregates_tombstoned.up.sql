@@ -0,0 +1 @@
+ALTER TABLE aggregates ADD COLUMN tombstoned BOOLEAN NOT NULL DEFAULT FALSE;
@@ -4,27 +4,33 @@
 //! Check out the [Repository] type for more information.
 
 use std::marker::PhantomData;
+use std::time::Duration;
 
 use anyhow::anyhow;
 use async_trait::async_trait;
+use eventually::aggregate::stream_name::{self, StreamNameStrategy};
 use eventually::aggregate::Aggregate;
 use eventually::version::Version;
-use eventually::{aggregate, serde, version};
+use eventually::{aggregate, serde};
 use sqlx::{PgPool, Postgres, Row};
 
 /// Implements the [`eventually::aggregate::Repository`] trait for
 /// `PostgreSQL` databases.
 #[derive(Debug, Clone)]
-pub struct Repository<T, Serde, EvtSerde>
+pub struct Repository<T, Serde, EvtSerde, Name = stream_name::AggregateId>
 where
     T: Aggregate,
     <T as Aggregate>::Id: ToString,
     Serde: serde::Serde<T>,
     EvtSerde: serde::Serde<T::Event>,
+    Name: StreamNameStrategy<T>,
 {
     pool: PgPool,
     aggregate_serde: Serde,
     event_serde: EvtSerde,
+    stream_name: Name,
+    store_events: bool,
+    statement_timeout: Option<Duration>,
     t: PhantomData<T>,
 }
 
@@ -45,25 +51,183 @@ where
         pool: PgPool,
         aggregate_serde: Serde,
         event_serde: EvtSerde,
-    ) -> Result<Self, sqlx::migrate::MigrateError> {
-        // Make sure the latest migrations are used before using the Repository instance.
-        crate::MIGRATIONS.run(&pool).await?;
+    ) -> Result<Self, crate::BuildError> {
+        Self::builder(pool, aggregate_serde, event_serde)
+            .build()
+            .await
+    }
 
-        Ok(Self {
+    /// Returns a [`RepositoryBuilder`] to configure a new [`Repository`] instance.
+    pub fn builder(
+        pool: PgPool,
+        aggregate_serde: Serde,
+        event_serde: EvtSerde,
+    ) -> RepositoryBuilder<T, Serde, EvtSerde> {
+        RepositoryBuilder {
             pool,
             aggregate_serde,
             event_serde,
+            run_migrations: true,
+            stream_name: stream_name::AggregateId,
+            store_events: true,
+            statement_timeout: None,
+            t: PhantomData,
+        }
+    }
+}
+
+/// Builder type used to configure and create a new [`Repository`] instance.
+///
+/// Use [`Repository::builder`] to create a new builder.
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct RepositoryBuilder<T, Serde, EvtSerde, Name = stream_name::AggregateId>
+where
+    T: Aggregate,
+    <T as Aggregate>::Id: ToString,
+    Serde: serde::Serde<T>,
+    EvtSerde: serde::Serde<T::Event>,
+    Name: StreamNameStrategy<T>,
+{
+    pool: PgPool,
+    aggregate_serde: Serde,
+    event_serde: EvtSerde,
+    run_migrations: bool,
+    stream_name: Name,
+    store_events: bool,
+    statement_timeout: Option<Duration>,
+    t: PhantomData<T>,
+}
+
+impl<T, Serde, EvtSerde, Name> RepositoryBuilder<T, Serde, EvtSerde, Name>
+where
+    T: Aggregate,
+    <T as Aggregate>::Id: ToString,
+    Serde: serde::Serde<T>,
+    EvtSerde: serde::Serde<T::Event>,
+    Name: StreamNameStrategy<T>,
+{
+    /// Specifies whether the latest migrations should be run when building
+    /// the [`Repository`] instance. Defaults to `true`.
+    ///
+    /// Disable this option when the database schema is managed separately,
+    /// e.g. by a dedicated deployment step.
+    pub fn run_migrations(mut self, run_migrations: bool) -> Self {
+        self.run_migrations = run_migrations;
+        self
+    }
+
+    /// Specifies whether the Domain Events recorded by the Aggregate Roots
+    /// should be appended to their Event Streams. Defaults to `true`.
+    ///
+    /// Disable this option to only persist the latest state of the Aggregate Roots,
+    /// e.g. to start with a simpler, state-stored model. Since the [`Repository`]
+    /// always loads Aggregate Roots from their latest state, the option can be enabled
+    /// again later without changing the application code: the Event Streams will only
+    /// contain the Domain Events recorded from that point onwards.
+    pub fn store_events(mut self, store_events: bool) -> Self {
+        self.store_events = store_events;
+        self
+    }
+
+    /// Specifies the [`StreamNameStrategy`] used to name the Event Stream
+    /// of each Aggregate Root. Defaults to [`stream_name::AggregateId`].
+    pub fn stream_name_strategy<N>(self, stream_name: N) -> RepositoryBuilder<T, Serde, EvtSerde, N>
+    where
+        N: StreamNameStrategy<T>,
+    {
+        RepositoryBuilder {
+            pool: self.pool,
+            aggregate_serde: self.aggregate_serde,
+            event_serde: self.event_serde,
+            run_migrations: self.run_migrations,
+            stream_name,
+            store_events: self.store_events,
+            statement_timeout: self.statement_timeout,
+            t: PhantomData,
+        }
+    }
+
+    /// Specifies the [`serde::Serde`] implementation used to serialize
+    /// the Aggregate Root state, replacing the one passed to [`Repository::builder`].
+    pub fn aggregate_serde<S>(self, aggregate_serde: S) -> RepositoryBuilder<T, S, EvtSerde, Name>
+    where
+        S: serde::Serde<T>,
+    {
+        RepositoryBuilder {
+            pool: self.pool,
+            aggregate_serde,
+            event_serde: self.event_serde,
+            run_migrations: self.run_migrations,
+            stream_name: self.stream_name,
+            store_events: self.store_events,
+            statement_timeout: self.statement_timeout,
+            t: PhantomData,
+        }
+    }
+
+    /// Specifies the [`serde::Serde`] implementation used to serialize
+    /// the Domain Events, replacing the one passed to [`Repository::builder`].
+    pub fn event_serde<S>(self, event_serde: S) -> RepositoryBuilder<T, Serde, S, Name>
+    where
+        S: serde::Serde<T::Event>,
+    {
+        RepositoryBuilder {
+            pool: self.pool,
+            aggregate_serde: self.aggregate_serde,
+            event_serde,
+            run_migrations: self.run_migrations,
+            stream_name: self.stream_name,
+            store_events: self.store_events,
+            statement_timeout: self.statement_timeout,
+            t: PhantomData,
+        }
+    }
+
+    /// Aborts any statement of the transactions used to save the Aggregate Roots
+    /// that takes longer than the specified timeout. Disabled by default.
+    ///
+    /// Reads are not covered: use the pool options to bound them instead.
+    /// The timeout must be between one millisecond and `i32::MAX` milliseconds.
+    pub fn statement_timeout(mut self, timeout: Duration) -> Self {
+        self.statement_timeout = Some(timeout);
+        self
+    }
+
+    /// Builds the new [`Repository`] instance, running the latest migrations
+    /// necessary for the implementation to work, unless disabled.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if one of the configured options is invalid,
+    /// or if the migrations fail to run.
+    pub async fn build(self) -> Result<Repository<T, Serde, EvtSerde, Name>, crate::BuildError> {
+        crate::validate_statement_timeout(self.statement_timeout)?;
+
+        if self.run_migrations {
+            // Make sure the latest migrations are used before using the Repository instance.
+            crate::MIGRATIONS.run(&self.pool).await?;
+        }
+
+        Ok(Repository {
+            pool: self.pool,
+            aggregate_serde: self.aggregate_serde,
+            event_serde: self.event_serde,
+            stream_name: self.stream_name,
+            store_events: self.store_events,
+            statement_timeout: self.statement_timeout,
             t: PhantomData,
         })
     }
 }
 
-impl<T, Serde, EvtSerde> Repository<T, Serde, EvtSerde>
+impl<T, Serde, EvtSerde, Name> Repository<T, Serde, EvtSerde, Name>
 where
     T: Aggregate + Send + Sync,
     <T as Aggregate>::Id: ToString,
     Serde: serde::Serde<T> + Send + Sync,
     EvtSerde: serde::Serde<T::Event> + Send + Sync,
+    Name: StreamNameStrategy<T>,
 {
     async fn save_aggregate_state(
         &self,
@@ -73,10 +237,9 @@ where
         root: &mut aggregate::Root<T>,
     ) -> Result<(), aggregate::repository::SaveError> {
         let out_state = root.to_aggregate_type::<T>();
-        let bytes_state = self
-            .aggregate_serde
-            .serialize(out_state)
-            .map_err(|err| anyhow!("failed to serialize aggregate root state: {}", err))?;
+        let bytes_state = self.aggregate_serde.serialize(out_state).map_err(|err| {
+            crate::Error::Serialization(anyhow!("failed to serialize aggregate root state: {err}"))
+        })?;
 
         #[allow(clippy::cast_possible_truncation)]
         sqlx::query("CALL upsert_aggregate($1, $2, $3, $4, $5)")
@@ -89,36 +252,51 @@ where
             .await
             .map_err(|err| match crate::check_for_conflict_error(&err) {
                 Some(err) => aggregate::repository::SaveError::Conflict(err),
-                None => match err
-                    .as_database_error()
-                    .and_then(sqlx::error::DatabaseError::code)
-                {
-                    Some(code) if code == "40001" => version::ConflictError {
-                        expected: expected_version,
-                        actual: root.version(),
-                    }
-                    .into(),
-                    _ => anyhow!("failed to save aggregate state: {}", err).into(),
-                },
+                // Serialization failures (40001) do not carry the actual version
+                // of the Aggregate Root: they are surfaced as transient contention.
+                None => crate::classify_error(&err, "failed to save aggregate state").into(),
             })?;
 
+        // The framework-level tombstone is not part of the Aggregate state.
+        sqlx::query("UPDATE aggregates SET tombstoned = $2 WHERE aggregate_id = $1")
+            .bind(aggregate_id)
+            .bind(root.is_stream_tombstoned())
+            .execute(&mut **tx)
+            .await
+            .map_err(|err| crate::classify_error(&err, "failed to save aggregate tombstone"))?;
+
         Ok(())
     }
 }
 
 #[async_trait]
-impl<T, Serde, EvtSerde> aggregate::repository::Getter<T> for Repository<T, Serde, EvtSerde>
+impl<T, Serde, EvtSerde, Name> aggregate::repository::Getter<T>
+    for Repository<T, Serde, EvtSerde, Name>
 where
     T: Aggregate + Send + Sync,
     <T as Aggregate>::Id: ToString,
     Serde: serde::Serde<T> + Send + Sync,
     EvtSerde: serde::Serde<T::Event> + Send + Sync,
+    Name: StreamNameStrategy<T>,
 {
     async fn get(&self, id: &T::Id) -> Result<aggregate::Root<T>, aggregate::repository::GetError> {
-        let aggregate_id = id.to_string();
+        let root = self.get_including_tombstoned(id).await?;
+
+        if root.is_tombstoned() {
+            return Err(aggregate::repository::GetError::NotFound);
+        }
+
+        Ok(root)
+    }
+
+    async fn get_including_tombstoned(
+        &self,
+        id: &T::Id,
+    ) -> Result<aggregate::Root<T>, aggregate::repository::GetError> {
+        let aggregate_id = self.stream_name.stream_name(id);
 
         let row = sqlx::query(
-            r#"SELECT version, state
+            r#"SELECT version, state, tombstoned
                FROM aggregates
                WHERE aggregate_id = $1 AND "type" = $2"#,
         )
@@ -128,42 +306,47 @@ where
         .await
         .map_err(|err| match err {
             sqlx::Error::RowNotFound => aggregate::repository::GetError::NotFound,
-            _ => anyhow!("failed to fetch the aggregate state row: {}", err).into(),
+            _ => crate::classify_error(&err, "failed to fetch the aggregate state row").into(),
         })?;
 
-        let version: i32 = row
-            .try_get("version")
-            .map_err(|err| anyhow!("failed to get 'version' column from row: {}", err))?;
+        let version: i32 = row.try_get("version").map_err(|err| {
+            crate::classify_error(&err, "failed to get 'version' column from row")
+        })?;
 
         let bytes_state: Vec<u8> = row
             .try_get("state")
-            .map_err(|err| anyhow!("failed to get 'state' column from row: {}", err))?;
+            .map_err(|err| crate::classify_error(&err, "failed to get 'state' column from row"))?;
+
+        let tombstoned: bool = row.try_get("tombstoned").map_err(|err| {
+            crate::classify_error(&err, "failed to get 'tombstoned' column from row")
+        })?;
 
         let aggregate: T = self
             .aggregate_serde
             .deserialize(&bytes_state)
             .map_err(|err| {
-                anyhow!(
-                    "failed to deserialize the aggregate state from the database row: {}",
-                    err
-                )
+                crate::Error::Serialization(anyhow!(
+                    "failed to deserialize the aggregate state from the database row: {err}"
+                ))
             })?;
 
         #[allow(clippy::cast_sign_loss)]
-        Ok(aggregate::Root::rehydrate_from_state(
-            version as Version,
-            aggregate,
-        ))
+        Ok(
+            aggregate::Root::rehydrate_from_state(version as Version, aggregate)
+                .with_stream_tombstoned(tombstoned),
+        )
     }
 }
 
 #[async_trait]
-impl<T, Serde, EvtSerde> aggregate::repository::Saver<T> for Repository<T, Serde, EvtSerde>
+impl<T, Serde, EvtSerde, Name> aggregate::repository::Saver<T>
+    for Repository<T, Serde, EvtSerde, Name>
 where
     T: Aggregate + Send + Sync,
     <T as Aggregate>::Id: ToString,
     Serde: serde::Serde<T> + Send + Sync,
     EvtSerde: serde::Serde<T::Event> + Send + Sync,
+    Name: StreamNameStrategy<T>,
 {
     async fn save(
         &self,
@@ -175,37 +358,30 @@ where
             return Ok(());
         }
 
-        let mut tx = self
-            .pool
-            .begin()
-            .await
-            .map_err(|err| anyhow!("failed to begin transaction: {}", err))?;
-
-        sqlx::query("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE DEFERRABLE")
-            .execute(&mut *tx)
-            .await
-            .map_err(|err| anyhow!("failed to begin transaction: {}", err))?;
+        let mut tx = crate::begin_transaction(&self.pool, true, self.statement_timeout).await?;
 
-        let aggregate_id = root.aggregate_id().to_string();
+        // An Aggregate Root is also an Event Stream, named after the configured strategy.
+        let aggregate_id = self.stream_name.stream_name(root.aggregate_id());
         let expected_root_version = root.version() - (events_to_commit.len() as Version);
 
         self.save_aggregate_state(&mut tx, &aggregate_id, expected_root_version, root)
             .await?;
 
-        #[allow(clippy::cast_possible_truncation)]
-        crate::event::append_domain_events(
-            &mut tx,
-            &self.event_serde,
-            &aggregate_id,
-            root.version() as i32,
-            events_to_commit,
-        )
-        .await
-        .map_err(|err| anyhow!("failed to append aggregate root domain events: {}", err))?;
+        if self.store_events {
+            #[allow(clippy::cast_possible_truncation)]
+            crate::event::append_domain_events(
+                &mut tx,
+                &self.event_serde,
+                &aggregate_id,
+                root.version() as i32,
+                events_to_commit,
+            )
+            .await?;
+        }
 
         tx.commit()
             .await
-            .map_err(|err| anyhow!("failed to commit transaction: {}", err))?;
+            .map_err(|err| crate::classify_error(&err, "failed to commit transaction"))?;
 
         Ok(())
     }
@@ -0,0 +1,106 @@
+//! This module contains the implementation of the [`eventually::event::effect::Journal`]
+//! trait, to record the side effects executed by long-running processes
+//! in a `PostgreSQL` database.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use eventually::event::effect::{self, Status};
+use sqlx::{PgPool, Row};
+
+/// Implements the [`eventually::event::effect::Journal`] trait for
+/// `PostgreSQL` databases, using the `effects` table.
+#[derive(Debug, Clone)]
+pub struct Journal {
+    pool: PgPool,
+}
+
+impl Journal {
+    /// Runs the latest migrations necessary for the implementation to work,
+    /// then returns a new [`Journal`] instance.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the migrations fail to run.
+    pub async fn new(pool: PgPool) -> Result<Self, sqlx::migrate::MigrateError> {
+        // Make sure the latest migrations are used before using the Journal instance.
+        crate::MIGRATIONS.run(&pool).await?;
+
+        Ok(Self { pool })
+    }
+}
+
+#[async_trait]
+impl effect::Journal for Journal {
+    type Error = anyhow::Error;
+
+    async fn status(&self, key: &str) -> Result<Option<Status>, Self::Error> {
+        let row = sqlx::query(r#"SELECT "status" FROM effects WHERE "key" = $1"#)
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|err| crate::classify_error(&err, "failed to fetch the side effect row"))?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let status: String = row
+            .try_get("status")
+            .map_err(|err| crate::classify_error(&err, "failed to get 'status' column from row"))?;
+
+        Ok(Some(if status == "completed" {
+            Status::Completed
+        } else {
+            Status::Pending
+        }))
+    }
+
+    async fn record_intent(&self, key: &str, lease: Option<Duration>) -> Result<bool, Self::Error> {
+        // The claim is taken over only if it is still pending and has expired:
+        // concurrent takeovers are serialized on the conflicting row, and all but
+        // the first one see the renewed "recorded_at" and affect no rows.
+        let result = sqlx::query(
+            r#"INSERT INTO effects ("key", "status")
+               VALUES ($1, 'pending')
+               ON CONFLICT ("key") DO UPDATE SET "recorded_at" = NOW()
+               WHERE effects."status" = 'pending'
+                 AND $2::DOUBLE PRECISION IS NOT NULL
+                 AND effects."recorded_at" <= NOW() - make_interval(secs => $2)"#,
+        )
+        .bind(key)
+        .bind(lease.map(|lease| lease.as_secs_f64()))
+        .execute(&self.pool)
+        .await
+        .map_err(|err| crate::classify_error(&err, "failed to record the side effect intent"))?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn record_completion(&self, key: &str) -> Result<(), Self::Error> {
+        sqlx::query(
+            r#"INSERT INTO effects ("key", "status")
+               VALUES ($1, 'completed')
+               ON CONFLICT ("key") DO
+               UPDATE SET "status" = 'completed', recorded_at = NOW()"#,
+        )
+        .bind(key)
+        .execute(&self.pool)
+        .await
+        .map_err(|err| {
+            crate::classify_error(&err, "failed to record the side effect completion")
+        })?;
+
+        Ok(())
+    }
+
+    async fn discard(&self, key: &str) -> Result<(), Self::Error> {
+        sqlx::query(r#"DELETE FROM effects WHERE "key" = $1"#)
+            .bind(key)
+            .execute(&self.pool)
+            .await
+            .map_err(|err| crate::classify_error(&err, "failed to discard the side effect"))?;
+
+        Ok(())
+    }
+}
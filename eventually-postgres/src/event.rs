@@ -1,9 +1,20 @@
+//! This module contains the implementation of the [`eventually::event::Store`] trait,
+//! to work specifically with `PostgreSQL` databases.
+//!
+//! Check out the [Store] type for more information.
+
 use std::marker::PhantomData;
 use std::string::ToString;
+use std::time::Duration;
 
 use anyhow::anyhow;
 use async_trait::async_trait;
 use chrono::Utc;
+use eventually::aggregate::timeline::CORRELATION_ID_KEY;
+use eventually::correlation::WorkflowQuery;
+use eventually::error::{Kind, Retryable};
+use eventually::event::audit;
+use eventually::event::metadata::{MetadataStore, StreamMetadata};
 use eventually::message::{Message, Metadata};
 use eventually::version::Version;
 use eventually::{event, serde, version};
@@ -12,20 +23,62 @@ use futures::{StreamExt, TryStreamExt};
 use sqlx::postgres::PgRow;
 use sqlx::{PgPool, Postgres, Row, Transaction};
 
+/// All possible errors returned by [`Store`] during an [`event::store::Streamer::stream`] call.
 #[derive(Debug, thiserror::Error)]
 pub enum StreamError {
+    /// Error returned when the Domain Event could not be deserialized
+    /// using the configured [`serde::Serde`] implementation.
     #[error("failed to deserialize event from database: {0}")]
     DeserializeEvent(#[source] anyhow::Error),
+    /// Error returned when a column could not be read from a result row.
     #[error("failed to get column '{name}' from result row: {error}")]
     ReadColumn {
+        /// The name of the column that could not be read.
         name: &'static str,
+        /// The underlying error returned by the database driver.
         #[source]
         error: sqlx::Error,
     },
+    /// Error returned when the database has returned an error.
     #[error("db returned an error: {0}")]
     Database(#[source] sqlx::Error),
 }
 
+impl StreamError {
+    /// Returns the [Kind] of the error.
+    #[must_use]
+    pub fn kind(&self) -> Kind {
+        match self {
+            StreamError::DeserializeEvent(_) | StreamError::ReadColumn { .. } => {
+                Kind::Serialization
+            },
+            StreamError::Database(err) => crate::error_kind(err),
+        }
+    }
+}
+
+impl Retryable for StreamError {
+    fn is_transient(&self) -> bool {
+        self.kind().is_transient()
+    }
+}
+
+pub use eventually::message::RECORDED_AT_KEY;
+
+/// [Metadata] key set automatically on each appended Domain Event,
+/// containing the new version of the Event Stream after the append.
+///
+/// Any value set by the caller under this key is overridden.
+pub const RECORDED_WITH_NEW_VERSION_KEY: &str = "Recorded-With-New-Version";
+
+/// The actor the [`audit::Operation`]s performed by [`Store::apply_retention`]
+/// are attributed to.
+pub const RETENTION_ACTOR: &str = "eventually-postgres/retention";
+
+/// The actor the [`audit::Operation`]s performed through the
+/// [`Truncator`][event::store::Truncator] implementation of the [`Store`] are attributed to.
+pub const TRUNCATOR_ACTOR: &str = "eventually-postgres/truncator";
+
 pub(crate) async fn append_domain_event<Evt>(
     tx: &mut Transaction<'_, Postgres>,
     serde: &impl serde::Serializer<Evt>,
@@ -39,13 +92,13 @@ where
 {
     let event_type = event.message.name();
     let mut metadata = event.metadata;
-    let serialized_event = serde
-        .serialize(event.message)
-        .map_err(|err| anyhow!("failed to serialize event message: {}", err))?;
+    let serialized_event = serde.serialize(event.message).map_err(|err| {
+        crate::Error::Serialization(anyhow!("failed to serialize event message: {err}"))
+    })?;
 
-    metadata.insert("Recorded-At".to_owned(), Utc::now().to_rfc3339());
+    metadata.insert(RECORDED_AT_KEY.to_owned(), Utc::now().to_rfc3339());
     metadata.insert(
-        "Recorded-With-New-Version".to_owned(),
+        RECORDED_WITH_NEW_VERSION_KEY.to_owned(),
         new_event_stream_version.to_string(),
     );
 
@@ -58,7 +111,8 @@ where
             .bind(serialized_event)
             .bind(sqlx::types::Json(metadata))
             .execute(&mut **tx)
-            .await?;
+            .await
+            .map_err(|err| crate::classify_error(&err, "failed to insert domain event"))?;
 
     Ok(())
 }
@@ -94,6 +148,8 @@ where
     Ok(())
 }
 
+/// Implements the [`eventually::event::Store`] trait for
+/// `PostgreSQL` databases.
 #[derive(Debug, Clone)]
 pub struct Store<Id, Evt, Serde>
 where
@@ -102,6 +158,9 @@ where
 {
     pool: PgPool,
     serde: Serde,
+    audit_stream_id: Option<String>,
+    statement_timeout: Option<Duration>,
+    read_page_size: Option<usize>,
     id_type: PhantomData<Id>,
     evt_type: PhantomData<Evt>,
 }
@@ -117,13 +176,137 @@ where
     /// # Errors
     ///
     /// An error is returned if the migrations fail to run.
-    pub async fn new(pool: PgPool, serde: Serde) -> Result<Self, sqlx::migrate::MigrateError> {
-        // Make sure the latest migrations are used before using the Store instance.
-        crate::MIGRATIONS.run(&pool).await?;
+    pub async fn new(pool: PgPool, serde: Serde) -> Result<Self, crate::BuildError> {
+        Self::builder(pool, serde).build().await
+    }
 
-        Ok(Self {
+    /// Returns a [`StoreBuilder`] to configure a new [`Store`] instance.
+    pub fn builder(pool: PgPool, serde: Serde) -> StoreBuilder<Id, Evt, Serde> {
+        StoreBuilder {
             pool,
             serde,
+            run_migrations: true,
+            audit_stream_id: None,
+            statement_timeout: None,
+            read_page_size: None,
+            id_type: PhantomData,
+            evt_type: PhantomData,
+        }
+    }
+}
+
+/// Builder type used to configure and create a new [`Store`] instance.
+///
+/// Use [`Store::builder`] to create a new builder.
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct StoreBuilder<Id, Evt, Serde>
+where
+    Id: ToString + Clone,
+    Serde: serde::Serde<Evt>,
+{
+    pool: PgPool,
+    serde: Serde,
+    run_migrations: bool,
+    audit_stream_id: Option<String>,
+    statement_timeout: Option<Duration>,
+    read_page_size: Option<usize>,
+    id_type: PhantomData<Id>,
+    evt_type: PhantomData<Evt>,
+}
+
+impl<Id, Evt, Serde> StoreBuilder<Id, Evt, Serde>
+where
+    Id: ToString + Clone,
+    Serde: serde::Serde<Evt>,
+{
+    /// Specifies whether the latest migrations should be run when building
+    /// the [`Store`] instance. Defaults to `true`.
+    ///
+    /// Disable this option when the database schema is managed separately,
+    /// e.g. by a dedicated deployment step.
+    pub fn run_migrations(mut self, run_migrations: bool) -> Self {
+        self.run_migrations = run_migrations;
+        self
+    }
+
+    /// Records the administrative operations performed on the [`Store`]
+    /// (e.g. [`Store::apply_retention`]) to the audit stream with the specified id,
+    /// as [`audit::Operation`] Domain Events. Disabled by default.
+    ///
+    /// The audit stream is kept apart from the Event Streams: read it back with [`Store::audit_log`].
+    pub fn audit_stream(mut self, stream_id: impl Into<String>) -> Self {
+        self.audit_stream_id = Some(stream_id.into());
+        self
+    }
+
+    /// Specifies the [`serde::Serde`] implementation used to serialize
+    /// the Domain Events, replacing the one passed to [`Store::builder`].
+    pub fn serde<S>(self, serde: S) -> StoreBuilder<Id, Evt, S>
+    where
+        S: serde::Serde<Evt>,
+    {
+        StoreBuilder {
+            pool: self.pool,
+            serde,
+            run_migrations: self.run_migrations,
+            audit_stream_id: self.audit_stream_id,
+            statement_timeout: self.statement_timeout,
+            read_page_size: self.read_page_size,
+            id_type: PhantomData,
+            evt_type: PhantomData,
+        }
+    }
+
+    /// Aborts any statement of the transactions opened by the [`Store`]
+    /// (appends, retention, truncation and deletion) that takes longer than
+    /// the specified timeout. Disabled by default.
+    ///
+    /// Reads are not covered: use the pool options to bound them instead.
+    /// The timeout must be between one millisecond and `i32::MAX` milliseconds.
+    pub fn statement_timeout(mut self, timeout: Duration) -> Self {
+        self.statement_timeout = Some(timeout);
+        self
+    }
+
+    /// Makes [`Store::stream`][event::store::Streamer::stream] read the Event Streams
+    /// in pages of the specified size, rather than with a single query.
+    /// Disabled by default.
+    ///
+    /// The page size must be greater than zero.
+    pub fn read_page_size(mut self, page_size: usize) -> Self {
+        self.read_page_size = Some(page_size);
+        self
+    }
+
+    /// Builds the new [`Store`] instance, running the latest migrations
+    /// necessary for the implementation to work, unless disabled.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if one of the configured options is invalid,
+    /// or if the migrations fail to run.
+    pub async fn build(self) -> Result<Store<Id, Evt, Serde>, crate::BuildError> {
+        crate::validate_statement_timeout(self.statement_timeout)?;
+
+        if self.read_page_size == Some(0) {
+            return Err(crate::BuildError::InvalidOption {
+                option: "read_page_size",
+                reason: "must be greater than zero",
+            });
+        }
+
+        if self.run_migrations {
+            // Make sure the latest migrations are used before using the Store instance.
+            crate::MIGRATIONS.run(&self.pool).await?;
+        }
+
+        Ok(Store {
+            pool: self.pool,
+            serde: self.serde,
+            audit_stream_id: self.audit_stream_id,
+            statement_timeout: self.statement_timeout,
+            read_page_size: self.read_page_size,
             id_type: PhantomData,
             evt_type: PhantomData,
         })
@@ -138,17 +321,36 @@ where
         .map_err(|err| StreamError::ReadColumn { name, error: err })
 }
 
+fn audit_row_to_persisted_operation(
+    audit_stream_id: String,
+    row: &PgRow,
+) -> Result<event::Persisted<String, audit::Operation>, StreamError> {
+    let version_column: i32 = try_get_column(row, "version")?;
+    let operation_column: sqlx::types::Json<audit::Operation> = try_get_column(row, "operation")?;
+    let metadata_column: sqlx::types::Json<Metadata> = try_get_column(row, "metadata")?;
+
+    #[allow(clippy::cast_sign_loss)]
+    Ok(event::Persisted {
+        stream_id: audit_stream_id,
+        version: version_column as Version,
+        event: event::Envelope {
+            message: operation_column.0,
+            metadata: metadata_column.0,
+        },
+    })
+}
+
 impl<Id, Evt, Serde> Store<Id, Evt, Serde>
 where
     Id: ToString + Clone + Send + Sync,
     Evt: Message + Send + Sync,
     Serde: serde::Serde<Evt> + Send + Sync,
 {
-    fn event_row_to_persisted_event(
+    fn event_row_to_persisted_event<StreamId>(
         &self,
-        stream_id: Id,
+        stream_id: StreamId,
         row: &PgRow,
-    ) -> Result<event::Persisted<Id, Evt>, StreamError> {
+    ) -> Result<event::Persisted<StreamId, Evt>, StreamError> {
         let version_column: i32 = try_get_column(row, "version")?;
         let event_column: Vec<u8> = try_get_column(row, "event")?;
         let metadata_column: sqlx::types::Json<Metadata> = try_get_column(row, "metadata")?;
@@ -170,6 +372,7 @@ where
     }
 }
 
+#[async_trait]
 impl<Id, Evt, Serde> event::store::Streamer<Id, Evt> for Store<Id, Evt, Serde>
 where
     Id: ToString + Clone + Send + Sync,
@@ -178,7 +381,20 @@ where
 {
     type Error = StreamError;
 
-    fn stream(&self, id: &Id, select: event::VersionSelect) -> event::Stream<Id, Evt, Self::Error> {
+    fn stream(
+        &self,
+        id: &Id,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, Id, Evt, Self::Error> {
+        if let Some(page_size) = self.read_page_size {
+            let from_version = match select {
+                event::VersionSelect::All => 0,
+                event::VersionSelect::From(v) => v,
+            };
+
+            return self.read_from(id, from_version, page_size);
+        }
+
         #[allow(clippy::cast_possible_truncation)]
         let from_version: i32 = match select {
             event::VersionSelect::All => 0,
@@ -202,32 +418,81 @@ where
             .and_then(move |row| ready(self.event_row_to_persisted_event(id.clone(), &row)))
             .boxed()
     }
+
+    fn read_from<'a>(
+        &'a self,
+        id: &Id,
+        from_version: Version,
+        batch_size: usize,
+    ) -> event::Stream<'a, Id, Evt, Self::Error>
+    where
+        Id: 'a,
+        Evt: 'a,
+    {
+        assert!(batch_size > 0, "batch size must be greater than zero");
+
+        let id = id.clone();
+        let string_id = id.to_string();
+        let limit = i64::try_from(batch_size).unwrap_or(i64::MAX);
+
+        event::store::paged(from_version, batch_size, move |from_version| {
+            let id = id.clone();
+            let string_id = string_id.clone();
+
+            async move {
+                let rows = sqlx::query(
+                    r"SELECT version, event, metadata
+                       FROM events
+                       WHERE event_stream_id = $1 AND version >= $2
+                       ORDER BY version
+                       LIMIT $3",
+                )
+                .bind(string_id)
+                .bind(i32::try_from(from_version).unwrap_or(i32::MAX))
+                .bind(limit)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(StreamError::Database)?;
+
+                rows.iter()
+                    .map(|row| self.event_row_to_persisted_event(id.clone(), row))
+                    .collect()
+            }
+        })
+    }
+
+    async fn head_version(&self, id: &Id) -> Result<Option<Version>, Self::Error> {
+        let version: Option<i32> =
+            sqlx::query_scalar("SELECT version FROM event_streams WHERE event_stream_id = $1")
+                .bind(id.to_string())
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(StreamError::Database)?;
+
+        #[allow(clippy::cast_sign_loss)]
+        Ok(version.map(|v| v as Version))
+    }
 }
 
-#[async_trait]
-impl<Id, Evt, Serde> event::store::Appender<Id, Evt> for Store<Id, Evt, Serde>
+impl<Id, Evt, Serde> Store<Id, Evt, Serde>
 where
     Id: ToString + Clone + Send + Sync,
     Evt: Message + Send + Sync,
     Serde: serde::Serde<Evt> + Send + Sync,
 {
-    async fn append(
+    async fn begin_append_transaction(
         &self,
+    ) -> Result<Transaction<'_, Postgres>, event::store::AppendError> {
+        Ok(crate::begin_transaction(&self.pool, true, self.statement_timeout).await?)
+    }
+
+    async fn append_in_transaction(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
         id: Id,
         version_check: version::Check,
         events: Vec<event::Envelope<Evt>>,
     ) -> Result<Version, event::store::AppendError> {
-        let mut tx = self
-            .pool
-            .begin()
-            .await
-            .map_err(|err| anyhow!("failed to begin transaction: {}", err))?;
-
-        sqlx::query("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE DEFERRABLE")
-            .execute(&mut *tx)
-            .await
-            .map_err(|err| anyhow!("failed to begin transaction: {}", err))?;
-
         let string_id = id.to_string();
 
         let new_version: i32 = match version_check {
@@ -238,12 +503,15 @@ where
                 sqlx::query("SELECT * FROM upsert_event_stream_with_no_version_check($1, $2)")
                     .bind(&string_id)
                     .bind(events_len)
-                    .fetch_one(&mut *tx)
+                    .fetch_one(&mut **tx)
                     .await
                     .and_then(|row| row.try_get(0))
-                    .map_err(|err| anyhow!("failed to upsert new event stream version: {}", err))?
+                    .map_err(|err| {
+                        crate::classify_error(&err, "failed to upsert new event stream version")
+                    })?
             },
-            version::Check::MustBe(v) => {
+            version::Check::MustBe(expected) => {
+                let v = expected.version();
                 let new_version = v + (events.len() as Version);
 
                 #[allow(clippy::cast_possible_truncation)]
@@ -251,39 +519,396 @@ where
                     .bind(&string_id)
                     .bind(v as i32)
                     .bind(new_version as i32)
-                    .execute(&mut *tx)
+                    .execute(&mut **tx)
                     .await
                     .map_err(|err| match crate::check_for_conflict_error(&err) {
                         Some(err) => event::store::AppendError::Conflict(err),
-                        None => match err
-                            .as_database_error()
-                            .and_then(sqlx::error::DatabaseError::code)
-                        {
-                            Some(code) if code == "40001" => {
-                                event::store::AppendError::Conflict(version::ConflictError {
-                                    expected: v,
-                                    actual: new_version,
-                                })
-                            },
-                            _ => event::store::AppendError::Internal(anyhow!(
-                                "failed to upsert new event stream version: {}",
-                                err
-                            )),
-                        },
+                        // Serialization failures (40001) do not carry the actual version
+                        // of the Event Stream: they are surfaced as transient contention.
+                        None => event::store::AppendError::Internal(crate::classify_error(
+                            &err,
+                            "failed to upsert new event stream version",
+                        )),
                     })
                     .map(|_| new_version as i32)?
             },
         };
 
-        append_domain_events(&mut tx, &self.serde, &string_id, new_version, events)
+        append_domain_events(tx, &self.serde, &string_id, new_version, events).await?;
+
+        #[allow(clippy::cast_sign_loss)]
+        Ok(new_version as Version)
+    }
+}
+
+#[async_trait]
+impl<Id, Evt, Serde> event::store::Appender<Id, Evt> for Store<Id, Evt, Serde>
+where
+    Id: ToString + Clone + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt> + Send + Sync,
+{
+    async fn append(
+        &self,
+        id: Id,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Evt>>,
+    ) -> Result<Version, event::store::AppendError> {
+        let mut tx = self.begin_append_transaction().await?;
+
+        let new_version = self
+            .append_in_transaction(&mut tx, id, version_check, events)
+            .await?;
+
+        tx.commit()
+            .await
+            .map_err(|err| crate::classify_error(&err, "failed to commit transaction"))?;
+
+        Ok(new_version)
+    }
+}
+
+/// All the [`StreamWrite`][event::store::StreamWrite]s are appended in a single
+/// serializable transaction.
+#[async_trait]
+impl<Id, Evt, Serde> event::store::MultiAppender<Id, Evt> for Store<Id, Evt, Serde>
+where
+    Id: ToString + Clone + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt> + Send + Sync,
+{
+    async fn append_all(
+        &self,
+        writes: Vec<event::store::StreamWrite<Id, Evt>>,
+    ) -> Result<Vec<Version>, event::store::AppendError> {
+        let mut tx = self.begin_append_transaction().await?;
+        let mut new_versions = Vec::with_capacity(writes.len());
+
+        for write in writes {
+            new_versions.push(
+                self.append_in_transaction(&mut tx, write.id, write.version_check, write.events)
+                    .await?,
+            );
+        }
+
+        tx.commit()
+            .await
+            .map_err(|err| crate::classify_error(&err, "failed to commit transaction"))?;
+
+        Ok(new_versions)
+    }
+}
+
+impl<Id, Evt, Serde> Store<Id, Evt, Serde>
+where
+    Id: ToString + Clone + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt> + Send + Sync,
+{
+    /// Enforces the retention rules set in the [`StreamMetadata`] of the Event Stream,
+    /// deleting the Domain Events exceeding its `max_count`, or older than its `max_age`
+    /// according to their [`RECORDED_AT_KEY`] metadata.
+    ///
+    /// Retention rules are not enforced on append: call this method periodically,
+    /// e.g. from a background task. The Event Stream version is not affected.
+    ///
+    /// If an [audit stream][StoreBuilder::audit_stream] is configured, the purge is recorded
+    /// in the same transaction, attributed to the [`RETENTION_ACTOR`].
+    ///
+    /// Returns the number of deleted Domain Events.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the database has returned an error.
+    pub async fn apply_retention(&self, id: &Id) -> anyhow::Result<u64> {
+        let Some(metadata) = self.stream_metadata(id).await? else {
+            return Ok(0);
+        };
+
+        #[allow(clippy::cast_possible_wrap)]
+        let max_count = metadata.max_count.map(|count| count as i64);
+        let max_age_millis = metadata
+            .max_age
+            .map(|age| i64::try_from(age.as_millis()).unwrap_or(i64::MAX));
+
+        let string_id = id.to_string();
+
+        let mut tx = crate::begin_transaction(&self.pool, false, self.statement_timeout).await?;
+
+        let deleted = sqlx::query(
+            r"DELETE FROM events e
+               WHERE e.event_stream_id = $1
+               AND (
+                   e.version <= (
+                       SELECT es.version FROM event_streams es WHERE es.event_stream_id = $1
+                   ) - $2
+                   OR (e.metadata->>$4)::TIMESTAMPTZ
+                       < NOW() - $3 * INTERVAL '1 millisecond'
+               )",
+        )
+        .bind(&string_id)
+        .bind(max_count)
+        .bind(max_age_millis)
+        .bind(RECORDED_AT_KEY)
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| crate::classify_error(&err, "failed to delete expired domain events"))?
+        .rows_affected();
+
+        if deleted > 0 {
+            self.record_operation(
+                &mut tx,
+                audit::Operation::RetentionPurged {
+                    stream_id: string_id,
+                    deleted,
+                }
+                .attributed_to(RETENTION_ACTOR, "event stream retention rules"),
+            )
+            .await?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|err| crate::classify_error(&err, "failed to commit transaction"))?;
+
+        Ok(deleted)
+    }
+
+    async fn record_operation(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        operation: event::Envelope<audit::Operation>,
+    ) -> anyhow::Result<()> {
+        let Some(audit_stream_id) = &self.audit_stream_id else {
+            return Ok(());
+        };
+
+        let operation_type = operation.message.name();
+        let mut metadata = operation.metadata;
+
+        metadata.insert(RECORDED_AT_KEY.to_owned(), Utc::now().to_rfc3339());
+
+        // Serializes the concurrent writers of the same audit stream,
+        // which would otherwise compute the same next version.
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+            .bind(audit_stream_id)
+            .execute(&mut **tx)
             .await
-            .map_err(|err| anyhow!("failed to append new domain events: {}", err))?;
+            .map_err(|err| crate::classify_error(&err, "failed to lock the audit stream"))?;
+
+        sqlx::query(
+            r#"INSERT INTO audit_log (audit_stream_id, "version", "type", operation, metadata)
+               SELECT $1, COALESCE(MAX("version"), 0) + 1, $2, $3, $4
+               FROM audit_log
+               WHERE audit_stream_id = $1"#,
+        )
+        .bind(audit_stream_id)
+        .bind(operation_type)
+        .bind(sqlx::types::Json(operation.message))
+        .bind(sqlx::types::Json(metadata))
+        .execute(&mut **tx)
+        .await
+        .map_err(|err| crate::classify_error(&err, "failed to record the audit operation"))?;
+
+        Ok(())
+    }
+
+    /// Streams the [`audit::Operation`]s recorded to the [audit stream][StoreBuilder::audit_stream],
+    /// in the order they have been recorded, or nothing if no audit stream is configured.
+    ///
+    /// [`audit::Operation`]s are kept in the `audit_log` table rather than with
+    /// the Domain Events, so they are always serialized as JSON, regardless of
+    /// the [`serde::Serde`] implementation used by the [`Store`].
+    pub fn audit_log(&self) -> event::Stream<'_, String, audit::Operation, StreamError> {
+        let Some(audit_stream_id) = self.audit_stream_id.clone() else {
+            return futures::stream::empty().boxed();
+        };
+
+        sqlx::query(
+            r#"SELECT "version", operation, metadata
+               FROM audit_log
+               WHERE audit_stream_id = $1
+               ORDER BY "version""#,
+        )
+        .bind(audit_stream_id.clone())
+        .fetch(&self.pool)
+        .map_err(StreamError::Database)
+        .and_then(move |row| {
+            ready(audit_row_to_persisted_operation(
+                audit_stream_id.clone(),
+                &row,
+            ))
+        })
+        .boxed()
+    }
+}
+
+/// Truncations and deletions are recorded to the [audit stream][StoreBuilder::audit_stream],
+/// if configured, in the same transaction, attributed to the [`TRUNCATOR_ACTOR`].
+#[async_trait]
+impl<Id, Evt, Serde> event::store::Truncator<Id> for Store<Id, Evt, Serde>
+where
+    Id: ToString + Clone + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt> + Send + Sync,
+{
+    type Error = anyhow::Error;
+
+    async fn truncate_before(&self, id: &Id, version: Version) -> Result<u64, Self::Error> {
+        let string_id = id.to_string();
+        let before_version = i32::try_from(version).unwrap_or(i32::MAX);
+
+        let mut tx = crate::begin_transaction(&self.pool, false, self.statement_timeout).await?;
+
+        let deleted = sqlx::query("DELETE FROM events WHERE event_stream_id = $1 AND version < $2")
+            .bind(&string_id)
+            .bind(before_version)
+            .execute(&mut *tx)
+            .await
+            .map_err(|err| crate::classify_error(&err, "failed to truncate the event stream"))?
+            .rows_affected();
+
+        if deleted > 0 {
+            self.record_operation(
+                &mut tx,
+                audit::Operation::StreamTruncated {
+                    stream_id: string_id,
+                    up_to: version - 1,
+                }
+                .attributed_to(TRUNCATOR_ACTOR, "event stream truncated"),
+            )
+            .await?;
+        }
 
         tx.commit()
             .await
-            .map_err(|err| anyhow!("failed to commit transaction: {}", err))?;
+            .map_err(|err| crate::classify_error(&err, "failed to commit transaction"))?;
 
-        #[allow(clippy::cast_sign_loss)]
-        Ok(new_version as Version)
+        Ok(deleted)
+    }
+
+    async fn delete_stream(&self, id: &Id) -> Result<u64, Self::Error> {
+        let string_id = id.to_string();
+
+        let mut tx = crate::begin_transaction(&self.pool, false, self.statement_timeout).await?;
+
+        let deleted = sqlx::query("DELETE FROM events WHERE event_stream_id = $1")
+            .bind(&string_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|err| crate::classify_error(&err, "failed to delete the domain events"))?
+            .rows_affected();
+
+        let stream_deleted = sqlx::query("DELETE FROM event_streams WHERE event_stream_id = $1")
+            .bind(&string_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|err| crate::classify_error(&err, "failed to delete the event stream"))?
+            .rows_affected()
+            > 0;
+
+        sqlx::query("DELETE FROM event_stream_metadata WHERE event_stream_id = $1")
+            .bind(&string_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|err| {
+                crate::classify_error(&err, "failed to delete the event stream metadata")
+            })?;
+
+        if stream_deleted {
+            self.record_operation(
+                &mut tx,
+                audit::Operation::StreamDeleted {
+                    stream_id: string_id,
+                }
+                .attributed_to(TRUNCATOR_ACTOR, "event stream deleted"),
+            )
+            .await?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|err| crate::classify_error(&err, "failed to commit transaction"))?;
+
+        Ok(deleted)
+    }
+}
+
+/// Domain Events are looked up by their [`CORRELATION_ID_KEY`] metadata
+/// through a sequential scan, unless an index on the expression
+/// `metadata->>'Correlation-Id'` has been created: meant for support tooling
+/// rather than for hot paths.
+#[async_trait]
+impl<Id, Evt, Serde> WorkflowQuery<String, Evt> for Store<Id, Evt, Serde>
+where
+    Id: ToString + Clone + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt> + Send + Sync,
+{
+    type Error = StreamError;
+
+    async fn correlated_events(
+        &self,
+        correlation_id: &str,
+    ) -> Result<Vec<event::Persisted<String, Evt>>, Self::Error> {
+        let rows = sqlx::query(
+            r"SELECT event_stream_id, version, event, metadata
+               FROM events
+               WHERE metadata->>$1 = $2",
+        )
+        .bind(CORRELATION_ID_KEY)
+        .bind(correlation_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(StreamError::Database)?;
+
+        rows.iter()
+            .map(|row| {
+                let stream_id: String = try_get_column(row, "event_stream_id")?;
+                self.event_row_to_persisted_event(stream_id, row)
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl<Id, Evt, Serde> MetadataStore<Id> for Store<Id, Evt, Serde>
+where
+    Id: ToString + Clone + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt> + Send + Sync,
+{
+    type Error = anyhow::Error;
+
+    async fn stream_metadata(&self, id: &Id) -> Result<Option<StreamMetadata>, Self::Error> {
+        let metadata: Option<sqlx::types::Json<StreamMetadata>> = sqlx::query_scalar(
+            "SELECT metadata FROM event_stream_metadata WHERE event_stream_id = $1",
+        )
+        .bind(id.to_string())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|err| crate::classify_error(&err, "failed to fetch the event stream metadata"))?;
+
+        Ok(metadata.map(|metadata| metadata.0))
+    }
+
+    async fn set_stream_metadata(
+        &self,
+        id: &Id,
+        metadata: StreamMetadata,
+    ) -> Result<(), Self::Error> {
+        sqlx::query(
+            r"INSERT INTO event_stream_metadata (event_stream_id, metadata)
+               VALUES ($1, $2)
+               ON CONFLICT (event_stream_id) DO
+               UPDATE SET metadata = $2, updated_at = NOW()",
+        )
+        .bind(id.to_string())
+        .bind(sqlx::types::Json(metadata))
+        .execute(&self.pool)
+        .await
+        .map_err(|err| crate::classify_error(&err, "failed to set the event stream metadata"))?;
+
+        Ok(())
     }
 }
@@ -0,0 +1,214 @@
+//! This module contains the [Checker] maintenance routine, which scans the
+//! `PostgreSQL` tables used by the [Store][crate::event::Store] for integrity
+//! violations, e.g. after an incident or a manual data migration.
+//!
+//! Duplicate `(event_stream_id, version)` pairs are prevented by the `events` table
+//! primary key, so the [Checker] looks for the [Violation]s that the schema cannot prevent.
+
+use std::marker::PhantomData;
+
+use eventually::message::Message;
+use eventually::serde;
+use eventually::version::Version;
+use futures::TryStreamExt;
+use sqlx::{PgPool, Row};
+
+/// An integrity violation found by the [Checker].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// Some versions are missing from an Event Stream, i.e. the Event Stream
+    /// jumps from the `previous` version to `next`, instead of `previous + 1`.
+    ///
+    /// Missing versions at the start of an Event Stream are not a violation, as
+    /// the oldest Domain Events can be deleted by truncation or retention rules.
+    VersionGap {
+        /// The id of the Event Stream.
+        event_stream_id: String,
+        /// The version of the Domain Event before the gap.
+        previous: Version,
+        /// The version of the Domain Event after the gap.
+        next: Version,
+    },
+    /// The version recorded for an Event Stream does not match the version
+    /// of its last Domain Event.
+    HeadVersionMismatch {
+        /// The id of the Event Stream.
+        event_stream_id: String,
+        /// The version recorded in the `event_streams` table.
+        recorded: Version,
+        /// The version of the last Domain Event of the Event Stream, 0 if it has none.
+        actual: Version,
+    },
+    /// A Domain Event could not be deserialized with the configured
+    /// [Deserializer][serde::Deserializer].
+    UndecodableEvent {
+        /// The id of the Event Stream.
+        event_stream_id: String,
+        /// The version of the Domain Event.
+        version: Version,
+        /// The deserialization error.
+        error: String,
+    },
+}
+
+/// The outcome of a [`Checker::run`] call.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    /// The number of Domain Events checked.
+    pub events_checked: usize,
+    /// All the [Violation]s found, including the ones that have been repaired.
+    pub violations: Vec<Violation>,
+    /// The number of [Violation]s that have been repaired.
+    pub repaired: usize,
+}
+
+/// Maintenance routine that scans the Event Store tables for integrity [Violation]s,
+/// and optionally repairs them.
+///
+/// Only [`Violation::HeadVersionMismatch`] can be repaired, by setting the Event Stream
+/// version to the version of its last Domain Event: all the other [Violation]s
+/// require an operator to decide how to restore the missing or corrupted data.
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct Checker<Evt, Serde>
+where
+    Serde: serde::Deserializer<Evt>,
+{
+    pool: PgPool,
+    serde: Serde,
+    repair: bool,
+    evt_type: PhantomData<Evt>,
+}
+
+impl<Evt, Serde> Checker<Evt, Serde>
+where
+    Evt: Message + Send + Sync,
+    Serde: serde::Deserializer<Evt>,
+{
+    /// Creates a new [Checker] that only reports the [Violation]s found,
+    /// using the same [Deserializer][serde::Deserializer] used by the
+    /// [Store][crate::event::Store] to check the Domain Events payloads.
+    pub fn new(pool: PgPool, serde: Serde) -> Self {
+        Self {
+            pool,
+            serde,
+            repair: false,
+            evt_type: PhantomData,
+        }
+    }
+
+    /// Specifies whether the [Violation]s found should be repaired, where possible.
+    /// Defaults to `false`.
+    pub fn repair(mut self, repair: bool) -> Self {
+        self.repair = repair;
+        self
+    }
+
+    /// Scans the Event Store tables and returns a [Report] of the [Violation]s found.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if any of the queries to the database fails.
+    pub async fn run(&self) -> Result<Report, sqlx::Error> {
+        let mut report = Report::default();
+
+        self.check_version_gaps(&mut report).await?;
+        self.check_head_versions(&mut report).await?;
+        self.check_payloads(&mut report).await?;
+
+        Ok(report)
+    }
+
+    async fn check_version_gaps(&self, report: &mut Report) -> Result<(), sqlx::Error> {
+        let rows = sqlx::query(
+            r"SELECT event_stream_id, previous, version
+               FROM (
+                   SELECT event_stream_id, version,
+                       LAG(version) OVER (PARTITION BY event_stream_id ORDER BY version) AS previous
+                   FROM events
+               ) e
+               WHERE previous IS NOT NULL AND version <> previous + 1
+               ORDER BY event_stream_id, version",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        for row in rows {
+            report.violations.push(Violation::VersionGap {
+                event_stream_id: row.try_get("event_stream_id")?,
+                previous: to_version(row.try_get("previous")?),
+                next: to_version(row.try_get("version")?),
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn check_head_versions(&self, report: &mut Report) -> Result<(), sqlx::Error> {
+        let rows = sqlx::query(
+            r"SELECT es.event_stream_id, es.version AS recorded, COALESCE(MAX(e.version), 0) AS actual
+               FROM event_streams es
+               LEFT JOIN events e ON e.event_stream_id = es.event_stream_id
+               GROUP BY es.event_stream_id, es.version
+               HAVING es.version <> COALESCE(MAX(e.version), 0)
+               ORDER BY es.event_stream_id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        for row in rows {
+            let event_stream_id: String = row.try_get("event_stream_id")?;
+            let actual: i32 = row.try_get("actual")?;
+
+            // The version of an Event Stream with no Domain Events cannot be repaired,
+            // as Event Stream versions must be greater than zero.
+            if self.repair && actual > 0 {
+                sqlx::query("UPDATE event_streams SET version = $2 WHERE event_stream_id = $1")
+                    .bind(&event_stream_id)
+                    .bind(actual)
+                    .execute(&self.pool)
+                    .await?;
+
+                report.repaired += 1;
+            }
+
+            report.violations.push(Violation::HeadVersionMismatch {
+                event_stream_id,
+                recorded: to_version(row.try_get("recorded")?),
+                actual: to_version(actual),
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn check_payloads(&self, report: &mut Report) -> Result<(), sqlx::Error> {
+        let mut rows = sqlx::query(
+            r"SELECT event_stream_id, version, event
+               FROM events
+               ORDER BY event_stream_id, version",
+        )
+        .fetch(&self.pool);
+
+        while let Some(row) = rows.try_next().await? {
+            let event: Vec<u8> = row.try_get("event")?;
+
+            report.events_checked += 1;
+
+            if let Err(err) = self.serde.deserialize(&event) {
+                report.violations.push(Violation::UndecodableEvent {
+                    event_stream_id: row.try_get("event_stream_id")?,
+                    version: to_version(row.try_get("version")?),
+                    error: err.to_string(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[allow(clippy::cast_sign_loss)]
+fn to_version(version: i32) -> Version {
+    version as Version
+}
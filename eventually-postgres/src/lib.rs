@@ -6,31 +6,115 @@
 
 #![deny(unsafe_code, unused_qualifications, trivial_casts)]
 #![deny(clippy::all, clippy::pedantic, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
 #![warn(missing_docs)]
 
 pub mod aggregate;
+pub mod effect;
 pub mod event;
+pub mod integrity;
+pub mod projection;
 
 pub(crate) static MIGRATIONS: sqlx::migrate::Migrator = sqlx::migrate!("./migrations");
 
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use eventually::error::Kind;
 use eventually::version::{ConflictError, Version};
-use lazy_static::lazy_static;
 use regex::Regex;
+use sqlx::{PgPool, Postgres, Transaction};
+
+/// All possible errors returned when building an [`event::Store`]
+/// or an [`aggregate::Repository`] instance.
+#[derive(Debug, thiserror::Error)]
+pub enum BuildError {
+    /// Error returned when one of the configured options has an invalid value.
+    #[error("invalid value for option '{option}': {reason}")]
+    InvalidOption {
+        /// The name of the builder option with the invalid value.
+        option: &'static str,
+        /// The reason why the value is invalid.
+        reason: &'static str,
+    },
+    /// Error returned when the migrations fail to run.
+    #[error("failed to run migrations: {0}")]
+    Migrate(#[from] sqlx::migrate::MigrateError),
+}
+
+/// Validates the `statement_timeout` builder option, which `PostgreSQL`
+/// expects as a positive number of milliseconds that fits in an `i32`.
+pub(crate) fn validate_statement_timeout(timeout: Option<Duration>) -> Result<(), BuildError> {
+    let Some(timeout) = timeout else {
+        return Ok(());
+    };
+
+    if timeout.as_millis() == 0 {
+        return Err(BuildError::InvalidOption {
+            option: "statement_timeout",
+            reason: "must be at least one millisecond",
+        });
+    }
+
+    if i32::try_from(timeout.as_millis()).is_err() {
+        return Err(BuildError::InvalidOption {
+            option: "statement_timeout",
+            reason: "must not exceed i32::MAX milliseconds",
+        });
+    }
+
+    Ok(())
+}
+
+/// Begins a new transaction, optionally with the `SERIALIZABLE DEFERRABLE` isolation level,
+/// applying the specified statement timeout to it, if any.
+pub(crate) async fn begin_transaction(
+    pool: &PgPool,
+    serializable: bool,
+    statement_timeout: Option<Duration>,
+) -> anyhow::Result<Transaction<'static, Postgres>> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|err| classify_error(&err, "failed to begin transaction"))?;
+
+    // The isolation level must be set before any other statement of the transaction.
+    if serializable {
+        sqlx::query("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE DEFERRABLE")
+            .execute(&mut *tx)
+            .await
+            .map_err(|err| classify_error(&err, "failed to begin transaction"))?;
+    }
+
+    if let Some(timeout) = statement_timeout {
+        // The setting is local to the transaction, so the pooled connection is left untouched.
+        sqlx::query("SELECT set_config('statement_timeout', $1, true)")
+            .bind(format!("{}ms", timeout.as_millis()))
+            .execute(&mut *tx)
+            .await
+            .map_err(|err| classify_error(&err, "failed to set the statement timeout"))?;
+    }
 
-lazy_static! {
-    static ref CONFLICT_ERROR_REGEX: Regex =
-        Regex::new(r"version check failed, expected: (?P<expected>\d), got: (?P<got>\d)")
-            .expect("regex compiles successfully");
+    Ok(tx)
 }
 
+/// Framework error type used to classify the errors returned by the database,
+/// which never carries a domain error.
+pub(crate) type Error = eventually::error::Error;
+
+static CONFLICT_ERROR_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"version check failed, expected: (?P<expected>\d+), got: (?P<got>\d+|<NULL>)")
+        .expect("regex compiles successfully")
+});
+
 pub(crate) fn check_for_conflict_error(err: &sqlx::Error) -> Option<ConflictError> {
     fn capture_to_version(captures: &regex::Captures, name: &'static str) -> Version {
-        let v: i32 = captures
-            .name(name)
-            .expect("field is captured")
-            .as_str()
-            .parse::<i32>()
-            .expect("field should be a valid integer");
+        let v: i32 = match captures.name(name).expect("field is captured").as_str() {
+            // The version is NULL when the row has not been found, i.e. it does not exist yet.
+            "<NULL>" => 0,
+            v => v.parse::<i32>().expect("field should be a valid integer"),
+        };
 
         #[allow(clippy::cast_sign_loss)]
         {
@@ -49,3 +133,102 @@ pub(crate) fn check_for_conflict_error(err: &sqlx::Error) -> Option<ConflictErro
 
     None
 }
+
+/// Returns the [`eventually::error::Kind`] that best describes the specified [`sqlx::Error`].
+pub(crate) fn error_kind(err: &sqlx::Error) -> Kind {
+    match err {
+        sqlx::Error::PoolTimedOut => Kind::Timeout,
+        sqlx::Error::Io(_)
+        | sqlx::Error::Tls(_)
+        | sqlx::Error::PoolClosed
+        | sqlx::Error::WorkerCrashed => Kind::Connection,
+        sqlx::Error::Decode(_) | sqlx::Error::ColumnDecode { .. } => Kind::Serialization,
+        sqlx::Error::RowNotFound => Kind::NotFound,
+        sqlx::Error::Database(db_err) => match db_err.code() {
+            // Class 08: Connection Exception.
+            Some(code) if code.starts_with("08") => Kind::Connection,
+            // query_canceled, usually returned when a statement timeout is hit.
+            Some(code) if code == "57014" => Kind::Timeout,
+            // serialization_failure and deadlock_detected: the transaction has been
+            // aborted because of a concurrent one, and can be attempted again.
+            Some(code) if code == "40001" || code == "40P01" => Kind::Conflict,
+            _ => Kind::Internal,
+        },
+        _ => Kind::Internal,
+    }
+}
+
+/// Wraps a [`sqlx::Error`] into an [`anyhow::Error`] with the specified context message,
+/// preserving its [`eventually::error::Kind`] classification.
+pub(crate) fn classify_error(err: &sqlx::Error, context: &str) -> anyhow::Error {
+    let kind = error_kind(err);
+    let err = anyhow!("{context}: {err}");
+
+    match kind {
+        Kind::Timeout => Error::Timeout(err).into(),
+        Kind::Conflict => Error::Contention(err).into(),
+        Kind::Connection => Error::Connection(err).into(),
+        Kind::Serialization => Error::Serialization(err).into(),
+        _ => err,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::error::Error as StdError;
+
+    use eventually::error::Retryable;
+    use sqlx::error::{DatabaseError, ErrorKind};
+
+    use super::*;
+
+    /// [`DatabaseError`] carrying only a SQLSTATE code, as returned by the server.
+    #[derive(Debug, thiserror::Error)]
+    #[error("database error with code {0}")]
+    struct SqlState(&'static str);
+
+    impl DatabaseError for SqlState {
+        fn message(&self) -> &str {
+            self.0
+        }
+
+        fn code(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::Borrowed(self.0))
+        }
+
+        fn as_error(&self) -> &(dyn StdError + Send + Sync + 'static) {
+            self
+        }
+
+        fn as_error_mut(&mut self) -> &mut (dyn StdError + Send + Sync + 'static) {
+            self
+        }
+
+        fn into_error(self: Box<Self>) -> Box<dyn StdError + Send + Sync + 'static> {
+            self
+        }
+
+        fn kind(&self) -> ErrorKind {
+            ErrorKind::Other
+        }
+    }
+
+    #[test]
+    fn serialization_failures_and_deadlocks_are_transient_conflicts() {
+        for code in ["40001", "40P01"] {
+            let err = sqlx::Error::Database(Box::new(SqlState(code)));
+
+            assert_eq!(Kind::Conflict, error_kind(&err), "sqlstate {code}");
+            assert!(
+                classify_error(&err, "failed to commit transaction").is_transient(),
+                "sqlstate {code}"
+            );
+        }
+
+        let err = sqlx::Error::Database(Box::new(SqlState("23505")));
+
+        assert_eq!(Kind::Internal, error_kind(&err));
+        assert!(!classify_error(&err, "failed to insert domain event").is_transient());
+    }
+}
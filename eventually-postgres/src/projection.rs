@@ -0,0 +1,274 @@
+//! This module contains a declarative [Projection] for table-backed read models,
+//! which maps Domain Events to parameterized SQL statements executed
+//! in a `PostgreSQL` database.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use eventually::event::stream::EventStreamExt;
+use eventually::message::Message;
+use eventually::{event, version};
+use futures::{TryStream, TryStreamExt};
+use sqlx::postgres::PgArguments;
+use sqlx::{PgPool, Postgres, Row};
+
+/// A parameterized SQL statement, whose arguments are bound by
+/// the functions registered with [`Projection::on`].
+pub type Query<'q> = sqlx::query::Query<'q, Postgres, PgArguments>;
+
+type Bind<Evt> = Box<dyn for<'q> Fn(Query<'q>, &Evt) -> Query<'q> + Send + Sync>;
+
+struct Statement<Evt> {
+    event_name: &'static str,
+    sql: String,
+    bind: Bind<Evt>,
+}
+
+/// All possible errors returned by [`Projection::run`].
+#[derive(Debug, thiserror::Error)]
+pub enum ProjectError<E> {
+    /// Error returned by the [Event Stream][event::Stream] being projected.
+    #[error("failed to stream domain events: {0}")]
+    Stream(#[source] E),
+    /// Error returned when the read model or the checkpoints could not be updated.
+    #[error("failed to update the read model: {0}")]
+    Database(#[source] anyhow::Error),
+}
+
+/// Projects Domain Events into table-backed read models, by executing
+/// the SQL statements registered for each Domain Event name.
+///
+/// Domain Events are applied in batches: each batch is executed in a single
+/// transaction, together with the update of the projection checkpoints, stored
+/// per Event Stream in the `projection_checkpoints` table. Domain Events at or below
+/// the checkpointed version of their Event Stream are skipped, so that the same
+/// Event Stream can be projected again safely after a failure or a restart.
+pub struct Projection<Evt> {
+    pool: PgPool,
+    name: String,
+    batch_size: usize,
+    statements: Vec<Statement<Evt>>,
+}
+
+impl<Evt> Debug for Projection<Evt> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Projection")
+            .field("name", &self.name)
+            .field("batch_size", &self.batch_size)
+            .field(
+                "statements",
+                &self
+                    .statements
+                    .iter()
+                    .map(|statement| (statement.event_name, &statement.sql))
+                    .collect::<Vec<_>>(),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+impl<Evt> Projection<Evt>
+where
+    Evt: Message + Send + Sync,
+{
+    /// Default number of Domain Events applied in a single transaction.
+    pub const DEFAULT_BATCH_SIZE: usize = 100;
+
+    /// Runs the latest migrations necessary for the implementation to work,
+    /// then returns a new [Projection] with the specified name and no statements.
+    ///
+    /// The name identifies the projection checkpoints, so it must be unique
+    /// across the projections sharing the same database.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the migrations fail to run.
+    pub async fn new(
+        pool: PgPool,
+        name: impl Into<String>,
+    ) -> Result<Self, sqlx::migrate::MigrateError> {
+        // Make sure the latest migrations are used before using the Projection instance.
+        crate::MIGRATIONS.run(&pool).await?;
+
+        Ok(Self {
+            pool,
+            name: name.into(),
+            batch_size: Self::DEFAULT_BATCH_SIZE,
+            statements: Vec::new(),
+        })
+    }
+
+    /// Sets the maximum number of Domain Events applied in a single transaction.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if `batch_size` is zero.
+    #[must_use]
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        assert!(batch_size > 0, "batch size must be greater than zero");
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Registers a SQL statement to execute for each Domain Event with the specified name,
+    /// using the `bind` function to bind the statement arguments from the Domain Event.
+    ///
+    /// More than one statement can be registered for the same Domain Event name:
+    /// they are executed in the order they have been registered.
+    #[must_use]
+    pub fn on<F>(mut self, event_name: &'static str, sql: impl Into<String>, bind: F) -> Self
+    where
+        F: for<'q> Fn(Query<'q>, &Evt) -> Query<'q> + Send + Sync + 'static,
+    {
+        self.statements.push(Statement {
+            event_name,
+            sql: sql.into(),
+            bind: Box::new(bind),
+        });
+        self
+    }
+
+    /// Returns the last version of the specified Event Stream applied by the [Projection],
+    /// or `None` if no Domain Event of the Event Stream has been applied yet.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the checkpoint could not be fetched.
+    pub async fn checkpoint(
+        &self,
+        event_stream_id: &str,
+    ) -> Result<Option<version::Version>, anyhow::Error> {
+        let version: Option<i32> = sqlx::query(
+            r#"SELECT "version" FROM projection_checkpoints
+               WHERE projection = $1 AND event_stream_id = $2"#,
+        )
+        .bind(&self.name)
+        .bind(event_stream_id)
+        .fetch_optional(&self.pool)
+        .await
+        .and_then(|row| row.map(|row| row.try_get(0)).transpose())
+        .map_err(|err| crate::classify_error(&err, "failed to fetch the projection checkpoint"))?;
+
+        #[allow(clippy::cast_sign_loss)]
+        Ok(version.map(|v| v as version::Version))
+    }
+
+    /// Applies all the Domain Events in the specified stream to the read model,
+    /// returning the number of Domain Events applied.
+    ///
+    /// Domain Events with no registered statements are only checkpointed.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the stream fails, or if a batch could not be applied,
+    /// in which case the whole batch is rolled back.
+    pub async fn run<S, Id, Err>(&self, stream: S) -> Result<usize, ProjectError<Err>>
+    where
+        S: TryStream<Ok = event::Persisted<Id, Evt>, Error = Err> + Send,
+        Id: ToString + Send,
+        Err: Send,
+    {
+        let mut batches = stream.batched(self.batch_size);
+        let mut applied = 0;
+
+        while let Some(batch) = batches.try_next().await.map_err(ProjectError::Stream)? {
+            applied += self
+                .apply_batch(batch)
+                .await
+                .map_err(ProjectError::Database)?;
+        }
+
+        Ok(applied)
+    }
+
+    async fn apply_batch<Id>(
+        &self,
+        batch: Vec<event::Persisted<Id, Evt>>,
+    ) -> Result<usize, anyhow::Error>
+    where
+        Id: ToString,
+    {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|err| crate::classify_error(&err, "failed to begin transaction"))?;
+
+        let stream_ids: Vec<String> = batch.iter().map(|e| e.stream_id.to_string()).collect();
+
+        // Lock the checkpoints of the Event Streams in the batch, so that concurrent runs
+        // of the same projection do not apply the same Domain Events twice.
+        let mut checkpoints: HashMap<String, i32> = sqlx::query(
+            r#"SELECT event_stream_id, "version" FROM projection_checkpoints
+               WHERE projection = $1 AND event_stream_id = ANY($2)
+               FOR UPDATE"#,
+        )
+        .bind(&self.name)
+        .bind(&stream_ids)
+        .fetch_all(&mut *tx)
+        .await
+        .and_then(|rows| {
+            rows.iter()
+                .map(|row| Ok((row.try_get(0)?, row.try_get(1)?)))
+                .collect()
+        })
+        .map_err(|err| crate::classify_error(&err, "failed to fetch the projection checkpoints"))?;
+
+        let mut applied = 0;
+
+        for (stream_id, persisted) in stream_ids.into_iter().zip(batch) {
+            #[allow(clippy::cast_possible_truncation)]
+            let version = persisted.version as i32;
+            let checkpoint = checkpoints.entry(stream_id).or_default();
+
+            if version <= *checkpoint {
+                continue;
+            }
+
+            let message = &persisted.event.message;
+
+            for statement in &self.statements {
+                if statement.event_name != message.name() {
+                    continue;
+                }
+
+                (statement.bind)(sqlx::query(&statement.sql), message)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|err| {
+                        crate::classify_error(
+                            &err,
+                            &format!("failed to apply '{}' domain event", statement.event_name),
+                        )
+                    })?;
+            }
+
+            *checkpoint = version;
+            applied += 1;
+        }
+
+        for (stream_id, version) in checkpoints.into_iter().filter(|(_, v)| *v > 0) {
+            sqlx::query(
+                r#"INSERT INTO projection_checkpoints (projection, event_stream_id, "version")
+                   VALUES ($1, $2, $3)
+                   ON CONFLICT (projection, event_stream_id) DO
+                   UPDATE SET "version" = $3, updated_at = NOW()
+                   WHERE projection_checkpoints."version" < $3"#,
+            )
+            .bind(&self.name)
+            .bind(stream_id)
+            .bind(version)
+            .execute(&mut *tx)
+            .await
+            .map_err(|err| {
+                crate::classify_error(&err, "failed to update the projection checkpoint")
+            })?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|err| crate::classify_error(&err, "failed to commit transaction"))?;
+
+        Ok(applied)
+    }
+}
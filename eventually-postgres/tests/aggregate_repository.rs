@@ -1,6 +1,11 @@
-use eventually::aggregate::repository::{self, GetError, Getter, Saver};
+use eventually::aggregate::repository::{GetError, Getter, Saver};
+use eventually::aggregate::stream_name;
+use eventually::error::Kind;
+use eventually::event::store::Streamer;
+use eventually::event::VersionSelect;
 use eventually::serde;
-use eventually_postgres::aggregate;
+use eventually_postgres::{aggregate, event};
+use futures::TryStreamExt;
 use rand::Rng;
 
 mod setup;
@@ -85,12 +90,167 @@ async fn it_detects_data_races_and_returns_conflict_error() {
         aggregate_repository.save(&mut cloned_root),
     );
 
+    // A serialization failure is reported as transient contention rather than
+    // as a version conflict, since the actual version of the Aggregate Root is not known.
     match result {
-        (Ok(()), Err(repository::SaveError::Conflict(_))) => (),
-        (Err(repository::SaveError::Conflict(_)), Ok(())) => (),
+        (Ok(()), Err(err)) | (Err(err), Ok(())) if err.kind() == Kind::Conflict => (),
         (first, second) => panic!(
             "invalid state detected, first: {:?}, second: {:?}",
             first, second
         ),
     };
 }
+
+#[tokio::test]
+async fn it_names_event_streams_using_the_configured_strategy() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let aggregate_repository = aggregate::Repository::builder(
+        pool.clone(),
+        serde::Json::<setup::TestAggregate>::default(),
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .stream_name_strategy(stream_name::Categorized::default().with_prefix("tenant"))
+    .build()
+    .await
+    .unwrap();
+
+    let event_store = event::Store::new(pool, serde::Json::<setup::TestDomainEvent>::default())
+        .await
+        .unwrap();
+
+    let aggregate_id = setup::TestAggregateId(rand::thread_rng().gen::<i64>());
+
+    let mut root = setup::TestAggregateRoot::create(aggregate_id, "John Dee".to_owned())
+        .expect("aggregate root should be created");
+
+    aggregate_repository
+        .save(&mut root)
+        .await
+        .expect("storing the new aggregate root should be successful");
+
+    let found_root = aggregate_repository
+        .get(&aggregate_id)
+        .await
+        .map(setup::TestAggregateRoot::from)
+        .expect("the aggregate root should be found successfully");
+
+    assert_eq!(found_root, root);
+
+    let events = event_store
+        .stream(
+            &format!("tenant-TestAggregate-{aggregate_id}"),
+            VersionSelect::All,
+        )
+        .try_collect::<Vec<_>>()
+        .await
+        .expect("the event store should stream the events back");
+
+    assert_eq!(1, events.len());
+}
+
+#[tokio::test]
+async fn it_only_stores_the_aggregate_state_when_events_are_disabled() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let aggregate_repository = aggregate::Repository::builder(
+        pool.clone(),
+        serde::Json::<setup::TestAggregate>::default(),
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .store_events(false)
+    .build()
+    .await
+    .unwrap();
+
+    let event_store = event::Store::new(pool, serde::Json::<setup::TestDomainEvent>::default())
+        .await
+        .unwrap();
+
+    let aggregate_id = setup::TestAggregateId(rand::thread_rng().gen::<i64>());
+
+    let mut root = setup::TestAggregateRoot::create(aggregate_id, "John Dee".to_owned())
+        .expect("aggregate root should be created");
+
+    root.delete().unwrap();
+
+    aggregate_repository
+        .save(&mut root)
+        .await
+        .expect("storing the new aggregate root should be successful");
+
+    let found_root = aggregate_repository
+        .get(&aggregate_id)
+        .await
+        .map(setup::TestAggregateRoot::from)
+        .expect("the aggregate root should be found successfully");
+
+    assert_eq!(found_root, root);
+
+    let events = event_store
+        .stream(&aggregate_id.to_string(), VersionSelect::All)
+        .try_collect::<Vec<_>>()
+        .await
+        .expect("the event store should stream the events back");
+
+    assert!(events.is_empty());
+}
+
+#[tokio::test]
+async fn it_treats_aggregate_roots_with_a_stream_tombstone_as_not_found_until_resurrected() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let aggregate_repository = aggregate::Repository::builder(
+        pool,
+        serde::Json::<setup::TestAggregate>::default(),
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .build()
+    .await
+    .unwrap();
+
+    let aggregate_id = setup::TestAggregateId(rand::thread_rng().gen::<i64>());
+
+    let mut root = setup::TestAggregateRoot::create(aggregate_id, "John Dee".to_owned())
+        .expect("aggregate root should be created");
+
+    root.tombstone();
+
+    aggregate_repository
+        .save(&mut root)
+        .await
+        .expect("storing the tombstoned aggregate root should be successful");
+
+    assert!(matches!(
+        aggregate_repository.get(&aggregate_id).await,
+        Err(GetError::NotFound)
+    ));
+
+    let mut root = aggregate_repository
+        .get_including_tombstoned(&aggregate_id)
+        .await
+        .expect("the tombstoned aggregate root should be found");
+
+    assert!(root.is_tombstoned());
+
+    root.resurrect(setup::TestDomainEvent::StreamResurrected.into())
+        .expect("the aggregate root should be resurrected");
+
+    aggregate_repository
+        .save(&mut root)
+        .await
+        .expect("storing the resurrected aggregate root should be successful");
+
+    let found_root = aggregate_repository
+        .get(&aggregate_id)
+        .await
+        .expect("the resurrected aggregate root should be found");
+
+    assert_eq!(3, found_root.version());
+}
@@ -0,0 +1,116 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use eventually::event::effect::{EffectError, Effects, Journal as _, Outcome, Status};
+use eventually_postgres::effect::Journal;
+use rand::Rng;
+
+mod setup;
+
+#[tokio::test]
+async fn it_records_side_effects_across_restarts() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let journal = Journal::new(pool.clone())
+        .await
+        .expect("the journal should be created");
+
+    let key = format!("test-effect-{}", rand::thread_rng().gen::<i64>());
+
+    let outcome = Effects::new(journal.clone())
+        .run(&key, || async { Ok::<_, std::io::Error>(42) })
+        .await
+        .expect("the side effect should be executed");
+
+    assert_eq!(Outcome::Executed(42), outcome);
+
+    // Simulate a restart of the process, using a fresh journal instance.
+    let journal = Journal::new(pool)
+        .await
+        .expect("the journal should be created");
+
+    assert_eq!(Some(Status::Completed), journal.status(&key).await.unwrap());
+
+    let outcome = Effects::new(journal.clone())
+        .run(&key, || async { Ok::<_, std::io::Error>(42) })
+        .await
+        .expect("the side effect should be skipped");
+
+    assert_eq!(Outcome::Skipped, outcome);
+
+    let interrupted_key = format!("{key}-interrupted");
+    journal.record_intent(&interrupted_key, None).await.unwrap();
+
+    let result = Effects::new(journal)
+        .run(&interrupted_key, || async { Ok::<_, std::io::Error>(42) })
+        .await;
+
+    assert!(matches!(result, Err(EffectError::InDoubt(key)) if key == interrupted_key));
+}
+
+#[tokio::test]
+async fn it_executes_side_effects_only_once_across_concurrent_runs() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let journal = Journal::new(pool)
+        .await
+        .expect("the journal should be created");
+
+    let key = format!("test-effect-{}", rand::thread_rng().gen::<i64>());
+    let executions = AtomicUsize::new(0);
+
+    let effect = || async {
+        executions.fetch_add(1, Ordering::SeqCst);
+        Ok::<_, std::io::Error>(42)
+    };
+
+    let results = futures::future::join_all(
+        (0..8).map(|_| async { Effects::new(journal.clone()).run(&key, effect).await }),
+    )
+    .await;
+
+    assert_eq!(1, executions.load(Ordering::SeqCst));
+    assert_eq!(
+        1,
+        results
+            .iter()
+            .filter(|result| matches!(result, Ok(Outcome::Executed(42))))
+            .count()
+    );
+
+    // A completed side effect is never claimed again.
+    assert!(!journal
+        .record_intent(&key, Some(Duration::ZERO))
+        .await
+        .unwrap());
+    assert_eq!(Some(Status::Completed), journal.status(&key).await.unwrap());
+}
+
+#[tokio::test]
+async fn it_takes_over_pending_side_effects_only_once_their_claim_has_expired() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let journal = Journal::new(pool)
+        .await
+        .expect("the journal should be created");
+
+    let key = format!("test-effect-{}", rand::thread_rng().gen::<i64>());
+    let lease = Duration::from_millis(200);
+
+    assert!(journal.record_intent(&key, None).await.unwrap());
+    assert!(!journal.record_intent(&key, Some(lease)).await.unwrap());
+
+    tokio::time::sleep(lease).await;
+
+    assert!(journal.record_intent(&key, Some(lease)).await.unwrap());
+
+    // The takeover renews the claim.
+    assert!(!journal.record_intent(&key, Some(lease)).await.unwrap());
+    assert_eq!(Some(Status::Pending), journal.status(&key).await.unwrap());
+}
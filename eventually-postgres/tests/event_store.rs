@@ -1,7 +1,15 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use eventually::event::store::{self, AppendError, Appender, Streamer};
-use eventually::event::{Persisted, VersionSelect};
+use eventually::aggregate::timeline::{ACTOR_KEY, CORRELATION_ID_KEY};
+use eventually::correlation::{WorkflowQuery, CAUSATION_ID_KEY};
+use eventually::error::Kind;
+use eventually::event::metadata::{MetadataStore, StreamMetadata};
+use eventually::event::store::{
+    AppendError, Appender, MultiAppender, StreamWrite, Streamer, Truncator,
+};
+use eventually::event::{audit, Persisted, VersionSelect};
+use eventually::message::CAUSED_BY_KEY;
 use eventually::version::Version;
 use eventually::{serde, version};
 use eventually_postgres::event;
@@ -105,7 +113,7 @@ async fn it_works_with_version_check_for_conflict() {
     let new_event_stream_version = event_store
         .append(
             event_stream_id.clone(),
-            version::Check::MustBe(0),
+            version::Check::must_be(0),
             expected_events,
         )
         .await
@@ -124,7 +132,7 @@ async fn it_works_with_version_check_for_conflict() {
     // Appending twice the with an unexpected Event Stream version should
     // result in a version::ConflictError.
     let error = event_store
-        .append(event_stream_id.clone(), version::Check::MustBe(0), vec![])
+        .append(event_stream_id.clone(), version::Check::must_be(0), vec![])
         .await
         .expect_err("the event store should have returned a conflict error");
 
@@ -167,19 +175,20 @@ async fn it_handles_concurrent_writes_to_the_same_stream() {
     let result = futures::join!(
         event_store.append(
             event_stream_id.clone(),
-            version::Check::MustBe(0),
+            version::Check::must_be(0),
             expected_events.clone(),
         ),
         event_store.append(
             event_stream_id.clone(),
-            version::Check::MustBe(0),
+            version::Check::must_be(0),
             expected_events,
         )
     );
 
+    // A serialization failure is reported as transient contention rather than
+    // as a version conflict, since the actual version of the stream is not known.
     match result {
-        (Ok(_), Err(store::AppendError::Conflict(_)))
-        | (Err(store::AppendError::Conflict(_)), Ok(_)) => {
+        (Ok(_), Err(err)) | (Err(err), Ok(_)) if err.kind() == Kind::Conflict => {
             // This is the expected scenario :)
         },
         (first, second) => panic!(
@@ -188,3 +197,794 @@ async fn it_handles_concurrent_writes_to_the_same_stream() {
         ),
     };
 }
+
+#[tokio::test]
+async fn it_returns_the_conflicting_events_on_version_conflict() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = event::Store::new(pool, serde::Json::<setup::TestDomainEvent>::default())
+        .await
+        .unwrap();
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{}", id);
+
+    // Appending to an Event Stream that does not exist yet with a non-zero
+    // expected version should report zero as the actual version.
+    let error = event_store
+        .append(event_stream_id.clone(), version::Check::must_be(3), vec![])
+        .await
+        .expect_err("the event store should have returned a conflict error");
+
+    match error {
+        AppendError::Conflict(err) => assert_eq!(
+            err,
+            version::ConflictError {
+                expected: 3,
+                actual: 0,
+            }
+        ),
+        error => panic!("unexpected error received: {}", error),
+    }
+
+    let expected_events: Vec<_> = (0..12)
+        .map(|i| {
+            setup::TestDomainEvent::WasCreated {
+                id: setup::TestAggregateId(id),
+                name: format!("test something {}", i),
+                at: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap()
+                    .as_millis(),
+            }
+            .into()
+        })
+        .collect();
+
+    event_store
+        .append(
+            event_stream_id.clone(),
+            version::Check::must_be(0),
+            expected_events.clone(),
+        )
+        .await
+        .expect("the event store should append the events");
+
+    let error = event_store
+        .append(event_stream_id.clone(), version::Check::must_be(10), vec![])
+        .await
+        .expect_err("the event store should have returned a conflict error");
+
+    let AppendError::Conflict(conflict) = error else {
+        panic!("unexpected error received: {}", error);
+    };
+
+    assert_eq!(
+        conflict,
+        version::ConflictError {
+            expected: 10,
+            actual: 12,
+        }
+    );
+
+    let conflicting_events = event_store
+        .stream_conflicting(&event_stream_id, conflict)
+        .try_collect::<Vec<_>>()
+        .await
+        .expect("the event store should stream the conflicting events back");
+
+    let expected_conflicting_events: Vec<_> = expected_events
+        .into_iter()
+        .enumerate()
+        .skip(10)
+        .map(|(i, event)| Persisted {
+            event,
+            stream_id: event_stream_id.clone(),
+            version: (i + 1) as Version,
+        })
+        .collect();
+
+    assert_eq!(conflicting_events, expected_conflicting_events);
+}
+
+#[tokio::test]
+async fn it_can_be_built_without_running_migrations() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    // Makes sure the migrations have been applied at least once.
+    event::Store::<String, setup::TestDomainEvent, _>::new(
+        pool.clone(),
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .await
+    .unwrap();
+
+    let event_store = event::Store::builder(pool, serde::Json::<setup::TestDomainEvent>::default())
+        .run_migrations(false)
+        .build()
+        .await
+        .unwrap();
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{}", id);
+
+    let new_event_stream_version = event_store
+        .append(
+            event_stream_id,
+            version::Check::must_be(0),
+            vec![setup::TestDomainEvent::WasDeleted {
+                id: setup::TestAggregateId(id),
+            }
+            .into()],
+        )
+        .await
+        .expect("the event store should append the events");
+
+    assert_eq!(1, new_event_stream_version);
+}
+
+#[tokio::test]
+async fn it_persists_the_domain_events_metadata() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = event::Store::new(pool, serde::Json::<setup::TestDomainEvent>::default())
+        .await
+        .unwrap();
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{}", id);
+
+    let domain_event = eventually::event::Envelope::from(setup::TestDomainEvent::WasDeleted {
+        id: setup::TestAggregateId(id),
+    })
+    .with_metadata_entries([("Correlation-Id", "correlation-1"), ("Tenant", "tenant-1")]);
+
+    event_store
+        .append(
+            event_stream_id.clone(),
+            version::Check::must_be(0),
+            vec![domain_event],
+        )
+        .await
+        .expect("the event store should append the events");
+
+    let persisted_events = event_store
+        .stream(&event_stream_id, VersionSelect::All)
+        .try_collect::<Vec<_>>()
+        .await
+        .expect("the event store should stream the events back");
+
+    let metadata = &persisted_events[0].event.metadata;
+
+    assert_eq!("correlation-1", metadata["Correlation-Id"]);
+    assert_eq!("tenant-1", metadata["Tenant"]);
+    assert_eq!("1", metadata[event::RECORDED_WITH_NEW_VERSION_KEY]);
+    assert!(metadata.contains_key(event::RECORDED_AT_KEY));
+}
+
+#[tokio::test]
+async fn it_returns_the_head_version_of_an_event_stream() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = event::Store::new(pool, serde::Json::<setup::TestDomainEvent>::default())
+        .await
+        .unwrap();
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{}", id);
+
+    assert!(!event_store.stream_exists(&event_stream_id).await.unwrap());
+
+    event_store
+        .append(
+            event_stream_id.clone(),
+            version::Check::must_be(0),
+            vec![
+                setup::TestDomainEvent::WasCreated {
+                    id: setup::TestAggregateId(id),
+                    name: "test something".to_owned(),
+                    at: 0,
+                }
+                .into(),
+                setup::TestDomainEvent::WasDeleted {
+                    id: setup::TestAggregateId(id),
+                }
+                .into(),
+            ],
+        )
+        .await
+        .expect("the event store should append the events");
+
+    assert_eq!(
+        Some(2),
+        event_store.head_version(&event_stream_id).await.unwrap()
+    );
+    assert!(event_store.stream_exists(&event_stream_id).await.unwrap());
+}
+
+#[tokio::test]
+async fn it_stores_stream_metadata_and_applies_retention() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = event::Store::new(pool, serde::Json::<setup::TestDomainEvent>::default())
+        .await
+        .unwrap();
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{}", id);
+
+    assert_eq!(
+        None,
+        event_store.stream_metadata(&event_stream_id).await.unwrap()
+    );
+
+    let metadata = StreamMetadata {
+        max_age: None,
+        max_count: Some(2),
+        properties: HashMap::from([("Owner".to_owned(), "billing".to_owned())]),
+    };
+
+    event_store
+        .set_stream_metadata(&event_stream_id, metadata.clone())
+        .await
+        .expect("the stream metadata should be set");
+
+    assert_eq!(
+        Some(metadata),
+        event_store.stream_metadata(&event_stream_id).await.unwrap()
+    );
+
+    event_store
+        .append(
+            event_stream_id.clone(),
+            version::Check::must_be(0),
+            (0..3)
+                .map(|i| {
+                    setup::TestDomainEvent::WasCreated {
+                        id: setup::TestAggregateId(id),
+                        name: format!("test something {i}"),
+                        at: 0,
+                    }
+                    .into()
+                })
+                .collect(),
+        )
+        .await
+        .expect("the event store should append the events");
+
+    assert_eq!(
+        1,
+        event_store
+            .apply_retention(&event_stream_id)
+            .await
+            .expect("the retention rules should be applied")
+    );
+
+    let versions: Vec<_> = event_store
+        .stream(&event_stream_id, VersionSelect::All)
+        .map_ok(|evt| evt.version)
+        .try_collect()
+        .await
+        .expect("the event store should stream the events back");
+
+    assert_eq!(vec![2, 3], versions);
+    assert_eq!(
+        Some(3),
+        event_store.head_version(&event_stream_id).await.unwrap()
+    );
+
+    event_store
+        .set_stream_metadata(
+            &event_stream_id,
+            StreamMetadata {
+                max_age: Some(Duration::ZERO),
+                ..StreamMetadata::default()
+            },
+        )
+        .await
+        .expect("the stream metadata should be updated");
+
+    assert_eq!(
+        2,
+        event_store
+            .apply_retention(&event_stream_id)
+            .await
+            .expect("the retention rules should be applied")
+    );
+}
+
+#[tokio::test]
+async fn it_records_retention_purges_in_the_audit_stream() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{}", id);
+    let audit_stream_id = format!("test-audit-stream-{}", id);
+
+    let event_store = event::Store::builder(
+        pool.clone(),
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .audit_stream(audit_stream_id.clone())
+    .build()
+    .await
+    .unwrap();
+
+    event_store
+        .set_stream_metadata(
+            &event_stream_id,
+            StreamMetadata {
+                max_count: Some(1),
+                ..StreamMetadata::default()
+            },
+        )
+        .await
+        .expect("the stream metadata should be set");
+
+    event_store
+        .append(
+            event_stream_id.clone(),
+            version::Check::must_be(0),
+            (0..3)
+                .map(|i| {
+                    setup::TestDomainEvent::WasCreated {
+                        id: setup::TestAggregateId(id),
+                        name: format!("test something {i}"),
+                        at: 0,
+                    }
+                    .into()
+                })
+                .collect(),
+        )
+        .await
+        .expect("the event store should append the events");
+
+    assert_eq!(
+        2,
+        event_store.apply_retention(&event_stream_id).await.unwrap()
+    );
+    // Nothing left to purge, nothing to record.
+    assert_eq!(
+        0,
+        event_store.apply_retention(&event_stream_id).await.unwrap()
+    );
+
+    let records: Vec<_> = event_store
+        .audit_log()
+        .try_collect()
+        .await
+        .expect("the audit stream should be streamed back");
+
+    assert_eq!(1, records.len());
+    assert_eq!(
+        audit::Operation::RetentionPurged {
+            stream_id: event_stream_id,
+            deleted: 2,
+        },
+        records[0].event.message
+    );
+    assert_eq!(event::RETENTION_ACTOR, records[0].event.metadata[ACTOR_KEY]);
+    assert!(records[0].event.metadata.contains_key(audit::REASON_KEY));
+}
+
+#[tokio::test]
+async fn it_truncates_and_deletes_event_streams() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{}", id);
+    let audit_stream_id = format!("test-audit-stream-{}", id);
+
+    let event_store = event::Store::builder(
+        pool.clone(),
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .audit_stream(audit_stream_id.clone())
+    .build()
+    .await
+    .unwrap();
+
+    event_store
+        .append(
+            event_stream_id.clone(),
+            version::Check::must_be(0),
+            (0..3)
+                .map(|i| {
+                    setup::TestDomainEvent::WasCreated {
+                        id: setup::TestAggregateId(id),
+                        name: format!("test something {i}"),
+                        at: 0,
+                    }
+                    .into()
+                })
+                .collect(),
+        )
+        .await
+        .expect("the event store should append the events");
+
+    assert_eq!(
+        2,
+        event_store
+            .truncate_before(&event_stream_id, 3)
+            .await
+            .expect("the event stream should be truncated")
+    );
+
+    let versions: Vec<_> = event_store
+        .stream(&event_stream_id, VersionSelect::All)
+        .map_ok(|evt| evt.version)
+        .try_collect()
+        .await
+        .expect("the event store should stream the events back");
+
+    assert_eq!(vec![3], versions);
+    assert_eq!(
+        Some(3),
+        event_store.head_version(&event_stream_id).await.unwrap()
+    );
+
+    assert_eq!(
+        1,
+        event_store
+            .delete_stream(&event_stream_id)
+            .await
+            .expect("the event stream should be deleted")
+    );
+    assert_eq!(
+        None,
+        event_store.head_version(&event_stream_id).await.unwrap()
+    );
+
+    let operations: Vec<_> = event_store
+        .audit_log()
+        .map_ok(|evt| evt.event.message)
+        .try_collect()
+        .await
+        .expect("the audit stream should be streamed back");
+
+    assert_eq!(
+        vec![
+            audit::Operation::StreamTruncated {
+                stream_id: event_stream_id.clone(),
+                up_to: 2,
+            },
+            audit::Operation::StreamDeleted {
+                stream_id: event_stream_id,
+            },
+        ],
+        operations
+    );
+}
+
+#[tokio::test]
+async fn it_reads_event_streams_in_pages() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = event::Store::new(pool, serde::Json::<setup::TestDomainEvent>::default())
+        .await
+        .unwrap();
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{}", id);
+
+    event_store
+        .append(
+            event_stream_id.clone(),
+            version::Check::must_be(0),
+            (0..5)
+                .map(|i| {
+                    setup::TestDomainEvent::WasCreated {
+                        id: setup::TestAggregateId(id),
+                        name: format!("test something {i}"),
+                        at: 0,
+                    }
+                    .into()
+                })
+                .collect(),
+        )
+        .await
+        .expect("the event store should append the events");
+
+    for batch_size in [1, 2, 5, 10] {
+        let versions: Vec<_> = event_store
+            .read_from(&event_stream_id, 2, batch_size)
+            .map_ok(|evt| evt.version)
+            .try_collect()
+            .await
+            .expect("the event store should read the events back");
+
+        assert_eq!(vec![2, 3, 4, 5], versions);
+    }
+}
+
+#[tokio::test]
+async fn it_appends_to_multiple_event_streams_atomically() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = event::Store::new(pool, serde::Json::<setup::TestDomainEvent>::default())
+        .await
+        .unwrap();
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{}", id);
+    let reservation_stream_id = format!("test-reservation-stream-{}", id);
+
+    let new_event = || -> eventually::event::Envelope<setup::TestDomainEvent> {
+        setup::TestDomainEvent::WasCreated {
+            id: setup::TestAggregateId(id),
+            name: "test something".to_owned(),
+            at: 0,
+        }
+        .into()
+    };
+
+    let new_versions = event_store
+        .append_all(vec![
+            StreamWrite {
+                id: event_stream_id.clone(),
+                version_check: version::Check::empty(),
+                events: vec![new_event(), new_event()],
+            },
+            StreamWrite {
+                id: reservation_stream_id.clone(),
+                version_check: version::Check::empty(),
+                events: vec![new_event()],
+            },
+        ])
+        .await
+        .expect("the event store should append the events");
+
+    assert_eq!(vec![2, 1], new_versions);
+
+    let err = event_store
+        .append_all(vec![
+            StreamWrite {
+                id: event_stream_id.clone(),
+                version_check: version::Check::must_be(2),
+                events: vec![new_event()],
+            },
+            StreamWrite {
+                id: reservation_stream_id,
+                version_check: version::Check::empty(),
+                events: vec![new_event()],
+            },
+        ])
+        .await
+        .expect_err("the reservation stream already exists");
+
+    assert!(matches!(err, AppendError::Conflict(_)));
+
+    // The whole transaction has been rolled back.
+    assert_eq!(
+        Some(2),
+        event_store.head_version(&event_stream_id).await.unwrap()
+    );
+}
+
+#[tokio::test]
+async fn it_returns_the_workflow_of_a_correlation_id() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = event::Store::new(pool, serde::Json::<setup::TestDomainEvent>::default())
+        .await
+        .unwrap();
+
+    let id = rand::thread_rng().gen::<i64>();
+    let correlation_id = format!("test-request-{}", id);
+
+    for (i, command) in ["CreateTest", "NotifyTest"].into_iter().enumerate() {
+        let event = eventually::event::Envelope::from(setup::TestDomainEvent::WasCreated {
+            id: setup::TestAggregateId(id),
+            name: format!("test something {i}"),
+            at: 0,
+        })
+        .with_metadata(CORRELATION_ID_KEY.to_owned(), correlation_id.clone())
+        .with_metadata(CAUSED_BY_KEY.to_owned(), command.to_owned())
+        .with_metadata(CAUSATION_ID_KEY.to_owned(), format!("command-{i}"));
+
+        event_store
+            .append(
+                format!("test-event-stream-{}-{}", id, i),
+                version::Check::empty(),
+                vec![event],
+            )
+            .await
+            .expect("the event store should append the events");
+    }
+
+    let workflow = event_store
+        .workflow(&correlation_id)
+        .await
+        .expect("the workflow should be returned");
+
+    let steps: Vec<_> = workflow
+        .steps
+        .iter()
+        .map(|step| {
+            (
+                step.name.clone(),
+                step.events
+                    .iter()
+                    .map(|evt| evt.stream_id.clone())
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        vec![
+            (
+                Some("CreateTest".to_owned()),
+                vec![format!("test-event-stream-{}-0", id)]
+            ),
+            (
+                Some("NotifyTest".to_owned()),
+                vec![format!("test-event-stream-{}-1", id)]
+            ),
+        ],
+        steps
+    );
+}
+
+#[tokio::test]
+async fn build_rejects_invalid_options() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let zero_page_size = event::Store::<String, setup::TestDomainEvent, _>::builder(
+        pool.clone(),
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .read_page_size(0)
+    .build()
+    .await;
+
+    assert!(matches!(
+        zero_page_size,
+        Err(eventually_postgres::BuildError::InvalidOption {
+            option: "read_page_size",
+            ..
+        })
+    ));
+
+    for timeout in [Duration::ZERO, Duration::from_micros(500), Duration::MAX] {
+        let result = event::Store::<String, setup::TestDomainEvent, _>::builder(
+            pool.clone(),
+            serde::Json::<setup::TestDomainEvent>::default(),
+        )
+        .statement_timeout(timeout)
+        .build()
+        .await;
+
+        assert!(
+            matches!(
+                result,
+                Err(eventually_postgres::BuildError::InvalidOption {
+                    option: "statement_timeout",
+                    ..
+                })
+            ),
+            "timeout {timeout:?} should be rejected"
+        );
+    }
+}
+
+#[tokio::test]
+async fn stream_reads_the_event_stream_in_pages_when_configured() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = event::Store::builder(pool, serde::Json::<setup::TestDomainEvent>::default())
+        .serde(serde::Json::<setup::TestDomainEvent>::default())
+        .statement_timeout(Duration::from_secs(5))
+        .read_page_size(2)
+        .build()
+        .await
+        .unwrap();
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{}", id);
+
+    let events: Vec<_> = (0..5)
+        .map(|i| {
+            setup::TestDomainEvent::WasCreated {
+                id: setup::TestAggregateId(id),
+                name: format!("test something {i}"),
+                at: 0,
+            }
+            .into()
+        })
+        .collect();
+
+    event_store
+        .append(event_stream_id.clone(), version::Check::empty(), events)
+        .await
+        .expect("the event store should append the events");
+
+    let all_versions: Vec<Version> = event_store
+        .stream(&event_stream_id, VersionSelect::All)
+        .map_ok(|persisted| persisted.version)
+        .try_collect()
+        .await
+        .expect("the event store should stream the events back");
+
+    assert_eq!(vec![1, 2, 3, 4, 5], all_versions);
+
+    let versions_from_three: Vec<Version> = event_store
+        .stream(&event_stream_id, VersionSelect::From(3))
+        .map_ok(|persisted| persisted.version)
+        .try_collect()
+        .await
+        .expect("the event store should stream the events back");
+
+    assert_eq!(vec![3, 4, 5], versions_from_three);
+}
+
+#[tokio::test]
+async fn append_is_aborted_when_the_statement_timeout_is_exceeded() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = event::Store::builder(
+        pool.clone(),
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .statement_timeout(Duration::from_millis(100))
+    .build()
+    .await
+    .unwrap();
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{}", id);
+    let event = || {
+        vec![setup::TestDomainEvent::WasDeleted {
+            id: setup::TestAggregateId(id),
+        }
+        .into()]
+    };
+
+    event_store
+        .append(event_stream_id.clone(), version::Check::Any, event())
+        .await
+        .expect("the event store should append the events");
+
+    // Holds a lock on the Event Stream row, so that the next append has to wait for it.
+    let mut lock = pool.begin().await.unwrap();
+    sqlx::query("SELECT version FROM event_streams WHERE event_stream_id = $1 FOR UPDATE")
+        .bind(&event_stream_id)
+        .execute(&mut *lock)
+        .await
+        .unwrap();
+
+    let err = event_store
+        .append(event_stream_id, version::Check::Any, event())
+        .await
+        .expect_err("the append should time out waiting for the lock");
+
+    assert_eq!(eventually::error::Kind::Timeout, err.kind());
+
+    lock.rollback().await.unwrap();
+}
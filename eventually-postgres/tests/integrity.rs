@@ -0,0 +1,219 @@
+use eventually::event::store::{Appender, Streamer, Truncator};
+use eventually::{serde, version};
+use eventually_postgres::event;
+use eventually_postgres::integrity::{Checker, Violation};
+use futures::TryStreamExt;
+use rand::Rng;
+
+mod setup;
+
+fn violations_of(violations: Vec<Violation>, event_stream_id: &str) -> Vec<Violation> {
+    violations
+        .into_iter()
+        .filter(|violation| match violation {
+            Violation::VersionGap {
+                event_stream_id: id,
+                ..
+            }
+            | Violation::HeadVersionMismatch {
+                event_stream_id: id,
+                ..
+            }
+            | Violation::UndecodableEvent {
+                event_stream_id: id,
+                ..
+            } => id == event_stream_id,
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn it_reports_and_repairs_integrity_violations() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = event::Store::new(
+        pool.clone(),
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .await
+    .unwrap();
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{}", id);
+
+    event_store
+        .append(
+            event_stream_id.clone(),
+            version::Check::must_be(0),
+            (0..3)
+                .map(|_| {
+                    setup::TestDomainEvent::WasDeleted {
+                        id: setup::TestAggregateId(id),
+                    }
+                    .into()
+                })
+                .collect(),
+        )
+        .await
+        .expect("the event store should append the events");
+
+    // Simulate an incident that has left the Event Stream in an inconsistent state.
+    for query in [
+        "DELETE FROM events WHERE event_stream_id = $1 AND version = 2",
+        "UPDATE events SET event = 'not-json' WHERE event_stream_id = $1 AND version = 3",
+        "UPDATE event_streams SET version = 5 WHERE event_stream_id = $1",
+    ] {
+        sqlx::query(query)
+            .bind(&event_stream_id)
+            .execute(&pool)
+            .await
+            .expect("the query should not fail");
+    }
+
+    let report = Checker::new(pool, serde::Json::<setup::TestDomainEvent>::default())
+        .repair(true)
+        .run()
+        .await
+        .expect("the integrity check should not fail");
+
+    let violations = violations_of(report.violations, &event_stream_id);
+
+    assert_eq!(3, violations.len(), "violations: {violations:?}");
+    assert_eq!(
+        Violation::VersionGap {
+            event_stream_id: event_stream_id.clone(),
+            previous: 1,
+            next: 3,
+        },
+        violations[0]
+    );
+    assert_eq!(
+        Violation::HeadVersionMismatch {
+            event_stream_id: event_stream_id.clone(),
+            recorded: 5,
+            actual: 3,
+        },
+        violations[1]
+    );
+    assert!(matches!(
+        violations[2],
+        Violation::UndecodableEvent { version: 3, .. }
+    ));
+    assert!(report.repaired >= 1);
+
+    let head_version = event_store
+        .head_version(&event_stream_id)
+        .await
+        .expect("the head version should be returned");
+
+    assert_eq!(Some(3), head_version);
+}
+
+#[tokio::test]
+async fn it_does_not_report_truncated_event_streams() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = event::Store::new(
+        pool.clone(),
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .await
+    .unwrap();
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{}", id);
+
+    event_store
+        .append(
+            event_stream_id.clone(),
+            version::Check::must_be(0),
+            (0..5)
+                .map(|_| {
+                    setup::TestDomainEvent::WasDeleted {
+                        id: setup::TestAggregateId(id),
+                    }
+                    .into()
+                })
+                .collect(),
+        )
+        .await
+        .expect("the event store should append the events");
+
+    let deleted = event_store
+        .truncate_before(&event_stream_id, 3)
+        .await
+        .expect("the event stream should be truncated");
+
+    assert_eq!(2, deleted);
+
+    let report = Checker::new(pool, serde::Json::<setup::TestDomainEvent>::default())
+        .run()
+        .await
+        .expect("the integrity check should not fail");
+
+    let violations = violations_of(report.violations, &event_stream_id);
+
+    assert!(violations.is_empty(), "violations: {violations:?}");
+}
+
+#[tokio::test]
+async fn it_does_not_check_audit_records_as_domain_events() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{}", id);
+    let audit_stream_id = format!("test-audit-stream-{}", id);
+
+    let event_store = event::Store::builder(
+        pool.clone(),
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .audit_stream(audit_stream_id.clone())
+    .build()
+    .await
+    .unwrap();
+
+    event_store
+        .append(
+            event_stream_id.clone(),
+            version::Check::must_be(0),
+            vec![setup::TestDomainEvent::WasDeleted {
+                id: setup::TestAggregateId(id),
+            }
+            .into()],
+        )
+        .await
+        .expect("the event store should append the events");
+
+    event_store
+        .delete_stream(&event_stream_id)
+        .await
+        .expect("the event stream should be deleted");
+
+    let report = Checker::new(pool, serde::Json::<setup::TestDomainEvent>::default())
+        .run()
+        .await
+        .expect("the integrity check should not fail");
+
+    let violations = violations_of(report.violations, &audit_stream_id);
+
+    assert!(violations.is_empty(), "violations: {violations:?}");
+
+    let audit_records: Vec<_> = event_store
+        .audit_log()
+        .try_collect()
+        .await
+        .expect("the audit stream should be streamed back");
+
+    assert_eq!(1, audit_records.len());
+    assert_eq!(
+        None,
+        event_store.head_version(&audit_stream_id).await.unwrap()
+    );
+}
@@ -0,0 +1,132 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use eventually::event::store::{Appender, Streamer};
+use eventually::event::VersionSelect;
+use eventually::{serde, version};
+use eventually_postgres::event;
+use eventually_postgres::projection::Projection;
+use rand::Rng;
+use sqlx::Row;
+
+mod setup;
+
+#[tokio::test]
+async fn it_projects_domain_events_into_read_model_tables() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    sqlx::query("CREATE TABLE IF NOT EXISTS test_projection_names (id BIGINT PRIMARY KEY, name TEXT NOT NULL)")
+        .execute(&pool)
+        .await
+        .expect("the read model table should be created");
+
+    let event_store = event::Store::new(
+        pool.clone(),
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .await
+    .unwrap();
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{}", id);
+    let other_id = rand::thread_rng().gen::<i64>();
+    let other_event_stream_id = format!("test-event-stream-{}", other_id);
+
+    let created = |id: i64, name: &str| {
+        setup::TestDomainEvent::WasCreated {
+            id: setup::TestAggregateId(id),
+            name: name.to_owned(),
+            at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_millis(),
+        }
+        .into()
+    };
+
+    event_store
+        .append(
+            event_stream_id.clone(),
+            version::Check::must_be(0),
+            vec![created(id, "test something")],
+        )
+        .await
+        .expect("the event should be appended");
+
+    event_store
+        .append(
+            other_event_stream_id.clone(),
+            version::Check::must_be(0),
+            vec![
+                created(other_id, "test something else"),
+                setup::TestDomainEvent::WasDeleted {
+                    id: setup::TestAggregateId(other_id),
+                }
+                .into(),
+            ],
+        )
+        .await
+        .expect("the events should be appended");
+
+    let projection = Projection::new(pool.clone(), format!("test-projection-{}", id))
+        .await
+        .expect("the projection should be created")
+        .batch_size(2)
+        .on(
+            "TestDomainSomethingWasCreated",
+            "INSERT INTO test_projection_names (id, name) VALUES ($1, $2)",
+            |query, event| match event {
+                setup::TestDomainEvent::WasCreated { id, name, .. } => {
+                    query.bind(id.0).bind(name.clone())
+                },
+                _ => query,
+            },
+        )
+        .on(
+            "TestDomainSomethingWasDeleted",
+            "DELETE FROM test_projection_names WHERE id = $1",
+            |query, event| match event {
+                setup::TestDomainEvent::WasDeleted { id } => query.bind(id.0),
+                _ => query,
+            },
+        );
+
+    let stream = || {
+        futures::stream::select(
+            event_store.stream(&event_stream_id, VersionSelect::All),
+            event_store.stream(&other_event_stream_id, VersionSelect::All),
+        )
+    };
+
+    let applied = projection
+        .run(stream())
+        .await
+        .expect("the domain events should be projected");
+
+    assert_eq!(3, applied);
+
+    let names: Vec<String> =
+        sqlx::query("SELECT name FROM test_projection_names WHERE id = ANY($1)")
+            .bind(vec![id, other_id])
+            .fetch_all(&pool)
+            .await
+            .expect("the read model should be queried")
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+
+    assert_eq!(vec!["test something".to_owned()], names);
+    assert_eq!(
+        Some(2),
+        projection.checkpoint(&other_event_stream_id).await.unwrap()
+    );
+
+    // Projecting the same Event Streams again skips the checkpointed Domain Events.
+    let applied = projection
+        .run(stream())
+        .await
+        .expect("the domain events should be skipped");
+
+    assert_eq!(0, applied);
+}
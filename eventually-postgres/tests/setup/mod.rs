@@ -33,6 +33,20 @@ pub enum TestDomainEvent {
     WasDeleted {
         id: TestAggregateId,
     },
+    StreamTombstoned,
+    StreamResurrected,
+}
+
+impl From<aggregate::StreamTombstoned> for TestDomainEvent {
+    fn from(_: aggregate::StreamTombstoned) -> Self {
+        TestDomainEvent::StreamTombstoned
+    }
+}
+
+impl From<aggregate::StreamResurrected> for TestDomainEvent {
+    fn from(_: aggregate::StreamResurrected) -> Self {
+        TestDomainEvent::StreamResurrected
+    }
 }
 
 impl Message for TestDomainEvent {
@@ -40,6 +54,8 @@ impl Message for TestDomainEvent {
         match self {
             TestDomainEvent::WasCreated { .. } => "TestDomainSomethingWasCreated",
             TestDomainEvent::WasDeleted { .. } => "TestDomainSomethingWasDeleted",
+            TestDomainEvent::StreamTombstoned => aggregate::StreamTombstoned::NAME,
+            TestDomainEvent::StreamResurrected => aggregate::StreamResurrected::NAME,
         }
     }
 }
@@ -84,7 +100,11 @@ impl Aggregate for TestAggregate {
                 a.is_deleted = true;
                 Ok(a)
             },
-            (None, TestDomainEvent::WasDeleted { .. }) => Err(TestAggregateError::NotCreatedYet),
+            // The tombstone markers are never applied to the Aggregate.
+            (Some(a), TestDomainEvent::StreamTombstoned | TestDomainEvent::StreamResurrected) => {
+                Ok(a)
+            },
+            (None, _) => Err(TestAggregateError::NotCreatedYet),
         }
     }
 }
@@ -0,0 +1,15 @@
+CREATE TABLE event_streams (
+    event_stream_id TEXT    NOT NULL PRIMARY KEY,
+    "version"       INTEGER NOT NULL CHECK ("version" > 0)
+);
+
+CREATE TABLE events (
+    event_stream_id  TEXT    NOT NULL,
+    "type"           TEXT    NOT NULL,
+    "version"        INTEGER NOT NULL CHECK ("version" > 0),
+    "event"          BLOB    NOT NULL,
+    metadata         TEXT,
+
+    PRIMARY KEY (event_stream_id, "version"),
+    FOREIGN KEY (event_stream_id) REFERENCES event_streams (event_stream_id) ON DELETE CASCADE
+);
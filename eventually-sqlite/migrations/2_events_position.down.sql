@@ -0,0 +1,19 @@
+CREATE TABLE events_without_position (
+    event_stream_id  TEXT    NOT NULL,
+    "type"           TEXT    NOT NULL,
+    "version"        INTEGER NOT NULL CHECK ("version" > 0),
+    "event"          BLOB    NOT NULL,
+    metadata         TEXT,
+
+    PRIMARY KEY (event_stream_id, "version"),
+    FOREIGN KEY (event_stream_id) REFERENCES event_streams (event_stream_id) ON DELETE CASCADE
+);
+
+INSERT INTO events_without_position (rowid, event_stream_id, "type", "version", "event", metadata)
+SELECT position, event_stream_id, "type", "version", "event", metadata
+FROM events
+ORDER BY position;
+
+DROP TABLE events;
+
+ALTER TABLE events_without_position RENAME TO events;
@@ -0,0 +1,23 @@
+-- The global position used to be the implicit rowid of the events table,
+-- which VACUUM may renumber and SQLite may reuse after deleting the last row.
+-- An INTEGER PRIMARY KEY AUTOINCREMENT column is stable and never reused.
+CREATE TABLE events_with_position (
+    position         INTEGER PRIMARY KEY AUTOINCREMENT,
+    event_stream_id  TEXT    NOT NULL,
+    "type"           TEXT    NOT NULL,
+    "version"        INTEGER NOT NULL CHECK ("version" > 0),
+    "event"          BLOB    NOT NULL,
+    metadata         TEXT,
+
+    UNIQUE (event_stream_id, "version"),
+    FOREIGN KEY (event_stream_id) REFERENCES event_streams (event_stream_id) ON DELETE CASCADE
+);
+
+INSERT INTO events_with_position (position, event_stream_id, "type", "version", "event", metadata)
+SELECT rowid, event_stream_id, "type", "version", "event", metadata
+FROM events
+ORDER BY rowid;
+
+DROP TABLE events;
+
+ALTER TABLE events_with_position RENAME TO events;
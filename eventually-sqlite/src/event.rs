@@ -0,0 +1,687 @@
+//! This module contains the implementation of the [`eventually::event::Store`] trait,
+//! to work specifically with `SQLite` databases.
+//!
+//! Check out the [Store] type for more information.
+
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::string::ToString;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::Utc;
+use eventually::error::{Kind, Retryable};
+use eventually::event::store::Streamer;
+use eventually::message::{Message, Metadata};
+use eventually::version::Version;
+use eventually::{event, serde, version};
+use futures::future::ready;
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, Sqlite, SqlitePool, Transaction};
+
+/// All possible errors returned by [`Store`] during an [`event::store::Streamer::stream`] call.
+#[derive(Debug, thiserror::Error)]
+pub enum StreamError {
+    /// Error returned when the Domain Event could not be deserialized
+    /// using the configured [`serde::Serde`] implementation.
+    #[error("failed to deserialize event from database: {0}")]
+    DeserializeEvent(#[source] anyhow::Error),
+    /// Error returned when a column could not be read from a result row.
+    #[error("failed to get column '{name}' from result row: {error}")]
+    ReadColumn {
+        /// The name of the column that could not be read.
+        name: &'static str,
+        /// The underlying error returned by the database driver.
+        #[source]
+        error: sqlx::Error,
+    },
+    /// Error returned when the database has returned an error.
+    #[error("db returned an error: {0}")]
+    Database(#[source] sqlx::Error),
+}
+
+impl StreamError {
+    /// Returns the [Kind] of the error.
+    #[must_use]
+    pub fn kind(&self) -> Kind {
+        match self {
+            StreamError::DeserializeEvent(_) | StreamError::ReadColumn { .. } => {
+                Kind::Serialization
+            },
+            StreamError::Database(err) => crate::error_kind(err),
+        }
+    }
+}
+
+impl Retryable for StreamError {
+    fn is_transient(&self) -> bool {
+        self.kind().is_transient()
+    }
+}
+
+pub use eventually::message::RECORDED_AT_KEY;
+
+/// [Metadata] key set automatically on each appended Domain Event,
+/// containing the new version of the Event Stream after the append.
+///
+/// Any value set by the caller under this key is overridden.
+pub const RECORDED_WITH_NEW_VERSION_KEY: &str = "Recorded-With-New-Version";
+
+async fn append_domain_events<Evt>(
+    tx: &mut Transaction<'_, Sqlite>,
+    serde: &impl serde::Serializer<Evt>,
+    event_stream_id: &str,
+    new_version: i64,
+    events: Vec<event::Envelope<Evt>>,
+) -> anyhow::Result<()>
+where
+    Evt: Message,
+{
+    #[allow(clippy::cast_possible_wrap)]
+    let current_event_stream_version = new_version - (events.len() as i64);
+    let recorded_at = Utc::now().to_rfc3339();
+
+    for (i, event) in events.into_iter().enumerate() {
+        #[allow(clippy::cast_possible_wrap)]
+        let event_version = current_event_stream_version + (i as i64) + 1;
+
+        let event_type = event.message.name();
+        let mut metadata = event.metadata;
+        let serialized_event = serde.serialize(event.message).map_err(|err| {
+            crate::Error::Serialization(anyhow!("failed to serialize event message: {err}"))
+        })?;
+
+        metadata.insert(RECORDED_AT_KEY.to_owned(), recorded_at.clone());
+        metadata.insert(
+            RECORDED_WITH_NEW_VERSION_KEY.to_owned(),
+            new_version.to_string(),
+        );
+
+        sqlx::query(
+            r#"INSERT INTO events (event_stream_id, "type", "version", event, metadata) VALUES ($1, $2, $3, $4, $5)"#,
+        )
+        .bind(event_stream_id)
+        .bind(event_type)
+        .bind(event_version)
+        .bind(serialized_event)
+        .bind(sqlx::types::Json(metadata))
+        .execute(&mut **tx)
+        .await
+        .map_err(|err| crate::classify_error(&err, "failed to insert domain event"))?;
+    }
+
+    Ok(())
+}
+
+/// Maximum number of Domain Events fetched by each poll of a subscription.
+const SUBSCRIPTION_PAGE_SIZE: usize = 256;
+
+/// Returns the smallest string greater than all the strings starting with the specified prefix,
+/// or [None] if every string greater than or equal to the prefix also starts with it.
+///
+/// Used to select a category of Event Streams with a range predicate, which,
+/// unlike a prefix comparison, can use the index on the Event Stream id.
+fn prefix_upper_bound(prefix: &str) -> Option<String> {
+    let mut chars: Vec<char> = prefix.chars().collect();
+
+    while let Some(last) = chars.pop() {
+        // Code points are ordered as their UTF-8 encoding, which SQLite compares by default.
+        if let Some(next) = (u32::from(last) + 1..=u32::from(char::MAX)).find_map(char::from_u32) {
+            chars.push(next);
+            return Some(chars.into_iter().collect());
+        }
+    }
+
+    None
+}
+
+/// Converts an optional page size into a `SQLite` `LIMIT`, where `-1` means no limit.
+fn query_limit(limit: Option<usize>) -> i64 {
+    limit.map_or(-1, |limit| i64::try_from(limit).unwrap_or(i64::MAX))
+}
+
+/// A Domain Event read from the global `$all` stream, returned by [`Store::stream_all`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlobalEvent<Evt>
+where
+    Evt: Message,
+{
+    /// The position of the Domain Event in the global stream.
+    ///
+    /// Positions are monotonically increasing and never reused, but not necessarily contiguous.
+    pub position: u64,
+    /// The Domain Event, with the id of the Event Stream it belongs to.
+    pub event: event::Persisted<String, Evt>,
+}
+
+/// Implements the [`eventually::event::Store`] trait for
+/// `SQLite` databases.
+///
+/// Use [`crate::connect`] to open a database configured for concurrent
+/// reads and appends.
+#[derive(Debug, Clone)]
+pub struct Store<Id, Evt, Serde>
+where
+    Id: ToString + Clone,
+    Serde: serde::Serde<Evt>,
+{
+    pool: SqlitePool,
+    serde: Serde,
+    id_type: PhantomData<Id>,
+    evt_type: PhantomData<Evt>,
+}
+
+impl<Id, Evt, Serde> Store<Id, Evt, Serde>
+where
+    Id: ToString + Clone,
+    Serde: serde::Serde<Evt>,
+{
+    /// Runs the latest migrations necessary for the implementation to work,
+    /// then returns a new [`Store`] instance.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the migrations fail to run.
+    pub async fn new(pool: SqlitePool, serde: Serde) -> Result<Self, sqlx::migrate::MigrateError> {
+        Self::builder(pool, serde).build().await
+    }
+
+    /// Returns a [`StoreBuilder`] to configure a new [`Store`] instance.
+    pub fn builder(pool: SqlitePool, serde: Serde) -> StoreBuilder<Id, Evt, Serde> {
+        StoreBuilder {
+            pool,
+            serde,
+            run_migrations: true,
+            id_type: PhantomData,
+            evt_type: PhantomData,
+        }
+    }
+}
+
+/// Builder type used to configure and create a new [`Store`] instance.
+///
+/// Use [`Store::builder`] to create a new builder.
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct StoreBuilder<Id, Evt, Serde>
+where
+    Id: ToString + Clone,
+    Serde: serde::Serde<Evt>,
+{
+    pool: SqlitePool,
+    serde: Serde,
+    run_migrations: bool,
+    id_type: PhantomData<Id>,
+    evt_type: PhantomData<Evt>,
+}
+
+impl<Id, Evt, Serde> StoreBuilder<Id, Evt, Serde>
+where
+    Id: ToString + Clone,
+    Serde: serde::Serde<Evt>,
+{
+    /// Specifies whether the latest migrations should be run when building
+    /// the [`Store`] instance. Defaults to `true`.
+    pub fn run_migrations(mut self, run_migrations: bool) -> Self {
+        self.run_migrations = run_migrations;
+        self
+    }
+
+    /// Builds the new [`Store`] instance, running the latest migrations
+    /// necessary for the implementation to work, unless disabled.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the migrations fail to run.
+    pub async fn build(self) -> Result<Store<Id, Evt, Serde>, sqlx::migrate::MigrateError> {
+        if self.run_migrations {
+            // Make sure the latest migrations are used before using the Store instance.
+            crate::MIGRATIONS.run(&self.pool).await?;
+        }
+
+        Ok(Store {
+            pool: self.pool,
+            serde: self.serde,
+            id_type: PhantomData,
+            evt_type: PhantomData,
+        })
+    }
+}
+
+fn try_get_column<T>(row: &SqliteRow, name: &'static str) -> Result<T, StreamError>
+where
+    for<'a> T: sqlx::Type<Sqlite> + sqlx::Decode<'a, Sqlite>,
+{
+    row.try_get(name)
+        .map_err(|err| StreamError::ReadColumn { name, error: err })
+}
+
+impl<Id, Evt, Serde> Store<Id, Evt, Serde>
+where
+    Id: ToString + Clone + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt> + Send + Sync,
+{
+    fn event_row_to_persisted_event<StreamId>(
+        &self,
+        stream_id: StreamId,
+        row: &SqliteRow,
+    ) -> Result<event::Persisted<StreamId, Evt>, StreamError> {
+        let version_column: i64 = try_get_column(row, "version")?;
+        let event_column: Vec<u8> = try_get_column(row, "event")?;
+        let metadata_column: sqlx::types::Json<Metadata> = try_get_column(row, "metadata")?;
+
+        let deserialized_event = self
+            .serde
+            .deserialize(&event_column)
+            .map_err(StreamError::DeserializeEvent)?;
+
+        #[allow(clippy::cast_sign_loss)]
+        Ok(event::Persisted {
+            stream_id,
+            version: version_column as Version,
+            event: event::Envelope {
+                message: deserialized_event,
+                metadata: metadata_column.0,
+            },
+        })
+    }
+
+    /// Streams all the Domain Events in the Event Store, across all Event Streams,
+    /// in the order they have been appended, starting from the specified position.
+    ///
+    /// Positions are assigned on append: since `SQLite` serializes concurrent appends,
+    /// a Domain Event with a lower position is never committed after one with a higher
+    /// position, so readers can safely resume from the last position they have seen plus one.
+    pub fn stream_all(
+        &self,
+        from_position: u64,
+    ) -> BoxStream<'_, Result<GlobalEvent<Evt>, StreamError>> {
+        self.stream_category("", from_position)
+    }
+
+    /// Streams all the Domain Events of a category of Event Streams, i.e. of all
+    /// the Event Streams whose id starts with the specified prefix (e.g. `order-`),
+    /// in the order they have been appended, starting from the specified position.
+    ///
+    /// Positions are shared with [`Store::stream_all`].
+    pub fn stream_category(
+        &self,
+        category: &str,
+        from_position: u64,
+    ) -> BoxStream<'_, Result<GlobalEvent<Evt>, StreamError>> {
+        self.category_page(category, from_position, None)
+    }
+
+    /// Streams the Domain Events of [`Store::stream_category`], up to the specified limit.
+    fn category_page(
+        &self,
+        category: &str,
+        from_position: u64,
+        limit: Option<usize>,
+    ) -> BoxStream<'_, Result<GlobalEvent<Evt>, StreamError>> {
+        #[allow(clippy::cast_possible_wrap)]
+        let from_position = from_position as i64;
+        let upper_bound = prefix_upper_bound(category);
+
+        let query = match (category.is_empty(), upper_bound) {
+            (true, _) => sqlx::query(
+                r"SELECT position, event_stream_id, version, event, metadata
+                   FROM events
+                   WHERE position >= $1
+                   ORDER BY position
+                   LIMIT $2",
+            )
+            .bind(from_position)
+            .bind(query_limit(limit)),
+            (false, None) => sqlx::query(
+                r"SELECT position, event_stream_id, version, event, metadata
+                   FROM events
+                   WHERE position >= $1 AND event_stream_id >= $3
+                   ORDER BY position
+                   LIMIT $2",
+            )
+            .bind(from_position)
+            .bind(query_limit(limit))
+            .bind(category.to_owned()),
+            (false, Some(upper_bound)) => sqlx::query(
+                r"SELECT position, event_stream_id, version, event, metadata
+                   FROM events
+                   WHERE position >= $1 AND event_stream_id >= $3 AND event_stream_id < $4
+                   ORDER BY position
+                   LIMIT $2",
+            )
+            .bind(from_position)
+            .bind(query_limit(limit))
+            .bind(category.to_owned())
+            .bind(upper_bound),
+        };
+
+        query
+            .fetch(&self.pool)
+            .map_err(StreamError::Database)
+            .and_then(move |row| {
+                ready((|| {
+                    let position: i64 = try_get_column(&row, "position")?;
+                    let stream_id: String = try_get_column(&row, "event_stream_id")?;
+
+                    #[allow(clippy::cast_sign_loss)]
+                    Ok(GlobalEvent {
+                        position: position as u64,
+                        event: self.event_row_to_persisted_event(stream_id, &row)?,
+                    })
+                })())
+            })
+            .boxed()
+    }
+
+    /// Returns the Domain Events of an Event Stream starting from the specified version,
+    /// up to the specified limit.
+    async fn stream_page(
+        &self,
+        id: &Id,
+        from_version: Version,
+        limit: Option<usize>,
+    ) -> Result<Vec<event::Persisted<Id, Evt>>, StreamError> {
+        let rows = sqlx::query(
+            r"SELECT version, event, metadata
+               FROM events
+               WHERE event_stream_id = $1 AND version >= $2
+               ORDER BY version
+               LIMIT $3",
+        )
+        .bind(id.to_string())
+        .bind(i64::try_from(from_version).unwrap_or(i64::MAX))
+        .bind(query_limit(limit))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(StreamError::Database)?;
+
+        rows.iter()
+            .map(|row| self.event_row_to_persisted_event(id.clone(), row))
+            .collect()
+    }
+
+    /// Subscribes to a category of Event Streams, returning the Domain Events
+    /// of [`Store::stream_category`] starting from the specified position,
+    /// followed by any Domain Event appended to the category afterwards.
+    ///
+    /// New Domain Events are discovered by polling the database with the
+    /// specified interval. The returned stream never ends: errors are yielded
+    /// and polling continues, so the caller can decide whether to stop.
+    pub fn subscribe_category(
+        &self,
+        category: &str,
+        from_position: u64,
+        poll_interval: Duration,
+    ) -> BoxStream<'_, Result<GlobalEvent<Evt>, StreamError>> {
+        let category = category.to_owned();
+
+        futures::stream::unfold(
+            (from_position, VecDeque::new()),
+            move |(mut from_position, mut buffer)| {
+                let category = category.clone();
+
+                async move {
+                    loop {
+                        if let Some(event) = buffer.pop_front() {
+                            return Some((Ok(event), (from_position, buffer)));
+                        }
+
+                        let result = self
+                            .category_page(&category, from_position, Some(SUBSCRIPTION_PAGE_SIZE))
+                            .try_collect::<Vec<_>>()
+                            .await;
+
+                        match result {
+                            Err(err) => return Some((Err(err), (from_position, buffer))),
+                            Ok(events) => match events.last() {
+                                None => tokio::time::sleep(poll_interval).await,
+                                Some(last) => {
+                                    from_position = last.position + 1;
+                                    buffer.extend(events);
+                                },
+                            },
+                        }
+                    }
+                }
+            },
+        )
+        .boxed()
+    }
+
+    /// Subscribes to an Event Stream, returning its Domain Events starting from
+    /// the specified version, followed by any Domain Event appended afterwards.
+    ///
+    /// New Domain Events are discovered by polling the database with the
+    /// specified interval. The returned stream never ends: errors are yielded
+    /// and polling continues, so the caller can decide whether to stop.
+    pub fn subscribe(
+        &self,
+        id: &Id,
+        select: event::VersionSelect,
+        poll_interval: Duration,
+    ) -> event::Stream<'_, Id, Evt, StreamError> {
+        let from_version = match select {
+            event::VersionSelect::All => 0,
+            event::VersionSelect::From(v) => v,
+        };
+
+        let id = id.clone();
+
+        futures::stream::unfold(
+            (from_version, VecDeque::new()),
+            move |(mut from_version, mut buffer)| {
+                let id = id.clone();
+
+                async move {
+                    loop {
+                        if let Some(event) = buffer.pop_front() {
+                            return Some((Ok(event), (from_version, buffer)));
+                        }
+
+                        let result = self
+                            .stream_page(&id, from_version, Some(SUBSCRIPTION_PAGE_SIZE))
+                            .await;
+
+                        match result {
+                            Err(err) => return Some((Err(err), (from_version, buffer))),
+                            Ok(events) => match events.last() {
+                                None => tokio::time::sleep(poll_interval).await,
+                                Some(last) => {
+                                    from_version = last.version + 1;
+                                    buffer.extend(events);
+                                },
+                            },
+                        }
+                    }
+                }
+            },
+        )
+        .boxed()
+    }
+
+    async fn current_version(
+        tx: &mut Transaction<'_, Sqlite>,
+        id: &str,
+    ) -> Result<Version, event::store::AppendError> {
+        let version: Option<i64> =
+            sqlx::query_scalar("SELECT version FROM event_streams WHERE event_stream_id = $1")
+                .bind(id)
+                .fetch_optional(&mut **tx)
+                .await
+                .map_err(|err| {
+                    crate::classify_error(&err, "failed to fetch the event stream version")
+                })?;
+
+        #[allow(clippy::cast_sign_loss)]
+        Ok(version.map_or(0, |v| v as Version))
+    }
+}
+
+#[async_trait]
+impl<Id, Evt, Serde> Streamer<Id, Evt> for Store<Id, Evt, Serde>
+where
+    Id: ToString + Clone + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt> + Send + Sync,
+{
+    type Error = StreamError;
+
+    fn stream(
+        &self,
+        id: &Id,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, Id, Evt, Self::Error> {
+        #[allow(clippy::cast_possible_wrap)]
+        let from_version: i64 = match select {
+            event::VersionSelect::All => 0,
+            event::VersionSelect::From(v) => v as i64,
+        };
+
+        let query = sqlx::query(
+            r"SELECT version, event, metadata
+               FROM events
+               WHERE event_stream_id = $1 AND version >= $2
+               ORDER BY version",
+        );
+
+        let id = id.clone();
+
+        query
+            .bind(id.to_string())
+            .bind(from_version)
+            .fetch(&self.pool)
+            .map_err(StreamError::Database)
+            .and_then(move |row| ready(self.event_row_to_persisted_event(id.clone(), &row)))
+            .boxed()
+    }
+
+    fn read_from<'a>(
+        &'a self,
+        id: &Id,
+        from_version: Version,
+        batch_size: usize,
+    ) -> event::Stream<'a, Id, Evt, Self::Error>
+    where
+        Id: 'a,
+        Evt: 'a,
+    {
+        assert!(batch_size > 0, "batch size must be greater than zero");
+
+        let id = id.clone();
+
+        event::store::paged(from_version, batch_size, move |from_version| {
+            let id = id.clone();
+
+            async move { self.stream_page(&id, from_version, Some(batch_size)).await }
+        })
+    }
+
+    async fn head_version(&self, id: &Id) -> Result<Option<Version>, Self::Error> {
+        let version: Option<i64> =
+            sqlx::query_scalar("SELECT version FROM event_streams WHERE event_stream_id = $1")
+                .bind(id.to_string())
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(StreamError::Database)?;
+
+        #[allow(clippy::cast_sign_loss)]
+        Ok(version.map(|v| v as Version))
+    }
+}
+
+#[async_trait]
+impl<Id, Evt, Serde> event::store::Appender<Id, Evt> for Store<Id, Evt, Serde>
+where
+    Id: ToString + Clone + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt> + Send + Sync,
+{
+    async fn append(
+        &self,
+        id: Id,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Evt>>,
+    ) -> Result<Version, event::store::AppendError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|err| crate::classify_error(&err, "failed to begin transaction"))?;
+
+        let string_id = id.to_string();
+
+        #[allow(clippy::cast_possible_wrap)]
+        let events_len = events.len() as i64;
+
+        // The Event Stream version is written before being read, so that the
+        // transaction acquires the database write lock straight away, and
+        // concurrent appends are serialized by SQLite.
+        let new_version: i64 = match version_check {
+            version::Check::Any => sqlx::query_scalar(
+                r"INSERT INTO event_streams (event_stream_id, version)
+                   VALUES ($1, $2)
+                   ON CONFLICT (event_stream_id) DO
+                   UPDATE SET version = version + $2
+                   RETURNING version",
+            )
+            .bind(&string_id)
+            .bind(events_len)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|err| {
+                crate::classify_error(&err, "failed to upsert new event stream version")
+            })?,
+            version::Check::MustBe(expected) => {
+                #[allow(clippy::cast_possible_wrap)]
+                let v = expected.version() as i64;
+                let new_version = v + events_len;
+
+                // A new Event Stream is expected to be inserted, while an existing one
+                // is only updated if it still has the expected version.
+                let query = if v == 0 {
+                    r"INSERT INTO event_streams (event_stream_id, version)
+                       VALUES ($1, $3)
+                       ON CONFLICT (event_stream_id) DO NOTHING"
+                } else {
+                    r"UPDATE event_streams SET version = $3
+                       WHERE event_stream_id = $1 AND version = $2"
+                };
+
+                let result = sqlx::query(query)
+                    .bind(&string_id)
+                    .bind(v)
+                    .bind(new_version)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|err| {
+                        crate::classify_error(&err, "failed to upsert new event stream version")
+                    })?;
+
+                if result.rows_affected() == 0 {
+                    return Err(event::store::AppendError::Conflict(
+                        version::ConflictError {
+                            expected: expected.version(),
+                            actual: Self::current_version(&mut tx, &string_id).await?,
+                        },
+                    ));
+                }
+
+                new_version
+            },
+        };
+
+        append_domain_events(&mut tx, &self.serde, &string_id, new_version, events).await?;
+
+        tx.commit()
+            .await
+            .map_err(|err| crate::classify_error(&err, "failed to commit transaction"))?;
+
+        #[allow(clippy::cast_sign_loss)]
+        Ok(new_version as Version)
+    }
+}
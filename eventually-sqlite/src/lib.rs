@@ -0,0 +1,79 @@
+//! `eventually-sqlite` contains implementations of traits from the [eventually] crate
+//! that are specific for embedded `SQLite` databases, useful for desktop and CLI applications
+//! that cannot rely on a database server.
+//!
+//! Check out the [`event::Store`] implementation to know more.
+
+#![deny(unsafe_code, unused_qualifications, trivial_casts)]
+#![deny(clippy::all, clippy::pedantic, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+#![warn(missing_docs)]
+
+pub mod event;
+
+pub(crate) static MIGRATIONS: sqlx::migrate::Migrator = sqlx::migrate!("./migrations");
+
+use std::path::Path;
+
+use anyhow::anyhow;
+use eventually::error::Kind;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode};
+use sqlx::SqlitePool;
+
+/// Framework error type used to classify the errors returned by the database,
+/// which never carries a domain error.
+pub(crate) type Error = eventually::error::Error;
+
+/// Opens a connection pool to the `SQLite` database at the specified path,
+/// creating the database file if missing.
+///
+/// The database is configured to use [Write-Ahead Logging](https://www.sqlite.org/wal.html),
+/// so that reads (e.g. polling subscriptions) do not block appends and vice versa.
+///
+/// # Errors
+///
+/// An error is returned if the database could not be opened.
+pub async fn connect(path: impl AsRef<Path>) -> Result<SqlitePool, sqlx::Error> {
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .foreign_keys(true);
+
+    SqlitePool::connect_with(options).await
+}
+
+/// Returns the [`eventually::error::Kind`] that best describes the specified [`sqlx::Error`].
+pub(crate) fn error_kind(err: &sqlx::Error) -> Kind {
+    match err {
+        sqlx::Error::PoolTimedOut => Kind::Timeout,
+        sqlx::Error::Io(_) | sqlx::Error::PoolClosed | sqlx::Error::WorkerCrashed => {
+            Kind::Connection
+        },
+        sqlx::Error::Decode(_) | sqlx::Error::ColumnDecode { .. } => Kind::Serialization,
+        sqlx::Error::RowNotFound => Kind::NotFound,
+        sqlx::Error::Database(db_err) => match db_err.code() {
+            // SQLITE_BUSY and SQLITE_LOCKED, including their extended codes:
+            // the database is locked by another writer for longer than the busy timeout.
+            Some(code) if matches!(code.parse::<i32>().map(|c| c & 0xff), Ok(5 | 6)) => {
+                Kind::Timeout
+            },
+            _ => Kind::Internal,
+        },
+        _ => Kind::Internal,
+    }
+}
+
+/// Wraps a [`sqlx::Error`] into an [`anyhow::Error`] with the specified context message,
+/// preserving its [`eventually::error::Kind`] classification.
+pub(crate) fn classify_error(err: &sqlx::Error, context: &str) -> anyhow::Error {
+    let kind = error_kind(err);
+    let err = anyhow!("{context}: {err}");
+
+    match kind {
+        Kind::Timeout => Error::Timeout(err).into(),
+        Kind::Connection => Error::Connection(err).into(),
+        Kind::Serialization => Error::Serialization(err).into(),
+        _ => err,
+    }
+}
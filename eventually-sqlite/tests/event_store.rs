@@ -0,0 +1,392 @@
+use std::time::Duration;
+
+use ::serde::{Deserialize, Serialize};
+use eventually::event::store::{AppendError, Appender, Streamer};
+use eventually::event::{Persisted, VersionSelect};
+use eventually::message::Message;
+use eventually::{serde, version};
+use eventually_sqlite::event;
+use futures::{StreamExt, TryStreamExt};
+use rand::Rng;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+enum TestDomainEvent {
+    WasCreated { name: String },
+    WasDeleted,
+}
+
+impl Message for TestDomainEvent {
+    fn name(&self) -> &'static str {
+        match self {
+            TestDomainEvent::WasCreated { .. } => "TestDomainSomethingWasCreated",
+            TestDomainEvent::WasDeleted => "TestDomainSomethingWasDeleted",
+        }
+    }
+}
+
+type Store = event::Store<String, TestDomainEvent, serde::Json<TestDomainEvent>>;
+
+async fn new_database() -> sqlx::SqlitePool {
+    let path = std::env::temp_dir().join(format!(
+        "eventually-sqlite-{}.db",
+        rand::thread_rng().gen::<u64>()
+    ));
+
+    eventually_sqlite::connect(path)
+        .await
+        .expect("the database should be opened")
+}
+
+async fn new_event_store() -> Store {
+    event::Store::new(new_database().await, serde::Json::default())
+        .await
+        .expect("the event store should be created")
+}
+
+fn created(name: &str) -> eventually::event::Envelope<TestDomainEvent> {
+    TestDomainEvent::WasCreated {
+        name: name.to_owned(),
+    }
+    .into()
+}
+
+#[tokio::test]
+async fn it_appends_and_streams_events_with_version_checks() {
+    let event_store = new_event_store().await;
+    let id = "test-event-stream".to_owned();
+
+    let err = event_store
+        .append(
+            id.clone(),
+            version::Check::must_be(1),
+            vec![created("John Dee")],
+        )
+        .await
+        .expect_err("the event stream should not exist yet");
+
+    assert!(matches!(
+        err,
+        AppendError::Conflict(version::ConflictError {
+            expected: 1,
+            actual: 0
+        })
+    ));
+
+    let new_version = event_store
+        .append(
+            id.clone(),
+            version::Check::must_be(0),
+            vec![created("John Dee"), TestDomainEvent::WasDeleted.into()],
+        )
+        .await
+        .expect("the events should be appended");
+
+    assert_eq!(2, new_version);
+
+    let err = event_store
+        .append(
+            id.clone(),
+            version::Check::must_be(0),
+            vec![created("John Dee")],
+        )
+        .await
+        .expect_err("the event stream already exists");
+
+    assert!(matches!(
+        err,
+        AppendError::Conflict(version::ConflictError {
+            expected: 0,
+            actual: 2
+        })
+    ));
+
+    let new_version = event_store
+        .append(id.clone(), version::Check::Any, vec![created("Jane Doe")])
+        .await
+        .expect("the event should be appended");
+
+    assert_eq!(3, new_version);
+
+    let events: Vec<_> = event_store
+        .stream(&id, VersionSelect::From(2))
+        .try_collect()
+        .await
+        .expect("the events should be streamed");
+
+    assert_eq!(
+        vec![
+            Persisted {
+                stream_id: id.clone(),
+                version: 2,
+                event: TestDomainEvent::WasDeleted.into(),
+            },
+            Persisted {
+                stream_id: id.clone(),
+                version: 3,
+                event: created("Jane Doe"),
+            },
+        ],
+        events
+    );
+
+    assert!(events[0]
+        .event
+        .metadata
+        .contains_key(event::RECORDED_AT_KEY));
+
+    let head_version = event_store
+        .head_version(&id)
+        .await
+        .expect("the head version should be returned");
+
+    assert_eq!(Some(3), head_version);
+}
+
+#[tokio::test]
+async fn it_polls_subscriptions_for_new_events() {
+    let event_store = new_event_store().await;
+    let id = "test-event-stream".to_owned();
+
+    event_store
+        .append(
+            id.clone(),
+            version::Check::must_be(0),
+            vec![created("John Dee")],
+        )
+        .await
+        .expect("the event should be appended");
+
+    let mut subscription =
+        event_store.subscribe(&id, VersionSelect::All, Duration::from_millis(10));
+
+    let first = subscription
+        .next()
+        .await
+        .expect("the subscription should not end")
+        .expect("the event should be returned");
+
+    assert_eq!(1, first.version);
+
+    let (second, _) = tokio::join!(subscription.next(), async {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        event_store
+            .append(
+                id.clone(),
+                version::Check::must_be(1),
+                vec![TestDomainEvent::WasDeleted.into()],
+            )
+            .await
+            .expect("the event should be appended");
+    });
+
+    let second = second
+        .expect("the subscription should not end")
+        .expect("the event should be returned");
+
+    assert_eq!(2, second.version);
+    assert_eq!(TestDomainEvent::WasDeleted, second.event.message);
+}
+
+#[tokio::test]
+async fn it_reads_and_subscribes_to_long_event_streams_in_pages() {
+    const EVENTS: usize = 600;
+
+    let event_store = new_event_store().await;
+    let id = "test-event-stream".to_owned();
+
+    event_store
+        .append(
+            id.clone(),
+            version::Check::must_be(0),
+            (0..EVENTS).map(|i| created(&i.to_string())).collect(),
+        )
+        .await
+        .expect("the events should be appended");
+
+    let read: Vec<_> = event_store
+        .read_from(&id, 3, 7)
+        .map_ok(|evt| evt.version)
+        .try_collect()
+        .await
+        .expect("the events should be read");
+
+    assert_eq!((3..=EVENTS as u64).collect::<Vec<_>>(), read);
+
+    // The subscription catches up over more than one poll.
+    let subscribed: Vec<_> = event_store
+        .subscribe(&id, VersionSelect::All, Duration::from_millis(10))
+        .take(EVENTS)
+        .map_ok(|evt| evt.version)
+        .try_collect()
+        .await
+        .expect("the events should be returned");
+
+    assert_eq!((1..=EVENTS as u64).collect::<Vec<_>>(), subscribed);
+
+    let subscribed_category: Vec<_> = event_store
+        .subscribe_category("test-", 0, Duration::from_millis(10))
+        .take(EVENTS)
+        .map_ok(|evt| evt.event.version)
+        .try_collect()
+        .await
+        .expect("the events should be returned");
+
+    assert_eq!((1..=EVENTS as u64).collect::<Vec<_>>(), subscribed_category);
+}
+
+#[tokio::test]
+async fn it_streams_all_events_in_append_order() {
+    let event_store = new_event_store().await;
+
+    for (id, check) in [("stream-a", 0), ("stream-b", 0), ("stream-a", 1)] {
+        event_store
+            .append(
+                id.to_owned(),
+                version::Check::must_be(check),
+                vec![created(id)],
+            )
+            .await
+            .expect("the event should be appended");
+    }
+
+    let all: Vec<_> = event_store
+        .stream_all(0)
+        .try_collect()
+        .await
+        .expect("the global stream should be read");
+
+    let streams_and_versions: Vec<_> = all
+        .iter()
+        .map(|evt| (evt.event.stream_id.as_str(), evt.event.version))
+        .collect();
+
+    assert_eq!(
+        vec![("stream-a", 1), ("stream-b", 1), ("stream-a", 2)],
+        streams_and_versions
+    );
+    assert!(all.windows(2).all(|w| w[0].position < w[1].position));
+
+    let resumed: Vec<_> = event_store
+        .stream_all(all[0].position + 1)
+        .map_ok(|evt| evt.position)
+        .try_collect()
+        .await
+        .expect("the global stream should be read");
+
+    assert_eq!(vec![all[1].position, all[2].position], resumed);
+}
+
+#[tokio::test]
+async fn it_never_reuses_global_positions() {
+    let pool = new_database().await;
+    let event_store: Store = event::Store::new(pool.clone(), serde::Json::default())
+        .await
+        .expect("the event store should be created");
+
+    event_store
+        .append(
+            "stream-a".to_owned(),
+            version::Check::empty(),
+            vec![created("a1"), created("a2")],
+        )
+        .await
+        .expect("the events should be appended");
+
+    let last_position = event_store
+        .stream_all(0)
+        .map_ok(|evt| evt.position)
+        .try_collect::<Vec<_>>()
+        .await
+        .expect("the global stream should be read")
+        .pop()
+        .expect("the global stream should not be empty");
+
+    // Deleting the Domain Event with the highest position, then compacting the database,
+    // would make SQLite reuse or renumber an implicit rowid.
+    sqlx::query("DELETE FROM events WHERE position = $1")
+        .bind(i64::try_from(last_position).unwrap())
+        .execute(&pool)
+        .await
+        .expect("the last domain event should be deleted");
+
+    sqlx::query("VACUUM")
+        .execute(&pool)
+        .await
+        .expect("the database should be compacted");
+
+    event_store
+        .append(
+            "stream-b".to_owned(),
+            version::Check::empty(),
+            vec![created("b1")],
+        )
+        .await
+        .expect("the event should be appended");
+
+    let positions: Vec<_> = event_store
+        .stream_all(0)
+        .map_ok(|evt| (evt.event.stream_id, evt.position))
+        .try_collect()
+        .await
+        .expect("the global stream should be read");
+
+    assert_eq!(
+        vec![
+            ("stream-a".to_owned(), last_position - 1),
+            ("stream-b".to_owned(), last_position + 1),
+        ],
+        positions
+    );
+}
+
+#[tokio::test]
+async fn it_streams_and_subscribes_to_categories_of_event_streams() {
+    let event_store = new_event_store().await;
+
+    // "order.1" sorts right after the "order-" prefix, but does not belong to the category.
+    for id in ["order-1", "invoice-1", "order.1", "order-2"] {
+        event_store
+            .append(id.to_owned(), version::Check::must_be(0), vec![created(id)])
+            .await
+            .expect("the event should be appended");
+    }
+
+    let orders: Vec<_> = event_store
+        .stream_category("order-", 0)
+        .map_ok(|evt| evt.event.stream_id)
+        .try_collect()
+        .await
+        .expect("the category should be read");
+
+    assert_eq!(vec!["order-1", "order-2"], orders);
+
+    let mut subscription = event_store.subscribe_category("order-", 0, Duration::from_millis(10));
+
+    for expected in ["order-1", "order-2"] {
+        let evt = subscription
+            .next()
+            .await
+            .expect("the subscription should not end")
+            .expect("the event should be returned");
+
+        assert_eq!(expected, evt.event.stream_id);
+    }
+
+    let (next, _) = tokio::join!(subscription.next(), async {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        for id in ["invoice-2", "order-3"] {
+            event_store
+                .append(id.to_owned(), version::Check::must_be(0), vec![created(id)])
+                .await
+                .expect("the event should be appended");
+        }
+    });
+
+    let next = next
+        .expect("the subscription should not end")
+        .expect("the event should be returned");
+
+    assert_eq!("order-3", next.event.stream_id);
+}
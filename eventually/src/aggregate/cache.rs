@@ -0,0 +1,342 @@
+//! Contains the [Cached] [Repository] decorator, which keeps the most recently
+//! used [Aggregate Root][aggregate::Root]s in memory, and can [preload][Cached::preload]
+//! them ahead of anticipated traffic to avoid a cold-start latency spike.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use futures::stream::{iter, StreamExt, TryStreamExt};
+
+use crate::aggregate::repository::{GetError, Getter, SaveError, Saver};
+use crate::aggregate::{self, Aggregate, Repository};
+
+struct Entries<T>
+where
+    T: Aggregate,
+{
+    roots: HashMap<T::Id, (u64, aggregate::Root<T>)>,
+    // Logical clock of the last access, used to evict the least recently used entry.
+    tick: u64,
+}
+
+/// Decorator type for a [Repository] implementation that caches up to `capacity`
+/// [Aggregate Root][aggregate::Root]s, evicting the least recently used ones.
+///
+/// Cached Aggregate Roots are updated on [save][Saver::save]. If the Aggregate
+/// is also modified through other instances, the cached copy can be stale:
+/// saving it fails with [`SaveError::Conflict`] and evicts it, so that retrying
+/// the operation (e.g. with [`command::Retry`][crate::command::Retry]) loads the latest version.
+///
+/// With the `tracing` feature enabled, each load records whether it has been
+/// served from the cache in the `cache_hit` field of the current span, e.g. the one
+/// of a `tracing::InstrumentedAggregateRepository` wrapping this Repository.
+pub struct Cached<T, R>
+where
+    T: Aggregate,
+{
+    repository: R,
+    capacity: usize,
+    entries: Arc<Mutex<Entries<T>>>,
+}
+
+impl<T, R> Debug for Cached<T, R>
+where
+    T: Aggregate,
+    R: Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Cached")
+            .field("repository", &self.repository)
+            .field("capacity", &self.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, R> Clone for Cached<T, R>
+where
+    T: Aggregate,
+    R: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            repository: self.repository.clone(),
+            capacity: self.capacity,
+            entries: self.entries.clone(),
+        }
+    }
+}
+
+impl<T, R> Cached<T, R>
+where
+    T: Aggregate,
+    T::Id: Clone + Eq + Hash,
+    R: Repository<T>,
+{
+    /// Creates a new [Cached] decorator over the specified [Repository],
+    /// caching at most `capacity` Aggregate Roots.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if `capacity` is zero.
+    pub fn new(repository: R, capacity: usize) -> Self {
+        assert!(capacity > 0, "cache capacity must be greater than zero");
+
+        Self {
+            repository,
+            capacity,
+            entries: Arc::new(Mutex::new(Entries {
+                roots: HashMap::new(),
+                tick: 0,
+            })),
+        }
+    }
+
+    /// Loads the Aggregate Roots with the specified ids, at most `concurrency`
+    /// at a time, and caches them.
+    ///
+    /// Aggregates that do not exist are skipped. Returns the number of cached Aggregate Roots.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the underlying [Repository] fails to load an Aggregate Root:
+    /// the Aggregate Roots loaded until then stay cached.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if `concurrency` is zero, or if the lock on the cache has been poisoned.
+    pub async fn preload<I>(&self, ids: I, concurrency: usize) -> Result<usize, GetError>
+    where
+        I: IntoIterator<Item = T::Id>,
+    {
+        assert!(
+            concurrency > 0,
+            "preload concurrency must be greater than zero"
+        );
+
+        iter(ids)
+            .map(|id| async move {
+                match self.repository.get_including_tombstoned(&id).await {
+                    Ok(root) => Ok(Some(root)),
+                    Err(GetError::NotFound) => Ok(None),
+                    Err(err) => Err(err),
+                }
+            })
+            .buffer_unordered(concurrency)
+            .try_fold(0, |preloaded, root| async move {
+                let Some(root) = root else {
+                    return Ok(preloaded);
+                };
+
+                self.insert(root);
+                Ok(preloaded + 1)
+            })
+            .await
+    }
+
+    /// Returns true if the Aggregate Root with the specified id is cached.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if the lock on the cache has been poisoned.
+    #[must_use]
+    pub fn is_cached(&self, id: &T::Id) -> bool {
+        self.entries
+            .lock()
+            .expect("acquire lock on aggregate roots cache")
+            .roots
+            .contains_key(id)
+    }
+
+    fn lookup(&self, id: &T::Id) -> Option<aggregate::Root<T>> {
+        let mut entries = self
+            .entries
+            .lock()
+            .expect("acquire lock on aggregate roots cache");
+
+        entries.tick += 1;
+        let tick = entries.tick;
+
+        entries.roots.get_mut(id).map(|(last_used, root)| {
+            *last_used = tick;
+            root.clone()
+        })
+    }
+
+    fn insert(&self, root: aggregate::Root<T>) {
+        let mut entries = self
+            .entries
+            .lock()
+            .expect("acquire lock on aggregate roots cache");
+
+        let id = root.aggregate_id().clone();
+
+        if !entries.roots.contains_key(&id) && entries.roots.len() >= self.capacity {
+            let evicted_id = entries
+                .roots
+                .iter()
+                .min_by_key(|(_, (last_used, _))| *last_used)
+                .map(|(evicted_id, _)| evicted_id.clone());
+
+            if let Some(evicted_id) = evicted_id {
+                entries.roots.remove(&evicted_id);
+            }
+        }
+
+        entries.tick += 1;
+        let tick = entries.tick;
+        entries.roots.insert(id, (tick, root));
+    }
+
+    fn evict(&self, id: &T::Id) {
+        self.entries
+            .lock()
+            .expect("acquire lock on aggregate roots cache")
+            .roots
+            .remove(id);
+    }
+}
+
+#[async_trait]
+impl<T, R> Getter<T> for Cached<T, R>
+where
+    T: Aggregate,
+    T::Id: Clone + Eq + Hash,
+    R: Repository<T>,
+{
+    async fn get(&self, id: &T::Id) -> Result<aggregate::Root<T>, GetError> {
+        let root = self.get_including_tombstoned(id).await?;
+
+        if root.is_tombstoned() {
+            return Err(GetError::NotFound);
+        }
+
+        Ok(root)
+    }
+
+    async fn get_including_tombstoned(&self, id: &T::Id) -> Result<aggregate::Root<T>, GetError> {
+        let cached_root = self.lookup(id);
+
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("cache_hit", cached_root.is_some());
+
+        if let Some(root) = cached_root {
+            return Ok(root);
+        }
+
+        let root = self.repository.get_including_tombstoned(id).await?;
+        self.insert(root.clone());
+
+        Ok(root)
+    }
+}
+
+#[async_trait]
+impl<T, R> Saver<T> for Cached<T, R>
+where
+    T: Aggregate,
+    T::Id: Clone + Eq + Hash,
+    R: Repository<T>,
+{
+    async fn save(&self, root: &mut aggregate::Root<T>) -> Result<(), SaveError> {
+        match self.repository.save(root).await {
+            Ok(()) => {
+                self.insert(root.clone());
+                Ok(())
+            },
+            Err(err) => {
+                self.evict(root.aggregate_id());
+                Err(err)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::test_user_domain::{User, UserEvent};
+    use crate::event::store::{Appender, InMemory};
+    use crate::{event, version};
+
+    #[tokio::test]
+    async fn cached_repository_preloads_and_evicts_stale_aggregate_roots() {
+        let event_store = InMemory::<String, UserEvent>::default();
+        let repository = Cached::new(
+            aggregate::EventSourcedRepository::<User, _>::from(event_store.clone()),
+            2,
+        );
+
+        for email in ["a@email.com", "b@email.com"] {
+            let mut user = aggregate::Root::<User>::create(email.to_owned(), "secret".to_owned())
+                .expect("user should be created successfully");
+
+            aggregate::EventSourcedRepository::<User, _>::from(event_store.clone())
+                .save(&mut user)
+                .await
+                .expect("user should be saved successfully");
+        }
+
+        let preloaded = repository
+            .preload(
+                ["a@email.com", "b@email.com", "missing@email.com"].map(str::to_owned),
+                2,
+            )
+            .await
+            .expect("users should be preloaded");
+
+        assert_eq!(2, preloaded);
+
+        // Another writer changes the password, bypassing the cache.
+        event_store
+            .append(
+                "a@email.com".to_owned(),
+                version::Check::must_be(1),
+                vec![event::Envelope::from(UserEvent::PasswordWasChanged {
+                    password: "changed".to_owned(),
+                })],
+            )
+            .await
+            .expect("append should not fail");
+
+        let mut user = repository
+            .get(&"a@email.com".to_owned())
+            .await
+            .expect("user should be served from the cache");
+
+        assert_eq!(1, user.version());
+
+        user.change_password("stale".to_owned())
+            .expect("password should be changed");
+
+        let err = repository
+            .save(&mut user)
+            .await
+            .expect_err("saving a stale aggregate root should fail");
+
+        assert!(matches!(err, SaveError::Conflict(_)));
+        assert!(!repository.is_cached(&"a@email.com".to_owned()));
+
+        let user = repository
+            .get(&"a@email.com".to_owned())
+            .await
+            .expect("user should be loaded again");
+
+        assert_eq!(2, user.version());
+
+        let mut user =
+            aggregate::Root::<User>::create("c@email.com".to_owned(), "secret".to_owned())
+                .expect("user should be created successfully");
+
+        repository
+            .save(&mut user)
+            .await
+            .expect("user should be saved successfully");
+
+        // The least recently used user has been evicted to make room.
+        assert!(!repository.is_cached(&"b@email.com".to_owned()));
+        assert!(repository.is_cached(&"c@email.com".to_owned()));
+    }
+}
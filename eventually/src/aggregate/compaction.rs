@@ -0,0 +1,106 @@
+//! Module containing the [Compaction] trait, used by [Aggregate]s to declare
+//! which Domain Events supersede earlier ones in their Event Stream,
+//! and the [compact] function, which uses it to shrink an Event Stream
+//! without changing the resulting [Aggregate] state.
+//!
+//! Compaction is meant to be run offline, e.g. by a maintenance job that
+//! rewrites large Event Streams.
+
+use crate::aggregate::Aggregate;
+use crate::event;
+
+/// An [Aggregate] which Domain Events can supersede earlier Domain Events
+/// in the same Event Stream.
+///
+/// A Domain Event supersedes an earlier one when applying the earlier Domain Event
+/// has no observable effect on the [Aggregate] state once the latter has been applied,
+/// e.g. a `ProfileUpdated` event that replaces the whole profile set by
+/// a previous `ProfileUpdated` event.
+pub trait Compaction: Aggregate {
+    /// Returns true if the `event` Domain Event supersedes the `previous` one,
+    /// which has been recorded earlier in the same Event Stream.
+    fn supersedes(event: &Self::Event, previous: &Self::Event) -> bool;
+}
+
+/// Compacts the Domain Events of an [Aggregate] Event Stream, removing all the
+/// Domain Events that have been superseded by a later one, as specified by
+/// [`Compaction::supersedes`].
+///
+/// The order of the remaining Domain Events is preserved.
+///
+/// **Please note**: the compacted Event Stream contains less Domain Events than
+/// the original one, so the [Aggregate Root][crate::aggregate::Root] version
+/// will be different after compaction.
+pub fn compact<T>(
+    events: impl IntoIterator<Item = event::Envelope<T::Event>>,
+) -> Vec<event::Envelope<T::Event>>
+where
+    T: Compaction,
+{
+    let events: Vec<_> = events.into_iter().collect();
+    let mut superseded = vec![false; events.len()];
+
+    for (i, event) in events.iter().enumerate() {
+        for (j, previous) in events[..i].iter().enumerate() {
+            if !superseded[j] && T::supersedes(&event.message, &previous.message) {
+                superseded[j] = true;
+            }
+        }
+    }
+
+    events
+        .into_iter()
+        .zip(superseded)
+        .filter_map(|(event, superseded)| (!superseded).then_some(event))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate;
+    use crate::aggregate::test_user_domain::{User, UserEvent};
+
+    impl Compaction for User {
+        fn supersedes(event: &Self::Event, previous: &Self::Event) -> bool {
+            matches!(
+                (event, previous),
+                (
+                    UserEvent::PasswordWasChanged { .. },
+                    UserEvent::PasswordWasChanged { .. }
+                )
+            )
+        }
+    }
+
+    #[test]
+    fn compaction_removes_superseded_events_and_preserves_state() {
+        let events: Vec<event::Envelope<UserEvent>> = vec![
+            UserEvent::WasCreated {
+                email: "test@email.com".to_owned(),
+                password: "password-1".to_owned(),
+            }
+            .into(),
+            UserEvent::PasswordWasChanged {
+                password: "password-2".to_owned(),
+            }
+            .into(),
+            UserEvent::PasswordWasChanged {
+                password: "password-3".to_owned(),
+            }
+            .into(),
+        ];
+
+        let compacted = compact::<User>(events.clone());
+
+        assert_eq!(vec![events[0].clone(), events[2].clone()], compacted);
+
+        let state = |events: Vec<event::Envelope<UserEvent>>| {
+            aggregate::Root::<User>::rehydrate(events.into_iter())
+                .expect("rehydration should not fail")
+                .map(|root| format!("{:?}", root.to_aggregate_type::<User>()))
+        };
+
+        assert_eq!(state(events), state(compacted));
+    }
+}
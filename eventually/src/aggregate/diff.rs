@@ -0,0 +1,209 @@
+//! Module containing the [diff] function, which computes a structural [Diff]
+//! between two [Aggregate][crate::aggregate::Aggregate] states, e.g. before and after
+//! applying some Domain Events.
+//!
+//! States are compared through their [`serde::Serialize`] representation, so that
+//! the resulting [Diff] can be used both for readable test failures and audit logging.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A single change between two states, identified by the
+/// [JSON Pointer](https://datatracker.ietf.org/doc/html/rfc6901) of the changed value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Change {
+    /// A value that is present only in the new state.
+    Added {
+        /// The path of the value.
+        path: String,
+        /// The added value.
+        value: Value,
+    },
+    /// A value that is present only in the old state.
+    Removed {
+        /// The path of the value.
+        path: String,
+        /// The removed value.
+        value: Value,
+    },
+    /// A value that is present in both states, with different content.
+    Changed {
+        /// The path of the value.
+        path: String,
+        /// The value in the old state.
+        before: Value,
+        /// The value in the new state.
+        after: Value,
+    },
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Change::Added { path, value } => write!(f, "+ {path}: {value}"),
+            Change::Removed { path, value } => write!(f, "- {path}: {value}"),
+            Change::Changed {
+                path,
+                before,
+                after,
+            } => write!(f, "~ {path}: {before} -> {after}"),
+        }
+    }
+}
+
+/// The structural difference between two states, as a list of [Change]s
+/// sorted by the order in which they are found in the states.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Diff(Vec<Change>);
+
+impl Diff {
+    /// Returns true if the two states are equal.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the list of [Change]s between the two states.
+    #[must_use]
+    pub fn changes(&self) -> &[Change] {
+        &self.0
+    }
+}
+
+impl Display for Diff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        for change in &self.0 {
+            writeln!(f, "{change}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Computes the structural [Diff] between the `before` and `after` states.
+///
+/// Objects are compared field by field, and arrays element by element;
+/// any other value is compared as a whole.
+///
+/// # Errors
+///
+/// An error is returned if any of the two states could not be serialized.
+pub fn diff<T>(before: &T, after: &T) -> Result<Diff, serde_json::Error>
+where
+    T: Serialize + ?Sized,
+{
+    let mut changes = Vec::new();
+
+    compare(
+        String::new(),
+        serde_json::to_value(before)?,
+        serde_json::to_value(after)?,
+        &mut changes,
+    );
+
+    Ok(Diff(changes))
+}
+
+fn compare(path: String, before: Value, after: Value, changes: &mut Vec<Change>) {
+    match (before, after) {
+        (before, after) if before == after => {},
+        (Value::Object(mut before), Value::Object(after)) => {
+            for (key, after) in after {
+                let path = format!("{path}/{}", escape(&key));
+
+                match before.remove(&key) {
+                    Some(before) => compare(path, before, after, changes),
+                    None => changes.push(Change::Added { path, value: after }),
+                }
+            }
+
+            for (key, before) in before {
+                let path = format!("{path}/{}", escape(&key));
+                changes.push(Change::Removed {
+                    path,
+                    value: before,
+                });
+            }
+        },
+        (Value::Array(before), Value::Array(after)) => {
+            let mut before = before.into_iter();
+            let mut after = after.into_iter();
+
+            for i in 0.. {
+                let path = format!("{path}/{i}");
+
+                match (before.next(), after.next()) {
+                    (Some(before), Some(after)) => compare(path, before, after, changes),
+                    (None, Some(value)) => changes.push(Change::Added { path, value }),
+                    (Some(value), None) => changes.push(Change::Removed { path, value }),
+                    (None, None) => break,
+                }
+            }
+        },
+        (before, after) => changes.push(Change::Changed {
+            path,
+            before,
+            after,
+        }),
+    }
+}
+
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn diff_reports_added_removed_and_changed_values() {
+        let before = json!({
+            "email": "test@email.com",
+            "roles": ["admin", "user"],
+            "address": { "city": "Berlin" },
+        });
+
+        let after = json!({
+            "email": "test@email.com",
+            "roles": ["owner"],
+            "address": { "city": "Munich", "zip/code": "80331" },
+        });
+
+        let diff = diff(&before, &after).expect("diff should not fail");
+
+        assert_eq!(
+            vec![
+                Change::Changed {
+                    path: "/address/city".to_owned(),
+                    before: json!("Berlin"),
+                    after: json!("Munich"),
+                },
+                Change::Added {
+                    path: "/address/zip~1code".to_owned(),
+                    value: json!("80331"),
+                },
+                Change::Changed {
+                    path: "/roles/0".to_owned(),
+                    before: json!("admin"),
+                    after: json!("owner"),
+                },
+                Change::Removed {
+                    path: "/roles/1".to_owned(),
+                    value: json!("user"),
+                },
+            ],
+            diff.changes()
+        );
+
+        assert!(super::diff(&before, &before)
+            .expect("diff should not fail")
+            .is_empty());
+    }
+}
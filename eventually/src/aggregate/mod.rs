@@ -25,11 +25,20 @@
 //! Aggregates should provide a way to **fold** Domain Events on the
 //! current value of the state, to produce the next state.
 
+use serde::{Deserialize, Serialize};
+
 use crate::version::Version;
 use crate::{event, message};
 
+pub mod cache;
+pub mod compaction;
+#[cfg(feature = "aggregate-diff")]
+pub mod diff;
+pub mod rebase;
 pub mod repository;
+pub mod stream_name;
 pub mod test;
+pub mod timeline;
 
 use futures::TryStreamExt;
 pub use repository::{EventSourced as EventSourcedRepository, Repository};
@@ -71,6 +80,63 @@ pub trait Aggregate: Sized + Send + Sync + Clone {
     /// The method can return an error if the event to apply is unexpected
     /// given the current state of the Aggregate.
     fn apply(state: Option<Self>, event: Self::Event) -> Result<Self, Self::Error>;
+
+    /// Returns whether the Aggregate has been _tombstoned_, i.e. soft-deleted,
+    /// by one of its own Domain Events.
+    ///
+    /// Tombstoned Aggregates are reported as not found by [`Repository`] implementations,
+    /// while their history is preserved: they can be loaded again with
+    /// [`Getter::get_including_tombstoned`][repository::Getter::get_including_tombstoned]
+    /// and brought back with [`Root::resurrect`].
+    ///
+    /// Override this method only if the tombstone is part of the Domain:
+    /// otherwise, use the framework-level [`StreamTombstoned`] marker
+    /// through [`Root::tombstone`].
+    ///
+    /// Aggregates are never tombstoned by their own Domain Events by default.
+    fn is_tombstoned(&self) -> bool {
+        false
+    }
+}
+
+/// The framework-level Domain Event recorded by [`Root::tombstone`] to tombstone
+/// an Aggregate, preserving its Event Stream.
+///
+/// The marker is recognized by its [name][message::Message::name] while recording and rehydrating
+/// an Aggregate [Root], and is never passed to [`Aggregate::apply`].
+/// To use it, add a variant to the Aggregate Domain Event type that converts
+/// [`From<StreamTombstoned>`] and is named [`StreamTombstoned::NAME`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct StreamTombstoned;
+
+impl StreamTombstoned {
+    /// The name of the [`StreamTombstoned`] marker.
+    pub const NAME: &'static str = "StreamTombstoned";
+}
+
+impl message::Message for StreamTombstoned {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+}
+
+/// The framework-level Domain Event that brings back an Aggregate tombstoned
+/// with [`StreamTombstoned`], to be recorded with [`Root::resurrect`].
+///
+/// Like [`StreamTombstoned`], the marker is recognized by its [name][message::Message::name],
+/// [`StreamResurrected::NAME`], and is never passed to [`Aggregate::apply`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct StreamResurrected;
+
+impl StreamResurrected {
+    /// The name of the [`StreamResurrected`] marker.
+    pub const NAME: &'static str = "StreamResurrected";
+}
+
+impl message::Message for StreamResurrected {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
 }
 
 /// An Aggregate Root represents the Domain Entity object used to
@@ -123,7 +189,11 @@ pub trait Aggregate: Sized + Send + Sync + Clone {
 ///     }
 /// }
 /// ```
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "T: Serialize, T::Event: Serialize",
+    deserialize = "T: Deserialize<'de>, T::Event: Deserialize<'de>"
+))]
 #[must_use]
 pub struct Root<T>
 where
@@ -132,6 +202,8 @@ where
     aggregate: T,
     version: Version,
     recorded_events: Vec<event::Envelope<T::Event>>,
+    #[serde(default)]
+    stream_tombstoned: bool,
 }
 
 impl<T> std::ops::Deref for Root<T>
@@ -171,6 +243,12 @@ where
         K::from(self.aggregate.clone())
     }
 
+    /// Returns the number of uncommitted, recorded Domain [Event]s in the [Root].
+    #[cfg(feature = "tracing")]
+    pub(crate) fn uncommitted_events_len(&self) -> usize {
+        self.recorded_events.len()
+    }
+
     /// Returns the list of uncommitted, recorded Domain [Event]s from the [Root]
     /// and resets the internal list to its default value.
     #[doc(hidden)]
@@ -203,6 +281,7 @@ where
             version: 1,
             aggregate: T::apply(None, event.message.clone())?,
             recorded_events: vec![event],
+            stream_tombstoned: false,
         })
     }
 
@@ -234,7 +313,108 @@ where
     /// The method can return an error if the event to apply is unexpected
     /// given the current state of the Aggregate.
     pub fn record_that(&mut self, event: event::Envelope<T::Event>) -> Result<(), T::Error> {
-        self.aggregate = T::apply(Some(self.aggregate.clone()), event.message.clone())?;
+        self.aggregate = Self::apply_event(
+            self.aggregate.clone(),
+            &mut self.stream_tombstoned,
+            event.message.clone(),
+        )?;
+        self.recorded_events.push(event);
+        self.version += 1;
+
+        Ok(())
+    }
+
+    /// Applies a Domain Event recorded concurrently by another writer,
+    /// without recording it as uncommitted.
+    pub(crate) fn rebase_on(&mut self, event: T::Event) -> Result<(), T::Error> {
+        self.aggregate =
+            Self::apply_event(self.aggregate.clone(), &mut self.stream_tombstoned, event)?;
+        self.version += 1;
+
+        Ok(())
+    }
+
+    /// Applies a Domain Event to the [Aggregate] state, unless it is one of the
+    /// [`StreamTombstoned`] or [`StreamResurrected`] markers, which only
+    /// update the tombstone flag of the [Root].
+    fn apply_event(
+        aggregate: T,
+        stream_tombstoned: &mut bool,
+        event: T::Event,
+    ) -> Result<T, T::Error> {
+        match message::Message::name(&event) {
+            StreamTombstoned::NAME => *stream_tombstoned = true,
+            StreamResurrected::NAME => *stream_tombstoned = false,
+            _ => return T::apply(Some(aggregate), event),
+        }
+
+        Ok(aggregate)
+    }
+
+    /// Returns whether the [Aggregate] has been tombstoned, either with the
+    /// [`StreamTombstoned`] marker or as reported by [`Aggregate::is_tombstoned`].
+    pub fn is_tombstoned(&self) -> bool {
+        self.stream_tombstoned || self.aggregate.is_tombstoned()
+    }
+
+    /// Returns whether the [Aggregate] has been tombstoned with the [`StreamTombstoned`] marker.
+    /// Useful for [Repository] implementations storing the [Aggregate] state.
+    #[doc(hidden)]
+    pub fn is_stream_tombstoned(&self) -> bool {
+        self.stream_tombstoned
+    }
+
+    /// Tombstones the [Aggregate] [Root], by recording the framework-level
+    /// [`StreamTombstoned`] marker. Does nothing if the [Aggregate] is already tombstoned.
+    ///
+    /// Use [`Root::resurrect`] with the [`StreamResurrected`] marker to bring it back.
+    pub fn tombstone(&mut self)
+    where
+        T::Event: From<StreamTombstoned>,
+    {
+        if self.is_tombstoned() {
+            return;
+        }
+
+        self.stream_tombstoned = true;
+        self.recorded_events
+            .push(event::Envelope::from(T::Event::from(StreamTombstoned)));
+        self.version += 1;
+    }
+
+    /// Resurrects a tombstoned [Aggregate] [Root], by recording the specified
+    /// Domain Event that brings the [Aggregate] back: [`StreamResurrected`],
+    /// if it has been tombstoned with [`Root::tombstone`].
+    ///
+    /// The [Root] is left untouched if the method fails.
+    ///
+    /// # Errors
+    ///
+    /// The method returns an error if the [Aggregate] is not tombstoned,
+    /// if the Domain Event cannot be applied, or if the [Aggregate]
+    /// would still be tombstoned after applying it.
+    pub fn resurrect(
+        &mut self,
+        event: event::Envelope<T::Event>,
+    ) -> Result<(), ResurrectError<T::Error>> {
+        if !self.is_tombstoned() {
+            return Err(ResurrectError::NotTombstoned);
+        }
+
+        let mut stream_tombstoned = self.stream_tombstoned;
+        let aggregate = Self::apply_event(
+            self.aggregate.clone(),
+            &mut stream_tombstoned,
+            event.message.clone(),
+        )
+        .map_err(ResurrectError::Domain)?;
+
+        if stream_tombstoned || aggregate.is_tombstoned() {
+            return Err(ResurrectError::StillTombstoned);
+        }
+
+        self.aggregate = aggregate;
+        self.stream_tombstoned = stream_tombstoned;
         self.recorded_events.push(event);
         self.version += 1;
 
@@ -242,19 +422,36 @@ where
     }
 }
 
+/// List of possible errors that can be returned by [`Root::resurrect`].
+#[derive(Debug, thiserror::Error)]
+pub enum ResurrectError<T> {
+    /// Error returned when the [Aggregate] to resurrect has not been tombstoned.
+    #[error("failed to resurrect aggregate: not tombstoned")]
+    NotTombstoned,
+
+    /// Error returned when the Domain Event used to resurrect the [Aggregate]
+    /// has left it tombstoned.
+    #[error("failed to resurrect aggregate: still tombstoned after the domain event")]
+    StillTombstoned,
+
+    /// Error returned when applying the Domain Event through [`Aggregate::apply`] fails.
+    #[error("failed to apply domain event while resurrecting aggregate: {0}")]
+    Domain(#[source] T),
+}
+
 /// List of possible errors that can be returned by [`Root::rehydrate_async`].
 #[derive(Debug, thiserror::Error)]
 pub enum RehydrateError<T, I> {
     /// Error returned during rehydration when the [Aggregate Root][Root]
-    /// is applying a Domain Event using [Aggregate::apply].
+    /// is applying a Domain Event using [`Aggregate::apply`].
     ///
     /// This usually implies the Event Stream for the [Aggregate]
     /// contains corrupted or unexpected data.
     #[error("failed to apply domain event while rehydrating aggregate: {0}")]
     Domain(#[source] T),
 
-    /// This error is returned by [Root::rehydrate_async] when the underlying
-    /// [futures::TryStream] has returned an error.
+    /// This error is returned by [`Root::rehydrate_async`] when the underlying
+    /// [`futures::TryStream`] has returned an error.
     #[error("failed to rehydrate aggregate from event stream: {0}")]
     Inner(#[source] I),
 }
@@ -271,9 +468,19 @@ where
             version,
             aggregate,
             recorded_events: Vec::default(),
+            stream_tombstoned: false,
         }
     }
 
+    /// Sets whether the rehydrated [Aggregate] Root has been tombstoned with
+    /// the [`StreamTombstoned`] marker, as reported by [`Root::is_stream_tombstoned`].
+    /// Useful for [Repository] implementations outside the [EventSourcedRepository] one.
+    #[doc(hidden)]
+    pub fn with_stream_tombstoned(mut self, stream_tombstoned: bool) -> Root<T> {
+        self.stream_tombstoned = stream_tombstoned;
+        self
+    }
+
     /// Rehydrates an [Aggregate Root][Root] from a stream of Domain Events.
     #[doc(hidden)]
     pub(crate) fn rehydrate(
@@ -320,6 +527,7 @@ where
             version: 1,
             aggregate: T::apply(None, event.message)?,
             recorded_events: Vec::default(),
+            stream_tombstoned: false,
         })
     }
 
@@ -335,7 +543,8 @@ where
         mut self,
         event: event::Envelope<T::Event>,
     ) -> Result<Root<T>, T::Error> {
-        self.aggregate = T::apply(Some(self.aggregate), event.message)?;
+        self.aggregate =
+            Self::apply_event(self.aggregate, &mut self.stream_tombstoned, event.message)?;
         self.version += 1;
 
         Ok(self)
@@ -349,16 +558,33 @@ where
 pub(crate) mod test_user_domain {
     use crate::{aggregate, message};
 
-    #[derive(Debug, Clone)]
+    #[derive(Debug, Clone, serde::Serialize)]
     pub(crate) struct User {
         email: String,
         password: String,
+        deleted: bool,
     }
 
     #[derive(Debug, Clone, PartialEq, Eq)]
     pub(crate) enum UserEvent {
         WasCreated { email: String, password: String },
         PasswordWasChanged { password: String },
+        WasDeleted,
+        WasRestored,
+        StreamTombstoned,
+        StreamResurrected,
+    }
+
+    impl From<aggregate::StreamTombstoned> for UserEvent {
+        fn from(_: aggregate::StreamTombstoned) -> Self {
+            UserEvent::StreamTombstoned
+        }
+    }
+
+    impl From<aggregate::StreamResurrected> for UserEvent {
+        fn from(_: aggregate::StreamResurrected) -> Self {
+            UserEvent::StreamResurrected
+        }
     }
 
     impl message::Message for UserEvent {
@@ -366,10 +592,25 @@ pub(crate) mod test_user_domain {
             match self {
                 UserEvent::WasCreated { .. } => "UserWasCreated",
                 UserEvent::PasswordWasChanged { .. } => "UserPasswordWasChanged",
+                UserEvent::WasDeleted => "UserWasDeleted",
+                UserEvent::WasRestored => "UserWasRestored",
+                UserEvent::StreamTombstoned => aggregate::StreamTombstoned::NAME,
+                UserEvent::StreamResurrected => aggregate::StreamResurrected::NAME,
             }
         }
     }
 
+    impl message::Registry for UserEvent {
+        const NAMES: &'static [&'static str] = &[
+            "UserWasCreated",
+            "UserPasswordWasChanged",
+            "UserWasDeleted",
+            "UserWasRestored",
+            aggregate::StreamTombstoned::NAME,
+            aggregate::StreamResurrected::NAME,
+        ];
+    }
+
     #[derive(Debug, thiserror::Error)]
     pub(crate) enum UserError {
         #[error("provided email was empty")]
@@ -380,6 +621,8 @@ pub(crate) mod test_user_domain {
         NotYetCreated,
         #[error("user was already created")]
         AlreadyCreated,
+        #[error("user was deleted")]
+        Deleted,
     }
 
     impl aggregate::Aggregate for User {
@@ -398,18 +641,36 @@ pub(crate) mod test_user_domain {
         fn apply(state: Option<Self>, event: Self::Event) -> Result<Self, Self::Error> {
             match state {
                 None => match event {
-                    UserEvent::WasCreated { email, password } => Ok(User { email, password }),
-                    UserEvent::PasswordWasChanged { .. } => Err(UserError::NotYetCreated),
+                    UserEvent::WasCreated { email, password } => Ok(User {
+                        email,
+                        password,
+                        deleted: false,
+                    }),
+                    _ => Err(UserError::NotYetCreated),
                 },
                 Some(mut state) => match event {
                     UserEvent::PasswordWasChanged { password } => {
                         state.password = password;
                         Ok(state)
                     },
+                    UserEvent::WasDeleted => {
+                        state.deleted = true;
+                        Ok(state)
+                    },
+                    UserEvent::WasRestored => {
+                        state.deleted = false;
+                        Ok(state)
+                    },
                     UserEvent::WasCreated { .. } => Err(UserError::AlreadyCreated),
+                    // The tombstone markers are never applied to the Aggregate.
+                    UserEvent::StreamTombstoned | UserEvent::StreamResurrected => Ok(state),
                 },
             }
         }
+
+        fn is_tombstoned(&self) -> bool {
+            self.deleted
+        }
     }
 
     impl aggregate::Root<User> {
@@ -434,6 +695,14 @@ pub(crate) mod test_user_domain {
 
             Ok(())
         }
+
+        pub(crate) fn delete(&mut self) -> Result<(), UserError> {
+            if self.is_tombstoned() {
+                return Err(UserError::Deleted);
+            }
+
+            self.record_that(UserEvent::WasDeleted.into())
+        }
     }
 }
 
@@ -442,9 +711,11 @@ pub(crate) mod test_user_domain {
 mod tests {
     use std::error::Error;
 
+    use futures::TryStreamExt;
+
     use crate::aggregate::repository::{Getter, Saver};
     use crate::aggregate::test_user_domain::{User, UserEvent};
-    use crate::event::store::EventStoreExt;
+    use crate::event::store::{EventStoreExt, Streamer};
     use crate::{aggregate, event, version};
 
     #[tokio::test]
@@ -556,7 +827,147 @@ mod tests {
         {
             assert!(error
                 .source()
-                .map_or(false, |src| src.is::<version::ConflictError>()));
+                .is_some_and(|src| src.is::<version::ConflictError>()));
         }
     }
+
+    #[tokio::test]
+    async fn repository_treats_tombstoned_aggregate_roots_as_not_found_until_resurrected() {
+        let event_store = event::store::InMemory::<String, UserEvent>::default();
+        let user_repository =
+            aggregate::EventSourcedRepository::<User, _>::from(event_store.clone());
+
+        let email = "test@email.com".to_owned();
+
+        let mut user = aggregate::Root::<User>::create(email.clone(), "not-a-secret".to_owned())
+            .expect("user should be created successfully");
+
+        assert!(matches!(
+            user.resurrect(UserEvent::WasRestored.into()),
+            Err(aggregate::ResurrectError::NotTombstoned)
+        ));
+
+        user.delete().expect("user should be deleted successfully");
+
+        user_repository
+            .save(&mut user)
+            .await
+            .expect("user should be saved successfully");
+
+        assert!(matches!(
+            user_repository.get(&email).await,
+            Err(aggregate::repository::GetError::NotFound)
+        ));
+
+        let mut user = user_repository
+            .get_including_tombstoned(&email)
+            .await
+            .expect("tombstoned user should be retrieved from the repository");
+
+        assert!(user.is_tombstoned());
+        assert!(matches!(
+            user.resurrect(UserEvent::WasDeleted.into()),
+            Err(aggregate::ResurrectError::StillTombstoned)
+        ));
+        assert_eq!(2, user.version());
+
+        user.resurrect(UserEvent::WasRestored.into())
+            .expect("user should be resurrected successfully");
+
+        user_repository
+            .save(&mut user)
+            .await
+            .expect("user should be saved successfully");
+
+        let user = user_repository
+            .get(&email)
+            .await
+            .expect("resurrected user should be retrieved from the repository");
+
+        assert_eq!(3, user.version());
+        assert_eq!(
+            Some(3),
+            event_store
+                .head_version(&email)
+                .await
+                .expect("head version should be returned")
+        );
+    }
+
+    #[tokio::test]
+    async fn repository_treats_aggregate_roots_with_a_stream_tombstone_as_not_found_until_resurrected(
+    ) {
+        let event_store = event::store::InMemory::<String, UserEvent>::default();
+        let user_repository =
+            aggregate::EventSourcedRepository::<User, _>::from(event_store.clone());
+
+        let email = "test@email.com".to_owned();
+
+        let mut user = aggregate::Root::<User>::create(email.clone(), "not-a-secret".to_owned())
+            .expect("user should be created successfully");
+
+        user.tombstone();
+        user.tombstone();
+
+        user_repository
+            .save(&mut user)
+            .await
+            .expect("user should be saved successfully");
+
+        assert!(matches!(
+            user_repository.get(&email).await,
+            Err(aggregate::repository::GetError::NotFound)
+        ));
+
+        let mut user = user_repository
+            .get_including_tombstoned(&email)
+            .await
+            .expect("tombstoned user should be retrieved from the repository");
+
+        assert!(user.is_tombstoned());
+        assert_eq!(2, user.version());
+        assert!(matches!(
+            user.resurrect(
+                UserEvent::PasswordWasChanged {
+                    password: "new-password".to_owned()
+                }
+                .into()
+            ),
+            Err(aggregate::ResurrectError::StillTombstoned)
+        ));
+
+        user.resurrect(UserEvent::from(aggregate::StreamResurrected).into())
+            .expect("user should be resurrected successfully");
+
+        user_repository
+            .save(&mut user)
+            .await
+            .expect("user should be saved successfully");
+
+        let user = user_repository
+            .get(&email)
+            .await
+            .expect("resurrected user should be retrieved from the repository");
+
+        assert_eq!(3, user.version());
+
+        let events: Vec<_> = event_store
+            .stream(&email, event::VersionSelect::All)
+            .map_ok(|persisted| persisted.event.message)
+            .try_collect()
+            .await
+            .expect("the event stream should be read");
+
+        assert_eq!(
+            vec![
+                UserEvent::WasCreated {
+                    email,
+                    password: "not-a-secret".to_owned()
+                },
+                UserEvent::StreamTombstoned,
+                UserEvent::StreamResurrected,
+            ],
+            events
+        );
+    }
 }
@@ -0,0 +1,288 @@
+//! Module containing the [Commutation] trait, used by [Aggregate]s to declare
+//! which Domain Events commute, i.e. do not conflict when recorded concurrently,
+//! and the [Rebasing] [Repository][aggregate::Repository], which uses it to resolve
+//! version conflicts without handling the Domain Command again.
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use futures::TryStreamExt;
+
+use crate::aggregate::repository::{EventSourced, GetError, Getter, SaveError, Saver};
+use crate::aggregate::{self, Aggregate};
+use crate::{event, version};
+
+/// An [Aggregate] which Domain Events can commute with the ones recorded
+/// concurrently on the same Event Stream.
+///
+/// Two Domain Events commute when applying them in either order results in
+/// the same [Aggregate] state, and neither of them would have been rejected
+/// by the Aggregate invariants had the other one been applied first,
+/// e.g. two `ItemWasAdded` events on a shopping cart with no item limit.
+pub trait Commutation: Aggregate {
+    /// Returns true if the `event` Domain Event, recorded by the [Aggregate Root][aggregate::Root]
+    /// being saved, commutes with the `concurrent` one, appended by another writer in the meantime.
+    fn commutes(event: &Self::Event, concurrent: &Self::Event) -> bool;
+}
+
+/// An Event-sourced [Repository][aggregate::Repository] that, on version conflict,
+/// _rebases_ the Domain Events of the [Aggregate Root][aggregate::Root] being saved
+/// on the ones appended concurrently, if they all commute according to [`Commutation::commutes`].
+///
+/// Rebased Domain Events are appended at the new Event Stream version, and the
+/// concurrent Domain Events are applied to the Aggregate Root, so that it reflects
+/// the latest state. Otherwise, [`SaveError::Conflict`] is returned as usual,
+/// and the Domain Command should be handled again, e.g. with [`command::Retry`][crate::command::Retry].
+pub struct Rebasing<T, S>
+where
+    T: Aggregate,
+    S: event::Store<T::Id, T::Event>,
+{
+    repository: EventSourced<T, S>,
+    max_rebases: usize,
+}
+
+impl<T, S> Debug for Rebasing<T, S>
+where
+    T: Aggregate,
+    S: event::Store<T::Id, T::Event> + Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Rebasing")
+            .field("store", &self.repository.store)
+            .field("max_rebases", &self.max_rebases)
+            .finish()
+    }
+}
+
+impl<T, S> Rebasing<T, S>
+where
+    T: Aggregate,
+    S: event::Store<T::Id, T::Event>,
+{
+    /// Creates a new [Rebasing] Repository over the specified Event Store,
+    /// rebasing at most `max_rebases` times per save.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if `max_rebases` is zero.
+    pub fn new(store: S, max_rebases: usize) -> Self {
+        assert!(max_rebases > 0, "at least one rebase must be allowed");
+
+        Self {
+            repository: EventSourced::from(store),
+            max_rebases,
+        }
+    }
+}
+
+#[async_trait]
+impl<T, S> Getter<T> for Rebasing<T, S>
+where
+    T: Aggregate,
+    T::Id: Clone,
+    T::Error: std::error::Error + Send + Sync + 'static,
+    S: event::Store<T::Id, T::Event>,
+    <S as event::store::Streamer<T::Id, T::Event>>::Error:
+        std::error::Error + Send + Sync + 'static,
+{
+    async fn get(&self, id: &T::Id) -> Result<aggregate::Root<T>, GetError> {
+        self.repository.get(id).await
+    }
+
+    async fn get_including_tombstoned(&self, id: &T::Id) -> Result<aggregate::Root<T>, GetError> {
+        self.repository.get_including_tombstoned(id).await
+    }
+}
+
+#[async_trait]
+impl<T, S> Saver<T> for Rebasing<T, S>
+where
+    T: Commutation,
+    T::Id: Clone,
+    T::Error: std::error::Error + Send + Sync + 'static,
+    S: event::Store<T::Id, T::Event>,
+    <S as event::store::Streamer<T::Id, T::Event>>::Error:
+        std::error::Error + Send + Sync + 'static,
+{
+    async fn save(&self, root: &mut aggregate::Root<T>) -> Result<(), SaveError> {
+        let events_to_commit = root.take_uncommitted_events();
+        let aggregate_id = root.aggregate_id().clone();
+        let store = &self.repository.store;
+
+        if events_to_commit.is_empty() {
+            return Ok(());
+        }
+
+        let mut expected_version = root.version() - (events_to_commit.len() as version::Version);
+        let mut rebases = 0;
+
+        loop {
+            let conflict = match store
+                .append(
+                    aggregate_id.clone(),
+                    version::Check::loaded(expected_version),
+                    events_to_commit.clone(),
+                )
+                .await
+            {
+                Ok(_) => return Ok(()),
+                Err(event::store::AppendError::Conflict(err)) => err,
+                Err(event::store::AppendError::Internal(err)) => {
+                    return Err(SaveError::Internal(err))
+                },
+            };
+
+            if rebases == self.max_rebases {
+                return Err(SaveError::Conflict(conflict));
+            }
+
+            let concurrent_events: Vec<_> = store
+                .stream(
+                    &aggregate_id,
+                    event::VersionSelect::From(expected_version + 1),
+                )
+                .map_ok(|persisted| persisted.event.message)
+                .try_collect()
+                .await
+                .map_err(anyhow::Error::from)?;
+
+            let commute = events_to_commit.iter().all(|event| {
+                concurrent_events
+                    .iter()
+                    .all(|concurrent| T::commutes(&event.message, concurrent))
+            });
+
+            if concurrent_events.is_empty() || !commute {
+                return Err(SaveError::Conflict(conflict));
+            }
+
+            for concurrent in concurrent_events {
+                root.rebase_on(concurrent).map_err(anyhow::Error::from)?;
+                expected_version += 1;
+            }
+
+            rebases += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::store::InMemory;
+    use crate::message;
+
+    #[derive(Debug, Clone)]
+    struct Cart {
+        id: String,
+        items: u32,
+        checked_out: bool,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum CartEvent {
+        WasCreated { id: String },
+        ItemWasAdded,
+        WasCheckedOut,
+    }
+
+    impl message::Message for CartEvent {
+        fn name(&self) -> &'static str {
+            match self {
+                CartEvent::WasCreated { .. } => "CartWasCreated",
+                CartEvent::ItemWasAdded => "CartItemWasAdded",
+                CartEvent::WasCheckedOut => "CartWasCheckedOut",
+            }
+        }
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("cart: unexpected domain event")]
+    struct CartError;
+
+    impl Aggregate for Cart {
+        type Id = String;
+        type Event = CartEvent;
+        type Error = CartError;
+
+        fn type_name() -> &'static str {
+            "Cart"
+        }
+
+        fn aggregate_id(&self) -> &Self::Id {
+            &self.id
+        }
+
+        fn apply(state: Option<Self>, event: Self::Event) -> Result<Self, Self::Error> {
+            match (state, event) {
+                (None, CartEvent::WasCreated { id }) => Ok(Cart {
+                    id,
+                    items: 0,
+                    checked_out: false,
+                }),
+                (Some(cart), CartEvent::ItemWasAdded) => Ok(Cart {
+                    items: cart.items + 1,
+                    ..cart
+                }),
+                (Some(cart), CartEvent::WasCheckedOut) => Ok(Cart {
+                    checked_out: true,
+                    ..cart
+                }),
+                _ => Err(CartError),
+            }
+        }
+    }
+
+    impl Commutation for Cart {
+        fn commutes(event: &Self::Event, concurrent: &Self::Event) -> bool {
+            matches!(
+                (event, concurrent),
+                (CartEvent::ItemWasAdded, CartEvent::ItemWasAdded)
+            )
+        }
+    }
+
+    #[tokio::test]
+    async fn rebasing_repository_appends_commuting_events_at_the_new_version() {
+        let repository = Rebasing::<Cart, _>::new(InMemory::<String, CartEvent>::default(), 3);
+        let id = "cart:1".to_owned();
+
+        let mut cart =
+            aggregate::Root::<Cart>::record_new(CartEvent::WasCreated { id: id.clone() }.into())
+                .unwrap();
+
+        repository.save(&mut cart).await.unwrap();
+
+        let mut first = repository.get(&id).await.unwrap();
+        let mut second = repository.get(&id).await.unwrap();
+        let mut third = repository.get(&id).await.unwrap();
+
+        first.record_that(CartEvent::ItemWasAdded.into()).unwrap();
+        repository.save(&mut first).await.unwrap();
+
+        second.record_that(CartEvent::ItemWasAdded.into()).unwrap();
+        repository
+            .save(&mut second)
+            .await
+            .expect("commuting domain events should be rebased");
+
+        assert_eq!(3, second.version());
+        assert_eq!(2, second.items);
+
+        third.record_that(CartEvent::WasCheckedOut.into()).unwrap();
+
+        let err = repository
+            .save(&mut third)
+            .await
+            .expect_err("non-commuting domain events should conflict");
+
+        assert!(matches!(err, SaveError::Conflict(_)));
+
+        let cart = repository.get(&id).await.unwrap();
+
+        assert_eq!(3, cart.version());
+        assert_eq!(2, cart.items);
+        assert!(!cart.checked_out);
+    }
+}
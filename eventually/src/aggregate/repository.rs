@@ -11,7 +11,7 @@ use async_trait::async_trait;
 use futures::TryStreamExt;
 
 use crate::aggregate::Aggregate;
-use crate::{aggregate, event, version};
+use crate::{aggregate, error, event, version};
 
 /// All possible errors returned by [`Getter::get`].
 #[derive(Debug, thiserror::Error)]
@@ -24,22 +24,71 @@ pub enum GetError {
     Internal(#[from] anyhow::Error),
 }
 
+impl GetError {
+    /// Returns the [Kind][error::Kind] of the error.
+    #[must_use]
+    pub fn kind(&self) -> error::Kind {
+        match self {
+            GetError::NotFound => error::Kind::NotFound,
+            GetError::Internal(err) => error::kind_of(err),
+        }
+    }
+}
+
+impl From<error::Error> for GetError {
+    fn from(err: error::Error) -> Self {
+        match err {
+            error::Error::NotFound => GetError::NotFound,
+            err => GetError::Internal(err.into()),
+        }
+    }
+}
+
+impl error::Retryable for GetError {
+    fn is_transient(&self) -> bool {
+        self.kind().is_transient()
+    }
+}
+
+impl<E> From<GetError> for error::Error<E> {
+    fn from(err: GetError) -> Self {
+        match err {
+            GetError::NotFound => error::Error::NotFound,
+            GetError::Internal(err) => err.into(),
+        }
+    }
+}
+
 /// Trait used to implement read access to a data store from which
-/// to load an [aggregate::Root] instance, given its id.
+/// to load an [`aggregate::Root`] instance, given its id.
 #[async_trait]
 pub trait Getter<T>: Send + Sync
 where
     T: Aggregate,
 {
-    /// Loads an [aggregate::Root] instance from the data store,
+    /// Loads an [`aggregate::Root`] instance from the data store,
     /// referenced by its unique identifier.
+    ///
+    /// Tombstoned Aggregates (see [`Aggregate::is_tombstoned`]) are reported
+    /// as [`GetError::NotFound`].
     async fn get(&self, id: &T::Id) -> Result<aggregate::Root<T>, GetError>;
+
+    /// Loads an [`aggregate::Root`] instance from the data store,
+    /// referenced by its unique identifier, even if it has been tombstoned.
+    ///
+    /// Use this method to load a tombstoned Aggregate Root
+    /// to [resurrect][aggregate::Root::resurrect] it.
+    ///
+    /// The default implementation forwards to [`Getter::get`].
+    async fn get_including_tombstoned(&self, id: &T::Id) -> Result<aggregate::Root<T>, GetError> {
+        self.get(id).await
+    }
 }
 
 /// All possible errors returned by [`Saver::save`].
 #[derive(Debug, thiserror::Error)]
 pub enum SaveError {
-    /// Error returned when [Saver::save] encounters a conflict error while saving the new Aggregate Root.
+    /// Error returned when [`Saver::save`] encounters a conflict error while saving the new Aggregate Root.
     #[error("failed to save aggregate root: {0}")]
     Conflict(#[from] version::ConflictError),
     /// Error returned when the [Saver] implementation has encountered an error.
@@ -47,14 +96,49 @@ pub enum SaveError {
     Internal(#[from] anyhow::Error),
 }
 
+impl SaveError {
+    /// Returns the [Kind][error::Kind] of the error.
+    #[must_use]
+    pub fn kind(&self) -> error::Kind {
+        match self {
+            SaveError::Conflict(_) => error::Kind::Conflict,
+            SaveError::Internal(err) => error::kind_of(err),
+        }
+    }
+}
+
+impl From<error::Error> for SaveError {
+    fn from(err: error::Error) -> Self {
+        match err {
+            error::Error::Conflict(err) => SaveError::Conflict(err),
+            err => SaveError::Internal(err.into()),
+        }
+    }
+}
+
+impl error::Retryable for SaveError {
+    fn is_transient(&self) -> bool {
+        self.kind().is_transient()
+    }
+}
+
+impl<E> From<SaveError> for error::Error<E> {
+    fn from(err: SaveError) -> Self {
+        match err {
+            SaveError::Conflict(err) => error::Error::Conflict(err),
+            SaveError::Internal(err) => err.into(),
+        }
+    }
+}
+
 /// Trait used to implement write access to a data store, which can be used
-/// to save the latest state of an [aggregate::Root] instance.
+/// to save the latest state of an [`aggregate::Root`] instance.
 #[async_trait]
 pub trait Saver<T>: Send + Sync
 where
     T: Aggregate,
 {
-    /// Saves a new version of an [aggregate::Root] instance to the data store.
+    /// Saves a new version of an [`aggregate::Root`] instance to the data store.
     async fn save(&self, root: &mut aggregate::Root<T>) -> Result<(), SaveError>;
 }
 
@@ -84,7 +168,7 @@ where
     T: Aggregate,
     S: event::Store<T::Id, T::Event>,
 {
-    store: S,
+    pub(crate) store: S,
     aggregate: PhantomData<T>,
 }
 
@@ -112,6 +196,16 @@ where
         std::error::Error + Send + Sync + 'static,
 {
     async fn get(&self, id: &T::Id) -> Result<aggregate::Root<T>, GetError> {
+        let root = self.get_including_tombstoned(id).await?;
+
+        if root.is_tombstoned() {
+            return Err(GetError::NotFound);
+        }
+
+        Ok(root)
+    }
+
+    async fn get_including_tombstoned(&self, id: &T::Id) -> Result<aggregate::Root<T>, GetError> {
         let stream = self
             .store
             .stream(id, event::VersionSelect::All)
@@ -147,7 +241,7 @@ where
         self.store
             .append(
                 aggregate_id.clone(),
-                version::Check::MustBe(current_event_stream_version),
+                version::Check::loaded(current_event_stream_version),
                 events_to_commit,
             )
             .await
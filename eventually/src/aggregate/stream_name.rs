@@ -0,0 +1,132 @@
+//! Module containing the [`StreamNameStrategy`] trait, used by Repository
+//! implementations to map an [Aggregate] type and identifier to the name
+//! of the Event Stream holding its Domain Events.
+//!
+//! This is useful to match the naming conventions of existing Event Stores,
+//! such as `<category>-<id>` or tenant-scoped stream names.
+
+use crate::aggregate::Aggregate;
+
+/// Strategy used to map an [Aggregate] identifier to the name of its Event Stream.
+pub trait StreamNameStrategy<T>: Send + Sync
+where
+    T: Aggregate,
+{
+    /// Returns the name of the Event Stream for the [Aggregate] with the specified identifier.
+    fn stream_name(&self, id: &T::Id) -> String;
+
+    /// Returns the category of the Event Streams produced by this strategy,
+    /// which groups together the Event Streams of the same [Aggregate] type.
+    ///
+    /// Defaults to [`Aggregate::type_name`].
+    fn category(&self) -> String {
+        T::type_name().to_owned()
+    }
+}
+
+/// [`StreamNameStrategy`] that uses the [Aggregate] identifier as the Event Stream name.
+///
+/// This is the default strategy used by the Repository implementations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AggregateId;
+
+impl<T> StreamNameStrategy<T> for AggregateId
+where
+    T: Aggregate,
+    T::Id: ToString,
+{
+    fn stream_name(&self, id: &T::Id) -> String {
+        id.to_string()
+    }
+}
+
+/// [`StreamNameStrategy`] that prepends the [Aggregate] category,
+/// and optionally a prefix such as a tenant segment, to the identifier,
+/// joining the segments using a configurable separator.
+///
+/// Using the default configuration, the Event Stream name has the
+/// `<category>-<id>` format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Categorized {
+    prefix: Option<String>,
+    separator: String,
+}
+
+impl Default for Categorized {
+    fn default() -> Self {
+        Self {
+            prefix: None,
+            separator: "-".to_owned(),
+        }
+    }
+}
+
+impl Categorized {
+    /// Specifies the prefix segment to put in front of the category,
+    /// e.g. the tenant the Event Streams belong to.
+    #[must_use]
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    /// Specifies the separator used to join the Event Stream name segments.
+    /// Defaults to `-`.
+    #[must_use]
+    pub fn with_separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+}
+
+impl<T> StreamNameStrategy<T> for Categorized
+where
+    T: Aggregate,
+    T::Id: ToString,
+{
+    fn stream_name(&self, id: &T::Id) -> String {
+        let category = StreamNameStrategy::<T>::category(self);
+        format!("{category}{}{}", self.separator, id.to_string())
+    }
+
+    fn category(&self) -> String {
+        match &self.prefix {
+            Some(prefix) => format!("{prefix}{}{}", self.separator, T::type_name()),
+            None => T::type_name().to_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::test_user_domain::User;
+
+    #[test]
+    fn stream_names_follow_the_configured_strategy() {
+        let id = "test@email.com".to_owned();
+
+        assert_eq!(
+            "test@email.com",
+            StreamNameStrategy::<User>::stream_name(&AggregateId, &id)
+        );
+
+        assert_eq!(
+            "User-test@email.com",
+            StreamNameStrategy::<User>::stream_name(&Categorized::default(), &id)
+        );
+
+        let strategy = Categorized::default()
+            .with_prefix("tenant")
+            .with_separator(":");
+
+        assert_eq!(
+            "tenant:User",
+            StreamNameStrategy::<User>::category(&strategy)
+        );
+        assert_eq!(
+            "tenant:User:test@email.com",
+            StreamNameStrategy::<User>::stream_name(&strategy, &id)
+        );
+    }
+}
@@ -179,6 +179,25 @@ where
             marker: PhantomData,
         }
     }
+
+    /// Specifies that the outcome of the [Scenario] is positive, and
+    /// should result in the specified [Aggregate] state.
+    ///
+    /// If the assertion fails, the panic message contains the [Diff][crate::aggregate::diff::Diff]
+    /// between the expected and the actual [Aggregate] state.
+    #[cfg(feature = "aggregate-diff")]
+    #[must_use]
+    pub fn then_state(self, state: T) -> ScenarioThenState<T, R, F, Err>
+    where
+        T: serde::Serialize,
+    {
+        ScenarioThenState {
+            mutate: self.mutate,
+            expected: state,
+            marker: PhantomData,
+            err_marker: PhantomData,
+        }
+    }
 }
 
 #[doc(hidden)]
@@ -213,3 +232,105 @@ where
         assert_eq!(self.expected, result);
     }
 }
+
+#[cfg(feature = "aggregate-diff")]
+#[doc(hidden)]
+pub struct ScenarioThenState<T, R, F, Err>
+where
+    T: Aggregate + serde::Serialize,
+    T::Event: Debug + PartialEq,
+    R: From<Root<T>> + Deref<Target = Root<T>>,
+    F: Fn() -> Result<R, Err>,
+{
+    mutate: F,
+    expected: T,
+    marker: PhantomData<R>,
+    err_marker: PhantomData<Err>,
+}
+
+#[cfg(feature = "aggregate-diff")]
+impl<T, R, F, Err> ScenarioThenState<T, R, F, Err>
+where
+    T: Aggregate + serde::Serialize,
+    T::Event: Debug + PartialEq,
+    R: From<Root<T>> + Deref<Target = Root<T>>,
+    F: Fn() -> Result<R, Err>,
+    Err: Debug,
+{
+    /// Runs the [Scenario] and asserts the resulting [Aggregate] state.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if the action/mutation has failed, or if the
+    /// resulting [Aggregate] state differs from the expected one, making the test fail.
+    pub fn assert(self) {
+        let root = (self.mutate)().unwrap_or_else(|err| {
+            panic!("the scenario was expected to succeed, but failed: {err:?}")
+        });
+
+        let diff = crate::aggregate::diff::diff(&self.expected, &**root)
+            .expect("the aggregate state should be serializable");
+
+        assert!(
+            diff.is_empty(),
+            "the aggregate state differs from the expected one (expected -> actual):\n{diff}"
+        );
+    }
+}
+
+#[cfg(feature = "aggregate-diff")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::test_user_domain::{User, UserEvent};
+
+    fn user(email: &str, password: &str) -> User {
+        User::apply(
+            None,
+            UserEvent::WasCreated {
+                email: email.to_owned(),
+                password: password.to_owned(),
+            },
+        )
+        .expect("user should be created")
+    }
+
+    struct UserRoot(Root<User>);
+
+    impl From<Root<User>> for UserRoot {
+        fn from(root: Root<User>) -> Self {
+            Self(root)
+        }
+    }
+
+    impl Deref for UserRoot {
+        type Target = Root<User>;
+
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    fn assert_password_change(expected: User) {
+        Scenario::<User>::new()
+            .given(vec![UserEvent::WasCreated {
+                email: "test@email.com".to_owned(),
+                password: "password-1".to_owned(),
+            }
+            .into()])
+            .when(|user: &mut UserRoot| user.0.change_password("password-2".to_owned()))
+            .then_state(expected)
+            .assert();
+    }
+
+    #[test]
+    fn then_state_asserts_the_resulting_aggregate_state() {
+        assert_password_change(user("test@email.com", "password-2"));
+    }
+
+    #[test]
+    #[should_panic(expected = "~ /password: \"password-3\" -> \"password-2\"")]
+    fn then_state_shows_the_diff_of_the_aggregate_state() {
+        assert_password_change(user("test@email.com", "password-3"));
+    }
+}
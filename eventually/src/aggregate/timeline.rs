@@ -0,0 +1,173 @@
+//! Module containing the [Timeline] type, which returns the history of an
+//! [Aggregate] as a list of human-readable [Entries][Entry], to build
+//! audit or history screens without writing a custom projection.
+
+use std::marker::PhantomData;
+
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+
+use crate::aggregate::Aggregate;
+use crate::event::store::Streamer;
+use crate::message::Message;
+use crate::{event, version};
+
+/// The [Metadata][crate::message::Metadata] key used to read the Actor
+/// (e.g. a User or a System) that caused a Domain Event.
+pub const ACTOR_KEY: &str = "Actor";
+
+/// The [Metadata][crate::message::Metadata] key used to read the correlation id
+/// of a Domain Event, e.g. the id of the request that caused it.
+pub const CORRELATION_ID_KEY: &str = "Correlation-Id";
+
+pub use crate::message::RECORDED_AT_KEY;
+
+/// A single, human-readable entry in the [Timeline] of an [Aggregate].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    /// The version of the [Aggregate] after the Domain Event.
+    pub version: version::Version,
+    /// The type of the Domain Event, as returned by [`Message::name`].
+    pub event_type: String,
+    /// The Actor that caused the Domain Event, from the [`ACTOR_KEY`] metadata.
+    pub actor: Option<String>,
+    /// When the Domain Event has been recorded, from the [`RECORDED_AT_KEY`] metadata.
+    pub recorded_at: Option<String>,
+    /// The correlation id of the Domain Event, from the [`CORRELATION_ID_KEY`] metadata.
+    pub correlation_id: Option<String>,
+    /// A summary of the Domain Event, as returned by the describer function.
+    pub summary: String,
+}
+
+/// Returns the history of an [Aggregate] as a list of [Entries][Entry],
+/// reading its Domain Events from the Event Store.
+///
+/// The `describe` function is used to produce a human-readable summary
+/// of each Domain Event.
+#[derive(Debug, Clone)]
+pub struct Timeline<T, S, D>
+where
+    T: Aggregate,
+    S: Streamer<T::Id, T::Event>,
+    D: Fn(&event::Envelope<T::Event>) -> String,
+{
+    store: S,
+    describe: D,
+    aggregate: PhantomData<T>,
+}
+
+impl<T, S, D> Timeline<T, S, D>
+where
+    T: Aggregate,
+    S: Streamer<T::Id, T::Event>,
+    D: Fn(&event::Envelope<T::Event>) -> String,
+{
+    /// Creates a new [Timeline] reading from the specified Event Store,
+    /// and summarizing the Domain Events with the `describe` function.
+    pub fn new(store: S, describe: D) -> Self {
+        Self {
+            store,
+            describe,
+            aggregate: PhantomData,
+        }
+    }
+
+    /// Returns the [Entries][Entry] of the [Aggregate] with the specified id,
+    /// from the oldest to the most recent one.
+    ///
+    /// An empty list is returned if the [Aggregate] does not exist.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the Domain Events could not be streamed from the Event Store.
+    pub async fn get(&self, id: &T::Id) -> Result<Vec<Entry>, S::Error> {
+        self.store
+            .stream(id, event::VersionSelect::All)
+            .map_ok(|persisted| {
+                let metadata = &persisted.event.metadata;
+
+                Entry {
+                    version: persisted.version,
+                    event_type: persisted.event.message.name().to_owned(),
+                    actor: metadata.get(ACTOR_KEY).cloned(),
+                    recorded_at: metadata.get(RECORDED_AT_KEY).cloned(),
+                    correlation_id: metadata.get(CORRELATION_ID_KEY).cloned(),
+                    summary: (self.describe)(&persisted.event),
+                }
+            })
+            .try_collect()
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::test_user_domain::{User, UserEvent};
+    use crate::event::store::{Appender, InMemory};
+
+    #[tokio::test]
+    async fn timeline_returns_the_described_history_of_an_aggregate() {
+        let event_store = InMemory::<String, UserEvent>::default();
+        let id = "test@email.com".to_owned();
+
+        event_store
+            .append(
+                id.clone(),
+                version::Check::must_be(0),
+                vec![
+                    event::Envelope::from(UserEvent::WasCreated {
+                        email: id.clone(),
+                        password: "password".to_owned(),
+                    })
+                    .with_metadata(ACTOR_KEY.to_owned(), "admin".to_owned()),
+                    event::Envelope::from(UserEvent::PasswordWasChanged {
+                        password: "new-password".to_owned(),
+                    })
+                    .with_metadata(CORRELATION_ID_KEY.to_owned(), "request-1".to_owned()),
+                ],
+            )
+            .await
+            .expect("append should not fail");
+
+        let timeline = Timeline::<User, _, _>::new(event_store, |evt| match &evt.message {
+            UserEvent::WasCreated { email, .. } => format!("User {email} was created"),
+            UserEvent::PasswordWasChanged { .. } => "Password was changed".to_owned(),
+            UserEvent::WasDeleted => "User was deleted".to_owned(),
+            UserEvent::WasRestored => "User was restored".to_owned(),
+            UserEvent::StreamTombstoned => "User was tombstoned".to_owned(),
+            UserEvent::StreamResurrected => "User was resurrected".to_owned(),
+        });
+
+        let entries = timeline.get(&id).await.expect("timeline should not fail");
+
+        assert_eq!(
+            vec![
+                Entry {
+                    version: 1,
+                    event_type: "UserWasCreated".to_owned(),
+                    actor: Some("admin".to_owned()),
+                    recorded_at: None,
+                    correlation_id: None,
+                    summary: "User test@email.com was created".to_owned(),
+                },
+                Entry {
+                    version: 2,
+                    event_type: "UserPasswordWasChanged".to_owned(),
+                    actor: None,
+                    recorded_at: None,
+                    correlation_id: Some("request-1".to_owned()),
+                    summary: "Password was changed".to_owned(),
+                },
+            ],
+            entries
+        );
+
+        let entries = timeline
+            .get(&"unknown@email.com".to_owned())
+            .await
+            .expect("timeline should not fail");
+
+        assert!(entries.is_empty());
+    }
+}
@@ -0,0 +1,248 @@
+//! Module containing synchronous facades over the asynchronous interfaces
+//! exposed by this crate, such as [`aggregate::Repository`], [`event::Store`]
+//! and [`command::Handler`].
+//!
+//! The facades drive the asynchronous implementations to completion using
+//! an internal, single-threaded `tokio` runtime, making them usable from
+//! CLI tools, scripts or synchronous codebases that can't adopt `async`.
+//!
+//! **Please note**: the facades must not be used from within an asynchronous
+//! context (e.g. inside a `tokio` runtime), as blocking on a future from there
+//! will result in a panic.
+
+use std::io;
+use std::sync::Arc;
+
+use futures::TryStreamExt;
+use tokio::runtime;
+
+use crate::aggregate::repository::{GetError, Getter, SaveError, Saver};
+use crate::aggregate::Aggregate;
+use crate::event::store::{AppendError, Appender, Streamer};
+use crate::{aggregate, command, event, message, version};
+
+/// The runtime used by the facades in this module to drive futures to completion.
+///
+/// The same [Runtime] can be shared by multiple facades.
+pub type Runtime = Arc<runtime::Runtime>;
+
+/// Creates a new single-threaded [Runtime] to use with the facades in this module.
+///
+/// # Errors
+///
+/// An error is returned if the underlying `tokio` runtime could not be created.
+pub fn runtime() -> io::Result<Runtime> {
+    runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map(Arc::new)
+}
+
+/// Synchronous facade over an [`aggregate::Repository`] implementation.
+#[derive(Debug, Clone)]
+pub struct Repository<R> {
+    inner: R,
+    runtime: Runtime,
+}
+
+impl<R> Repository<R> {
+    /// Creates a new synchronous facade over the provided [`aggregate::Repository`],
+    /// using a new single-threaded [Runtime].
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the underlying `tokio` runtime could not be created.
+    pub fn new(inner: R) -> io::Result<Self> {
+        Ok(Self::with_runtime(inner, runtime()?))
+    }
+
+    /// Creates a new synchronous facade over the provided [`aggregate::Repository`],
+    /// using the specified [Runtime].
+    pub fn with_runtime(inner: R, runtime: Runtime) -> Self {
+        Self { inner, runtime }
+    }
+
+    /// Loads an [`aggregate::Root`] instance from the data store,
+    /// referenced by its unique identifier.
+    ///
+    /// # Errors
+    ///
+    /// Check out [`Getter::get`] for more information.
+    pub fn get<T>(&self, id: &T::Id) -> Result<aggregate::Root<T>, GetError>
+    where
+        T: Aggregate,
+        R: Getter<T>,
+    {
+        self.runtime.block_on(self.inner.get(id))
+    }
+
+    /// Saves a new version of an [`aggregate::Root`] instance to the data store.
+    ///
+    /// # Errors
+    ///
+    /// Check out [`Saver::save`] for more information.
+    pub fn save<T>(&self, root: &mut aggregate::Root<T>) -> Result<(), SaveError>
+    where
+        T: Aggregate,
+        R: Saver<T>,
+    {
+        self.runtime.block_on(self.inner.save(root))
+    }
+}
+
+/// Synchronous facade over an [`event::Store`] implementation.
+#[derive(Debug, Clone)]
+pub struct Store<S> {
+    inner: S,
+    runtime: Runtime,
+}
+
+impl<S> Store<S> {
+    /// Creates a new synchronous facade over the provided [`event::Store`],
+    /// using a new single-threaded [Runtime].
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the underlying `tokio` runtime could not be created.
+    pub fn new(inner: S) -> io::Result<Self> {
+        Ok(Self::with_runtime(inner, runtime()?))
+    }
+
+    /// Creates a new synchronous facade over the provided [`event::Store`],
+    /// using the specified [Runtime].
+    pub fn with_runtime(inner: S, runtime: Runtime) -> Self {
+        Self { inner, runtime }
+    }
+
+    /// Reads all the Domain Events in the selected slice of the Event Stream.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the underlying [`Streamer`] has failed
+    /// while streaming the Domain Events.
+    pub fn read<StreamId, Event>(
+        &self,
+        id: &StreamId,
+        select: event::VersionSelect,
+    ) -> Result<Vec<event::Persisted<StreamId, Event>>, S::Error>
+    where
+        StreamId: Send + Sync,
+        Event: message::Message + Send + Sync,
+        S: Streamer<StreamId, Event>,
+    {
+        self.runtime
+            .block_on(self.inner.stream(id, select).try_collect())
+    }
+
+    /// Appends new Domain Events to the specified Event Stream.
+    ///
+    /// # Errors
+    ///
+    /// Check out [`Appender::append`] for more information.
+    pub fn append<StreamId, Event>(
+        &self,
+        id: StreamId,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Event>>,
+    ) -> Result<version::Version, AppendError>
+    where
+        StreamId: Send + Sync,
+        Event: message::Message + Send + Sync,
+        S: Appender<StreamId, Event>,
+    {
+        self.runtime
+            .block_on(self.inner.append(id, version_check, events))
+    }
+}
+
+/// Synchronous facade over a [`command::Handler`] implementation.
+#[derive(Debug, Clone)]
+pub struct CommandHandler<H> {
+    inner: H,
+    runtime: Runtime,
+}
+
+impl<H> CommandHandler<H> {
+    /// Creates a new synchronous facade over the provided [`command::Handler`],
+    /// using a new single-threaded [Runtime].
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the underlying `tokio` runtime could not be created.
+    pub fn new(inner: H) -> io::Result<Self> {
+        Ok(Self::with_runtime(inner, runtime()?))
+    }
+
+    /// Creates a new synchronous facade over the provided [`command::Handler`],
+    /// using the specified [Runtime].
+    pub fn with_runtime(inner: H, runtime: Runtime) -> Self {
+        Self { inner, runtime }
+    }
+
+    /// Handles a Domain Command.
+    ///
+    /// # Errors
+    ///
+    /// Check out [`command::Handler::handle`] for more information.
+    pub fn handle<T>(&self, command: command::Envelope<T>) -> Result<(), H::Error>
+    where
+        T: message::Message,
+        H: command::Handler<T>,
+    {
+        self.runtime.block_on(self.inner.handle(command))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::test_user_domain::{User, UserEvent};
+    use crate::event::store::InMemory;
+
+    #[test]
+    fn blocking_facades_share_the_same_runtime() {
+        let runtime = runtime().expect("runtime should be created");
+        let event_store = InMemory::<String, UserEvent>::default();
+
+        let store = Store::with_runtime(event_store.clone(), runtime.clone());
+        let repository = Repository::with_runtime(
+            aggregate::EventSourcedRepository::<User, _>::from(event_store),
+            runtime,
+        );
+
+        let email = "test@email.com".to_owned();
+        let password = "not-a-secret".to_owned();
+
+        let mut user = aggregate::Root::<User>::create(email.clone(), password.clone())
+            .expect("user should be created successfully");
+
+        repository
+            .save(&mut user)
+            .expect("user should be saved successfully");
+
+        let mut user: aggregate::Root<User> = repository
+            .get(&email)
+            .expect("user should be retrieved from the repository");
+
+        user.change_password("new-password".to_owned())
+            .expect("user password should be changed successfully");
+
+        repository
+            .save(&mut user)
+            .expect("new user version should be saved successfully");
+
+        let events = store
+            .read(&email, event::VersionSelect::All)
+            .expect("events should be read from the store");
+
+        assert_eq!(
+            vec![
+                event::Envelope::from(UserEvent::WasCreated { email, password }),
+                event::Envelope::from(UserEvent::PasswordWasChanged {
+                    password: "new-password".to_owned()
+                }),
+            ],
+            events.into_iter().map(|evt| evt.event).collect::<Vec<_>>()
+        );
+    }
+}
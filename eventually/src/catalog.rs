@@ -0,0 +1,223 @@
+//! Contains the [Catalog] type, a machine-readable description of the
+//! Domain Commands and Domain Events of an application, built by introspecting
+//! their [Registry][message::Registry] implementations.
+//!
+//! The [Catalog] implements [`serde::Serialize`], so that it can be exported
+//! (e.g. as JSON) to power documentation portals, or used to check the contracts
+//! between the producers and consumers of Domain Events.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+
+use crate::{aggregate, message};
+
+/// Description of a Domain Event in the [Catalog].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct EventEntry {
+    /// The version of the Domain Event schema, if any has been set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+    /// The Domain Event schema (e.g. a JSON Schema or Protobuf definition), if any has been set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+    /// The names of the Aggregates emitting the Domain Event.
+    pub emitted_by: BTreeSet<&'static str>,
+    /// The names of the consumers of the Domain Event.
+    pub consumed_by: BTreeSet<&'static str>,
+}
+
+/// Description of a Domain Command in the [Catalog].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CommandEntry {
+    /// The version of the Domain Command schema, if any has been set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+    /// The Domain Command schema (e.g. a JSON Schema or Protobuf definition), if any has been set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+    /// The names of the Command Handlers handling the Domain Command.
+    pub handled_by: BTreeSet<&'static str>,
+}
+
+/// Machine-readable catalog of the Domain Commands and Domain Events of an application,
+/// indexed by their [name][message::Message::name].
+///
+/// Use the builder methods to register the Aggregates, Command Handlers
+/// and Domain Event consumers of the application.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Catalog {
+    /// The registered Domain Commands.
+    pub commands: BTreeMap<&'static str, CommandEntry>,
+    /// The registered Domain Events.
+    pub events: BTreeMap<&'static str, EventEntry>,
+}
+
+impl Catalog {
+    /// Registers all the Domain Events emitted by the specified [Aggregate][aggregate::Aggregate].
+    #[must_use]
+    pub fn aggregate<T>(mut self) -> Self
+    where
+        T: aggregate::Aggregate,
+        T::Event: message::Registry,
+    {
+        for name in <T::Event as message::Registry>::NAMES {
+            self.events
+                .entry(name)
+                .or_default()
+                .emitted_by
+                .insert(T::type_name());
+        }
+
+        self
+    }
+
+    /// Registers all the Domain Commands of type `T` as handled
+    /// by the Command Handler with the specified name.
+    #[must_use]
+    pub fn command_handler<T>(mut self, handler: &'static str) -> Self
+    where
+        T: message::Registry,
+    {
+        for name in T::NAMES {
+            self.commands
+                .entry(name)
+                .or_default()
+                .handled_by
+                .insert(handler);
+        }
+
+        self
+    }
+
+    /// Registers all the Domain Events of type `T` as consumed
+    /// by the consumer with the specified name, e.g. a projection or a process manager.
+    #[must_use]
+    pub fn consumer<T>(mut self, consumer: &'static str) -> Self
+    where
+        T: message::Registry,
+    {
+        for name in T::NAMES {
+            self.events
+                .entry(name)
+                .or_default()
+                .consumed_by
+                .insert(consumer);
+        }
+
+        self
+    }
+
+    /// Sets the version and schema of the registered Domain Command or Domain Event
+    /// with the specified name.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if no Domain Command or Domain Event with the specified name
+    /// has been registered.
+    #[must_use]
+    pub fn with_schema(mut self, name: &str, version: u32, schema: impl Into<String>) -> Self {
+        let schema = Some(schema.into());
+
+        if let Some(entry) = self.commands.get_mut(name) {
+            entry.version = Some(version);
+            entry.schema = schema;
+        } else if let Some(entry) = self.events.get_mut(name) {
+            entry.version = Some(version);
+            entry.schema = schema;
+        } else {
+            panic!("message '{name}' has not been registered in the catalog");
+        }
+
+        self
+    }
+
+    /// Returns the names of the Domain Events that are consumed, but not emitted
+    /// by any registered Aggregate: a consumer contract that no producer fulfills.
+    #[must_use]
+    pub fn unemitted_events(&self) -> Vec<&'static str> {
+        self.events
+            .iter()
+            .filter(|(_, entry)| entry.emitted_by.is_empty() && !entry.consumed_by.is_empty())
+            .map(|(name, _)| *name)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::test_user_domain::User;
+
+    #[allow(dead_code)]
+    #[derive(Debug)]
+    enum UserCommand {
+        Create,
+        ChangePassword,
+    }
+
+    impl message::Message for UserCommand {
+        fn name(&self) -> &'static str {
+            match self {
+                UserCommand::Create => "CreateUser",
+                UserCommand::ChangePassword => "ChangeUserPassword",
+            }
+        }
+    }
+
+    impl message::Registry for UserCommand {
+        const NAMES: &'static [&'static str] = &["CreateUser", "ChangeUserPassword"];
+    }
+
+    #[allow(dead_code)]
+    #[derive(Debug)]
+    enum SubscriberEvent {
+        Created,
+        Unsubscribed,
+    }
+
+    impl message::Message for SubscriberEvent {
+        fn name(&self) -> &'static str {
+            match self {
+                SubscriberEvent::Created => "UserWasCreated",
+                SubscriberEvent::Unsubscribed => "UserWasUnsubscribed",
+            }
+        }
+    }
+
+    impl message::Registry for SubscriberEvent {
+        const NAMES: &'static [&'static str] = &["UserWasCreated", "UserWasUnsubscribed"];
+    }
+
+    #[test]
+    fn catalog_describes_registered_commands_and_events() {
+        let catalog = Catalog::default()
+            .aggregate::<User>()
+            .command_handler::<UserCommand>("UserService")
+            .consumer::<SubscriberEvent>("NewsletterProjection")
+            .with_schema("UserWasCreated", 2, r#"{"type":"object"}"#);
+
+        assert_eq!(vec!["UserWasUnsubscribed"], catalog.unemitted_events());
+
+        let json = serde_json::to_value(&catalog).expect("catalog should be serializable");
+
+        assert_eq!(
+            serde_json::json!({
+                "handled_by": ["UserService"],
+            }),
+            json["commands"]["CreateUser"]
+        );
+
+        assert_eq!(
+            serde_json::json!({
+                "version": 2,
+                "schema": r#"{"type":"object"}"#,
+                "emitted_by": ["User"],
+                "consumed_by": ["NewsletterProjection"],
+            }),
+            json["events"]["UserWasCreated"]
+        );
+
+        assert_eq!(7, json["events"].as_object().unwrap().len());
+    }
+}
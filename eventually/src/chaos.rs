@@ -0,0 +1,231 @@
+//! Contains the [Chaos] decorator, which injects latency, failures and duplicate
+//! deliveries into Command [Handler][command::Handler]s and Domain Event
+//! [Consumer][event::consumer::Consumer]s.
+//!
+//! Meant for staging environments, to continuously exercise the retry,
+//! idempotency and timeout paths of an application. Faults are injected
+//! deterministically, e.g. every N messages, so that their rate is predictable.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::event::consumer::Consumer;
+use crate::{command, error, event, message};
+
+/// Configuration of the faults injected by a [Chaos] decorator.
+///
+/// The default configuration is disabled, so that it can be turned on
+/// through the application configuration without changing the code.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Config {
+    /// Whether any fault is injected at all.
+    pub enabled: bool,
+    /// The latency added before each message is handled.
+    pub latency: Option<Duration>,
+    /// Fails one message every N, without handling it, with [`ChaosError::Injected`].
+    pub fail_every: Option<usize>,
+    /// Delivers one message every N twice in a row.
+    pub duplicate_every: Option<usize>,
+}
+
+/// Error returned by a [Chaos] decorator.
+#[derive(Debug, thiserror::Error)]
+pub enum ChaosError<E> {
+    /// Error injected by the [Chaos] decorator. It is considered [transient][error::Retryable].
+    #[error("chaos: injected failure")]
+    Injected,
+    /// Error returned by the wrapped component.
+    #[error(transparent)]
+    Inner(E),
+}
+
+impl<E> error::Retryable for ChaosError<E>
+where
+    E: error::Retryable,
+{
+    fn is_transient(&self) -> bool {
+        match self {
+            ChaosError::Injected => true,
+            ChaosError::Inner(err) => err.is_transient(),
+        }
+    }
+}
+
+enum Fault {
+    Fail,
+    Duplicate,
+    None,
+}
+
+/// Decorator for a Command [Handler][command::Handler] or a Domain Event
+/// [Consumer][event::consumer::Consumer], which injects faults as specified by its [Config].
+#[derive(Debug, Clone)]
+pub struct Chaos<T> {
+    inner: T,
+    config: Config,
+    received: Arc<AtomicUsize>,
+}
+
+impl<T> Chaos<T> {
+    /// Creates a new [Chaos] decorator over the specified component.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if `fail_every` or `duplicate_every` are zero.
+    pub fn new(inner: T, config: Config) -> Self {
+        assert!(
+            config.fail_every != Some(0) && config.duplicate_every != Some(0),
+            "fault intervals must be greater than zero"
+        );
+
+        Self {
+            inner,
+            config,
+            received: Arc::default(),
+        }
+    }
+
+    async fn next_fault(&self) -> Fault {
+        if !self.config.enabled {
+            return Fault::None;
+        }
+
+        if let Some(latency) = self.config.latency {
+            tokio::time::sleep(latency).await;
+        }
+
+        let received = self.received.fetch_add(1, Ordering::Relaxed) + 1;
+        let every = |n: Option<usize>| n.is_some_and(|n| received % n == 0);
+
+        if every(self.config.fail_every) {
+            Fault::Fail
+        } else if every(self.config.duplicate_every) {
+            Fault::Duplicate
+        } else {
+            Fault::None
+        }
+    }
+}
+
+#[async_trait]
+impl<T, H> command::Handler<T> for Chaos<H>
+where
+    T: message::Message + Clone + Send + Sync + 'static,
+    H: command::Handler<T>,
+{
+    type Error = ChaosError<H::Error>;
+
+    async fn handle(&self, command: command::Envelope<T>) -> Result<(), Self::Error> {
+        match self.next_fault().await {
+            Fault::Fail => return Err(ChaosError::Injected),
+            Fault::Duplicate => {
+                self.inner
+                    .handle(command.clone())
+                    .await
+                    .map_err(ChaosError::Inner)?;
+            },
+            Fault::None => {},
+        }
+
+        self.inner.handle(command).await.map_err(ChaosError::Inner)
+    }
+}
+
+#[async_trait]
+impl<StreamId, Event, C> Consumer<StreamId, Event> for Chaos<C>
+where
+    StreamId: Clone + Send + Sync + 'static,
+    Event: message::Message + Clone + Send + Sync + 'static,
+    C: Consumer<StreamId, Event>,
+{
+    type Error = ChaosError<C::Error>;
+
+    async fn consume(&self, event: event::Persisted<StreamId, Event>) -> Result<(), Self::Error> {
+        match self.next_fault().await {
+            Fault::Fail => return Err(ChaosError::Injected),
+            Fault::Duplicate => {
+                self.inner
+                    .consume(event.clone())
+                    .await
+                    .map_err(ChaosError::Inner)?;
+            },
+            Fault::None => {},
+        }
+
+        self.inner.consume(event).await.map_err(ChaosError::Inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::command::Handler;
+    use crate::message::tests::StringMessage;
+
+    #[tokio::test]
+    async fn chaos_injects_latency_failures_and_duplicates_when_enabled() {
+        let calls = Arc::new(AtomicUsize::default());
+
+        let counting_handler = {
+            let calls = calls.clone();
+
+            move |_: command::Envelope<StringMessage>| {
+                let calls = calls.clone();
+
+                async move {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    Ok::<_, std::convert::Infallible>(())
+                }
+            }
+        };
+
+        let config = Config {
+            enabled: true,
+            latency: Some(Duration::from_millis(5)),
+            fail_every: Some(3),
+            duplicate_every: Some(2),
+        };
+
+        let handler = Chaos::new(counting_handler.clone(), config);
+        let started_at = Instant::now();
+
+        let mut results = Vec::new();
+        for _ in 0..4 {
+            results.push(
+                handler
+                    .handle(StringMessage("command").into())
+                    .await
+                    .is_ok(),
+            );
+        }
+
+        assert!(started_at.elapsed() >= Duration::from_millis(20));
+        // The 2nd and 4th commands are handled twice, the 3rd one fails.
+        assert_eq!(vec![true, true, false, true], results);
+        assert_eq!(5, calls.load(Ordering::SeqCst));
+
+        let handler = Chaos::new(
+            counting_handler,
+            Config {
+                enabled: false,
+                ..config
+            },
+        );
+
+        for _ in 0..3 {
+            handler
+                .handle(StringMessage("command").into())
+                .await
+                .expect("no fault should be injected when disabled");
+        }
+
+        assert_eq!(8, calls.load(Ordering::SeqCst));
+    }
+}
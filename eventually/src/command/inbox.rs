@@ -0,0 +1,255 @@
+//! Contains the [Inbox] type, which dispatches messages received from external
+//! transports (e.g. Kafka or SQS) as [Command][command::Envelope]s, deduplicating them
+//! by their message id.
+//!
+//! At-least-once transports may deliver the same message more than once: the [Inbox]
+//! records each message in an [effect Journal][Journal] before translating it into
+//! a [Command][command::Envelope], so that redeliveries are not dispatched again.
+
+use std::time::Duration;
+
+use crate::command::Handler;
+use crate::event::effect::{EffectError, Effects, Journal, Outcome};
+use crate::{command, message};
+
+/// The result of a successful [`Inbox::receive`] call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Received {
+    /// The message has been translated and dispatched as a [Command][command::Envelope].
+    Dispatched,
+    /// The message does not translate to any [Command][command::Envelope], so it has been
+    /// recorded without dispatching anything.
+    Ignored,
+    /// The message had already been received, so it has been skipped.
+    Duplicate,
+}
+
+/// Dispatches external messages to a Command [Handler], at most once per message id.
+///
+/// The [Inbox] uses the same [Journal] as the [Effects] runner: a message whose
+/// dispatch has been interrupted (e.g. by a crash) is reported as
+/// [`EffectError::InDoubt`] when received again, unless [`Inbox::retry_pending_after`]
+/// is enabled.
+///
+/// Each message id is [claimed][Journal::record_intent] atomically before dispatching,
+/// so concurrent redeliveries of the same message (e.g. after a visibility timeout)
+/// dispatch it only once: the other receivers get either [`Received::Duplicate`]
+/// or [`EffectError::InDoubt`], if the dispatch is still in progress.
+/// With [`Inbox::retry_pending_after`], this holds only as long as the dispatch
+/// completes within the lease: a message is dispatched again by a redelivery
+/// received after the lease expired, even if the first dispatch is still running.
+#[derive(Debug, Clone)]
+pub struct Inbox<J, H>
+where
+    J: Journal,
+{
+    effects: Effects<J>,
+    handler: H,
+}
+
+impl<J, H> Inbox<J, H>
+where
+    J: Journal,
+{
+    /// Creates a new [Inbox], recording the received messages in the specified [Journal]
+    /// and dispatching the translated [Command][command::Envelope]s to the specified [Handler].
+    pub fn new(journal: J, handler: H) -> Self {
+        Self {
+            effects: Effects::new(journal),
+            handler,
+        }
+    }
+
+    /// Dispatches again the messages whose dispatch started longer than `lease` ago
+    /// and never completed, considering it interrupted, instead of returning
+    /// [`EffectError::InDoubt`]. Disabled by default.
+    ///
+    /// `lease` must be longer than the longest dispatch: a redelivery received
+    /// while a dispatch outlasting its lease is still in progress dispatches the message again.
+    /// Enable this option only if the [Handler] is idempotent for those messages,
+    /// e.g. when the resulting Domain Events are appended with a version check.
+    #[must_use]
+    pub fn retry_pending_after(mut self, lease: Duration) -> Self {
+        self.effects = self.effects.retry_pending_after(lease);
+        self
+    }
+
+    /// Receives an external message with the specified id, translating it into
+    /// a [Command][command::Envelope] with the `translate` function and dispatching it
+    /// to the [Handler], unless a message with the same id has already been received.
+    ///
+    /// Messages translated to `None` are recorded as received, without
+    /// dispatching any [Command][command::Envelope].
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the [Handler] fails, in which case the message can be
+    /// received again, if a previous dispatch of the message has been interrupted,
+    /// or if the [Journal] could not be accessed.
+    pub async fn receive<M, T, F>(
+        &self,
+        message_id: &str,
+        message: M,
+        translate: F,
+    ) -> Result<Received, EffectError<J::Error, H::Error>>
+    where
+        M: Send,
+        T: message::Message + Send,
+        F: FnOnce(M) -> Option<command::Envelope<T>> + Send,
+        H: Handler<T>,
+    {
+        let key = format!("inbox/{message_id}");
+
+        let outcome = self
+            .effects
+            .run(&key, || async {
+                match translate(message) {
+                    Some(command) => self
+                        .handler
+                        .handle(command)
+                        .await
+                        .map(|()| Received::Dispatched),
+                    None => Ok(Received::Ignored),
+                }
+            })
+            .await?;
+
+        Ok(match outcome {
+            Outcome::Executed(received) => received,
+            Outcome::Skipped => Received::Duplicate,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::event::effect::InMemory;
+    use crate::message::tests::StringMessage;
+
+    #[tokio::test]
+    async fn inbox_dispatches_each_message_at_most_once() {
+        let dispatched = Arc::new(AtomicUsize::default());
+
+        let inbox = Inbox::new(InMemory::default(), {
+            let dispatched = dispatched.clone();
+
+            move |_: command::Envelope<StringMessage>| {
+                let dispatched = dispatched.clone();
+
+                async move {
+                    dispatched.fetch_add(1, Ordering::SeqCst);
+                    Ok::<_, std::convert::Infallible>(())
+                }
+            }
+        });
+
+        let translate = |payload: &'static str| match payload {
+            "order-placed" => Some(command::Envelope::from(StringMessage("request-payment"))),
+            _ => None,
+        };
+
+        for (message_id, payload, expected) in [
+            ("message-1", "order-placed", Received::Dispatched),
+            ("message-1", "order-placed", Received::Duplicate),
+            ("message-2", "newsletter-sent", Received::Ignored),
+            ("message-2", "newsletter-sent", Received::Duplicate),
+        ] {
+            let received = inbox
+                .receive(message_id, payload, translate)
+                .await
+                .expect("the message should be received");
+
+            assert_eq!(expected, received, "message {message_id}");
+        }
+
+        assert_eq!(1, dispatched.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn inbox_dispatches_concurrent_redeliveries_only_once() {
+        let dispatched = Arc::new(AtomicUsize::default());
+
+        let inbox = Inbox::new(InMemory::default(), {
+            let dispatched = dispatched.clone();
+
+            move |_: command::Envelope<StringMessage>| {
+                let dispatched = dispatched.clone();
+
+                async move {
+                    // Lets the other redeliveries be received while dispatching.
+                    tokio::task::yield_now().await;
+                    dispatched.fetch_add(1, Ordering::SeqCst);
+                    Ok::<_, std::convert::Infallible>(())
+                }
+            }
+        });
+
+        let translate = |_| Some(command::Envelope::from(StringMessage("request-payment")));
+
+        let results = futures::future::join_all(
+            (0..4).map(|_| inbox.receive("message-1", "order-placed", translate)),
+        )
+        .await;
+
+        assert_eq!(1, dispatched.load(Ordering::SeqCst));
+        assert!(matches!(results[0], Ok(Received::Dispatched)));
+
+        for result in &results[1..] {
+            assert!(matches!(
+                result,
+                Ok(Received::Duplicate) | Err(EffectError::InDoubt(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn inbox_retrying_pending_dispatches_does_not_take_over_dispatches_in_progress() {
+        let dispatched = Arc::new(AtomicUsize::default());
+        let lease = Duration::from_millis(100);
+
+        let inbox = Inbox::new(InMemory::default(), {
+            let dispatched = dispatched.clone();
+
+            move |_: command::Envelope<StringMessage>| {
+                let dispatched = dispatched.clone();
+
+                async move {
+                    dispatched.fetch_add(1, Ordering::SeqCst);
+                    // Lets the other redeliveries be received while dispatching.
+                    tokio::time::sleep(lease / 5).await;
+                    Ok::<_, std::convert::Infallible>(())
+                }
+            }
+        })
+        .retry_pending_after(lease);
+
+        let translate = |_| Some(command::Envelope::from(StringMessage("request-payment")));
+
+        let results = futures::future::join_all(
+            (0..4).map(|_| inbox.receive("message-1", "order-placed", translate)),
+        )
+        .await;
+
+        assert_eq!(1, dispatched.load(Ordering::SeqCst));
+        assert!(matches!(results[0], Ok(Received::Dispatched)));
+
+        for result in &results[1..] {
+            assert!(matches!(result, Err(EffectError::InDoubt(_))));
+        }
+
+        // Once completed, the message is never dispatched again, even after the lease.
+        tokio::time::sleep(lease).await;
+
+        let received = inbox
+            .receive("message-1", "order-placed", translate)
+            .await
+            .expect("the message should be received");
+
+        assert_eq!(Received::Duplicate, received);
+        assert_eq!(1, dispatched.load(Ordering::SeqCst));
+    }
+}
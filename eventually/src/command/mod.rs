@@ -11,12 +11,14 @@
 //!
 //! Check out the type documentation exported in this module.
 
+pub mod inbox;
 pub mod test;
 
 use std::future::Future;
 
 use async_trait::async_trait;
 
+use crate::error::Retryable;
 use crate::message;
 
 /// A Command represents an intent by an Actor (e.g. a User, or a System)
@@ -62,6 +64,232 @@ where
     }
 }
 
+/// [Handler] decorator that handles a [Command] again when the wrapped [Handler]
+/// fails with a [transient][Retryable] error, up to a maximum number of attempts.
+///
+/// Since the wrapped [Handler] is called again as a whole, version conflicts are retried
+/// by re-evaluating the [Command] on the latest state of the system, while domain errors
+/// are returned immediately.
+#[derive(Debug, Clone)]
+pub struct Retry<H> {
+    handler: H,
+    max_attempts: usize,
+}
+
+impl<H> Retry<H> {
+    /// Creates a new [Retry] decorator, which calls the [Handler] provided
+    /// at most `max_attempts` times per [Command].
+    ///
+    /// # Panics
+    ///
+    /// The method panics if `max_attempts` is zero.
+    pub fn new(handler: H, max_attempts: usize) -> Self {
+        assert!(max_attempts > 0, "at least one attempt must be allowed");
+
+        Self {
+            handler,
+            max_attempts,
+        }
+    }
+}
+
+#[async_trait]
+impl<T, H> Handler<T> for Retry<H>
+where
+    T: message::Message + Clone + Send + Sync + 'static,
+    H: Handler<T>,
+    H::Error: Retryable,
+{
+    type Error = H::Error;
+
+    async fn handle(&self, command: Envelope<T>) -> Result<(), Self::Error> {
+        let mut attempt = 1;
+
+        loop {
+            match self.handler.handle(command.clone()).await {
+                Err(err) if attempt < self.max_attempts && err.is_transient() => attempt += 1,
+                result => return result,
+            }
+        }
+    }
+}
+
+/// [Handler] decorator that maps the error returned by the wrapped [Handler]
+/// to a different error type.
+///
+/// Use [`HandlerExt::map_err`] to create a new instance.
+#[derive(Debug, Clone)]
+pub struct MapErr<H, F> {
+    handler: H,
+    f: F,
+}
+
+#[async_trait]
+impl<T, H, F, Err> Handler<T> for MapErr<H, F>
+where
+    T: message::Message + Send + Sync + 'static,
+    H: Handler<T>,
+    F: Fn(H::Error) -> Err + Send + Sync,
+    Err: Send + Sync,
+{
+    type Error = Err;
+
+    async fn handle(&self, command: Envelope<T>) -> Result<(), Self::Error> {
+        self.handler.handle(command).await.map_err(&self.f)
+    }
+}
+
+/// [Handler] decorator that handles a [Command] with a fallback [Handler]
+/// when the wrapped [Handler] fails.
+///
+/// Use [`HandlerExt::or_else`] to create a new instance.
+#[derive(Debug, Clone)]
+pub struct Fallback<H, G> {
+    handler: H,
+    fallback: G,
+}
+
+#[async_trait]
+impl<T, H, G> Handler<T> for Fallback<H, G>
+where
+    T: message::Message + Clone + Send + Sync + 'static,
+    H: Handler<T>,
+    G: Handler<T, Error = H::Error>,
+{
+    type Error = H::Error;
+
+    async fn handle(&self, command: Envelope<T>) -> Result<(), Self::Error> {
+        match self.handler.handle(command.clone()).await {
+            Ok(()) => Ok(()),
+            Err(_) => self.fallback.handle(command).await,
+        }
+    }
+}
+
+/// [Handler] decorator that transforms, or rejects, a [Command]
+/// before passing it to the wrapped [Handler].
+///
+/// Use [`HandlerExt::before`] to create a new instance.
+#[derive(Debug, Clone)]
+pub struct Before<H, F> {
+    handler: H,
+    f: F,
+}
+
+#[async_trait]
+impl<T, H, F> Handler<T> for Before<H, F>
+where
+    T: message::Message + Send + Sync + 'static,
+    H: Handler<T>,
+    F: Fn(Envelope<T>) -> Result<Envelope<T>, H::Error> + Send + Sync,
+{
+    type Error = H::Error;
+
+    async fn handle(&self, command: Envelope<T>) -> Result<(), Self::Error> {
+        let command = (self.f)(command)?;
+        self.handler.handle(command).await
+    }
+}
+
+/// [Handler] decorator that transforms the result of the wrapped [Handler].
+///
+/// Use [`HandlerExt::after`] to create a new instance.
+#[derive(Debug, Clone)]
+pub struct After<H, F> {
+    handler: H,
+    f: F,
+}
+
+#[async_trait]
+impl<T, H, F> Handler<T> for After<H, F>
+where
+    T: message::Message + Send + Sync + 'static,
+    H: Handler<T>,
+    F: Fn(Result<(), H::Error>) -> Result<(), H::Error> + Send + Sync,
+{
+    type Error = H::Error;
+
+    async fn handle(&self, command: Envelope<T>) -> Result<(), Self::Error> {
+        (self.f)(self.handler.handle(command).await)
+    }
+}
+
+/// Extension trait for any [Handler] type, to compose it with other [Handler]s
+/// and layer cross-cutting behaviors onto it.
+pub trait HandlerExt<T>: Handler<T> + Sized
+where
+    T: message::Message,
+{
+    /// Maps the error returned by the [Handler] using the provided function,
+    /// e.g. to compose [Handler]s with different error types.
+    fn map_err<F, Err>(self, f: F) -> MapErr<Self, F>
+    where
+        F: Fn(Self::Error) -> Err,
+    {
+        MapErr { handler: self, f }
+    }
+
+    /// Handles the [Command] with the `fallback` [Handler] if this [Handler] fails,
+    /// discarding the error of the former.
+    ///
+    /// Calls can be chained to try more than one fallback [Handler], in order.
+    fn or_else<G>(self, fallback: G) -> Fallback<Self, G>
+    where
+        G: Handler<T, Error = Self::Error>,
+    {
+        Fallback {
+            handler: self,
+            fallback,
+        }
+    }
+
+    /// Transforms the [Command] using the provided function before handling it,
+    /// e.g. to enrich its [Metadata][message::Metadata].
+    ///
+    /// The function can reject the [Command] by returning an error,
+    /// in which case the [Handler] is not called.
+    fn before<F>(self, f: F) -> Before<Self, F>
+    where
+        F: Fn(Envelope<T>) -> Result<Envelope<T>, Self::Error>,
+    {
+        Before { handler: self, f }
+    }
+
+    /// Transforms the result of the [Handler] using the provided function,
+    /// e.g. to ignore some errors.
+    fn after<F>(self, f: F) -> After<Self, F>
+    where
+        F: Fn(Result<(), Self::Error>) -> Result<(), Self::Error>,
+    {
+        After { handler: self, f }
+    }
+
+    /// Returns a [Retry] decorator over the [Handler], which calls the [Handler]
+    /// at most `max_attempts` times per [Command].
+    ///
+    /// # Panics
+    ///
+    /// The method panics if `max_attempts` is zero.
+    fn with_retry(self, max_attempts: usize) -> Retry<Self> {
+        Retry::new(self, max_attempts)
+    }
+
+    /// Returns a [`Correlated`][crate::correlation::Correlated] decorator over the [Handler],
+    /// which propagates the correlation and causation metadata of the [Command]
+    /// to the Domain Events appended while handling it.
+    #[cfg(feature = "correlation")]
+    fn with_correlation(self) -> crate::correlation::Correlated<Self> {
+        crate::correlation::Correlated::new(self)
+    }
+}
+
+impl<T, H> HandlerExt<T> for H
+where
+    T: message::Message,
+    H: Handler<T>,
+{
+}
+
 #[cfg(test)]
 mod test_user_domain {
     use std::sync::Arc;
@@ -107,6 +335,7 @@ mod test_user_domain {
         }
     }
 
+    #[derive(Clone)]
     struct ChangeUserPassword {
         email: String,
         password: String,
@@ -138,6 +367,43 @@ mod test_user_domain {
         }
     }
 
+    struct ImportDeletedUser {
+        email: String,
+        password: String,
+    }
+
+    impl message::Message for ImportDeletedUser {
+        fn name(&self) -> &'static str {
+            "ImportDeletedUser"
+        }
+    }
+
+    #[async_trait]
+    impl command::Handler<ImportDeletedUser> for UserService {
+        type Error = anyhow::Error;
+
+        async fn handle(
+            &self,
+            command: command::Envelope<ImportDeletedUser>,
+        ) -> Result<(), Self::Error> {
+            let command = command.message;
+            let was_created = UserEvent::WasCreated {
+                email: command.email.clone(),
+                password: command.password.clone(),
+            };
+
+            let mut user = aggregate::Root::<User>::record_new(
+                event::Envelope::from(was_created.clone()).caused_by(&command),
+            )?;
+
+            user.record_that(event::Envelope::from(UserEvent::WasDeleted).caused_by(&was_created))?;
+
+            self.0.save(&mut user).await?;
+
+            Ok(())
+        }
+    }
+
     #[tokio::test]
     async fn it_creates_a_new_user_successfully() {
         command::test::Scenario
@@ -209,6 +475,109 @@ mod test_user_domain {
             .await;
     }
 
+    #[tokio::test]
+    async fn it_updates_the_password_again_after_a_conflicting_save() {
+        command::test::Scenario
+            .given(vec![event::Persisted {
+                stream_id: "test@test.com".to_owned(),
+                version: 1,
+                event: event::Envelope::from(UserEvent::WasCreated {
+                    email: "test@test.com".to_owned(),
+                    password: "not-a-secret".to_owned(),
+                }),
+            }])
+            .given_conflicting_append_on_save(vec![event::Persisted {
+                stream_id: "test@test.com".to_owned(),
+                version: 2,
+                event: event::Envelope::from(UserEvent::PasswordWasChanged {
+                    password: "concurrent-password".to_owned(),
+                }),
+            }])
+            .when(command::Envelope::from(ChangeUserPassword {
+                email: "test@test.com".to_owned(),
+                password: "new-password".to_owned(),
+            }))
+            .then(vec![event::Persisted {
+                stream_id: "test@test.com".to_owned(),
+                version: 3,
+                event: event::Envelope::from(UserEvent::PasswordWasChanged {
+                    password: "new-password".to_owned(),
+                }),
+            }])
+            .assert_on(|event_store| {
+                command::Retry::new(
+                    UserService::from(aggregate::EventSourcedRepository::from(event_store)),
+                    2,
+                )
+            })
+            .await;
+    }
+
+    #[tokio::test]
+    async fn it_fails_to_update_the_password_on_a_conflicting_save_without_retries() {
+        command::test::Scenario
+            .given(vec![event::Persisted {
+                stream_id: "test@test.com".to_owned(),
+                version: 1,
+                event: event::Envelope::from(UserEvent::WasCreated {
+                    email: "test@test.com".to_owned(),
+                    password: "not-a-secret".to_owned(),
+                }),
+            }])
+            .given_conflicting_append_on_save(vec![event::Persisted {
+                stream_id: "test@test.com".to_owned(),
+                version: 2,
+                event: event::Envelope::from(UserEvent::WasDeleted),
+            }])
+            .when(command::Envelope::from(ChangeUserPassword {
+                email: "test@test.com".to_owned(),
+                password: "new-password".to_owned(),
+            }))
+            .then_fails()
+            .assert_on(|event_store| {
+                UserService::from(aggregate::EventSourcedRepository::from(event_store))
+            })
+            .await;
+    }
+
+    #[tokio::test]
+    async fn it_imports_a_deleted_user_with_causation_links() {
+        command::test::Scenario
+            .when(command::Envelope::from(ImportDeletedUser {
+                email: "test@test.com".to_owned(),
+                password: "not-a-secret".to_owned(),
+            }))
+            .then_expect(vec![
+                command::test::expect("UserWasCreated").caused_by("ImportDeletedUser"),
+                command::test::expect("UserWasDeleted")
+                    .after("UserWasCreated")
+                    .caused_by("UserWasCreated"),
+            ])
+            .assert_on(|event_store| {
+                UserService::from(aggregate::EventSourcedRepository::from(event_store))
+            })
+            .await;
+    }
+
+    #[tokio::test]
+    #[should_panic(
+        expected = "expected event 'UserWasCreated' to be produced after 'UserWasDeleted'"
+    )]
+    async fn it_fails_the_scenario_when_events_are_produced_out_of_order() {
+        command::test::Scenario
+            .when(command::Envelope::from(ImportDeletedUser {
+                email: "test@test.com".to_owned(),
+                password: "not-a-secret".to_owned(),
+            }))
+            .then_expect(vec![
+                command::test::expect("UserWasCreated").after("UserWasDeleted")
+            ])
+            .assert_on(|event_store| {
+                UserService::from(aggregate::EventSourcedRepository::from(event_store))
+            })
+            .await;
+    }
+
     #[tokio::test]
     async fn it_fails_to_update_the_password_if_the_user_does_not_exist() {
         command::test::Scenario
@@ -223,3 +592,109 @@ mod test_user_domain {
             .await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use crate::command::{self, Handler, HandlerExt};
+    use crate::error::Error;
+    use crate::message::tests::StringMessage;
+    use crate::version;
+
+    fn failing_handler(
+        calls: Arc<AtomicUsize>,
+        error: fn() -> Error<&'static str>,
+    ) -> impl Handler<StringMessage, Error = Error<&'static str>> {
+        move |_: command::Envelope<StringMessage>| {
+            let calls = calls.clone();
+
+            async move {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(error())
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn retry_handles_the_command_again_on_transient_errors() {
+        let calls = Arc::new(AtomicUsize::default());
+        let handler = command::Retry::new(
+            failing_handler(calls.clone(), || {
+                Error::Conflict(version::ConflictError {
+                    expected: 1,
+                    actual: 2,
+                })
+            }),
+            3,
+        );
+
+        handler
+            .handle(StringMessage("command").into())
+            .await
+            .expect_err("the handler should fail after all attempts");
+
+        assert_eq!(3, calls.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn retry_does_not_handle_the_command_again_on_domain_errors() {
+        let calls = Arc::new(AtomicUsize::default());
+        let handler = command::Retry::new(
+            failing_handler(calls.clone(), || Error::Domain("invalid command")),
+            3,
+        );
+
+        handler
+            .handle(StringMessage("command").into())
+            .await
+            .expect_err("the handler should fail");
+
+        assert_eq!(1, calls.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn composed_handlers_fall_back_and_transform_commands_and_errors() {
+        let calls = Arc::new(AtomicUsize::default());
+        let fallback_calls = Arc::new(AtomicUsize::default());
+
+        let handler = failing_handler(calls.clone(), || Error::Domain("primary failed"))
+            .or_else(failing_handler(fallback_calls.clone(), || {
+                Error::Domain("fallback failed")
+            }))
+            .map_err(|err| err.to_string());
+
+        let err = handler
+            .handle(StringMessage("command").into())
+            .await
+            .expect_err("both handlers should fail");
+
+        assert_eq!("domain error: fallback failed", err);
+        assert_eq!(1, calls.load(Ordering::SeqCst));
+        assert_eq!(1, fallback_calls.load(Ordering::SeqCst));
+
+        let handler = failing_handler(calls.clone(), || Error::Domain("unreachable"))
+            .before(
+                |command: command::Envelope<StringMessage>| match command.message.0 {
+                    "invalid" => Err(Error::Domain("rejected")),
+                    _ => Ok(command),
+                },
+            )
+            .after(|result| result.or_else(|_| Ok(())));
+
+        handler
+            .handle(StringMessage("command").into())
+            .await
+            .expect("the error should be ignored after handling");
+
+        assert_eq!(2, calls.load(Ordering::SeqCst));
+
+        handler
+            .handle(StringMessage("invalid").into())
+            .await
+            .expect("the error should be ignored after handling");
+
+        assert_eq!(2, calls.load(Ordering::SeqCst));
+    }
+}
@@ -1,10 +1,14 @@
 //! Module exposing a test [Scenario] type to write Domain [Command][command::Envelope]s
 //! test cases using the [given-then-when canvas](https://www.agilealliance.org/glossary/gwt/).
 
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::hash::Hash;
+use std::sync::{Arc, Mutex};
 
-use crate::event::store::{Appender, EventStoreExt};
+use async_trait::async_trait;
+
+use crate::event::store::{AppendError, Appender, EventStoreExt, Streamer};
 use crate::{command, event, message, version};
 
 /// A test scenario that can be used to test a [Command][command::Envelope] [Handler][command::Handler]
@@ -19,7 +23,10 @@ impl Scenario {
     where
         Evt: message::Message,
     {
-        ScenarioGiven { given: events }
+        ScenarioGiven {
+            given: events,
+            conflicting: Vec::default(),
+        }
     }
 
     /// Specifies the [Command][command::Envelope] to test in the [Scenario], in the peculiar case
@@ -37,6 +44,7 @@ impl Scenario {
     {
         ScenarioWhen {
             given: Vec::default(),
+            conflicting: Vec::default(),
             when: command,
         }
     }
@@ -48,12 +56,31 @@ where
     Evt: message::Message,
 {
     given: Vec<event::Persisted<Id, Evt>>,
+    conflicting: Vec<event::Persisted<Id, Evt>>,
 }
 
 impl<Id, Evt> ScenarioGiven<Id, Evt>
 where
     Evt: message::Message,
 {
+    /// Simulates a concurrent writer that appends the specified Domain [Event][event::Envelope]s
+    /// right before the [Command][command::Envelope] [Handler][command::Handler] saves
+    /// its changes to the same Event Stream, so that the first save fails with
+    /// a version conflict.
+    ///
+    /// Useful to test how the [Handler][command::Handler] retries or rebases its changes
+    /// on conflicts, e.g. when decorated with [`command::Retry`].
+    /// The conflicting Domain [Event][event::Envelope]s are not included in the Domain
+    /// [Event][event::Envelope]s asserted with [`ScenarioWhen::then`].
+    #[must_use]
+    pub fn given_conflicting_append_on_save(
+        mut self,
+        events: Vec<event::Persisted<Id, Evt>>,
+    ) -> Self {
+        self.conflicting.extend(events);
+        self
+    }
+
     /// Specifies the [Command][command::Envelope] to test in the [Scenario].
     #[must_use]
     pub fn when<Cmd>(self, command: command::Envelope<Cmd>) -> ScenarioWhen<Id, Evt, Cmd>
@@ -62,6 +89,7 @@ where
     {
         ScenarioWhen {
             given: self.given,
+            conflicting: self.conflicting,
             when: command,
         }
     }
@@ -74,6 +102,7 @@ where
     Cmd: message::Message,
 {
     given: Vec<event::Persisted<Id, Evt>>,
+    conflicting: Vec<event::Persisted<Id, Evt>>,
     when: command::Envelope<Cmd>,
 }
 
@@ -88,16 +117,33 @@ where
     pub fn then(self, events: Vec<event::Persisted<Id, Evt>>) -> ScenarioThen<Id, Evt, Cmd> {
         ScenarioThen {
             given: self.given,
+            conflicting: self.conflicting,
             when: self.when,
             case: ScenarioThenCase::Produces(events),
         }
     }
 
+    /// Sets the expectations on the Domain [Event]s produced by the [Scenario],
+    /// in terms of their relative ordering and causation links, rather than
+    /// the exact list of Domain [Event]s.
+    ///
+    /// Use [`expect`] to create an [Expectation].
+    #[must_use]
+    pub fn then_expect(self, expectations: Vec<Expectation>) -> ScenarioThen<Id, Evt, Cmd> {
+        ScenarioThen {
+            given: self.given,
+            conflicting: self.conflicting,
+            when: self.when,
+            case: ScenarioThenCase::Matches(expectations),
+        }
+    }
+
     /// Sets the expectation on the result of the [Scenario] to return an error.
     #[must_use]
     pub fn then_fails(self) -> ScenarioThen<Id, Evt, Cmd> {
         ScenarioThen {
             given: self.given,
+            conflicting: self.conflicting,
             when: self.when,
             case: ScenarioThenCase::Fails,
         }
@@ -109,9 +155,97 @@ where
     Evt: message::Message,
 {
     Produces(Vec<event::Persisted<Id, Evt>>),
+    Matches(Vec<Expectation>),
     Fails,
 }
 
+/// Expects a Domain [Event][event::Envelope] with the specified [name][message::Message::name]
+/// to be produced by a [Scenario], to use with [`ScenarioWhen::then_expect`].
+///
+/// Example of usage:
+/// ```text
+/// Scenario
+///     .when(command::Envelope::from(PlaceOrder { .. }))
+///     .then_expect(vec![
+///         expect("OrderWasPlaced").caused_by("PlaceOrder"),
+///         expect("PaymentWasRequested").after("OrderWasPlaced").caused_by("OrderWasPlaced"),
+///     ])
+///     .assert_on(|event_store| { .. })
+///     .await;
+/// ```
+pub fn expect(name: &'static str) -> Expectation {
+    Expectation {
+        name,
+        after: Vec::default(),
+        caused_by: None,
+    }
+}
+
+/// An expectation on a Domain [Event][event::Envelope] produced by a [Scenario].
+///
+/// Use [`expect`] to create a new one.
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct Expectation {
+    name: &'static str,
+    after: Vec<&'static str>,
+    caused_by: Option<&'static str>,
+}
+
+impl Expectation {
+    /// Expects the Domain [Event][event::Envelope] to be produced after
+    /// a Domain [Event][event::Envelope] with the specified name.
+    pub fn after(mut self, name: &'static str) -> Self {
+        self.after.push(name);
+        self
+    }
+
+    /// Expects the Domain [Event][event::Envelope] to be caused by the [Message][message::Message]
+    /// with the specified name, as recorded by [`message::Envelope::caused_by`].
+    pub fn caused_by(mut self, name: &'static str) -> Self {
+        self.caused_by = Some(name);
+        self
+    }
+
+    fn assert<Id, Evt>(&self, events: &[event::Persisted<Id, Evt>])
+    where
+        Evt: message::Message,
+    {
+        let names: Vec<_> = events.iter().map(|evt| evt.event.message.name()).collect();
+        let position = |name| names.iter().position(|n| *n == name);
+
+        let index = position(self.name).unwrap_or_else(|| {
+            panic!(
+                "expected event '{}' to be produced, got: {names:?}",
+                self.name
+            )
+        });
+
+        for previous in &self.after {
+            assert!(
+                position(previous).is_some_and(|i| i < index),
+                "expected event '{}' to be produced after '{previous}', got: {names:?}",
+                self.name,
+            );
+        }
+
+        if let Some(cause) = self.caused_by {
+            let actual = events[index]
+                .event
+                .metadata
+                .get(message::CAUSED_BY_KEY)
+                .map(String::as_str);
+
+            assert_eq!(
+                Some(cause),
+                actual,
+                "expected event '{}' to be caused by '{cause}'",
+                self.name,
+            );
+        }
+    }
+}
+
 #[doc(hidden)]
 pub struct ScenarioThen<Id, Evt, Cmd>
 where
@@ -119,10 +253,95 @@ where
     Cmd: message::Message,
 {
     given: Vec<event::Persisted<Id, Evt>>,
+    conflicting: Vec<event::Persisted<Id, Evt>>,
     when: command::Envelope<Cmd>,
     case: ScenarioThenCase<Id, Evt>,
 }
 
+type ConflictingEvents<Id, Evt> = Arc<Mutex<HashMap<Id, Vec<event::Persisted<Id, Evt>>>>>;
+
+/// [Event Store][event::Store] used by a [Scenario], which appends the Domain
+/// [Event][event::Envelope]s set with [`ScenarioGiven::given_conflicting_append_on_save`]
+/// right before the first append on their Event Stream.
+#[doc(hidden)]
+#[derive(Debug, Clone)]
+pub struct ScenarioEventStore<Id, Evt>
+where
+    Evt: message::Message,
+{
+    store: event::store::InMemory<Id, Evt>,
+    conflicting: ConflictingEvents<Id, Evt>,
+}
+
+#[async_trait]
+impl<Id, Evt> Streamer<Id, Evt> for ScenarioEventStore<Id, Evt>
+where
+    Id: Clone + Eq + Hash + Send + Sync,
+    Evt: message::Message + Clone + Send + Sync,
+{
+    type Error = <event::store::InMemory<Id, Evt> as Streamer<Id, Evt>>::Error;
+
+    fn stream(
+        &self,
+        id: &Id,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, Id, Evt, Self::Error> {
+        self.store.stream(id, select)
+    }
+
+    fn read_from<'a>(
+        &'a self,
+        id: &Id,
+        from_version: version::Version,
+        batch_size: usize,
+    ) -> event::Stream<'a, Id, Evt, Self::Error>
+    where
+        Id: Clone + 'a,
+        Evt: 'a,
+        Self::Error: 'a,
+    {
+        self.store.read_from(id, from_version, batch_size)
+    }
+
+    async fn head_version(&self, id: &Id) -> Result<Option<version::Version>, Self::Error> {
+        self.store.head_version(id).await
+    }
+}
+
+#[async_trait]
+impl<Id, Evt> Appender<Id, Evt> for ScenarioEventStore<Id, Evt>
+where
+    Id: Clone + Eq + Hash + Send + Sync,
+    Evt: message::Message + Clone + Send + Sync,
+{
+    async fn append(
+        &self,
+        id: Id,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Evt>>,
+    ) -> Result<version::Version, AppendError> {
+        let conflicting = self
+            .conflicting
+            .lock()
+            .expect("acquire lock on conflicting domain events")
+            .remove(&id)
+            .unwrap_or_default();
+
+        for event in conflicting {
+            self.store
+                .append(
+                    event.stream_id,
+                    version::Check::must_be(event.version - 1),
+                    vec![event.event],
+                )
+                .await
+                .expect("conflicting domain event should be inserted in the event store");
+        }
+
+        self.store.append(id, version_check, events).await
+    }
+}
+
 impl<Id, Evt, Cmd> ScenarioThen<Id, Evt, Cmd>
 where
     Id: Clone + Eq + Hash + Send + Sync + Debug,
@@ -137,17 +356,30 @@ where
     /// The method panics if the assertion fails.
     pub async fn assert_on<F, H>(self, handler_factory: F)
     where
-        F: Fn(event::store::Tracking<event::store::InMemory<Id, Evt>, Id, Evt>) -> H,
+        F: Fn(event::store::Tracking<ScenarioEventStore<Id, Evt>, Id, Evt>) -> H,
         H: command::Handler<Cmd>,
     {
         let event_store = event::store::InMemory::<Id, Evt>::default();
-        let tracking_event_store = event_store.clone().with_recorded_events_tracking();
+
+        let mut conflicting: HashMap<Id, Vec<_>> = HashMap::new();
+        for event in self.conflicting {
+            conflicting
+                .entry(event.stream_id.clone())
+                .or_default()
+                .push(event);
+        }
+
+        let tracking_event_store = ScenarioEventStore {
+            store: event_store.clone(),
+            conflicting: Arc::new(Mutex::new(conflicting)),
+        }
+        .with_recorded_events_tracking();
 
         for event in self.given {
             event_store
                 .append(
                     event.stream_id,
-                    version::Check::MustBe(event.version - 1),
+                    version::Check::must_be(event.version - 1),
                     vec![event.event],
                 )
                 .await
@@ -162,7 +394,14 @@ where
                 let recorded_events = tracking_event_store.recorded_events();
                 assert_eq!(events, recorded_events);
             },
+            ScenarioThenCase::Matches(expectations) => {
+                let recorded_events = tracking_event_store.recorded_events();
+
+                for expectation in expectations {
+                    expectation.assert(&recorded_events);
+                }
+            },
             ScenarioThenCase::Fails => assert!(result.is_err()),
-        };
+        }
     }
 }
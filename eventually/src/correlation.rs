@@ -0,0 +1,560 @@
+//! Contains the [Correlated] Command [Handler][command::Handler] decorator and the
+//! [Stamped] [`event::Store`] decorator, which thread the correlation and causation
+//! [Metadata][message::Metadata] of a Domain Command onto the Domain Events
+//! appended while handling it, without passing it through the domain code.
+//!
+//! The Domain Command [Metadata][message::Metadata] is captured by [Correlated]
+//! in a task-local context, which [Stamped] reads on append: both decorators must
+//! run on the same task, i.e. the Domain Events must not be appended from a
+//! spawned task.
+//!
+//! The Event Stores implementing [`WorkflowQuery`] can then return the whole
+//! [Workflow] of a correlation id, e.g. for support tooling.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::aggregate::timeline::CORRELATION_ID_KEY;
+use crate::event::store::{AppendError, Appender, Streamer};
+use crate::message::RECORDED_AT_KEY;
+use crate::{command, event, message, version};
+
+/// [Metadata][message::Metadata] key of the id of a Domain Command or Domain Event,
+/// set by the application (e.g. from the request id, or a generated UUID).
+pub const MESSAGE_ID_KEY: &str = "Message-Id";
+
+/// [Metadata][message::Metadata] key of the id of the Domain Command that caused
+/// a Domain Event, i.e. the [`MESSAGE_ID_KEY`] of the Domain Command.
+pub const CAUSATION_ID_KEY: &str = "Causation-Id";
+
+tokio::task_local! {
+    static CONTEXT: Arc<message::Metadata>;
+}
+
+/// Returns the [Metadata][message::Metadata] that [Stamped] adds to the Domain Events
+/// appended while handling the Domain Command with the specified name and metadata.
+fn propagated_metadata(
+    command_name: &str,
+    command_metadata: &message::Metadata,
+    headers: &[&'static str],
+) -> message::Metadata {
+    let mut metadata = message::Metadata::new();
+
+    metadata.insert(message::CAUSED_BY_KEY.to_owned(), command_name.to_owned());
+
+    let correlation_id = command_metadata
+        .get(CORRELATION_ID_KEY)
+        .or_else(|| command_metadata.get(MESSAGE_ID_KEY));
+
+    if let Some(correlation_id) = correlation_id {
+        metadata.insert(CORRELATION_ID_KEY.to_owned(), correlation_id.clone());
+    }
+
+    if let Some(message_id) = command_metadata.get(MESSAGE_ID_KEY) {
+        metadata.insert(CAUSATION_ID_KEY.to_owned(), message_id.clone());
+    }
+
+    for header in headers {
+        if let Some(value) = command_metadata.get(*header) {
+            metadata.insert((*header).to_owned(), value.clone());
+        }
+    }
+
+    metadata
+}
+
+/// Command [Handler][command::Handler] decorator that makes the correlation and
+/// causation [Metadata][message::Metadata] of the handled Domain Command available
+/// to the [Stamped] Event Stores used by the wrapped Handler.
+///
+/// The Domain Events inherit the [`CORRELATION_ID_KEY`] of the Domain Command,
+/// or its [`MESSAGE_ID_KEY`] if it starts a new correlation, and record the
+/// [`MESSAGE_ID_KEY`] of the Domain Command as their [`CAUSATION_ID_KEY`].
+#[derive(Debug, Clone)]
+pub struct Correlated<H> {
+    handler: H,
+    headers: Vec<&'static str>,
+}
+
+impl<H> Correlated<H> {
+    /// Creates a new [Correlated] decorator over the specified Command
+    /// [Handler][command::Handler].
+    pub fn new(handler: H) -> Self {
+        Self {
+            handler,
+            headers: Vec::new(),
+        }
+    }
+
+    /// Propagates the user-defined header with the specified [Metadata][message::Metadata]
+    /// key from the Domain Command to the Domain Events, e.g. a tenant id.
+    #[must_use]
+    pub fn with_header(mut self, key: &'static str) -> Self {
+        self.headers.push(key);
+        self
+    }
+}
+
+#[async_trait]
+impl<T, H> command::Handler<T> for Correlated<H>
+where
+    T: message::Message + Send + Sync + 'static,
+    H: command::Handler<T>,
+{
+    type Error = H::Error;
+
+    async fn handle(&self, command: command::Envelope<T>) -> Result<(), Self::Error> {
+        let metadata =
+            propagated_metadata(command.message.name(), &command.metadata, &self.headers);
+
+        CONTEXT
+            .scope(Arc::new(metadata), self.handler.handle(command))
+            .await
+    }
+}
+
+/// [`event::Store`] decorator that adds the [Metadata][message::Metadata] propagated
+/// by a [Correlated] Command [Handler][command::Handler] to the appended Domain Events.
+///
+/// Metadata already set on a Domain Event is never overwritten. Domain Events
+/// appended outside of a [Correlated] Handler are appended unchanged.
+#[derive(Debug, Clone)]
+pub struct Stamped<S> {
+    store: S,
+}
+
+impl<S> Stamped<S> {
+    /// Creates a new [Stamped] decorator over the specified [`event::Store`].
+    pub fn new(store: S) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl<S, StreamId, Event> Streamer<StreamId, Event> for Stamped<S>
+where
+    S: Streamer<StreamId, Event>,
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    type Error = S::Error;
+
+    fn stream(
+        &self,
+        id: &StreamId,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, StreamId, Event, Self::Error> {
+        self.store.stream(id, select)
+    }
+
+    fn read_from<'a>(
+        &'a self,
+        id: &StreamId,
+        from_version: version::Version,
+        batch_size: usize,
+    ) -> event::Stream<'a, StreamId, Event, Self::Error>
+    where
+        StreamId: Clone + 'a,
+        Event: 'a,
+        Self::Error: 'a,
+    {
+        self.store.read_from(id, from_version, batch_size)
+    }
+
+    async fn head_version(&self, id: &StreamId) -> Result<Option<version::Version>, Self::Error> {
+        self.store.head_version(id).await
+    }
+}
+
+#[async_trait]
+impl<S, StreamId, Event> Appender<StreamId, Event> for Stamped<S>
+where
+    S: Appender<StreamId, Event>,
+    StreamId: Send + Sync + 'static,
+    Event: message::Message + Send + Sync + 'static,
+{
+    async fn append(
+        &self,
+        id: StreamId,
+        version_check: version::Check,
+        mut events: Vec<event::Envelope<Event>>,
+    ) -> Result<version::Version, AppendError> {
+        if let Ok(metadata) = CONTEXT.try_with(Arc::clone) {
+            for event in &mut events {
+                for (key, value) in metadata.iter() {
+                    event
+                        .metadata
+                        .entry(key.clone())
+                        .or_insert_with(|| value.clone());
+                }
+            }
+        }
+
+        self.store.append(id, version_check, events).await
+    }
+}
+
+/// A Domain Command of a [Workflow], with the Domain Events recorded while handling it.
+///
+/// Domain Commands are not persisted: they are reconstructed from the
+/// [Metadata][message::Metadata] propagated to their Domain Events by [Correlated].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandStep<StreamId, Event>
+where
+    Event: message::Message,
+{
+    /// The id of the Domain Command, from the [`CAUSATION_ID_KEY`] of its Domain Events.
+    pub message_id: Option<String>,
+    /// The name of the Domain Command, from the [`CAUSED_BY_KEY`][message::CAUSED_BY_KEY]
+    /// of its Domain Events.
+    pub name: Option<String>,
+    /// The Domain Events recorded while handling the Domain Command, in causal order.
+    pub events: Vec<event::Persisted<StreamId, Event>>,
+}
+
+/// The causally-ordered chain of Domain Commands and Domain Events sharing
+/// the same correlation id, across all the Event Streams.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Workflow<StreamId, Event>
+where
+    Event: message::Message,
+{
+    /// The correlation id of the [Workflow].
+    pub correlation_id: String,
+    /// The Domain Commands of the [Workflow], in causal order.
+    pub steps: Vec<CommandStep<StreamId, Event>>,
+}
+
+impl<StreamId, Event> Workflow<StreamId, Event>
+where
+    StreamId: Eq,
+    Event: message::Message,
+{
+    /// Builds the [Workflow] from the Domain Events with the specified correlation id,
+    /// in any order.
+    ///
+    /// Domain Events are ordered by their [`RECORDED_AT_KEY`] metadata, if any, while
+    /// always following the ones with a lower version in the same Event Stream, and
+    /// the ones which [`MESSAGE_ID_KEY`] is their [`CAUSATION_ID_KEY`]. Consecutive
+    /// Domain Events caused by the same Domain Command are grouped in a [`CommandStep`].
+    pub fn from_events(
+        correlation_id: impl Into<String>,
+        events: impl IntoIterator<Item = event::Persisted<StreamId, Event>>,
+    ) -> Self {
+        let mut events: Vec<_> = events.into_iter().collect();
+
+        // RFC 3339 timestamps in the same timezone sort lexicographically.
+        events.sort_by(|a, b| {
+            a.event
+                .metadata
+                .get(RECORDED_AT_KEY)
+                .cmp(&b.event.metadata.get(RECORDED_AT_KEY))
+        });
+
+        let happens_before = |a: &event::Persisted<StreamId, Event>,
+                              b: &event::Persisted<StreamId, Event>| {
+            let same_stream = a.stream_id == b.stream_id && a.version < b.version;
+            let causes = a
+                .event
+                .metadata
+                .get(MESSAGE_ID_KEY)
+                .is_some_and(|message_id| {
+                    b.event.metadata.get(CAUSATION_ID_KEY) == Some(message_id)
+                });
+
+            same_stream || causes
+        };
+
+        // Topological sort, preferring the earliest recorded Domain Event.
+        let mut pending: Vec<_> = events.into_iter().map(Some).collect();
+        let mut ordered = Vec::with_capacity(pending.len());
+
+        while let Some(next) = pending
+            .iter()
+            .enumerate()
+            .filter_map(|(i, evt)| evt.as_ref().map(|evt| (i, evt)))
+            .find(|(_, candidate)| {
+                !pending
+                    .iter()
+                    .flatten()
+                    .any(|other| happens_before(other, candidate))
+            })
+            .map(|(i, _)| i)
+            // A causal cycle can only come from inconsistent metadata:
+            // fall back to the recorded order.
+            .or_else(|| pending.iter().position(Option::is_some))
+        {
+            ordered.extend(pending[next].take());
+        }
+
+        let mut steps: Vec<CommandStep<StreamId, Event>> = Vec::new();
+
+        for event in ordered {
+            let message_id = event.event.metadata.get(CAUSATION_ID_KEY).cloned();
+            let name = event.event.metadata.get(message::CAUSED_BY_KEY).cloned();
+
+            match steps.last_mut() {
+                Some(step) if step.message_id == message_id && step.name == name => {
+                    step.events.push(event);
+                },
+                _ => steps.push(CommandStep {
+                    message_id,
+                    name,
+                    events: vec![event],
+                }),
+            }
+        }
+
+        Self {
+            correlation_id: correlation_id.into(),
+            steps,
+        }
+    }
+}
+
+/// Interface implemented by the Event Stores able to look up the Domain Events
+/// by their [`CORRELATION_ID_KEY`] metadata, across all the Event Streams.
+#[async_trait]
+pub trait WorkflowQuery<StreamId, Event>: Send + Sync
+where
+    StreamId: Eq + Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    /// The error type returned when the Domain Events could not be looked up.
+    type Error: Send + Sync;
+
+    /// Returns all the Domain Events with the specified correlation id, in any order.
+    async fn correlated_events(
+        &self,
+        correlation_id: &str,
+    ) -> Result<Vec<event::Persisted<StreamId, Event>>, Self::Error>;
+
+    /// Returns the [Workflow] with the specified correlation id,
+    /// built with [`Workflow::from_events`].
+    async fn workflow(
+        &self,
+        correlation_id: &str,
+    ) -> Result<Workflow<StreamId, Event>, Self::Error> {
+        let events = self.correlated_events(correlation_id).await?;
+
+        Ok(Workflow::from_events(correlation_id, events))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::command::Handler;
+    use crate::event::store::InMemory;
+    use crate::message::tests::StringMessage;
+
+    type Store = Stamped<InMemory<&'static str, StringMessage>>;
+
+    struct Service(Store);
+
+    #[async_trait]
+    impl Handler<StringMessage> for Service {
+        type Error = AppendError;
+
+        async fn handle(&self, _: command::Envelope<StringMessage>) -> Result<(), Self::Error> {
+            self.0
+                .append(
+                    "stream:test",
+                    version::Check::Any,
+                    vec![
+                        event::Envelope::from(StringMessage("first")),
+                        event::Envelope::from(StringMessage("second"))
+                            .with_metadata(CAUSATION_ID_KEY.to_owned(), "first".to_owned()),
+                    ],
+                )
+                .await
+                .map(|_| ())
+        }
+    }
+
+    fn correlated_event(
+        message: &'static str,
+        correlation_id: &str,
+        recorded_at: &str,
+        causation: (&str, &str),
+    ) -> event::Envelope<StringMessage> {
+        event::Envelope::from(StringMessage(message))
+            .with_metadata(CORRELATION_ID_KEY.to_owned(), correlation_id.to_owned())
+            .with_metadata(RECORDED_AT_KEY.to_owned(), recorded_at.to_owned())
+            .with_metadata(message::CAUSED_BY_KEY.to_owned(), causation.0.to_owned())
+            .with_metadata(CAUSATION_ID_KEY.to_owned(), causation.1.to_owned())
+    }
+
+    #[tokio::test]
+    async fn workflow_query_returns_causally_ordered_commands_and_events() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+
+        let writes = [
+            (
+                "account:1",
+                vec![
+                    correlated_event(
+                        "opened",
+                        "request-1",
+                        "2026-01-01T00:00:01+00:00",
+                        ("OpenAccount", "command-1"),
+                    ),
+                    // Recorded with a skewed clock.
+                    correlated_event(
+                        "deposited",
+                        "request-1",
+                        "2026-01-01T00:00:00+00:00",
+                        ("OpenAccount", "command-1"),
+                    ),
+                ],
+            ),
+            (
+                "email:1",
+                vec![correlated_event(
+                    "welcome sent",
+                    "request-1",
+                    "2026-01-01T00:00:02+00:00",
+                    ("SendWelcomeEmail", "command-2"),
+                )],
+            ),
+            (
+                "account:2",
+                vec![correlated_event(
+                    "closed",
+                    "request-2",
+                    "2026-01-01T00:00:05+00:00",
+                    ("CloseAccount", "command-3"),
+                )
+                .with_metadata(MESSAGE_ID_KEY.to_owned(), "event-1".to_owned())],
+            ),
+            (
+                "email:2",
+                // Recorded with a skewed clock, caused by the previous Domain Event.
+                vec![correlated_event(
+                    "goodbye sent",
+                    "request-2",
+                    "2026-01-01T00:00:04+00:00",
+                    ("SendGoodbyeEmail", "event-1"),
+                )],
+            ),
+        ];
+
+        for (id, events) in writes {
+            event_store
+                .append(id, version::Check::Any, events)
+                .await
+                .unwrap();
+        }
+
+        let workflow = event_store.workflow("request-1").await.unwrap();
+
+        let steps: Vec<_> = workflow
+            .steps
+            .iter()
+            .map(|step| {
+                (
+                    step.name.as_deref(),
+                    step.message_id.as_deref(),
+                    step.events
+                        .iter()
+                        .map(|evt| evt.event.message.0)
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            vec![
+                (
+                    Some("OpenAccount"),
+                    Some("command-1"),
+                    vec!["opened", "deposited"]
+                ),
+                (
+                    Some("SendWelcomeEmail"),
+                    Some("command-2"),
+                    vec!["welcome sent"]
+                ),
+            ],
+            steps
+        );
+
+        let workflow = event_store.workflow("request-2").await.unwrap();
+
+        let events: Vec<_> = workflow
+            .steps
+            .iter()
+            .flat_map(|step| step.events.iter().map(|evt| evt.event.message.0))
+            .collect();
+
+        assert_eq!(vec!["closed", "goodbye sent"], events);
+        assert!(event_store
+            .workflow("missing")
+            .await
+            .unwrap()
+            .steps
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn correlated_handler_stamps_command_metadata_on_appended_events() {
+        let event_store: Store = Stamped::new(InMemory::default());
+
+        let handler = Correlated::new(Service(event_store.clone())).with_header("Tenant-Id");
+
+        handler
+            .handle(
+                command::Envelope::from(StringMessage("command"))
+                    .with_metadata(MESSAGE_ID_KEY.to_owned(), "command-1".to_owned())
+                    .with_metadata(CORRELATION_ID_KEY.to_owned(), "request-1".to_owned())
+                    .with_metadata("Tenant-Id".to_owned(), "tenant-1".to_owned())
+                    .with_metadata("Ignored".to_owned(), "ignored".to_owned()),
+            )
+            .await
+            .expect("command should be handled successfully");
+
+        let events: Vec<_> = event_store
+            .stream(&"stream:test", event::VersionSelect::All)
+            .map_ok(|evt| evt.event.metadata)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            message::Metadata::from([
+                (
+                    message::CAUSED_BY_KEY.to_owned(),
+                    "string_payload".to_owned()
+                ),
+                (CORRELATION_ID_KEY.to_owned(), "request-1".to_owned()),
+                (CAUSATION_ID_KEY.to_owned(), "command-1".to_owned()),
+                ("Tenant-Id".to_owned(), "tenant-1".to_owned()),
+            ]),
+            events[0]
+        );
+
+        // Metadata set explicitly on the Domain Event takes precedence.
+        assert_eq!("first", events[1][CAUSATION_ID_KEY]);
+
+        // Outside of a correlated handler, Domain Events are appended unchanged.
+        event_store
+            .append(
+                "stream:test",
+                version::Check::Any,
+                vec![event::Envelope::from(StringMessage("third"))],
+            )
+            .await
+            .unwrap();
+
+        let last = event_store
+            .stream(&"stream:test", event::VersionSelect::From(3))
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        assert!(last[0].event.metadata.is_empty());
+    }
+}
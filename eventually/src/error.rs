@@ -0,0 +1,248 @@
+//! Module containing the framework [Error] type, together with its
+//! machine-readable [Kind] classification.
+//!
+//! Operation-specific errors, such as [`AppendError`] or [`SaveError`], expose their [Kind]
+//! and can be converted into an [Error], so that middlewares, API error mappers
+//! and retry policies can branch on the error without resorting to string matching.
+
+use std::convert::Infallible;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use serde::{Deserialize, Serialize};
+
+use crate::aggregate::repository::{GetError, SaveError};
+use crate::event::store::AppendError;
+use crate::version;
+
+/// Machine-readable classification of an [Error].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    /// An optimistic concurrency check has failed, or the operation
+    /// has been aborted by the data store because of a concurrent one.
+    Conflict,
+    /// The requested resource could not be found.
+    NotFound,
+    /// Some data could not be serialized or deserialized.
+    Serialization,
+    /// The connection to the underlying data store could not be established or has been lost.
+    Connection,
+    /// The operation did not complete in the expected time.
+    Timeout,
+    /// A domain invariant has been violated.
+    Domain,
+    /// Any other unexpected error.
+    Internal,
+}
+
+impl Display for Kind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let kind = match self {
+            Kind::Conflict => "conflict",
+            Kind::NotFound => "not_found",
+            Kind::Serialization => "serialization",
+            Kind::Connection => "connection",
+            Kind::Timeout => "timeout",
+            Kind::Domain => "domain",
+            Kind::Internal => "internal",
+        };
+
+        f.write_str(kind)
+    }
+}
+
+impl Kind {
+    /// Returns true if an operation that failed with this [Kind] of error
+    /// may succeed when attempted again, e.g. after a network blip.
+    ///
+    /// Conflicts are considered transient too, as evaluating the operation
+    /// again on the latest state usually resolves them.
+    #[must_use]
+    pub fn is_transient(self) -> bool {
+        matches!(self, Kind::Conflict | Kind::Connection | Kind::Timeout)
+    }
+}
+
+/// Classifies errors as transient (i.e. worth retrying) or permanent.
+///
+/// Components that implement some retry logic, such as [`command::Retry`][crate::command::Retry],
+/// rely on this trait to retry infrastructure failures while failing fast on domain errors.
+pub trait Retryable {
+    /// Returns true if the operation that returned this error may succeed
+    /// when attempted again.
+    fn is_transient(&self) -> bool;
+}
+
+impl Retryable for Kind {
+    fn is_transient(&self) -> bool {
+        Kind::is_transient(*self)
+    }
+}
+
+/// The framework error type, carrying a [Kind] for each of its variants.
+///
+/// Domain errors are carried by the [`Error::Domain`] variant, which type is
+/// specified through the `E` type parameter.
+///
+/// Data store implementations can attach a specific [Kind] to the errors
+/// they return through [`anyhow::Error`] by wrapping an [Error] in it:
+/// the classification is preserved when converting those errors into [Error].
+#[derive(Debug, thiserror::Error)]
+pub enum Error<E = Infallible> {
+    /// Error returned when an optimistic concurrency check has failed.
+    #[error(transparent)]
+    Conflict(#[from] version::ConflictError),
+    /// Error returned when the data store has aborted the operation because of
+    /// a concurrent one, e.g. on a transaction serialization failure or a deadlock.
+    #[error("operation aborted by a concurrent one: {0}")]
+    Contention(#[source] anyhow::Error),
+    /// Error returned when the requested resource could not be found.
+    #[error("resource not found")]
+    NotFound,
+    /// Error returned when some data could not be serialized or deserialized.
+    #[error("failed to serialize or deserialize data: {0}")]
+    Serialization(#[source] anyhow::Error),
+    /// Error returned when the connection to the data store has failed.
+    #[error("failed to connect to the data store: {0}")]
+    Connection(#[source] anyhow::Error),
+    /// Error returned when an operation did not complete in the expected time.
+    #[error("operation timed out: {0}")]
+    Timeout(#[source] anyhow::Error),
+    /// Error returned when a domain invariant has been violated.
+    #[error("domain error: {0}")]
+    Domain(#[source] E),
+    /// Error returned for any other unexpected failure.
+    #[error("an internal error occurred: {0}")]
+    Internal(#[source] anyhow::Error),
+}
+
+impl<E> Error<E> {
+    /// Returns the [Kind] of the error.
+    #[must_use]
+    pub fn kind(&self) -> Kind {
+        match self {
+            Error::Conflict(_) | Error::Contention(_) => Kind::Conflict,
+            Error::NotFound => Kind::NotFound,
+            Error::Serialization(_) => Kind::Serialization,
+            Error::Connection(_) => Kind::Connection,
+            Error::Timeout(_) => Kind::Timeout,
+            Error::Domain(_) => Kind::Domain,
+            Error::Internal(_) => Kind::Internal,
+        }
+    }
+}
+
+impl<E> From<anyhow::Error> for Error<E> {
+    fn from(err: anyhow::Error) -> Self {
+        match err.downcast::<Error>() {
+            Ok(Error::Conflict(err)) => Error::Conflict(err),
+            Ok(Error::Contention(err)) => Error::Contention(err),
+            Ok(Error::NotFound) => Error::NotFound,
+            Ok(Error::Serialization(err)) => Error::Serialization(err),
+            Ok(Error::Connection(err)) => Error::Connection(err),
+            Ok(Error::Timeout(err)) => Error::Timeout(err),
+            Ok(Error::Internal(err)) | Err(err) => Error::Internal(err),
+            Ok(Error::Domain(never)) => match never {},
+        }
+    }
+}
+
+impl<E> Retryable for Error<E> {
+    fn is_transient(&self) -> bool {
+        self.kind().is_transient()
+    }
+}
+
+impl Retryable for anyhow::Error {
+    /// Looks for a framework error in the error chain to determine whether
+    /// the error is transient: unknown errors are never considered transient.
+    fn is_transient(&self) -> bool {
+        self.chain().any(|cause| {
+            cause
+                .downcast_ref::<AppendError>()
+                .map(AppendError::kind)
+                .or_else(|| cause.downcast_ref::<GetError>().map(GetError::kind))
+                .or_else(|| cause.downcast_ref::<SaveError>().map(SaveError::kind))
+                .or_else(|| cause.downcast_ref::<Error>().map(Error::kind))
+                .or_else(|| {
+                    cause
+                        .downcast_ref::<version::ConflictError>()
+                        .map(|_| Kind::Conflict)
+                })
+                .is_some_and(Kind::is_transient)
+        })
+    }
+}
+
+/// Returns the [Kind] of an [`anyhow::Error`], which is [`Kind::Internal`]
+/// unless the error wraps a classified [Error].
+pub(crate) fn kind_of(err: &anyhow::Error) -> Kind {
+    err.downcast_ref::<Error>()
+        .map_or(Kind::Internal, Error::kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use super::*;
+
+    #[test]
+    fn unclassified_errors_are_internal() {
+        let err = AppendError::Internal(anyhow!("something went wrong"));
+        assert_eq!(Kind::Internal, err.kind());
+
+        let err: Error = err.into();
+        assert_eq!(Kind::Internal, err.kind());
+    }
+
+    #[test]
+    fn classified_errors_preserve_their_kind_through_anyhow() {
+        let err = AppendError::from(Error::Timeout(anyhow!("query took too long")));
+        assert_eq!(Kind::Timeout, err.kind());
+
+        let err: Error<std::io::Error> = err.into();
+        assert_eq!(Kind::Timeout, err.kind());
+
+        let err = GetError::from(Error::Connection(anyhow!("connection reset")));
+        assert_eq!(Kind::Connection, Error::<Infallible>::from(err).kind());
+        assert_eq!(
+            Kind::NotFound,
+            Error::<Infallible>::from(GetError::NotFound).kind()
+        );
+
+        let conflict = version::ConflictError {
+            expected: 1,
+            actual: 2,
+        };
+
+        assert_eq!(Kind::Conflict, SaveError::Conflict(conflict).kind());
+        let err: Error<version::ConflictError> = Error::Domain(conflict);
+        assert_eq!(Kind::Domain, err.kind());
+    }
+
+    #[test]
+    fn only_infrastructure_errors_and_conflicts_are_transient() {
+        let conflict = version::ConflictError {
+            expected: 1,
+            actual: 2,
+        };
+
+        assert!(AppendError::Conflict(conflict).is_transient());
+        assert!(GetError::from(Error::Timeout(anyhow!("query took too long"))).is_transient());
+        assert!(!GetError::NotFound.is_transient());
+        assert!(!SaveError::Internal(anyhow!("something went wrong")).is_transient());
+        assert!(
+            AppendError::from(Error::Contention(anyhow!("could not serialize access")))
+                .is_transient()
+        );
+
+        let err: Error<version::ConflictError> = Error::Domain(conflict);
+        assert!(!err.is_transient());
+
+        // Errors wrapped in anyhow::Error are classified through their chain.
+        let err = anyhow::Error::from(SaveError::Conflict(conflict)).context("command failed");
+        assert!(err.is_transient());
+        assert!(!anyhow!("unknown error").is_transient());
+    }
+}
@@ -0,0 +1,168 @@
+//! Contains the [Operation] Domain Event type, which describes an administrative
+//! operation performed on an Event Store (e.g. a retention purge), and the [`AuditLog`]
+//! used to record it to a dedicated audit Event Stream.
+//!
+//! Recording the destructive maintenance operations, together with who
+//! performed them and why, keeps them traceable after the data is gone.
+
+use serde::{Deserialize, Serialize};
+
+use crate::aggregate::timeline::ACTOR_KEY;
+use crate::event::store::{AppendError, Appender};
+use crate::{event, message, version};
+
+/// [Metadata][message::Metadata] key of the reason of an administrative [Operation].
+///
+/// The actor performing the [Operation] is recorded under the [`ACTOR_KEY`] key.
+pub const REASON_KEY: &str = "Reason";
+
+/// An administrative operation performed on an Event Stream.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Operation {
+    /// All the Domain Events of the Event Stream have been deleted.
+    StreamDeleted {
+        /// The id of the deleted Event Stream.
+        stream_id: String,
+    },
+    /// The Domain Events of the Event Stream up to a version have been deleted.
+    StreamTruncated {
+        /// The id of the truncated Event Stream.
+        stream_id: String,
+        /// The version of the last deleted Domain Event.
+        up_to: version::Version,
+    },
+    /// The Domain Events of the Event Stream have been rewritten, e.g. by a migration.
+    StreamRewritten {
+        /// The id of the rewritten Event Stream.
+        stream_id: String,
+    },
+    /// The Domain Events of the Event Stream exceeding its retention rules have been deleted.
+    RetentionPurged {
+        /// The id of the purged Event Stream.
+        stream_id: String,
+        /// The number of deleted Domain Events.
+        deleted: u64,
+    },
+}
+
+impl message::Message for Operation {
+    fn name(&self) -> &'static str {
+        match self {
+            Operation::StreamDeleted { .. } => "EventStreamWasDeleted",
+            Operation::StreamTruncated { .. } => "EventStreamWasTruncated",
+            Operation::StreamRewritten { .. } => "EventStreamWasRewritten",
+            Operation::RetentionPurged { .. } => "EventStreamRetentionWasPurged",
+        }
+    }
+}
+
+impl message::Registry for Operation {
+    const NAMES: &'static [&'static str] = &[
+        "EventStreamWasDeleted",
+        "EventStreamWasTruncated",
+        "EventStreamWasRewritten",
+        "EventStreamRetentionWasPurged",
+    ];
+}
+
+impl Operation {
+    /// Returns the [Operation] as a Domain Event, attributed to the specified
+    /// actor and reason through its [Metadata][message::Metadata].
+    #[must_use]
+    pub fn attributed_to(
+        self,
+        actor: impl Into<String>,
+        reason: impl Into<String>,
+    ) -> event::Envelope<Operation> {
+        event::Envelope::from(self)
+            .with_metadata(ACTOR_KEY.to_owned(), actor.into())
+            .with_metadata(REASON_KEY.to_owned(), reason.into())
+    }
+}
+
+/// Records the administrative [Operation]s to a dedicated audit Event Stream
+/// of the wrapped Event [Appender].
+#[derive(Debug, Clone)]
+pub struct AuditLog<S, StreamId> {
+    store: S,
+    stream_id: StreamId,
+}
+
+impl<S, StreamId> AuditLog<S, StreamId>
+where
+    S: Appender<StreamId, Operation>,
+    StreamId: Clone + Send + Sync,
+{
+    /// Creates a new [`AuditLog`] recording to the Event Stream with the specified id.
+    pub fn new(store: S, stream_id: StreamId) -> Self {
+        Self { store, stream_id }
+    }
+
+    /// Records the [Operation], performed by the specified actor for the specified reason.
+    ///
+    /// Returns the new version of the audit Event Stream.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the Event [Appender] has failed.
+    pub async fn record(
+        &self,
+        operation: Operation,
+        actor: impl Into<String>,
+        reason: impl Into<String>,
+    ) -> Result<version::Version, AppendError> {
+        self.store
+            .append(
+                self.stream_id.clone(),
+                version::Check::Any,
+                vec![operation.attributed_to(actor, reason)],
+            )
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::event::store::{InMemory, Streamer};
+
+    #[tokio::test]
+    async fn audit_log_records_operations_with_actor_and_reason() {
+        let event_store = InMemory::<&'static str, Operation>::default();
+        let audit_log = AuditLog::new(event_store.clone(), "$audit");
+
+        audit_log
+            .record(
+                Operation::StreamTruncated {
+                    stream_id: "user:john".to_owned(),
+                    up_to: 10,
+                },
+                "admin@example.com",
+                "GDPR erasure request",
+            )
+            .await
+            .expect("the operation should be recorded");
+
+        let records: Vec<_> = event_store
+            .stream(&"$audit", event::VersionSelect::All)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(1, records.len());
+        assert_eq!(
+            Operation::StreamTruncated {
+                stream_id: "user:john".to_owned(),
+                up_to: 10,
+            },
+            records[0].event.message
+        );
+        assert_eq!("admin@example.com", records[0].event.metadata[ACTOR_KEY]);
+        assert_eq!(
+            "GDPR erasure request",
+            records[0].event.metadata[REASON_KEY]
+        );
+    }
+}
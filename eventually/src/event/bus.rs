@@ -0,0 +1,353 @@
+//! Contains the in-process Event [Bus], which dispatches Domain Events to the
+//! [Consumer]s of the modules of a modular monolith, without an external broker.
+//!
+//! Each module subscribes to the Domain Events it is interested in through
+//! its own Domain Event type, and keeps its own checkpoint per Event Stream,
+//! so that a failing module does not cause the other ones to receive
+//! the same Domain Event twice.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use crate::event::consumer::Consumer;
+use crate::{event, message, version};
+
+/// Error returned by the [Bus] when the [Consumer] of a module has failed.
+///
+/// The modules subscribed after the failed one have not received the Domain Event.
+#[derive(Debug, thiserror::Error)]
+#[error("module '{module}' failed to consume domain event: {source}")]
+pub struct BusError {
+    /// The name of the failed module.
+    pub module: &'static str,
+    /// The error returned by the module [Consumer].
+    #[source]
+    pub source: anyhow::Error,
+}
+
+/// A module [Consumer], erased of its Domain Event and error types.
+#[async_trait]
+trait Module<StreamId, Event>: Send + Sync
+where
+    Event: message::Message,
+{
+    async fn deliver(&self, event: event::Persisted<StreamId, Event>) -> anyhow::Result<()>;
+}
+
+/// Adapts a [Consumer] of decoded Domain Events into a [Module],
+/// skipping the Domain Events that cannot be decoded.
+struct Decoding<C, E> {
+    consumer: C,
+    decoded: PhantomData<fn() -> E>,
+}
+
+#[async_trait]
+impl<C, E, StreamId, Event> Module<StreamId, Event> for Decoding<C, E>
+where
+    C: Consumer<StreamId, E>,
+    C::Error: Into<anyhow::Error>,
+    E: message::Message + TryFrom<Event> + Send + 'static,
+    StreamId: Send + 'static,
+    Event: message::Message + Send + 'static,
+{
+    async fn deliver(&self, event: event::Persisted<StreamId, Event>) -> anyhow::Result<()> {
+        let Ok(message) = E::try_from(event.event.message) else {
+            // The module is not interested in this Domain Event.
+            return Ok(());
+        };
+
+        self.consumer
+            .consume(event::Persisted {
+                stream_id: event.stream_id,
+                version: event.version,
+                event: event::Envelope {
+                    message,
+                    metadata: event.event.metadata,
+                },
+            })
+            .await
+            .map_err(Into::into)
+    }
+}
+
+struct Subscription<StreamId, Event>
+where
+    Event: message::Message,
+{
+    module: &'static str,
+    consumer: Box<dyn Module<StreamId, Event>>,
+    checkpoints: Mutex<HashMap<StreamId, version::Version>>,
+}
+
+/// In-process [Consumer] that dispatches each Domain Event to all the subscribed
+/// modules, in the order they have subscribed.
+///
+/// Feed the [Bus] with the Domain Events of an Event Store, e.g. through
+/// [`Reliable::consume`][event::consumer::Reliable::consume] or a store subscription.
+/// When a module fails, the Domain Event should be delivered again: the modules
+/// that have already consumed it skip it, using their checkpoints.
+pub struct Bus<StreamId, Event>
+where
+    Event: message::Message,
+{
+    subscriptions: Vec<Subscription<StreamId, Event>>,
+}
+
+impl<StreamId, Event> Debug for Bus<StreamId, Event>
+where
+    Event: message::Message,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Bus")
+            .field(
+                "modules",
+                &self
+                    .subscriptions
+                    .iter()
+                    .map(|subscription| subscription.module)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl<StreamId, Event> Default for Bus<StreamId, Event>
+where
+    Event: message::Message,
+{
+    fn default() -> Self {
+        Self {
+            subscriptions: Vec::new(),
+        }
+    }
+}
+
+impl<StreamId, Event> Bus<StreamId, Event>
+where
+    StreamId: Clone + Eq + Hash + Send + Sync + 'static,
+    Event: message::Message + Send + Sync + 'static,
+{
+    /// Subscribes the [Consumer] of the module with the specified name.
+    ///
+    /// The [Consumer] receives only the Domain Events that can be converted
+    /// into its Domain Event type `E`, using its [`TryFrom`] implementation.
+    #[must_use]
+    pub fn subscribe<E, C>(mut self, module: &'static str, consumer: C) -> Self
+    where
+        C: Consumer<StreamId, E> + 'static,
+        C::Error: Into<anyhow::Error>,
+        E: message::Message + TryFrom<Event> + Send + 'static,
+    {
+        self.subscriptions.push(Subscription {
+            module,
+            consumer: Box::new(Decoding {
+                consumer,
+                decoded: PhantomData,
+            }),
+            checkpoints: Mutex::default(),
+        });
+        self
+    }
+
+    /// Restores the checkpoint of the specified module for an Event Stream,
+    /// e.g. as previously returned by [`Bus::checkpoints`].
+    ///
+    /// # Panics
+    ///
+    /// The method panics if no module with the specified name has subscribed,
+    /// or if the lock on its checkpoints has been poisoned.
+    #[must_use]
+    pub fn with_checkpoint(
+        self,
+        module: &str,
+        stream_id: StreamId,
+        version: version::Version,
+    ) -> Self {
+        self.subscription(module)
+            .unwrap_or_else(|| panic!("module '{module}' has not subscribed to the bus"))
+            .checkpoints
+            .lock()
+            .expect("acquire lock on module checkpoints")
+            .insert(stream_id, version);
+        self
+    }
+
+    /// Returns the version of the last Domain Event delivered to the specified module
+    /// for each Event Stream, or `None` if no module with the specified name has subscribed.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if the lock on the module checkpoints has been poisoned.
+    #[must_use]
+    pub fn checkpoints(&self, module: &str) -> Option<HashMap<StreamId, version::Version>> {
+        self.subscription(module).map(|subscription| {
+            subscription
+                .checkpoints
+                .lock()
+                .expect("acquire lock on module checkpoints")
+                .clone()
+        })
+    }
+
+    fn subscription(&self, module: &str) -> Option<&Subscription<StreamId, Event>> {
+        self.subscriptions
+            .iter()
+            .find(|subscription| subscription.module == module)
+    }
+}
+
+#[async_trait]
+impl<StreamId, Event> Consumer<StreamId, Event> for Bus<StreamId, Event>
+where
+    StreamId: Clone + Eq + Hash + Send + Sync + 'static,
+    Event: message::Message + Clone + Send + Sync + 'static,
+{
+    type Error = BusError;
+
+    async fn consume(&self, event: event::Persisted<StreamId, Event>) -> Result<(), Self::Error> {
+        for subscription in &self.subscriptions {
+            let checkpoint = subscription
+                .checkpoints
+                .lock()
+                .expect("acquire lock on module checkpoints")
+                .get(&event.stream_id)
+                .copied();
+
+            if checkpoint.is_some_and(|checkpoint| event.version <= checkpoint) {
+                continue;
+            }
+
+            subscription
+                .consumer
+                .deliver(event.clone())
+                .await
+                .map_err(|source| BusError {
+                    module: subscription.module,
+                    source,
+                })?;
+
+            subscription
+                .checkpoints
+                .lock()
+                .expect("acquire lock on module checkpoints")
+                .insert(event.stream_id.clone(), event.version);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::message::tests::StringMessage;
+
+    #[derive(Debug)]
+    struct Greeting(&'static str);
+
+    impl message::Message for Greeting {
+        fn name(&self) -> &'static str {
+            "greeting"
+        }
+    }
+
+    impl TryFrom<StringMessage> for Greeting {
+        type Error = &'static str;
+
+        fn try_from(value: StringMessage) -> Result<Self, Self::Error> {
+            value
+                .0
+                .strip_prefix("hello ")
+                .map(Greeting)
+                .ok_or("not a greeting")
+        }
+    }
+
+    fn persisted(
+        version: version::Version,
+        message: &'static str,
+    ) -> event::Persisted<&'static str, StringMessage> {
+        event::Persisted {
+            stream_id: "stream:test",
+            version,
+            event: event::Envelope::from(StringMessage(message)),
+        }
+    }
+
+    #[tokio::test]
+    async fn bus_delivers_decoded_events_to_modules_with_own_checkpoints() {
+        let greeted = Arc::new(Mutex::new(Vec::new()));
+        let audit_failing = Arc::new(AtomicBool::new(true));
+
+        let bus = Bus::default()
+            .subscribe("greetings", {
+                let greeted = greeted.clone();
+
+                move |event: event::Persisted<&'static str, Greeting>| {
+                    let greeted = greeted.clone();
+
+                    async move {
+                        greeted
+                            .lock()
+                            .expect("acquire lock on greeted names")
+                            .push(event.event.message.0);
+
+                        Ok::<_, std::convert::Infallible>(())
+                    }
+                }
+            })
+            .subscribe("audit", {
+                let audit_failing = audit_failing.clone();
+
+                move |_: event::Persisted<&'static str, StringMessage>| {
+                    let failing = audit_failing.load(Ordering::SeqCst);
+
+                    async move {
+                        if failing {
+                            return Err(anyhow::anyhow!("audit log unavailable"));
+                        }
+
+                        Ok(())
+                    }
+                }
+            });
+
+        let err = bus
+            .consume(persisted(1, "hello John"))
+            .await
+            .expect_err("the audit module should fail");
+
+        assert_eq!("audit", err.module);
+
+        // Redelivery after the audit module has recovered.
+        audit_failing.store(false, Ordering::SeqCst);
+
+        for event in [persisted(1, "hello John"), persisted(2, "goodbye John")] {
+            bus.consume(event)
+                .await
+                .expect("the domain event should be consumed");
+        }
+
+        assert_eq!(
+            vec!["John"],
+            *greeted.lock().expect("acquire lock on greeted names")
+        );
+        assert_eq!(
+            Some(HashMap::from([("stream:test", 2)])),
+            bus.checkpoints("greetings")
+        );
+        assert_eq!(
+            Some(HashMap::from([("stream:test", 2)])),
+            bus.checkpoints("audit")
+        );
+        assert_eq!(None, bus.checkpoints("missing"));
+    }
+}
@@ -0,0 +1,416 @@
+//! Contains the [Consumer] trait, used to implement side effects triggered
+//! by Domain Events (e.g. an HTTP call per Domain Event), and the [Reliable]
+//! wrapper, which retries failed Domain Events with an optional backoff, forwards
+//! the ones that keep failing to a dead-letter Event Stream, and consumes Domain
+//! Events concurrently.
+//!
+//! Use [`Reliable::consume`] on an [Event Stream][event::Stream], together with
+//! [`EventStreamExt::checkpoint_every`][event::EventStreamExt::checkpoint_every],
+//! to checkpoint the progress of the consumer.
+
+use std::fmt::{Debug, Display};
+use std::future::Future;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::future::{BoxFuture, FutureExt};
+use futures::stream::{StreamExt, TryStream, TryStreamExt};
+
+use crate::error::Retryable;
+use crate::event::store::{AppendError, Appender};
+use crate::{event, message, version};
+
+/// The [Metadata][message::Metadata] key used to record why a Domain Event
+/// has been forwarded to the dead-letter Event Stream.
+pub const DEAD_LETTER_REASON_KEY: &str = "Dead-Letter-Reason";
+
+/// A software component that performs some side effect for each
+/// [Persisted][event::Persisted] Domain Event it receives, and can fail.
+#[async_trait]
+pub trait Consumer<StreamId, Event>: Send + Sync
+where
+    Event: message::Message,
+{
+    /// The error type returned by the Consumer when failing to consume a Domain Event.
+    type Error: Send + Sync;
+
+    /// Consumes a Domain Event, returning an error if that failed.
+    async fn consume(&self, event: event::Persisted<StreamId, Event>) -> Result<(), Self::Error>;
+}
+
+#[async_trait]
+impl<StreamId, Event, Err, F, Fut> Consumer<StreamId, Event> for F
+where
+    StreamId: Send + 'static,
+    Event: message::Message + Send + 'static,
+    Err: Send + Sync,
+    F: Send + Sync + Fn(event::Persisted<StreamId, Event>) -> Fut,
+    Fut: Send + Future<Output = Result<(), Err>>,
+{
+    type Error = Err;
+
+    async fn consume(&self, event: event::Persisted<StreamId, Event>) -> Result<(), Self::Error> {
+        self(event).await
+    }
+}
+
+/// All possible errors returned by [`Reliable::consume`].
+#[derive(Debug, thiserror::Error)]
+pub enum ConsumeError<StreamErr, ConsumerErr> {
+    /// Error returned when the underlying [Event Stream][event::Stream] has failed.
+    #[error("failed to stream domain event: {0}")]
+    Stream(#[source] StreamErr),
+    /// Error returned when the [Consumer] has failed for all the configured attempts,
+    /// and no dead-letter Event Stream has been configured.
+    #[error("failed to consume domain event: {0}")]
+    Consumer(#[source] ConsumerErr),
+    /// Error returned when the Domain Event could not be appended
+    /// to the dead-letter Event Stream.
+    #[error("failed to append domain event to the dead-letter event stream: {0}")]
+    DeadLetter(#[source] AppendError),
+}
+
+type DeadLetter<StreamId, Event> = (Arc<dyn Appender<StreamId, Event>>, StreamId);
+
+type RetryIf<Err> = Arc<dyn Fn(&Err) -> bool + Send + Sync>;
+
+type Backoff = Arc<dyn Fn(usize) -> BoxFuture<'static, ()> + Send + Sync>;
+
+/// Wrapper for a [Consumer] that retries failed Domain Events, forwards the
+/// Domain Events that keep failing to a dead-letter Event Stream, if configured,
+/// and consumes up to a maximum number of Domain Events concurrently.
+///
+/// By default, all the errors are retried immediately: use [`Reliable::retry_transient`]
+/// or [`Reliable::retry_if`] to fail fast on permanent errors, and [`Reliable::with_backoff`]
+/// to wait between attempts.
+pub struct Reliable<C, StreamId, Event>
+where
+    C: Consumer<StreamId, Event>,
+    Event: message::Message,
+{
+    consumer: C,
+    max_attempts: usize,
+    concurrency: usize,
+    dead_letter: Option<DeadLetter<StreamId, Event>>,
+    retry_if: RetryIf<C::Error>,
+    backoff: Option<Backoff>,
+}
+
+impl<C, StreamId, Event> Clone for Reliable<C, StreamId, Event>
+where
+    C: Consumer<StreamId, Event> + Clone,
+    StreamId: Clone,
+    Event: message::Message,
+{
+    fn clone(&self) -> Self {
+        Self {
+            consumer: self.consumer.clone(),
+            max_attempts: self.max_attempts,
+            concurrency: self.concurrency,
+            dead_letter: self.dead_letter.clone(),
+            retry_if: self.retry_if.clone(),
+            backoff: self.backoff.clone(),
+        }
+    }
+}
+
+impl<C, StreamId, Event> Debug for Reliable<C, StreamId, Event>
+where
+    C: Consumer<StreamId, Event> + Debug,
+    StreamId: Debug,
+    Event: message::Message,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Reliable")
+            .field("consumer", &self.consumer)
+            .field("max_attempts", &self.max_attempts)
+            .field("concurrency", &self.concurrency)
+            .field(
+                "dead_letter_stream_id",
+                &self.dead_letter.as_ref().map(|(_, id)| id),
+            )
+            .field("backoff", &self.backoff.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<C, StreamId, Event> Reliable<C, StreamId, Event>
+where
+    C: Consumer<StreamId, Event>,
+    C::Error: Display,
+    StreamId: Clone + Send + Sync,
+    Event: message::Message + Clone + Send + Sync,
+{
+    /// Creates a new [Reliable] wrapper, which consumes one Domain Event at a time
+    /// and makes at most `max_attempts` attempts per Domain Event.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if `max_attempts` is zero.
+    pub fn new(consumer: C, max_attempts: usize) -> Self {
+        assert!(max_attempts > 0, "max_attempts must be greater than zero");
+
+        Self {
+            consumer,
+            max_attempts,
+            concurrency: 1,
+            dead_letter: None,
+            retry_if: Arc::new(|_| true),
+            backoff: None,
+        }
+    }
+
+    /// Specifies which errors returned by the [Consumer] are worth another attempt:
+    /// the Domain Events failing with any other error are dead-lettered,
+    /// or returned, right away.
+    #[must_use]
+    pub fn retry_if<F>(mut self, retry_if: F) -> Self
+    where
+        F: Fn(&C::Error) -> bool + Send + Sync + 'static,
+    {
+        self.retry_if = Arc::new(retry_if);
+        self
+    }
+
+    /// Retries only the [transient][Retryable] errors returned by the [Consumer],
+    /// e.g. connection failures, failing fast on the permanent ones.
+    #[must_use]
+    pub fn retry_transient(self) -> Self
+    where
+        C::Error: Retryable + 'static,
+    {
+        self.retry_if(Retryable::is_transient)
+    }
+
+    /// Specifies the backoff to wait for before attempting to consume a failed
+    /// Domain Event again, as a function returning a future from the number of
+    /// failed attempts so far, e.g. a `tokio::time::sleep` growing with each attempt.
+    #[must_use]
+    pub fn with_backoff<F, Fut>(mut self, backoff: F) -> Self
+    where
+        F: Fn(usize) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.backoff = Some(Arc::new(move |attempt| backoff(attempt).boxed()));
+        self
+    }
+
+    /// Specifies the maximum number of Domain Events to consume concurrently.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if `concurrency` is zero.
+    #[must_use]
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        assert!(concurrency > 0, "concurrency must be greater than zero");
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Specifies the Event Stream the Domain Events that could not be consumed
+    /// are appended to, with the failure reason recorded in their [Metadata][message::Metadata]
+    /// under the [`DEAD_LETTER_REASON_KEY`] key.
+    #[must_use]
+    pub fn with_dead_letter<A>(mut self, store: A, stream_id: StreamId) -> Self
+    where
+        A: Appender<StreamId, Event> + 'static,
+    {
+        self.dead_letter = Some((Arc::new(store), stream_id));
+        self
+    }
+
+    /// Consumes all the Domain Events in the provided stream, returning a stream
+    /// that yields each Domain Event once it has been consumed or dead-lettered,
+    /// in the same order of the original stream.
+    ///
+    /// The returned stream can be used to checkpoint the consumer progress, as all
+    /// the Domain Events preceding a yielded Domain Event have also been handled.
+    pub fn consume<'a, S>(
+        &'a self,
+        stream: S,
+    ) -> event::Stream<'a, StreamId, Event, ConsumeError<S::Error, C::Error>>
+    where
+        S: TryStream<Ok = event::Persisted<StreamId, Event>> + Send + 'a,
+        S::Error: Send + 'a,
+        StreamId: 'a,
+        Event: 'a,
+    {
+        stream
+            .map_err(ConsumeError::Stream)
+            .map_ok(move |persisted| self.consume_one(persisted))
+            .try_buffered(self.concurrency)
+            .boxed()
+    }
+
+    async fn consume_one<StreamErr>(
+        &self,
+        persisted: event::Persisted<StreamId, Event>,
+    ) -> Result<event::Persisted<StreamId, Event>, ConsumeError<StreamErr, C::Error>> {
+        let mut attempt = 1;
+
+        let error = loop {
+            match self.consumer.consume(persisted.clone()).await {
+                Ok(()) => return Ok(persisted),
+                Err(err) if attempt >= self.max_attempts || !(self.retry_if)(&err) => break err,
+                Err(_) => {
+                    if let Some(backoff) = &self.backoff {
+                        backoff(attempt).await;
+                    }
+
+                    attempt += 1;
+                },
+            }
+        };
+
+        let Some((store, stream_id)) = &self.dead_letter else {
+            return Err(ConsumeError::Consumer(error));
+        };
+
+        let dead_letter = persisted
+            .event
+            .clone()
+            .with_metadata(DEAD_LETTER_REASON_KEY.to_owned(), error.to_string());
+
+        store
+            .append(stream_id.clone(), version::Check::Any, vec![dead_letter])
+            .await
+            .map_err(ConsumeError::DeadLetter)?;
+
+        Ok(persisted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures::future;
+
+    use super::*;
+    use crate::error::Kind;
+    use crate::event::store::{InMemory, Streamer};
+    use crate::message::tests::StringMessage;
+
+    const STREAM_ID: &str = "stream:test";
+    const DEAD_LETTER_STREAM_ID: &str = "stream:dead-letter";
+
+    #[tokio::test]
+    async fn failing_domain_events_are_retried_and_dead_lettered() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+
+        event_store
+            .append(
+                STREAM_ID,
+                version::Check::must_be(0),
+                ["event-1", "poison", "event-3"]
+                    .into_iter()
+                    .map(|msg| event::Envelope::from(StringMessage(msg)))
+                    .collect(),
+            )
+            .await
+            .expect("append should not fail");
+
+        let calls = AtomicUsize::new(0);
+        let consumer = |evt: event::Persisted<&'static str, StringMessage>| {
+            calls.fetch_add(1, Ordering::SeqCst);
+
+            async move {
+                match evt.event.message.0 {
+                    "poison" => Err("cannot consume poison event"),
+                    _ => Ok(()),
+                }
+            }
+        };
+
+        let reliable = Reliable::new(consumer, 3)
+            .with_concurrency(2)
+            .with_dead_letter(event_store.clone(), DEAD_LETTER_STREAM_ID);
+
+        let versions: Vec<_> = reliable
+            .consume(event_store.stream(&STREAM_ID, event::VersionSelect::All))
+            .map_ok(|evt| evt.version)
+            .try_collect()
+            .await
+            .expect("consuming should not fail");
+
+        assert_eq!(vec![1, 2, 3], versions);
+        assert_eq!(5, calls.load(Ordering::SeqCst));
+
+        let dead_letters: Vec<_> = event_store
+            .stream(&DEAD_LETTER_STREAM_ID, event::VersionSelect::All)
+            .try_collect()
+            .await
+            .expect("streaming should not fail");
+
+        assert_eq!(1, dead_letters.len());
+        assert_eq!(StringMessage("poison"), dead_letters[0].event.message);
+        assert_eq!(
+            "cannot consume poison event",
+            dead_letters[0].event.metadata[DEAD_LETTER_REASON_KEY]
+        );
+    }
+
+    #[tokio::test]
+    async fn only_transient_errors_are_retried_after_the_backoff() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+
+        event_store
+            .append(
+                STREAM_ID,
+                version::Check::must_be(0),
+                ["flaky", "poison"]
+                    .into_iter()
+                    .map(|msg| event::Envelope::from(StringMessage(msg)))
+                    .collect(),
+            )
+            .await
+            .expect("append should not fail");
+
+        let calls = AtomicUsize::new(0);
+        let consumer = |evt: event::Persisted<&'static str, StringMessage>| {
+            let call = calls.fetch_add(1, Ordering::SeqCst);
+
+            async move {
+                match (evt.event.message.0, call) {
+                    ("flaky", 0) => Err(Kind::Timeout),
+                    ("poison", _) => Err(Kind::Domain),
+                    _ => Ok(()),
+                }
+            }
+        };
+
+        let backoffs = Arc::new(AtomicUsize::new(0));
+
+        let reliable = Reliable::new(consumer, 3)
+            .retry_transient()
+            .with_backoff({
+                let backoffs = backoffs.clone();
+                move |attempt| {
+                    backoffs.fetch_add(attempt, Ordering::SeqCst);
+                    future::ready(())
+                }
+            })
+            .with_dead_letter(event_store.clone(), DEAD_LETTER_STREAM_ID);
+
+        let versions: Vec<_> = reliable
+            .consume(event_store.stream(&STREAM_ID, event::VersionSelect::All))
+            .map_ok(|evt| evt.version)
+            .try_collect()
+            .await
+            .expect("consuming should not fail");
+
+        assert_eq!(vec![1, 2], versions);
+        // The flaky Domain Event is attempted twice, the poison one only once.
+        assert_eq!(3, calls.load(Ordering::SeqCst));
+        assert_eq!(1, backoffs.load(Ordering::SeqCst));
+
+        let dead_letters: Vec<_> = event_store
+            .stream(&DEAD_LETTER_STREAM_ID, event::VersionSelect::All)
+            .try_collect()
+            .await
+            .expect("streaming should not fail");
+
+        assert_eq!(1, dead_letters.len());
+        assert_eq!(StringMessage("poison"), dead_letters[0].event.message);
+    }
+}
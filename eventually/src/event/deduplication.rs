@@ -0,0 +1,217 @@
+//! Contains the [Deduplicated] [`event::Store`] decorator, which makes appends
+//! idempotent through a deduplication ID carried in the Domain Events [Metadata][message::Metadata].
+//!
+//! This is useful for at-least-once ingestion pipelines, where the same batch
+//! of Domain Events might be appended more than once.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+
+use crate::event::store::{AppendError, Appender, Store, Streamer};
+use crate::{event, message, version};
+
+/// The [Metadata][message::Metadata] key used to specify the deduplication ID
+/// of an append operation.
+///
+/// The deduplication ID is read from the first Domain Event of the appended batch.
+pub const DEDUPLICATION_ID_KEY: &str = "Deduplication-Id";
+
+/// Decorator type for an [`event::Store`] implementation that skips appends
+/// whose deduplication ID has already been appended to the same Event Stream,
+/// returning the original result instead.
+///
+/// Deduplication IDs are remembered for the configured retention window.
+///
+/// **Please note**: deduplication IDs are kept in memory, so they are not shared
+/// across processes nor survive restarts. Moreover, concurrent appends carrying
+/// the same deduplication ID are not deduplicated against each other; use
+/// [`version::Check::MustBe`] to guard against those.
+#[derive(Debug, Clone)]
+pub struct Deduplicated<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync,
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    store: T,
+    retention: Duration,
+
+    #[allow(clippy::type_complexity)] // It is a complex type but still readable.
+    appended: Arc<Mutex<HashMap<(StreamId, String), (version::Version, Instant)>>>,
+    event: PhantomData<Event>,
+}
+
+impl<T, StreamId, Event> Deduplicated<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync,
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    /// Creates a new [Deduplicated] decorator over the specified [`event::Store`],
+    /// remembering deduplication IDs for the specified `retention` window.
+    pub fn new(store: T, retention: Duration) -> Self {
+        Self {
+            store,
+            retention,
+            appended: Arc::default(),
+            event: PhantomData,
+        }
+    }
+}
+
+#[async_trait]
+impl<T, StreamId, Event> Streamer<StreamId, Event> for Deduplicated<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync,
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    type Error = <T as Streamer<StreamId, Event>>::Error;
+
+    fn stream(
+        &self,
+        id: &StreamId,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, StreamId, Event, Self::Error> {
+        self.store.stream(id, select)
+    }
+
+    fn read_from<'a>(
+        &'a self,
+        id: &StreamId,
+        from_version: version::Version,
+        batch_size: usize,
+    ) -> event::Stream<'a, StreamId, Event, Self::Error>
+    where
+        StreamId: Clone + 'a,
+        Event: 'a,
+        Self::Error: 'a,
+    {
+        self.store.read_from(id, from_version, batch_size)
+    }
+
+    async fn head_version(&self, id: &StreamId) -> Result<Option<version::Version>, Self::Error> {
+        self.store.head_version(id).await
+    }
+}
+
+#[async_trait]
+impl<T, StreamId, Event> Appender<StreamId, Event> for Deduplicated<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync,
+    StreamId: Clone + Eq + Hash + Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    async fn append(
+        &self,
+        id: StreamId,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Event>>,
+    ) -> Result<version::Version, AppendError> {
+        let Some(deduplication_id) = events
+            .first()
+            .and_then(|evt| evt.metadata.get(DEDUPLICATION_ID_KEY))
+            .cloned()
+        else {
+            return self.store.append(id, version_check, events).await;
+        };
+
+        let key = (id, deduplication_id);
+
+        {
+            let mut appended = self
+                .appended
+                .lock()
+                .expect("acquire lock on deduplication ids");
+
+            appended.retain(|_, (_, appended_at)| appended_at.elapsed() < self.retention);
+
+            if let Some((version, _)) = appended.get(&key) {
+                return Ok(*version);
+            }
+        }
+
+        let new_version = self
+            .store
+            .append(key.0.clone(), version_check, events)
+            .await?;
+
+        self.appended
+            .lock()
+            .expect("acquire lock on deduplication ids")
+            .insert(key, (new_version, Instant::now()));
+
+        Ok(new_version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::event::store::InMemory;
+    use crate::message::tests::StringMessage;
+
+    const STREAM_ID: &str = "stream:test";
+
+    fn events(deduplication_id: &str) -> Vec<event::Envelope<StringMessage>> {
+        vec![
+            event::Envelope::from(StringMessage("event-1"))
+                .with_metadata(DEDUPLICATION_ID_KEY.to_owned(), deduplication_id.to_owned()),
+            event::Envelope::from(StringMessage("event-2")),
+        ]
+    }
+
+    #[tokio::test]
+    async fn appends_with_the_same_deduplication_id_are_skipped() {
+        let event_store = Deduplicated::new(InMemory::default(), Duration::from_mins(1));
+
+        for _ in 0..2 {
+            let version = event_store
+                .append(STREAM_ID, version::Check::Any, events("batch-1"))
+                .await
+                .expect("append should not fail");
+
+            assert_eq!(2, version);
+        }
+
+        let version = event_store
+            .append(STREAM_ID, version::Check::Any, events("batch-2"))
+            .await
+            .expect("append should not fail");
+
+        assert_eq!(4, version);
+
+        let count = event_store
+            .stream(&STREAM_ID, event::VersionSelect::All)
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("streaming should not fail")
+            .len();
+
+        assert_eq!(4, count);
+    }
+
+    #[tokio::test]
+    async fn deduplication_ids_expire_after_the_retention_window() {
+        let event_store = Deduplicated::new(InMemory::default(), Duration::ZERO);
+
+        event_store
+            .append(STREAM_ID, version::Check::Any, events("batch-1"))
+            .await
+            .expect("append should not fail");
+
+        let version = event_store
+            .append(STREAM_ID, version::Check::Any, events("batch-1"))
+            .await
+            .expect("append should not fail");
+
+        assert_eq!(4, version);
+    }
+}
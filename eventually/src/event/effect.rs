@@ -0,0 +1,443 @@
+//! Contains the [Journal] trait and the [Effects] runner, used to checkpoint
+//! external side effects (e.g. HTTP calls, emails) performed by long-running processes
+//! and [Consumers][crate::event::consumer::Consumer].
+//!
+//! The intent of a side effect is recorded in the [Journal] before executing it,
+//! and its completion afterwards: after a restart, completed side effects are skipped,
+//! while side effects that have been interrupted half-way are reported as in doubt,
+//! instead of being silently executed again.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+
+use crate::{event, message};
+
+/// The status of a side effect recorded in a [Journal].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// The intent of executing the side effect has been recorded,
+    /// but its completion has not.
+    Pending,
+    /// The side effect has been executed successfully.
+    Completed,
+}
+
+/// A durable log of the side effects executed by a process,
+/// each identified by a unique key.
+#[async_trait]
+pub trait Journal: Send + Sync {
+    /// The error type returned by the [Journal] when failing to access its data store.
+    type Error: Send + Sync;
+
+    /// Returns the [Status] of the side effect with the specified key,
+    /// or `None` if it has never been recorded.
+    async fn status(&self, key: &str) -> Result<Option<Status>, Self::Error>;
+
+    /// Atomically claims the side effect with the specified key, recording
+    /// the intent of executing it as [`Status::Pending`], together with the time of the claim.
+    ///
+    /// When a `lease` is specified, a [`Status::Pending`] side effect claimed longer
+    /// than `lease` ago is considered interrupted, and is claimed again.
+    ///
+    /// Returns `false` if the side effect has already been recorded and its claim
+    /// has not expired, e.g. because it is being executed concurrently,
+    /// in which case the [Journal] is left unchanged.
+    async fn record_intent(&self, key: &str, lease: Option<Duration>) -> Result<bool, Self::Error>;
+
+    /// Records the successful execution of the side effect with the specified key,
+    /// marking it as [`Status::Completed`].
+    async fn record_completion(&self, key: &str) -> Result<(), Self::Error>;
+
+    /// Removes the side effect with the specified key from the [Journal],
+    /// e.g. when its execution has failed and it can be safely attempted again.
+    async fn discard(&self, key: &str) -> Result<(), Self::Error>;
+}
+
+/// In-memory implementation of a [Journal], mostly useful for testing.
+///
+/// **Please note**: the recorded side effects do not survive restarts.
+#[derive(Debug, Clone, Default)]
+pub struct InMemory {
+    effects: Arc<Mutex<HashMap<String, (Status, Instant)>>>,
+}
+
+impl InMemory {
+    fn set(&self, key: &str, status: Option<Status>) {
+        let mut effects = self
+            .effects
+            .lock()
+            .expect("acquire lock on recorded side effects");
+
+        match status {
+            Some(status) => effects.insert(key.to_owned(), (status, Instant::now())),
+            None => effects.remove(key),
+        };
+    }
+}
+
+#[async_trait]
+impl Journal for InMemory {
+    type Error = std::convert::Infallible;
+
+    async fn status(&self, key: &str) -> Result<Option<Status>, Self::Error> {
+        Ok(self
+            .effects
+            .lock()
+            .expect("acquire lock on recorded side effects")
+            .get(key)
+            .map(|(status, _)| *status))
+    }
+
+    async fn record_intent(&self, key: &str, lease: Option<Duration>) -> Result<bool, Self::Error> {
+        let mut effects = self
+            .effects
+            .lock()
+            .expect("acquire lock on recorded side effects");
+
+        Ok(match effects.entry(key.to_owned()) {
+            Entry::Vacant(entry) => {
+                entry.insert((Status::Pending, Instant::now()));
+                true
+            },
+            Entry::Occupied(mut entry) => match (*entry.get(), lease) {
+                ((Status::Pending, claimed_at), Some(lease)) if claimed_at.elapsed() >= lease => {
+                    entry.insert((Status::Pending, Instant::now()));
+                    true
+                },
+                _ => false,
+            },
+        })
+    }
+
+    async fn record_completion(&self, key: &str) -> Result<(), Self::Error> {
+        self.set(key, Some(Status::Completed));
+        Ok(())
+    }
+
+    async fn discard(&self, key: &str) -> Result<(), Self::Error> {
+        self.set(key, None);
+        Ok(())
+    }
+}
+
+/// Returns the [Journal] key for the side effect with the specified name,
+/// triggered by a [Persisted][event::Persisted] Domain Event.
+pub fn key<StreamId, Event>(event: &event::Persisted<StreamId, Event>, effect: &str) -> String
+where
+    StreamId: Display,
+    Event: message::Message,
+{
+    format!("{}@{}/{effect}", event.stream_id, event.version)
+}
+
+/// The result of a successful [`Effects::run`] call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome<T> {
+    /// The side effect has been executed, returning the specified value.
+    Executed(T),
+    /// The side effect had already been completed, so it has been skipped.
+    Skipped,
+}
+
+/// All possible errors returned by [`Effects::run`].
+#[derive(Debug, thiserror::Error)]
+pub enum EffectError<J, E> {
+    /// Error returned when the side effect has been interrupted during
+    /// a previous execution, or is being executed concurrently,
+    /// so it is unknown whether it took place or not.
+    ///
+    /// The side effect must be reconciled manually, then either
+    /// [completed][Journal::record_completion] or [discarded][Journal::discard]
+    /// in the [Journal]. Use [`Effects::retry_pending_after`] for idempotent side effects.
+    #[error("side effect '{0}' was interrupted during a previous execution")]
+    InDoubt(String),
+
+    /// Error returned when the side effect has failed.
+    ///
+    /// The side effect is discarded from the [Journal], so it can be attempted again.
+    #[error("side effect failed: {0}")]
+    Effect(#[source] E),
+
+    /// Error returned when the [Journal] could not be accessed.
+    #[error("failed to access the side effects journal: {0}")]
+    Journal(#[source] J),
+}
+
+/// Executes side effects at most once, recording their intent and completion
+/// in the specified [Journal].
+#[derive(Debug, Clone)]
+pub struct Effects<J>
+where
+    J: Journal,
+{
+    journal: J,
+    lease: Option<Duration>,
+}
+
+impl<J> Effects<J>
+where
+    J: Journal,
+{
+    /// Creates a new [Effects] runner using the specified [Journal].
+    pub fn new(journal: J) -> Self {
+        Self {
+            journal,
+            lease: None,
+        }
+    }
+
+    /// Executes again the side effects claimed longer than `lease` ago and never completed,
+    /// considering them interrupted, instead of returning [`EffectError::InDoubt`].
+    /// Disabled by default.
+    ///
+    /// The [Journal] cannot tell an interrupted execution apart from one still in progress,
+    /// so `lease` must be longer than the longest execution of the side effects:
+    /// a side effect still running after `lease` is executed again by a concurrent run.
+    ///
+    /// Enable this option only for idempotent side effects, e.g. HTTP calls
+    /// carrying the [Journal] key as an idempotency key.
+    #[must_use]
+    pub fn retry_pending_after(mut self, lease: Duration) -> Self {
+        self.lease = Some(lease);
+        self
+    }
+
+    /// Executes the side effect identified by the specified key,
+    /// unless it has already been completed.
+    ///
+    /// The side effect is [claimed][Journal::record_intent] before being executed,
+    /// so that concurrent runs with the same key execute it only once, unless
+    /// [`Effects::retry_pending_after`] is enabled and the execution outlasts its lease.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the side effect fails, if it has been interrupted
+    /// during a previous execution, or if the [Journal] could not be accessed.
+    pub async fn run<F, Fut, T, E>(
+        &self,
+        key: &str,
+        effect: F,
+    ) -> Result<Outcome<T>, EffectError<J::Error, E>>
+    where
+        F: FnOnce() -> Fut + Send,
+        Fut: Future<Output = Result<T, E>> + Send,
+    {
+        let claimed = self
+            .journal
+            .record_intent(key, self.lease)
+            .await
+            .map_err(EffectError::Journal)?;
+
+        if !claimed {
+            match self
+                .journal
+                .status(key)
+                .await
+                .map_err(EffectError::Journal)?
+            {
+                Some(Status::Completed) => return Ok(Outcome::Skipped),
+                // Either pending with an unexpired claim, or discarded
+                // by a concurrent execution in the meantime.
+                _ => return Err(EffectError::InDoubt(key.to_owned())),
+            }
+        }
+
+        match effect().await {
+            Ok(value) => {
+                self.journal
+                    .record_completion(key)
+                    .await
+                    .map_err(EffectError::Journal)?;
+
+                Ok(Outcome::Executed(value))
+            },
+            Err(err) => {
+                self.journal
+                    .discard(key)
+                    .await
+                    .map_err(EffectError::Journal)?;
+
+                Err(EffectError::Effect(err))
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::message::tests::StringMessage;
+
+    #[tokio::test]
+    async fn effects_are_executed_at_most_once() {
+        let journal = InMemory::default();
+        let effects = Effects::new(journal.clone());
+        let executions = AtomicUsize::new(0);
+
+        let event = event::Persisted {
+            stream_id: "order:1",
+            version: 1,
+            event: event::Envelope::from(StringMessage("order-placed")),
+        };
+
+        let key = key(&event, "send-email");
+        assert_eq!("order:1@1/send-email", key);
+
+        let send_email = || async {
+            executions.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, std::convert::Infallible>("sent")
+        };
+
+        for expected in [Outcome::Executed("sent"), Outcome::Skipped] {
+            let outcome = effects
+                .run(&key, send_email)
+                .await
+                .expect("the side effect should not fail");
+
+            assert_eq!(expected, outcome);
+        }
+
+        assert_eq!(1, executions.load(Ordering::SeqCst));
+
+        // Simulate a crash that happened while executing the side effect.
+        journal.record_intent("interrupted", None).await.unwrap();
+
+        let result = effects.run("interrupted", send_email).await;
+        assert!(matches!(result, Err(EffectError::InDoubt(key)) if key == "interrupted"));
+        assert_eq!(1, executions.load(Ordering::SeqCst));
+
+        let outcome = effects
+            .retry_pending_after(Duration::ZERO)
+            .run("interrupted", send_email)
+            .await
+            .expect("the side effect should be retried");
+
+        assert_eq!(Outcome::Executed("sent"), outcome);
+        assert_eq!(2, executions.load(Ordering::SeqCst));
+    }
+
+    /// [`InMemory`] Journal yielding to the other tasks before each access,
+    /// like a [Journal] backed by a remote data store would.
+    #[derive(Clone, Default)]
+    struct Yielding(InMemory);
+
+    #[async_trait]
+    impl Journal for Yielding {
+        type Error = std::convert::Infallible;
+
+        async fn status(&self, key: &str) -> Result<Option<Status>, Self::Error> {
+            tokio::task::yield_now().await;
+            self.0.status(key).await
+        }
+
+        async fn record_intent(
+            &self,
+            key: &str,
+            lease: Option<Duration>,
+        ) -> Result<bool, Self::Error> {
+            tokio::task::yield_now().await;
+            self.0.record_intent(key, lease).await
+        }
+
+        async fn record_completion(&self, key: &str) -> Result<(), Self::Error> {
+            tokio::task::yield_now().await;
+            self.0.record_completion(key).await
+        }
+
+        async fn discard(&self, key: &str) -> Result<(), Self::Error> {
+            tokio::task::yield_now().await;
+            self.0.discard(key).await
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrent_runs_execute_effects_only_once() {
+        let effects = Effects::new(Yielding::default());
+        let executions = AtomicUsize::new(0);
+
+        let send_email = || async {
+            executions.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, std::convert::Infallible>("sent")
+        };
+
+        let (first, second) = futures::join!(
+            effects.run("send-email", send_email),
+            effects.run("send-email", send_email),
+        );
+
+        assert_eq!(1, executions.load(Ordering::SeqCst));
+        assert!(matches!(first, Ok(Outcome::Executed("sent"))));
+        // The losing run either sees the side effect completed, or still pending.
+        assert!(matches!(
+            second,
+            Ok(Outcome::Skipped) | Err(EffectError::InDoubt(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn pending_effects_are_retried_only_once_their_claim_has_expired() {
+        let lease = Duration::from_millis(50);
+        let effects = Effects::new(Yielding::default()).retry_pending_after(lease);
+        let executions = AtomicUsize::new(0);
+
+        let slow_send_email = || async {
+            executions.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(lease / 5).await;
+            Ok::<_, std::convert::Infallible>("sent")
+        };
+
+        // The claim of an execution still in progress is not taken over.
+        let (first, second) = futures::join!(
+            effects.run("send-email", slow_send_email),
+            effects.run("send-email", slow_send_email),
+        );
+
+        assert_eq!(1, executions.load(Ordering::SeqCst));
+        assert!(matches!(first, Ok(Outcome::Executed("sent"))));
+        assert!(matches!(second, Err(EffectError::InDoubt(_))));
+
+        // Simulate a crash that happened while executing the side effect.
+        effects
+            .journal
+            .record_intent("interrupted", None)
+            .await
+            .unwrap();
+
+        let result = effects.run("interrupted", slow_send_email).await;
+        assert!(matches!(result, Err(EffectError::InDoubt(_))));
+
+        tokio::time::sleep(lease).await;
+
+        let outcome = effects
+            .run("interrupted", slow_send_email)
+            .await
+            .expect("the expired claim should be taken over");
+
+        assert_eq!(Outcome::Executed("sent"), outcome);
+        assert_eq!(2, executions.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn failed_effects_are_discarded_from_the_journal() {
+        let journal = InMemory::default();
+        let effects = Effects::new(journal.clone());
+
+        let result = effects
+            .run("failing", || async { Err::<(), _>("service unavailable") })
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(EffectError::Effect("service unavailable"))
+        ));
+        assert_eq!(None, journal.status("failing").await.unwrap());
+    }
+}
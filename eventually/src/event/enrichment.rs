@@ -0,0 +1,327 @@
+//! Contains the [Enriched] [Consumer] decorator, which runs a chain of asynchronous
+//! [Enricher] hooks on each Domain Event before it reaches the wrapped [Consumer],
+//! e.g. to resolve user ids into user names through a cache.
+//!
+//! Each hook is named, and its [Metrics] can be read with [`Enriched::metrics`].
+
+use std::fmt::Debug;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+
+use crate::event::consumer::Consumer;
+use crate::{event, message};
+
+/// A hook that enriches or transforms a [Persisted][event::Persisted] Domain Event,
+/// e.g. by adding [Metadata][message::Metadata] entries or by filling in missing fields.
+#[async_trait]
+pub trait Enricher<StreamId, Event>: Send + Sync
+where
+    Event: message::Message,
+{
+    /// The error type returned by the Enricher when failing to enrich a Domain Event.
+    type Error: Send + Sync;
+
+    /// Returns the enriched Domain Event, or an error if that failed.
+    async fn enrich(
+        &self,
+        event: event::Persisted<StreamId, Event>,
+    ) -> Result<event::Persisted<StreamId, Event>, Self::Error>;
+}
+
+#[async_trait]
+impl<StreamId, Event, Err, F, Fut> Enricher<StreamId, Event> for F
+where
+    StreamId: Send + 'static,
+    Event: message::Message + Send + 'static,
+    Err: Send + Sync,
+    F: Send + Sync + Fn(event::Persisted<StreamId, Event>) -> Fut,
+    Fut: Send + Future<Output = Result<event::Persisted<StreamId, Event>, Err>>,
+{
+    type Error = Err;
+
+    async fn enrich(
+        &self,
+        event: event::Persisted<StreamId, Event>,
+    ) -> Result<event::Persisted<StreamId, Event>, Self::Error> {
+        self(event).await
+    }
+}
+
+/// All possible errors returned by the [Enriched] [Consumer].
+#[derive(Debug, thiserror::Error)]
+pub enum EnrichError<E> {
+    /// Error returned when an [Enricher] hook has failed: the Domain Event
+    /// has not been passed to the wrapped [Consumer].
+    #[error("enrichment hook '{hook}' failed: {source}")]
+    Hook {
+        /// The name of the failed hook.
+        hook: &'static str,
+        /// The error returned by the hook.
+        #[source]
+        source: anyhow::Error,
+    },
+    /// Error returned by the wrapped [Consumer].
+    #[error("failed to consume enriched domain event: {0}")]
+    Consumer(#[source] E),
+}
+
+/// Usage metrics of an [Enricher] hook, returned by [`Enriched::metrics`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// The number of Domain Events enriched successfully.
+    pub enriched: u64,
+    /// The number of Domain Events the hook failed to enrich.
+    pub failed: u64,
+    /// The total time spent in the hook, including failed calls.
+    pub total_time: Duration,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    enriched: AtomicU64,
+    failed: AtomicU64,
+    total_time_nanos: AtomicU64,
+}
+
+type ErasedEnricher<StreamId, Event> =
+    Box<dyn Enricher<StreamId, Event, Error = anyhow::Error> + Send + Sync>;
+
+/// Adapts an [Enricher] to return [`anyhow::Error`]s, so that hooks with
+/// different error types can be chained together.
+struct AnyhowEnricher<E>(E);
+
+#[async_trait]
+impl<StreamId, Event, E> Enricher<StreamId, Event> for AnyhowEnricher<E>
+where
+    StreamId: Send + 'static,
+    Event: message::Message + Send + 'static,
+    E: Enricher<StreamId, Event>,
+    E::Error: Into<anyhow::Error>,
+{
+    type Error = anyhow::Error;
+
+    async fn enrich(
+        &self,
+        event: event::Persisted<StreamId, Event>,
+    ) -> Result<event::Persisted<StreamId, Event>, Self::Error> {
+        self.0.enrich(event).await.map_err(Into::into)
+    }
+}
+
+struct Hook<StreamId, Event>
+where
+    Event: message::Message,
+{
+    name: &'static str,
+    enricher: ErasedEnricher<StreamId, Event>,
+    counters: Counters,
+}
+
+/// [Consumer] decorator that runs the registered [Enricher] hooks on each Domain Event,
+/// in the order they have been added, before passing it to the wrapped [Consumer].
+pub struct Enriched<C, StreamId, Event>
+where
+    Event: message::Message,
+{
+    consumer: C,
+    hooks: Vec<Hook<StreamId, Event>>,
+}
+
+impl<C, StreamId, Event> Debug for Enriched<C, StreamId, Event>
+where
+    C: Debug,
+    Event: message::Message,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Enriched")
+            .field("consumer", &self.consumer)
+            .field(
+                "hooks",
+                &self.hooks.iter().map(|hook| hook.name).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl<C, StreamId, Event> Enriched<C, StreamId, Event>
+where
+    C: Consumer<StreamId, Event>,
+    StreamId: Send + Sync + 'static,
+    Event: message::Message + Send + Sync + 'static,
+{
+    /// Creates a new [Enriched] decorator over the specified [Consumer], with no hooks.
+    pub fn new(consumer: C) -> Self {
+        Self {
+            consumer,
+            hooks: Vec::new(),
+        }
+    }
+
+    /// Adds an [Enricher] hook with the specified name, used to report its [Metrics]
+    /// and to identify it in [`EnrichError::Hook`] errors.
+    #[must_use]
+    pub fn hook<E>(mut self, name: &'static str, enricher: E) -> Self
+    where
+        E: Enricher<StreamId, Event> + 'static,
+        E::Error: Into<anyhow::Error>,
+    {
+        self.hooks.push(Hook {
+            name,
+            enricher: Box::new(AnyhowEnricher(enricher)),
+            counters: Counters::default(),
+        });
+        self
+    }
+
+    /// Returns the [Metrics] of the hook with the specified name,
+    /// or `None` if no such hook has been added.
+    pub fn metrics(&self, name: &str) -> Option<Metrics> {
+        self.hooks
+            .iter()
+            .find(|hook| hook.name == name)
+            .map(|hook| Metrics {
+                enriched: hook.counters.enriched.load(Ordering::Relaxed),
+                failed: hook.counters.failed.load(Ordering::Relaxed),
+                total_time: Duration::from_nanos(
+                    hook.counters.total_time_nanos.load(Ordering::Relaxed),
+                ),
+            })
+    }
+}
+
+#[async_trait]
+impl<C, StreamId, Event> Consumer<StreamId, Event> for Enriched<C, StreamId, Event>
+where
+    C: Consumer<StreamId, Event>,
+    StreamId: Send + Sync + 'static,
+    Event: message::Message + Send + Sync + 'static,
+{
+    type Error = EnrichError<C::Error>;
+
+    async fn consume(
+        &self,
+        mut event: event::Persisted<StreamId, Event>,
+    ) -> Result<(), Self::Error> {
+        for hook in &self.hooks {
+            let started_at = Instant::now();
+            let result = hook.enricher.enrich(event).await;
+            let elapsed = u64::try_from(started_at.elapsed().as_nanos()).unwrap_or(u64::MAX);
+
+            hook.counters
+                .total_time_nanos
+                .fetch_add(elapsed, Ordering::Relaxed);
+
+            event = match result {
+                Ok(enriched) => {
+                    hook.counters.enriched.fetch_add(1, Ordering::Relaxed);
+                    enriched
+                },
+                Err(source) => {
+                    hook.counters.failed.fetch_add(1, Ordering::Relaxed);
+                    return Err(EnrichError::Hook {
+                        hook: hook.name,
+                        source,
+                    });
+                },
+            };
+        }
+
+        self.consumer
+            .consume(event)
+            .await
+            .map_err(EnrichError::Consumer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::message::tests::StringMessage;
+
+    #[tokio::test]
+    async fn enriched_consumer_runs_hooks_in_order_and_reports_metrics() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+
+        let enriched = Enriched::new({
+            let received = received.clone();
+
+            move |event: event::Persisted<&'static str, StringMessage>| {
+                let received = received.clone();
+
+                async move {
+                    received
+                        .lock()
+                        .expect("acquire lock on consumed events")
+                        .push(event.event.metadata);
+
+                    Ok::<_, std::convert::Infallible>(())
+                }
+            }
+        })
+        .hook(
+            "user-name",
+            |mut event: event::Persisted<&'static str, StringMessage>| async move {
+                if event.event.message.0 == "unknown-user" {
+                    return Err(anyhow::anyhow!("user not found"));
+                }
+
+                event
+                    .event
+                    .metadata
+                    .insert("User-Name".to_owned(), "John Dee".to_owned());
+
+                Ok(event)
+            },
+        )
+        .hook(
+            "greeting",
+            |mut event: event::Persisted<&'static str, StringMessage>| async move {
+                let greeting = format!("Hello, {}", event.event.metadata["User-Name"]);
+                event.event.metadata.insert("Greeting".to_owned(), greeting);
+
+                Ok::<_, std::convert::Infallible>(event)
+            },
+        );
+
+        let persisted = |message| event::Persisted {
+            stream_id: "stream:test",
+            version: 1,
+            event: event::Envelope::from(StringMessage(message)),
+        };
+
+        enriched
+            .consume(persisted("known-user"))
+            .await
+            .expect("the domain event should be enriched and consumed");
+
+        let err = enriched
+            .consume(persisted("unknown-user"))
+            .await
+            .expect_err("the first hook should fail");
+
+        assert!(matches!(
+            err,
+            EnrichError::Hook {
+                hook: "user-name",
+                ..
+            }
+        ));
+
+        let received = received.lock().expect("acquire lock on consumed events");
+        assert_eq!(1, received.len());
+        assert_eq!("Hello, John Dee", received[0]["Greeting"]);
+
+        let user_name = enriched.metrics("user-name").expect("hook should exist");
+        assert_eq!((1, 1), (user_name.enriched, user_name.failed));
+
+        let greeting = enriched.metrics("greeting").expect("hook should exist");
+        assert_eq!((1, 0), (greeting.enriched, greeting.failed));
+
+        assert_eq!(None, enriched.metrics("missing"));
+    }
+}
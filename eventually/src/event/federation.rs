@@ -0,0 +1,250 @@
+//! Contains the [Federated] [`event::Store`] facade, which routes each Event Stream
+//! to one of many underlying [`event::Store`]s, e.g. high-volume telemetry Event Streams
+//! to a dedicated store and core domain Event Streams to the primary database,
+//! while exposing a single [`event::Store`] to the application.
+
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::stream::{StreamExt, TryStreamExt};
+
+use crate::event::store::{AppendError, Appender, Store, Streamer};
+use crate::{event, message, version};
+
+/// Adapter for an [`event::Store`] that erases its [`Streamer::Error`] type,
+/// so that stores of different types can be used in the same [Federated] facade.
+struct Erased<T>(T);
+
+#[async_trait]
+impl<T, StreamId, Event> Streamer<StreamId, Event> for Erased<T>
+where
+    T: Store<StreamId, Event>,
+    <T as Streamer<StreamId, Event>>::Error: std::error::Error + Send + Sync + 'static,
+    StreamId: Send + Sync + 'static,
+    Event: message::Message + Send + Sync + 'static,
+{
+    type Error = anyhow::Error;
+
+    fn stream(
+        &self,
+        id: &StreamId,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, StreamId, Event, Self::Error> {
+        self.0
+            .stream(id, select)
+            .map_err(anyhow::Error::from)
+            .boxed()
+    }
+
+    fn read_from<'a>(
+        &'a self,
+        id: &StreamId,
+        from_version: version::Version,
+        batch_size: usize,
+    ) -> event::Stream<'a, StreamId, Event, Self::Error>
+    where
+        StreamId: Clone + 'a,
+        Event: 'a,
+        Self::Error: 'a,
+    {
+        self.0
+            .read_from(id, from_version, batch_size)
+            .map_err(anyhow::Error::from)
+            .boxed()
+    }
+
+    async fn head_version(&self, id: &StreamId) -> Result<Option<version::Version>, Self::Error> {
+        Ok(self.0.head_version(id).await?)
+    }
+}
+
+#[async_trait]
+impl<T, StreamId, Event> Appender<StreamId, Event> for Erased<T>
+where
+    T: Store<StreamId, Event>,
+    StreamId: Send + Sync + 'static,
+    Event: message::Message + Send + Sync + 'static,
+{
+    async fn append(
+        &self,
+        id: StreamId,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Event>>,
+    ) -> Result<version::Version, AppendError> {
+        self.0.append(id, version_check, events).await
+    }
+}
+
+type ErasedStore<StreamId, Event> = Arc<dyn Store<StreamId, Event, Error = anyhow::Error>>;
+type Route<StreamId, Event> = (
+    Arc<dyn Fn(&StreamId) -> bool + Send + Sync>,
+    ErasedStore<StreamId, Event>,
+);
+
+/// [`event::Store`] facade that routes each Event Stream to one of the
+/// configured [`event::Store`]s, based on the Event Stream id.
+///
+/// Routes are evaluated in the order they have been added: Event Streams
+/// not matched by any route are routed to the default [`event::Store`].
+///
+/// **Please note**: an Event Stream must always be routed to the same [`event::Store`],
+/// so route predicates should only depend on stable properties of the Event Stream id,
+/// such as its category.
+#[derive(Clone)]
+pub struct Federated<StreamId, Event>
+where
+    Event: message::Message,
+{
+    routes: Vec<Route<StreamId, Event>>,
+    default: ErasedStore<StreamId, Event>,
+}
+
+impl<StreamId, Event> Debug for Federated<StreamId, Event>
+where
+    Event: message::Message,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Federated")
+            .field("routes", &self.routes.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<StreamId, Event> Federated<StreamId, Event>
+where
+    StreamId: Send + Sync + 'static,
+    Event: message::Message + Send + Sync + 'static,
+{
+    /// Creates a new [Federated] facade, routing all Event Streams
+    /// to the specified default [`event::Store`].
+    pub fn new<T>(default: T) -> Self
+    where
+        T: Store<StreamId, Event> + 'static,
+        <T as Streamer<StreamId, Event>>::Error: std::error::Error + Send + Sync + 'static,
+    {
+        Self {
+            routes: Vec::new(),
+            default: Arc::new(Erased(default)),
+        }
+    }
+
+    /// Routes the Event Streams matched by the `predicate` to the specified [`event::Store`].
+    #[must_use]
+    pub fn route<F, T>(mut self, predicate: F, store: T) -> Self
+    where
+        F: Fn(&StreamId) -> bool + Send + Sync + 'static,
+        T: Store<StreamId, Event> + 'static,
+        <T as Streamer<StreamId, Event>>::Error: std::error::Error + Send + Sync + 'static,
+    {
+        self.routes
+            .push((Arc::new(predicate), Arc::new(Erased(store))));
+        self
+    }
+
+    fn store_for(&self, id: &StreamId) -> &ErasedStore<StreamId, Event> {
+        self.routes
+            .iter()
+            .find(|(predicate, _)| predicate(id))
+            .map_or(&self.default, |(_, store)| store)
+    }
+}
+
+#[async_trait]
+impl<StreamId, Event> Streamer<StreamId, Event> for Federated<StreamId, Event>
+where
+    StreamId: Send + Sync + 'static,
+    Event: message::Message + Send + Sync + 'static,
+{
+    type Error = anyhow::Error;
+
+    fn stream(
+        &self,
+        id: &StreamId,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, StreamId, Event, Self::Error> {
+        self.store_for(id).stream(id, select)
+    }
+
+    fn read_from<'a>(
+        &'a self,
+        id: &StreamId,
+        from_version: version::Version,
+        batch_size: usize,
+    ) -> event::Stream<'a, StreamId, Event, Self::Error>
+    where
+        StreamId: Clone + 'a,
+        Event: 'a,
+        Self::Error: 'a,
+    {
+        self.store_for(id).read_from(id, from_version, batch_size)
+    }
+
+    async fn head_version(&self, id: &StreamId) -> Result<Option<version::Version>, Self::Error> {
+        self.store_for(id).head_version(id).await
+    }
+}
+
+#[async_trait]
+impl<StreamId, Event> Appender<StreamId, Event> for Federated<StreamId, Event>
+where
+    StreamId: Send + Sync + 'static,
+    Event: message::Message + Send + Sync + 'static,
+{
+    async fn append(
+        &self,
+        id: StreamId,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Event>>,
+    ) -> Result<version::Version, AppendError> {
+        self.store_for(&id).append(id, version_check, events).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::store::InMemory;
+    use crate::message::tests::StringMessage;
+
+    #[tokio::test]
+    async fn federated_event_store_routes_event_streams_by_category() {
+        let core = InMemory::<&'static str, StringMessage>::default();
+        let telemetry = InMemory::<&'static str, StringMessage>::default();
+
+        let event_store = Federated::new(core.clone())
+            .route(|id| id.starts_with("telemetry:"), telemetry.clone());
+
+        for id in ["order:1", "telemetry:1"] {
+            event_store
+                .append(
+                    id,
+                    version::Check::must_be(0),
+                    vec![event::Envelope::from(StringMessage("event"))],
+                )
+                .await
+                .expect("append should not fail");
+
+            let head_version = event_store
+                .head_version(&id)
+                .await
+                .expect("head version should be returned");
+
+            assert_eq!(Some(1), head_version);
+        }
+
+        for (store, id, exists) in [
+            (&core, "order:1", true),
+            (&core, "telemetry:1", false),
+            (&telemetry, "order:1", false),
+            (&telemetry, "telemetry:1", true),
+        ] {
+            let actual = store
+                .stream_exists(&id)
+                .await
+                .expect("stream_exists should not fail");
+
+            assert_eq!(exists, actual, "event stream {id}");
+        }
+    }
+}
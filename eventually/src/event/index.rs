@@ -0,0 +1,258 @@
+//! Contains the [Link] Domain Event, a pointer to a Domain Event recorded in
+//! a different Event Stream, and the [Indexed] [`event::Store`] decorator,
+//! which uses [Link]s to maintain custom index Event Streams.
+//!
+//! Index Event Streams are useful to read Domain Events across different
+//! Event Streams, e.g. "all the Domain Events related to customer X",
+//! regardless of the Aggregate that has recorded them.
+
+use async_trait::async_trait;
+use futures::stream::{StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::event::store::{AppendError, Appender, Streamer};
+use crate::{event, message, version};
+
+/// A Domain Event that references a Domain Event recorded in a different Event Stream.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Link<StreamId> {
+    /// The id of the Event Stream the linked Domain Event belongs to.
+    pub stream_id: StreamId,
+    /// The version of the linked Domain Event in its Event Stream.
+    pub version: version::Version,
+}
+
+impl<StreamId> message::Message for Link<StreamId> {
+    fn name(&self) -> &'static str {
+        "Link"
+    }
+}
+
+/// All possible errors returned when streaming the Domain Events
+/// referenced by an index Event Stream, using [`Indexed::stream_index`].
+#[derive(Debug, thiserror::Error)]
+pub enum ResolveError<StreamId, IndexErr, StoreErr> {
+    /// Error returned when the index Event Stream could not be streamed.
+    #[error("failed to stream the index event stream: {0}")]
+    Index(#[source] IndexErr),
+    /// Error returned when the linked Domain Event could not be streamed.
+    #[error("failed to stream the linked domain event: {0}")]
+    Store(#[source] StoreErr),
+    /// Error returned when the linked Domain Event does not exist.
+    #[error("linked domain event not found, at version {}", .0.version)]
+    NotFound(Link<StreamId>),
+}
+
+/// Decorator type for an [`event::Store`] implementation that appends a [Link]
+/// to one or more index Event Streams for each Domain Event appended through it.
+///
+/// The index Event Streams a Domain Event should be linked into are computed by the
+/// `indexer` function, and are appended to the `index` [`event::Store`].
+///
+/// **Please note**: the index Event Streams are updated after the Domain Events have
+/// been appended to the decorated [`event::Store`], not atomically. If appending the
+/// [Link]s fails, an error is returned even though the Domain Events have been appended.
+#[derive(Clone)]
+pub struct Indexed<S, I, F> {
+    store: S,
+    index: I,
+    indexer: F,
+}
+
+impl<S, I, F> Indexed<S, I, F> {
+    /// Creates a new [Indexed] decorator over the specified [`event::Store`],
+    /// appending [Link]s into the `index` [`event::Store`] as returned by `indexer`.
+    pub fn new(store: S, index: I, indexer: F) -> Self {
+        Self {
+            store,
+            index,
+            indexer,
+        }
+    }
+
+    /// Streams all the Domain Events referenced by the specified index Event Stream,
+    /// in the order in which they have been indexed.
+    #[allow(clippy::type_complexity)] // It is a complex type but still readable.
+    pub fn stream_index<'a, StreamId, Event, IndexId>(
+        &'a self,
+        index_id: &IndexId,
+        select: event::VersionSelect,
+    ) -> event::Stream<
+        'a,
+        StreamId,
+        Event,
+        ResolveError<StreamId, <I as Streamer<IndexId, Link<StreamId>>>::Error, S::Error>,
+    >
+    where
+        StreamId: Send + Sync + 'a,
+        Event: message::Message + Send + Sync + 'a,
+        IndexId: Send + Sync + 'a,
+        S: Streamer<StreamId, Event>,
+        I: Streamer<IndexId, Link<StreamId>>,
+        F: Sync,
+    {
+        self.index
+            .stream(index_id, select)
+            .map_err(ResolveError::Index)
+            .and_then(move |persisted| async move {
+                let link = persisted.event.message;
+
+                self.store
+                    .stream(&link.stream_id, event::VersionSelect::From(link.version))
+                    .try_next()
+                    .await
+                    .map_err(ResolveError::Store)?
+                    .filter(|evt| evt.version == link.version)
+                    .ok_or(ResolveError::NotFound(link))
+            })
+            .boxed()
+    }
+}
+
+#[async_trait]
+impl<S, I, F, StreamId, Event> Streamer<StreamId, Event> for Indexed<S, I, F>
+where
+    S: Streamer<StreamId, Event>,
+    I: Send + Sync,
+    F: Send + Sync,
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    type Error = S::Error;
+
+    fn stream(
+        &self,
+        id: &StreamId,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, StreamId, Event, Self::Error> {
+        self.store.stream(id, select)
+    }
+
+    fn read_from<'a>(
+        &'a self,
+        id: &StreamId,
+        from_version: version::Version,
+        batch_size: usize,
+    ) -> event::Stream<'a, StreamId, Event, Self::Error>
+    where
+        StreamId: Clone + 'a,
+        Event: 'a,
+        Self::Error: 'a,
+    {
+        self.store.read_from(id, from_version, batch_size)
+    }
+
+    async fn head_version(&self, id: &StreamId) -> Result<Option<version::Version>, Self::Error> {
+        self.store.head_version(id).await
+    }
+}
+
+#[async_trait]
+impl<S, I, F, StreamId, Event, IndexId> Appender<StreamId, Event> for Indexed<S, I, F>
+where
+    S: Appender<StreamId, Event>,
+    I: Appender<IndexId, Link<StreamId>>,
+    F: Fn(&StreamId, &event::Envelope<Event>) -> Vec<IndexId> + Send + Sync,
+    StreamId: Clone + Send + Sync + 'static,
+    Event: message::Message + Send + Sync + 'static,
+    IndexId: Send + Sync + 'static,
+{
+    async fn append(
+        &self,
+        id: StreamId,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Event>>,
+    ) -> Result<version::Version, AppendError> {
+        let index_ids: Vec<Vec<IndexId>> =
+            events.iter().map(|evt| (self.indexer)(&id, evt)).collect();
+
+        let new_version = self.store.append(id.clone(), version_check, events).await?;
+
+        let previous_version = new_version - (index_ids.len() as version::Version);
+
+        for (i, index_ids) in index_ids.into_iter().enumerate() {
+            let link = Link {
+                stream_id: id.clone(),
+                version: previous_version + (i as version::Version) + 1,
+            };
+
+            for index_id in index_ids {
+                self.index
+                    .append(
+                        index_id,
+                        version::Check::Any,
+                        vec![event::Envelope::from(link.clone())],
+                    )
+                    .await
+                    .map_err(|err| {
+                        anyhow::Error::from(err).context(
+                            "domain events appended, but failed to append link to index event stream",
+                        )
+                    })?;
+            }
+        }
+
+        Ok(new_version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::store::InMemory;
+    use crate::message::tests::StringMessage;
+
+    #[tokio::test]
+    async fn index_event_streams_link_domain_events_across_event_streams() {
+        let event_store = Indexed::new(
+            InMemory::<&'static str, StringMessage>::default(),
+            InMemory::<&'static str, Link<&'static str>>::default(),
+            |_: &&'static str, evt: &event::Envelope<StringMessage>| {
+                if evt.message.0.starts_with("customer") {
+                    vec!["index:customer"]
+                } else {
+                    vec![]
+                }
+            },
+        );
+
+        event_store
+            .append(
+                "stream:first",
+                version::Check::must_be(0),
+                vec![
+                    event::Envelope::from(StringMessage("customer-1")),
+                    event::Envelope::from(StringMessage("other-1")),
+                ],
+            )
+            .await
+            .expect("append should not fail");
+
+        event_store
+            .append(
+                "stream:second",
+                version::Check::must_be(0),
+                vec![
+                    event::Envelope::from(StringMessage("other-2")),
+                    event::Envelope::from(StringMessage("customer-2")),
+                ],
+            )
+            .await
+            .expect("append should not fail");
+
+        let indexed: Vec<_> = event_store
+            .stream_index(&"index:customer", event::VersionSelect::All)
+            .map_ok(|evt| (evt.stream_id, evt.version, evt.event.message))
+            .try_collect()
+            .await
+            .expect("streaming the index should not fail");
+
+        assert_eq!(
+            vec![
+                ("stream:first", 1, StringMessage("customer-1")),
+                ("stream:second", 2, StringMessage("customer-2")),
+            ],
+            indexed
+        );
+    }
+}
@@ -0,0 +1,206 @@
+//! Contains the ingestion [Router], which appends external event payloads
+//! (e.g. historical data imported from a legacy system) to the correct Event Stream,
+//! based on user-defined extraction rules.
+
+use std::fmt::Debug;
+
+use crate::event::store::{AppendError, Store, Streamer};
+use crate::{event, message, version};
+
+/// All possible errors returned by [`Router::ingest`].
+#[derive(Debug, thiserror::Error)]
+pub enum IngestError<E> {
+    /// Error returned when no rule of the [Router] matches the payload.
+    #[error("no ingestion rule matches the payload")]
+    Unroutable,
+    /// Error returned when the payload has been routed to an Event Stream
+    /// that does not exist, and the matching rule does not allow to create it.
+    #[error("the payload has been routed to an event stream that does not exist")]
+    StreamNotFound,
+    /// Error returned when the existence of the Event Stream could not be checked.
+    #[error("failed to check the event stream existence: {0}")]
+    Stream(#[source] E),
+    /// Error returned when the Domain Event could not be appended to the Event Stream.
+    #[error("failed to append the domain event: {0}")]
+    Append(#[source] AppendError),
+}
+
+type Extract<In, StreamId, Event> =
+    Box<dyn Fn(&In) -> Option<(StreamId, event::Envelope<Event>)> + Send + Sync>;
+
+struct Rule<In, StreamId, Event>
+where
+    Event: message::Message,
+{
+    extract: Extract<In, StreamId, Event>,
+    create: bool,
+}
+
+/// Routes external payloads to the Event Streams of an [`event::Store`].
+///
+/// Each rule extracts the target Event Stream id and the Domain Event from a payload,
+/// or returns `None` if it does not apply to it. Rules are evaluated in the order
+/// they have been added, and the first matching one is used.
+pub struct Router<S, In, StreamId, Event>
+where
+    Event: message::Message,
+{
+    store: S,
+    rules: Vec<Rule<In, StreamId, Event>>,
+}
+
+impl<S, In, StreamId, Event> Debug for Router<S, In, StreamId, Event>
+where
+    S: Debug,
+    Event: message::Message,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Router")
+            .field("store", &self.store)
+            .field("rules", &self.rules.len())
+            .finish()
+    }
+}
+
+impl<S, In, StreamId, Event> Router<S, In, StreamId, Event>
+where
+    S: Store<StreamId, Event>,
+    Event: message::Message + Send + Sync,
+    StreamId: Send + Sync,
+{
+    /// Creates a new [Router] with no rules, appending to the specified [`event::Store`].
+    pub fn new(store: S) -> Self {
+        Self {
+            store,
+            rules: Vec::new(),
+        }
+    }
+
+    /// Adds a rule that routes the matching payloads to their Event Stream,
+    /// creating it if it does not exist yet.
+    #[must_use]
+    pub fn route<F>(mut self, extract: F) -> Self
+    where
+        F: Fn(&In) -> Option<(StreamId, event::Envelope<Event>)> + Send + Sync + 'static,
+    {
+        self.rules.push(Rule {
+            extract: Box::new(extract),
+            create: true,
+        });
+        self
+    }
+
+    /// Adds a rule that routes the matching payloads to their Event Stream,
+    /// only if it already exists.
+    #[must_use]
+    pub fn route_existing<F>(mut self, extract: F) -> Self
+    where
+        F: Fn(&In) -> Option<(StreamId, event::Envelope<Event>)> + Send + Sync + 'static,
+    {
+        self.rules.push(Rule {
+            extract: Box::new(extract),
+            create: false,
+        });
+        self
+    }
+
+    /// Appends the Domain Event extracted from the payload to its Event Stream,
+    /// returning the Event Stream id and its new version.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if no rule matches the payload, if the Event Stream
+    /// does not exist and the matching rule does not allow to create it,
+    /// or if the [`event::Store`] fails.
+    pub async fn ingest(
+        &self,
+        payload: &In,
+    ) -> Result<(StreamId, version::Version), IngestError<<S as Streamer<StreamId, Event>>::Error>>
+    where
+        StreamId: Clone,
+    {
+        let (create, (stream_id, event)) = self
+            .rules
+            .iter()
+            .find_map(|rule| (rule.extract)(payload).map(|extracted| (rule.create, extracted)))
+            .ok_or(IngestError::Unroutable)?;
+
+        if !create
+            && !self
+                .store
+                .stream_exists(&stream_id)
+                .await
+                .map_err(IngestError::Stream)?
+        {
+            return Err(IngestError::StreamNotFound);
+        }
+
+        let new_version = self
+            .store
+            .append(stream_id.clone(), version::Check::Any, vec![event])
+            .await
+            .map_err(IngestError::Append)?;
+
+        Ok((stream_id, new_version))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::store::{Appender, InMemory};
+    use crate::message::tests::StringMessage;
+
+    #[tokio::test]
+    async fn router_appends_payloads_to_the_extracted_event_streams() {
+        let event_store = InMemory::<String, StringMessage>::default();
+
+        event_store
+            .append(
+                "customer:1".to_owned(),
+                version::Check::must_be(0),
+                vec![event::Envelope::from(StringMessage("customer-registered"))],
+            )
+            .await
+            .expect("append should not fail");
+
+        let router = Router::new(event_store.clone())
+            .route(|payload: &(&str, &str)| match payload {
+                ("order", id) => Some((
+                    format!("order:{id}"),
+                    event::Envelope::from(StringMessage("order-imported")),
+                )),
+                _ => None,
+            })
+            .route_existing(|payload: &(&str, &str)| match payload {
+                ("customer", id) => Some((
+                    format!("customer:{id}"),
+                    event::Envelope::from(StringMessage("customer-imported")),
+                )),
+                _ => None,
+            });
+
+        let (stream_id, version) = router
+            .ingest(&("order", "1"))
+            .await
+            .expect("the payload should be ingested");
+
+        assert_eq!(("order:1", 1), (stream_id.as_str(), version));
+
+        let (stream_id, version) = router
+            .ingest(&("customer", "1"))
+            .await
+            .expect("the payload should be ingested");
+
+        assert_eq!(("customer:1", 2), (stream_id.as_str(), version));
+
+        assert!(matches!(
+            router.ingest(&("customer", "2")).await,
+            Err(IngestError::StreamNotFound)
+        ));
+        assert!(matches!(
+            router.ingest(&("invoice", "1")).await,
+            Err(IngestError::Unroutable)
+        ));
+    }
+}
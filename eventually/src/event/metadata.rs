@@ -0,0 +1,47 @@
+//! Contains the [`StreamMetadata`] type, used to attach retention rules and
+//! arbitrary properties to an Event Stream, and the [`MetadataStore`] trait
+//! implemented by the Event Stores that can persist it.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// Metadata of an Event Stream, stored alongside it.
+///
+/// The retention rules are enforced by the Event Store implementation,
+/// either on append or by a separate retention task: check the documentation
+/// of the implementation in use.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamMetadata {
+    /// The maximum age of the Domain Events to retain in the Event Stream.
+    pub max_age: Option<Duration>,
+    /// The maximum number of the most recent Domain Events to retain in the Event Stream.
+    pub max_count: Option<u64>,
+    /// Arbitrary, application-defined properties of the Event Stream.
+    pub properties: HashMap<String, String>,
+}
+
+/// Interface used to read and write the [`StreamMetadata`] of Event Streams.
+#[async_trait]
+pub trait MetadataStore<StreamId>: Send + Sync
+where
+    StreamId: Send + Sync,
+{
+    /// The error type returned by the Store when reading or writing the metadata.
+    type Error: Send + Sync;
+
+    /// Returns the [`StreamMetadata`] of the Event Stream,
+    /// or [None] if it has never been set.
+    async fn stream_metadata(&self, id: &StreamId) -> Result<Option<StreamMetadata>, Self::Error>;
+
+    /// Sets the [`StreamMetadata`] of the Event Stream, replacing the previous one.
+    ///
+    /// The metadata can be set before the Event Stream exists.
+    async fn set_stream_metadata(
+        &self,
+        id: &StreamId,
+        metadata: StreamMetadata,
+    ) -> Result<(), Self::Error>;
+}
@@ -1,13 +1,34 @@
 //! Module `event` contains types and abstractions helpful for working
 //! with Domain Events.
 
+pub mod audit;
+pub mod bus;
+pub mod consumer;
+pub mod deduplication;
+pub mod effect;
+pub mod enrichment;
+pub mod federation;
+pub mod index;
+pub mod ingestion;
+pub mod metadata;
+pub mod proxy;
+pub mod quota;
+pub mod replica;
 pub mod store;
+pub mod stream;
+pub mod tap;
+#[cfg(feature = "throttling")]
+pub mod throttle;
+pub mod tiered;
+pub mod translation;
+pub mod validation;
 use std::fmt::Debug;
 
 use futures::stream::BoxStream;
 use serde::{Deserialize, Serialize};
 
 pub use crate::event::store::Store;
+pub use crate::event::stream::EventStreamExt;
 use crate::{message, version};
 
 /// An Event is a [Message][message::Message] carring the information about a Domain Event,
@@ -37,7 +58,7 @@ where
 }
 
 /// Specifies the slice of the Event Stream to select when calling [`Store::stream`].
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum VersionSelect {
     /// Selects all [Event][Envelope]s in the Event [Stream].
     All,
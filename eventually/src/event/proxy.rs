@@ -0,0 +1,386 @@
+//! Contains the [`CachingProxy`] [`event::Store`] decorator, meant for edge services
+//! fronting a central Event Store (e.g. through a network client): Event Streams
+//! are cached locally, so that rehydrating an Aggregate only fetches the Domain Events
+//! appended since the last read, while appends are written through to the central Event Store.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use futures::stream::{iter, once, StreamExt, TryStreamExt};
+
+use crate::event::store::{AppendError, Appender, Streamer};
+use crate::{event, message, version};
+
+struct CachedStreams<StreamId, Event>
+where
+    Event: message::Message,
+{
+    streams: HashMap<StreamId, (u64, Vec<event::Persisted<StreamId, Event>>)>,
+    // Logical clock of the last access, used to evict the least recently used Event Stream.
+    tick: u64,
+}
+
+/// [`event::Store`] decorator that caches up to `capacity` Event Streams locally,
+/// evicting the least recently used ones, and writes the appended Domain Events
+/// through to the wrapped Event Store, which stays the source of truth.
+///
+/// Each read fetches from the wrapped Event Store only the Domain Events that are more
+/// recent than the cached ones. Event Streams truncated or deleted in the wrapped
+/// Event Store must be [invalidated][CachingProxy::invalidate] explicitly.
+pub struct CachingProxy<S, StreamId, Event>
+where
+    Event: message::Message,
+{
+    store: S,
+    capacity: usize,
+    cache: Arc<Mutex<CachedStreams<StreamId, Event>>>,
+}
+
+impl<S, StreamId, Event> Debug for CachingProxy<S, StreamId, Event>
+where
+    S: Debug,
+    Event: message::Message,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CachingProxy")
+            .field("store", &self.store)
+            .field("capacity", &self.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, StreamId, Event> Clone for CachingProxy<S, StreamId, Event>
+where
+    S: Clone,
+    Event: message::Message,
+{
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            capacity: self.capacity,
+            cache: self.cache.clone(),
+        }
+    }
+}
+
+impl<S, StreamId, Event> CachingProxy<S, StreamId, Event>
+where
+    StreamId: Clone + Eq + Hash,
+    Event: message::Message + Clone,
+{
+    /// Creates a new [`CachingProxy`] over the specified [`event::Store`],
+    /// caching at most `capacity` Event Streams.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if `capacity` is zero.
+    pub fn new(store: S, capacity: usize) -> Self {
+        assert!(capacity > 0, "cache capacity must be greater than zero");
+
+        Self {
+            store,
+            capacity,
+            cache: Arc::new(Mutex::new(CachedStreams {
+                streams: HashMap::new(),
+                tick: 0,
+            })),
+        }
+    }
+
+    /// Removes the Event Stream with the specified id from the cache,
+    /// so that the next read fetches it again from the wrapped Event Store.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if the lock on the cache has been poisoned.
+    pub fn invalidate(&self, id: &StreamId) {
+        self.cache
+            .lock()
+            .expect("acquire lock on event streams cache")
+            .streams
+            .remove(id);
+    }
+
+    fn cached_events(&self, id: &StreamId) -> Vec<event::Persisted<StreamId, Event>> {
+        let mut cache = self
+            .cache
+            .lock()
+            .expect("acquire lock on event streams cache");
+
+        cache.tick += 1;
+        let tick = cache.tick;
+
+        cache
+            .streams
+            .get_mut(id)
+            .map(|(last_used, events)| {
+                *last_used = tick;
+                events.clone()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Adds the Domain Events to the cached Event Stream, if they directly
+    /// follow the cached ones: otherwise, the next read fetches them.
+    fn extend(&self, id: &StreamId, events: Vec<event::Persisted<StreamId, Event>>) {
+        let Some(first_version) = events.first().map(|evt| evt.version) else {
+            return;
+        };
+
+        let mut cache = self
+            .cache
+            .lock()
+            .expect("acquire lock on event streams cache");
+
+        if !cache.streams.contains_key(id) && cache.streams.len() >= self.capacity {
+            let evicted_id = cache
+                .streams
+                .iter()
+                .min_by_key(|(_, (last_used, _))| *last_used)
+                .map(|(evicted_id, _)| evicted_id.clone());
+
+            if let Some(evicted_id) = evicted_id {
+                cache.streams.remove(&evicted_id);
+            }
+        }
+
+        cache.tick += 1;
+        let tick = cache.tick;
+
+        let (last_used, cached_events) = cache.streams.entry(id.clone()).or_default();
+        let cached_version = cached_events.last().map_or(0, |evt| evt.version);
+
+        *last_used = tick;
+
+        if first_version == cached_version + 1 {
+            cached_events.extend(events);
+        }
+    }
+}
+
+#[async_trait]
+impl<S, StreamId, Event> Streamer<StreamId, Event> for CachingProxy<S, StreamId, Event>
+where
+    S: Streamer<StreamId, Event>,
+    StreamId: Clone + Eq + Hash + Send + Sync + 'static,
+    Event: message::Message + Clone + Send + Sync + 'static,
+{
+    type Error = S::Error;
+
+    fn stream(
+        &self,
+        id: &StreamId,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, StreamId, Event, Self::Error> {
+        let id = id.clone();
+
+        once(async move {
+            let mut events = self.cached_events(&id);
+            let cached_version = events.last().map_or(0, |evt| evt.version);
+
+            let new_events: Vec<_> = self
+                .store
+                .stream(&id, event::VersionSelect::From(cached_version + 1))
+                .try_collect()
+                .await?;
+
+            self.extend(&id, new_events.clone());
+            events.extend(new_events);
+
+            let events = events.into_iter().filter(move |evt| match select {
+                event::VersionSelect::All => true,
+                event::VersionSelect::From(v) => evt.version >= v,
+            });
+
+            Ok(iter(events).map(Ok))
+        })
+        .try_flatten()
+        .boxed()
+    }
+
+    /// Paged reads bypass the cache, and are forwarded to the underlying [Streamer],
+    /// so that its native paged query is used.
+    fn read_from<'a>(
+        &'a self,
+        id: &StreamId,
+        from_version: version::Version,
+        batch_size: usize,
+    ) -> event::Stream<'a, StreamId, Event, Self::Error>
+    where
+        StreamId: 'a,
+        Event: 'a,
+        Self::Error: 'a,
+    {
+        self.store.read_from(id, from_version, batch_size)
+    }
+
+    async fn head_version(&self, id: &StreamId) -> Result<Option<version::Version>, Self::Error> {
+        self.store.head_version(id).await
+    }
+}
+
+#[async_trait]
+impl<S, StreamId, Event> Appender<StreamId, Event> for CachingProxy<S, StreamId, Event>
+where
+    S: Appender<StreamId, Event>,
+    StreamId: Clone + Eq + Hash + Send + Sync + 'static,
+    Event: message::Message + Clone + Send + Sync + 'static,
+{
+    async fn append(
+        &self,
+        id: StreamId,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Event>>,
+    ) -> Result<version::Version, AppendError> {
+        let new_version = self
+            .store
+            .append(id.clone(), version_check, events.clone())
+            .await?;
+
+        let first_version = new_version + 1 - events.len() as version::Version;
+
+        let persisted_events = events
+            .into_iter()
+            .zip(first_version..)
+            .map(|(event, version)| event::Persisted {
+                stream_id: id.clone(),
+                version,
+                event,
+            })
+            .collect();
+
+        self.extend(&id, persisted_events);
+
+        Ok(new_version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::event::store::InMemory;
+    use crate::message::tests::StringMessage;
+
+    const STREAM_ID: &str = "stream:test";
+
+    /// Counts the Domain Events streamed from the wrapped Event Store.
+    #[derive(Clone)]
+    struct Counting {
+        store: InMemory<&'static str, StringMessage>,
+        streamed: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Streamer<&'static str, StringMessage> for Counting {
+        type Error = std::convert::Infallible;
+
+        fn stream(
+            &self,
+            id: &&'static str,
+            select: event::VersionSelect,
+        ) -> event::Stream<'_, &'static str, StringMessage, Self::Error> {
+            self.store
+                .stream(id, select)
+                .inspect_ok(|_| {
+                    self.streamed.fetch_add(1, Ordering::SeqCst);
+                })
+                .boxed()
+        }
+    }
+
+    #[async_trait]
+    impl Appender<&'static str, StringMessage> for Counting {
+        async fn append(
+            &self,
+            id: &'static str,
+            version_check: version::Check,
+            events: Vec<event::Envelope<StringMessage>>,
+        ) -> Result<version::Version, AppendError> {
+            self.store.append(id, version_check, events).await
+        }
+    }
+
+    async fn versions(
+        event_store: &impl Streamer<&'static str, StringMessage, Error = std::convert::Infallible>,
+        select: event::VersionSelect,
+    ) -> Vec<version::Version> {
+        event_store
+            .stream(&STREAM_ID, select)
+            .map_ok(|evt| evt.version)
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn caching_proxy_fetches_only_new_events_and_writes_through() {
+        let central = InMemory::<&'static str, StringMessage>::default();
+        let streamed = Arc::new(AtomicUsize::new(0));
+        let proxy = CachingProxy::new(
+            Counting {
+                store: central.clone(),
+                streamed: streamed.clone(),
+            },
+            10,
+        );
+
+        central
+            .append(
+                STREAM_ID,
+                version::Check::empty(),
+                vec![
+                    StringMessage("event-1").into(),
+                    StringMessage("event-2").into(),
+                ],
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            vec![1, 2],
+            versions(&proxy, event::VersionSelect::All).await
+        );
+        assert_eq!(2, streamed.load(Ordering::SeqCst));
+
+        // Written through to the central Event Store, and cached.
+        proxy
+            .append(
+                STREAM_ID,
+                version::Check::must_be(2),
+                vec![StringMessage("event-3").into()],
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(Some(3), central.head_version(&STREAM_ID).await.unwrap());
+
+        // Appended by another service.
+        central
+            .append(
+                STREAM_ID,
+                version::Check::must_be(3),
+                vec![StringMessage("event-4").into()],
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            vec![2, 3, 4],
+            versions(&proxy, event::VersionSelect::From(2)).await
+        );
+        assert_eq!(3, streamed.load(Ordering::SeqCst));
+
+        proxy.invalidate(&STREAM_ID);
+
+        assert_eq!(
+            vec![1, 2, 3, 4],
+            versions(&proxy, event::VersionSelect::All).await
+        );
+        assert_eq!(7, streamed.load(Ordering::SeqCst));
+    }
+}
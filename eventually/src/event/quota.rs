@@ -0,0 +1,436 @@
+//! Contains the [Metered] [`event::Store`] decorator, which accounts the number
+//! of Domain Events and bytes appended by each account (e.g. a tenant or a category),
+//! and optionally rejects the appends exceeding the account [Limits].
+//!
+//! Useful when exposing an Event Store as a multi-tenant platform.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+
+use crate::event::store::{AppendError, Appender, Store, Streamer};
+use crate::{event, message, serde, version};
+
+/// Error returned by the [Metered] Event Store when an append would exceed
+/// the [Limits] of its account.
+///
+/// The error is returned as an [`AppendError::Internal`], and can be recovered
+/// by downcasting the inner [`anyhow::Error`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum QuotaExceededError {
+    /// Error returned when the account would exceed its maximum number of Domain Events.
+    #[error("account '{account}' would exceed its quota of {limit} events")]
+    Events {
+        /// The account the append has been accounted to.
+        account: String,
+        /// The maximum number of Domain Events of the account.
+        limit: u64,
+    },
+    /// Error returned when the account would exceed its maximum number of bytes.
+    #[error("account '{account}' would exceed its quota of {limit} bytes")]
+    Bytes {
+        /// The account the append has been accounted to.
+        account: String,
+        /// The maximum number of bytes of the account.
+        limit: u64,
+    },
+}
+
+/// The resources used by an account, returned by [`Metered::usage`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    /// The number of Domain Events appended.
+    pub events: u64,
+    /// The size of the Domain Events payloads appended, in bytes.
+    ///
+    /// Always zero, unless a [Serializer][serde::Serializer] has been set
+    /// with [`Metered::with_serializer`].
+    pub bytes: u64,
+}
+
+/// The maximum resources an account can use. By default, there are no limits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// The maximum number of Domain Events the account can append.
+    pub max_events: Option<u64>,
+    /// The maximum size of the Domain Events payloads the account can append, in bytes.
+    pub max_bytes: Option<u64>,
+}
+
+type AccountOf<StreamId> = Arc<dyn Fn(&StreamId) -> String + Send + Sync>;
+
+/// Decorator type for an [`event::Store`] implementation that accounts
+/// the appended Domain Events to the account of their Event Stream,
+/// and rejects the appends exceeding the account [Limits].
+///
+/// The usage is accounted since the decorator has been created, and is kept in memory:
+/// use [`Metered::with_usage`] to restore the usage of an account, e.g. after a restart.
+/// Failed appends are not accounted.
+#[derive(Clone)]
+pub struct Metered<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync,
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    store: T,
+    account_of: AccountOf<StreamId>,
+    serializer: Option<Arc<dyn serde::Serializer<Event>>>,
+    default_limits: Limits,
+    limits: HashMap<String, Limits>,
+    usage: Arc<Mutex<HashMap<String, Usage>>>,
+}
+
+impl<T, StreamId, Event> Debug for Metered<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync + Debug,
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Metered")
+            .field("store", &self.store)
+            .field("default_limits", &self.default_limits)
+            .field("limits", &self.limits)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, StreamId, Event> Metered<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync,
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    /// Creates a new [Metered] decorator over the specified [`event::Store`],
+    /// using the `account_of` function to find the account of each Event Stream.
+    pub fn new<F>(store: T, account_of: F) -> Self
+    where
+        F: Fn(&StreamId) -> String + Send + Sync + 'static,
+    {
+        Self {
+            store,
+            account_of: Arc::new(account_of),
+            serializer: None,
+            default_limits: Limits::default(),
+            limits: HashMap::new(),
+            usage: Arc::default(),
+        }
+    }
+
+    /// Accounts the size of the Domain Events payloads, serialized using
+    /// the specified [Serializer][serde::Serializer].
+    ///
+    /// Use the same [Serializer][serde::Serializer] used by the underlying Event Store.
+    #[must_use]
+    pub fn with_serializer<S>(mut self, serializer: S) -> Self
+    where
+        S: serde::Serializer<Event> + 'static,
+    {
+        self.serializer = Some(Arc::new(serializer));
+        self
+    }
+
+    /// Sets the [Limits] of the accounts with no specific [Limits].
+    #[must_use]
+    pub fn with_default_limits(mut self, limits: Limits) -> Self {
+        self.default_limits = limits;
+        self
+    }
+
+    /// Sets the [Limits] of the specified account.
+    #[must_use]
+    pub fn with_limits(mut self, account: impl Into<String>, limits: Limits) -> Self {
+        self.limits.insert(account.into(), limits);
+        self
+    }
+
+    /// Sets the current [Usage] of the specified account.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if the lock on the accounts usage has been poisoned.
+    #[must_use]
+    pub fn with_usage(self, account: impl Into<String>, usage: Usage) -> Self {
+        self.usage
+            .lock()
+            .expect("acquire lock on accounts usage")
+            .insert(account.into(), usage);
+        self
+    }
+
+    /// Returns the current [Usage] of the specified account.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if the lock on the accounts usage has been poisoned.
+    #[must_use]
+    pub fn usage(&self, account: &str) -> Usage {
+        self.usage
+            .lock()
+            .expect("acquire lock on accounts usage")
+            .get(account)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns the current [Usage] of all the accounts that appended some Domain Events.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if the lock on the accounts usage has been poisoned.
+    #[must_use]
+    pub fn usages(&self) -> HashMap<String, Usage> {
+        self.usage
+            .lock()
+            .expect("acquire lock on accounts usage")
+            .clone()
+    }
+}
+
+impl<T, StreamId, Event> Metered<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync,
+    StreamId: Send + Sync,
+    Event: message::Message + Clone + Send + Sync,
+{
+    fn measure(&self, events: &[event::Envelope<Event>]) -> Result<Usage, AppendError> {
+        let mut usage = Usage {
+            events: events.len() as u64,
+            bytes: 0,
+        };
+
+        if let Some(serializer) = &self.serializer {
+            for event in events {
+                let size = serializer
+                    .serialize(event.message.clone())
+                    .map_err(AppendError::Internal)?
+                    .len();
+
+                usage.bytes += size as u64;
+            }
+        }
+
+        Ok(usage)
+    }
+
+    /// Adds the specified usage to the account, unless it would exceed its [Limits].
+    fn reserve(&self, account: &str, requested: Usage) -> Result<(), QuotaExceededError> {
+        let limits = self.limits.get(account).unwrap_or(&self.default_limits);
+
+        let mut usages = self.usage.lock().expect("acquire lock on accounts usage");
+        let usage = usages.entry(account.to_owned()).or_default();
+
+        if let Some(limit) = limits.max_events {
+            if usage.events + requested.events > limit {
+                return Err(QuotaExceededError::Events {
+                    account: account.to_owned(),
+                    limit,
+                });
+            }
+        }
+
+        if let Some(limit) = limits.max_bytes {
+            if usage.bytes + requested.bytes > limit {
+                return Err(QuotaExceededError::Bytes {
+                    account: account.to_owned(),
+                    limit,
+                });
+            }
+        }
+
+        usage.events += requested.events;
+        usage.bytes += requested.bytes;
+
+        Ok(())
+    }
+
+    fn release(&self, account: &str, reserved: Usage) {
+        let mut usages = self.usage.lock().expect("acquire lock on accounts usage");
+
+        if let Some(usage) = usages.get_mut(account) {
+            usage.events -= reserved.events;
+            usage.bytes -= reserved.bytes;
+        }
+    }
+}
+
+#[async_trait]
+impl<T, StreamId, Event> Streamer<StreamId, Event> for Metered<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync,
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    type Error = <T as Streamer<StreamId, Event>>::Error;
+
+    fn stream(
+        &self,
+        id: &StreamId,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, StreamId, Event, Self::Error> {
+        self.store.stream(id, select)
+    }
+
+    fn read_from<'a>(
+        &'a self,
+        id: &StreamId,
+        from_version: version::Version,
+        batch_size: usize,
+    ) -> event::Stream<'a, StreamId, Event, Self::Error>
+    where
+        StreamId: Clone + 'a,
+        Event: 'a,
+        Self::Error: 'a,
+    {
+        self.store.read_from(id, from_version, batch_size)
+    }
+
+    async fn head_version(&self, id: &StreamId) -> Result<Option<version::Version>, Self::Error> {
+        self.store.head_version(id).await
+    }
+}
+
+#[async_trait]
+impl<T, StreamId, Event> Appender<StreamId, Event> for Metered<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync,
+    StreamId: Send + Sync,
+    Event: message::Message + Clone + Send + Sync,
+{
+    async fn append(
+        &self,
+        id: StreamId,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Event>>,
+    ) -> Result<version::Version, AppendError> {
+        let account = (self.account_of)(&id);
+        let requested = self.measure(&events)?;
+
+        // Reserve the usage before appending, so that concurrent appends
+        // cannot exceed the account limits together.
+        self.reserve(&account, requested)
+            .map_err(anyhow::Error::from)?;
+
+        let result = self.store.append(id, version_check, events).await;
+
+        if result.is_err() {
+            self.release(&account, requested);
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::store::InMemory;
+    use crate::message::tests::StringMessage;
+
+    struct Utf8Length;
+
+    impl serde::Serializer<StringMessage> for Utf8Length {
+        fn serialize(&self, value: StringMessage) -> anyhow::Result<Vec<u8>> {
+            Ok(value.0.as_bytes().to_vec())
+        }
+    }
+
+    #[tokio::test]
+    async fn metered_event_store_accounts_usage_and_enforces_limits() {
+        let event_store = Metered::new(
+            InMemory::<&'static str, StringMessage>::default(),
+            |id: &&'static str| id.split(':').next().unwrap_or_default().to_owned(),
+        )
+        .with_serializer(Utf8Length)
+        .with_limits(
+            "tenant-a",
+            Limits {
+                max_events: Some(3),
+                max_bytes: None,
+            },
+        )
+        .with_default_limits(Limits {
+            max_events: None,
+            max_bytes: Some(10),
+        });
+
+        event_store
+            .append(
+                "tenant-a:stream-1",
+                version::Check::must_be(0),
+                vec![
+                    StringMessage("created").into(),
+                    StringMessage("updated").into(),
+                ],
+            )
+            .await
+            .expect("append should be within the quota");
+
+        // Failed appends are not accounted.
+        event_store
+            .append(
+                "tenant-a:stream-1",
+                version::Check::must_be(0),
+                vec![StringMessage("created").into()],
+            )
+            .await
+            .expect_err("append should fail with a version conflict");
+
+        let err = event_store
+            .append(
+                "tenant-a:stream-2",
+                version::Check::Any,
+                vec![
+                    StringMessage("created").into(),
+                    StringMessage("updated").into(),
+                ],
+            )
+            .await
+            .expect_err("append should exceed the events quota");
+
+        assert!(err.to_string().contains("quota of 3 events"));
+
+        event_store
+            .append(
+                "tenant-b:stream-1",
+                version::Check::Any,
+                vec![StringMessage("created").into()],
+            )
+            .await
+            .expect("append should be within the default quota");
+
+        let err = event_store
+            .append(
+                "tenant-b:stream-1",
+                version::Check::Any,
+                vec![StringMessage("deleted").into()],
+            )
+            .await
+            .expect_err("append should exceed the bytes quota");
+
+        assert!(err.to_string().contains("quota of 10 bytes"));
+
+        assert_eq!(
+            HashMap::from([
+                (
+                    "tenant-a".to_owned(),
+                    Usage {
+                        events: 2,
+                        bytes: 14
+                    }
+                ),
+                (
+                    "tenant-b".to_owned(),
+                    Usage {
+                        events: 1,
+                        bytes: 7
+                    }
+                ),
+            ]),
+            event_store.usages()
+        );
+    }
+}
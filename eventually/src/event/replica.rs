@@ -0,0 +1,228 @@
+//! Contains the [`ReadOnly`] and [`ReplicaRouted`] [`event::Store`] decorators,
+//! used to run read-heavy workloads (e.g. projection rebuilds or audit queries)
+//! against database replicas, without risking writes or loading the primary.
+
+use async_trait::async_trait;
+
+use crate::event::store::{AppendError, Appender, Store, Streamer};
+use crate::{event, message, version};
+
+/// Error returned by the [`ReadOnly`] Event Store on every append.
+///
+/// The error is returned as an [`AppendError::Internal`], and can be recovered
+/// by downcasting the inner [`anyhow::Error`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("the event store is read-only")]
+pub struct ReadOnlyError;
+
+/// Decorator type for an Event [Streamer] that implements the [`event::Store`] trait
+/// by rejecting all appends with a [`ReadOnlyError`].
+///
+/// Useful to hand a read replica to components that require an [`event::Store`],
+/// while making sure they never write to it.
+#[derive(Debug, Clone)]
+pub struct ReadOnly<T> {
+    store: T,
+}
+
+impl<T> ReadOnly<T> {
+    /// Creates a new [`ReadOnly`] decorator over the specified Event [Streamer].
+    pub fn new(store: T) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl<T, StreamId, Event> Streamer<StreamId, Event> for ReadOnly<T>
+where
+    T: Streamer<StreamId, Event>,
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    type Error = T::Error;
+
+    fn stream(
+        &self,
+        id: &StreamId,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, StreamId, Event, Self::Error> {
+        self.store.stream(id, select)
+    }
+
+    fn read_from<'a>(
+        &'a self,
+        id: &StreamId,
+        from_version: version::Version,
+        batch_size: usize,
+    ) -> event::Stream<'a, StreamId, Event, Self::Error>
+    where
+        StreamId: Clone + 'a,
+        Event: 'a,
+        Self::Error: 'a,
+    {
+        self.store.read_from(id, from_version, batch_size)
+    }
+
+    async fn head_version(&self, id: &StreamId) -> Result<Option<version::Version>, Self::Error> {
+        self.store.head_version(id).await
+    }
+}
+
+#[async_trait]
+impl<T, StreamId, Event> Appender<StreamId, Event> for ReadOnly<T>
+where
+    T: Send + Sync,
+    StreamId: Send + Sync + 'static,
+    Event: message::Message + Send + Sync + 'static,
+{
+    async fn append(
+        &self,
+        _id: StreamId,
+        _version_check: version::Check,
+        _events: Vec<event::Envelope<Event>>,
+    ) -> Result<version::Version, AppendError> {
+        Err(anyhow::Error::from(ReadOnlyError).into())
+    }
+}
+
+/// Decorator type for an [`event::Store`] implementation that routes appends
+/// to the primary [`event::Store`], and reads to a replica Event [Streamer].
+///
+/// Since replicas may lag behind the primary, reads might not observe
+/// the latest appends: optimistic concurrency is still enforced by the primary,
+/// so stale reads result in [`AppendError::Conflict`] errors rather than lost writes.
+#[derive(Debug, Clone)]
+pub struct ReplicaRouted<P, R> {
+    primary: P,
+    replica: R,
+}
+
+impl<P, R> ReplicaRouted<P, R> {
+    /// Creates a new [`ReplicaRouted`] decorator, appending to the `primary`
+    /// [`event::Store`] and reading from the `replica` Event [Streamer].
+    pub fn new(primary: P, replica: R) -> Self {
+        Self { primary, replica }
+    }
+
+    /// Returns the primary [`event::Store`], to read the latest appends
+    /// when stale reads are not acceptable.
+    pub fn primary(&self) -> &P {
+        &self.primary
+    }
+}
+
+#[async_trait]
+impl<P, R, StreamId, Event> Streamer<StreamId, Event> for ReplicaRouted<P, R>
+where
+    P: Send + Sync,
+    R: Streamer<StreamId, Event>,
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    type Error = R::Error;
+
+    fn stream(
+        &self,
+        id: &StreamId,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, StreamId, Event, Self::Error> {
+        self.replica.stream(id, select)
+    }
+
+    fn read_from<'a>(
+        &'a self,
+        id: &StreamId,
+        from_version: version::Version,
+        batch_size: usize,
+    ) -> event::Stream<'a, StreamId, Event, Self::Error>
+    where
+        StreamId: Clone + 'a,
+        Event: 'a,
+        Self::Error: 'a,
+    {
+        self.replica.read_from(id, from_version, batch_size)
+    }
+
+    async fn head_version(&self, id: &StreamId) -> Result<Option<version::Version>, Self::Error> {
+        self.replica.head_version(id).await
+    }
+}
+
+#[async_trait]
+impl<P, R, StreamId, Event> Appender<StreamId, Event> for ReplicaRouted<P, R>
+where
+    P: Store<StreamId, Event>,
+    R: Send + Sync,
+    StreamId: Send + Sync + 'static,
+    Event: message::Message + Send + Sync + 'static,
+{
+    async fn append(
+        &self,
+        id: StreamId,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Event>>,
+    ) -> Result<version::Version, AppendError> {
+        self.primary.append(id, version_check, events).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::event::store::InMemory;
+    use crate::message::tests::StringMessage;
+
+    const STREAM_ID: &str = "stream:test";
+
+    #[tokio::test]
+    async fn replica_routed_event_store_appends_to_primary_and_reads_from_replica() {
+        let primary = InMemory::<&'static str, StringMessage>::default();
+        let replica = InMemory::<&'static str, StringMessage>::default();
+        let event_store = ReplicaRouted::new(primary.clone(), ReadOnly::new(replica.clone()));
+
+        let new_version = event_store
+            .append(
+                STREAM_ID,
+                version::Check::must_be(0),
+                vec![event::Envelope::from(StringMessage("event-1"))],
+            )
+            .await
+            .expect("append should go to the primary");
+
+        assert_eq!(1, new_version);
+        assert_eq!(Some(1), primary.head_version(&STREAM_ID).await.unwrap());
+
+        // The replica has not caught up yet.
+        assert_eq!(None, event_store.head_version(&STREAM_ID).await.unwrap());
+
+        let events: Vec<_> = primary
+            .stream(&STREAM_ID, event::VersionSelect::All)
+            .map_ok(|persisted| persisted.event)
+            .try_collect()
+            .await
+            .unwrap();
+
+        replica
+            .append(STREAM_ID, version::Check::must_be(0), events)
+            .await
+            .expect("replication should not fail");
+
+        assert_eq!(Some(1), event_store.head_version(&STREAM_ID).await.unwrap());
+
+        let err = ReadOnly::new(replica)
+            .append(
+                STREAM_ID,
+                version::Check::Any,
+                vec![event::Envelope::from(StringMessage("event-2"))],
+            )
+            .await
+            .expect_err("appends to a read-only event store should fail");
+
+        assert!(matches!(
+            err,
+            AppendError::Internal(err) if err.downcast_ref::<ReadOnlyError>().is_some()
+        ));
+    }
+}
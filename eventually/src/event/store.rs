@@ -3,16 +3,23 @@
 
 use std::collections::HashMap;
 use std::convert::Infallible;
+use std::fs::{File, OpenOptions};
 use std::hash::Hash;
-use std::sync::{Arc, RwLock};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
 
 use async_trait::async_trait;
-use futures::stream::{iter, StreamExt};
+use futures::future;
+use futures::stream::{self, iter, StreamExt, TryStreamExt};
 
-use crate::{event, message, version};
+use crate::serde::Serde;
+use crate::{error, event, message, version};
 
 /// Interface used to stream [Persisted][event::Persisted] Domain Events
 /// from an Event Store to an application.
+#[async_trait]
 pub trait Streamer<StreamId, Event>: Send + Sync
 where
     StreamId: Send + Sync,
@@ -27,13 +34,130 @@ where
         &self,
         id: &StreamId,
         select: event::VersionSelect,
-    ) -> event::Stream<StreamId, Event, Self::Error>;
+    ) -> event::Stream<'_, StreamId, Event, Self::Error>;
+
+    /// Streams the Domain Events that caused the specified [`version::ConflictError`],
+    /// that is, all the Domain Events appended to the Event Stream after the expected
+    /// [Version][version::Version], up to the actual one found when the conflict was detected.
+    ///
+    /// Useful to implement merge or rebase strategies after an [`Appender::append`] call
+    /// has failed with [`AppendError::Conflict`].
+    fn stream_conflicting<'a>(
+        &'a self,
+        id: &StreamId,
+        conflict: version::ConflictError,
+    ) -> event::Stream<'a, StreamId, Event, Self::Error>
+    where
+        StreamId: 'a,
+        Event: 'a,
+        Self::Error: 'a,
+    {
+        self.stream(id, event::VersionSelect::From(conflict.expected + 1))
+            .try_take_while(move |evt| future::ready(Ok(evt.version <= conflict.actual)))
+            .boxed()
+    }
+
+    /// Streams the Domain Events of an Event Stream starting from the specified
+    /// [Version][version::Version], fetching them lazily in pages of at most
+    /// `batch_size` Domain Events, so that memory stays bounded while reading
+    /// long Event Streams.
+    ///
+    /// The next page is fetched only once the previous one has been consumed.
+    /// The default implementation opens a new [`stream`] for each page:
+    /// implementations should override it with a native paged query, where possible.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if `batch_size` is zero.
+    fn read_from<'a>(
+        &'a self,
+        id: &StreamId,
+        from_version: version::Version,
+        batch_size: usize,
+    ) -> event::Stream<'a, StreamId, Event, Self::Error>
+    where
+        StreamId: Clone + 'a,
+        Event: 'a,
+        Self::Error: 'a,
+    {
+        assert!(batch_size > 0, "batch size must be greater than zero");
+
+        let id = id.clone();
+
+        paged(from_version, batch_size, move |from_version| {
+            self.stream(&id, event::VersionSelect::From(from_version))
+                .take(batch_size)
+                .try_collect()
+        })
+    }
+
+    /// Returns the current [Version][version::Version] of the Event Stream,
+    /// i.e. the version of the last Domain Event appended to it, even if truncated,
+    /// or [None] if the Event Stream does not exist.
+    ///
+    /// The default implementation streams all the Domain Events of the Event Stream:
+    /// implementations should override it with a cheaper lookup, where possible.
+    async fn head_version(&self, id: &StreamId) -> Result<Option<version::Version>, Self::Error> {
+        self.stream(id, event::VersionSelect::All)
+            .try_fold(None, |_, evt| future::ready(Ok(Some(evt.version))))
+            .await
+    }
+
+    /// Returns true if the Event Stream exists, i.e. it has at least one Domain Event.
+    async fn stream_exists(&self, id: &StreamId) -> Result<bool, Self::Error> {
+        Ok(self.head_version(id).await?.is_some())
+    }
+}
+
+/// Returns a lazy [`event::Stream`] over the pages of Domain Events returned by `fetch_page`,
+/// called with the version of the first Domain Event of each page.
+///
+/// Stops after the first page with less than `batch_size` Domain Events.
+/// Useful to implement [`Streamer::read_from`] with a native paged query.
+pub fn paged<'a, StreamId, Event, Err, F, Fut>(
+    from_version: version::Version,
+    batch_size: usize,
+    fetch_page: F,
+) -> event::Stream<'a, StreamId, Event, Err>
+where
+    StreamId: Send + 'a,
+    Event: message::Message + Send + 'a,
+    Err: Send + 'a,
+    F: Fn(version::Version) -> Fut + Send + 'a,
+    Fut: std::future::Future<Output = Result<Vec<event::Persisted<StreamId, Event>>, Err>>
+        + Send
+        + 'a,
+{
+    stream::try_unfold(Some(from_version), move |next_version| {
+        let page = next_version.map(&fetch_page);
+
+        async move {
+            let Some(page) = page else {
+                return Ok(None);
+            };
+
+            let page = page.await?;
+
+            let next_version = page
+                .last()
+                .filter(|_| page.len() == batch_size)
+                .map(|evt| evt.version + 1);
+
+            if page.is_empty() {
+                return Ok(None);
+            }
+
+            Ok(Some((iter(page).map(Ok), next_version)))
+        }
+    })
+    .try_flatten()
+    .boxed()
 }
 
 /// All possible error types returned by [`Appender::append`].
 #[derive(Debug, thiserror::Error)]
 pub enum AppendError {
-    /// Error returned when [Appender::append] encounters a conflict error
+    /// Error returned when [`Appender::append`] encounters a conflict error
     /// while appending the new Domain Events.
     #[error("failed to append new domain events: {0}")]
     Conflict(#[from] version::ConflictError),
@@ -42,6 +166,41 @@ pub enum AppendError {
     Internal(#[from] anyhow::Error),
 }
 
+impl AppendError {
+    /// Returns the [Kind][error::Kind] of the error.
+    #[must_use]
+    pub fn kind(&self) -> error::Kind {
+        match self {
+            AppendError::Conflict(_) => error::Kind::Conflict,
+            AppendError::Internal(err) => error::kind_of(err),
+        }
+    }
+}
+
+impl From<error::Error> for AppendError {
+    fn from(err: error::Error) -> Self {
+        match err {
+            error::Error::Conflict(err) => AppendError::Conflict(err),
+            err => AppendError::Internal(err.into()),
+        }
+    }
+}
+
+impl error::Retryable for AppendError {
+    fn is_transient(&self) -> bool {
+        self.kind().is_transient()
+    }
+}
+
+impl<E> From<AppendError> for error::Error<E> {
+    fn from(err: AppendError) -> Self {
+        match err {
+            AppendError::Conflict(err) => error::Error::Conflict(err),
+            AppendError::Internal(err) => err.into(),
+        }
+    }
+}
+
 #[async_trait]
 /// Interface used to append new Domain Events in an Event Store.
 pub trait Appender<StreamId, Event>: Send + Sync
@@ -61,6 +220,42 @@ where
     ) -> Result<version::Version, AppendError>;
 }
 
+/// The Domain Events to append to a single Event Stream,
+/// as part of a [`MultiAppender::append_all`] call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamWrite<StreamId, Event>
+where
+    Event: message::Message,
+{
+    /// The id of the Event Stream to append the Domain Events to.
+    pub id: StreamId,
+    /// The version check performed on the Event Stream before appending.
+    pub version_check: version::Check,
+    /// The Domain Events to append.
+    pub events: Vec<event::Envelope<Event>>,
+}
+
+/// Interface implemented by the Event Stores able to append Domain Events
+/// to several Event Streams atomically, e.g. to a reservation Event Stream
+/// enforcing a uniqueness constraint, alongside the Aggregate Event Stream.
+#[async_trait]
+pub trait MultiAppender<StreamId, Event>: Send + Sync
+where
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    /// Appends the Domain Events of all the [`StreamWrite`]s in a single atomic operation:
+    /// if any version check fails, no Domain Event is appended.
+    ///
+    /// Returns the new [Version][version::Version] of each Event Stream,
+    /// in the same order as the [`StreamWrite`]s. Writes to the same Event Stream
+    /// are applied in order, each one observing the previous ones.
+    async fn append_all(
+        &self,
+        writes: Vec<StreamWrite<StreamId, Event>>,
+    ) -> Result<Vec<version::Version>, AppendError>;
+}
+
 /// An [Event][event::Envelope] Store, used to store Domain Events in Event Streams -- a stream
 /// of Domain Events -- and retrieve them.
 ///
@@ -81,27 +276,365 @@ where
 {
 }
 
+/// Interface used to delete Domain Events from an Event Store,
+/// e.g. to purge test data or to implement retention policies.
+#[async_trait]
+pub trait Truncator<StreamId>: Send + Sync
+where
+    StreamId: Send + Sync,
+{
+    /// The error type returned when the Domain Events could not be deleted.
+    type Error: Send + Sync;
+
+    /// Deletes the Domain Events of the Event Stream with a version lower than
+    /// the specified one, which are then excluded from the Event Stream replays.
+    ///
+    /// The Event Stream version is not affected, so that the version checks
+    /// of the next appends keep working. Returns the number of deleted Domain Events.
+    async fn truncate_before(
+        &self,
+        id: &StreamId,
+        version: version::Version,
+    ) -> Result<u64, Self::Error>;
+
+    /// Deletes the Event Stream with all its Domain Events: a new Event Stream
+    /// with the same id can be created afterwards, starting from version zero.
+    ///
+    /// Returns the number of deleted Domain Events.
+    async fn delete_stream(&self, id: &StreamId) -> Result<u64, Self::Error>;
+}
+
+/// Capacity limits of an [`InMemory`] Event Store, used to avoid unbounded
+/// memory growth in long-running tests and soak runs.
+///
+/// By default, an [`InMemory`] Event Store has no capacity limits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Capacity {
+    /// The maximum number of Domain Events in a single Event Stream.
+    ///
+    /// Appends exceeding this limit always fail with a [`CapacityExceededError`].
+    pub max_events_per_stream: Option<usize>,
+    /// The maximum number of Domain Events in the whole Event Store.
+    ///
+    /// What happens when this limit is exceeded depends on the [Eviction] policy.
+    pub max_total_events: Option<usize>,
+    /// The policy used when [`Capacity::max_total_events`] is exceeded.
+    pub eviction: Eviction,
+}
+
+/// Policy used by an [`InMemory`] Event Store when its total [Capacity] is exceeded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Eviction {
+    /// Fails the append with a [`CapacityExceededError`].
+    #[default]
+    Error,
+    /// Evicts the least recently used Event Streams, until the new Domain Events fit.
+    LeastRecentlyUsed,
+}
+
+/// Error returned by the [`InMemory`] Event Store when an append would exceed its [Capacity].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("in-memory event store capacity exceeded, limit: {limit} events")]
+pub struct CapacityExceededError {
+    /// The limit that would have been exceeded.
+    pub limit: usize,
+}
+
 #[derive(Debug)]
 struct InMemoryBackend<Id, Evt>
 where
     Evt: message::Message,
 {
     event_streams: HashMap<Id, Vec<event::Persisted<Id, Evt>>>,
+    // The last Domain Event appended to each Event Stream, kept even once truncated:
+    // it holds the Event Stream version, and identifies the Event Stream in the log.
+    heads: HashMap<Id, event::Persisted<Id, Evt>>,
+    capacity: Capacity,
+    total_events: usize,
+    // Logical clock of the last access to each Event Stream, used for LRU eviction.
+    last_used: Mutex<(u64, HashMap<Id, u64>)>,
+    log: Option<Log<Id, Evt>>,
 }
 
-impl<Id, Evt> Default for InMemoryBackend<Id, Evt>
+/// Append-only file where an [`InMemory`] Event Store opened with [`InMemory::open`]
+/// writes its Domain Events, as a sequence of length-prefixed records.
+///
+/// Truncations and deletions of Event Streams are written as control records,
+/// marked by the [`Log::CONTROL_PREFIX`] length prefix and followed by the
+/// [`LogControl`] kind, its [Version][version::Version] and the record of
+/// the Event Stream head, which identifies the Event Stream.
+struct Log<Id, Evt>
 where
     Evt: message::Message,
 {
-    fn default() -> Self {
+    file: File,
+    len: u64,
+    serde: Box<dyn Serde<event::Persisted<Id, Evt>>>,
+}
+
+impl<Id, Evt> std::fmt::Debug for Log<Id, Evt>
+where
+    Evt: message::Message,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Log")
+            .field("file", &self.file)
+            .field("len", &self.len)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Operation recorded by a control record of the [Log].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LogControl {
+    /// The Domain Events of the Event Stream with a lower version have been truncated.
+    TruncateBefore(version::Version),
+    /// The Event Stream has been deleted.
+    DeleteStream,
+}
+
+impl<Id, Evt> Log<Id, Evt>
+where
+    Evt: message::Message,
+{
+    // Size of the little-endian length prefix of each record.
+    const PREFIX_LEN: usize = 4;
+    // Length prefix reserved to mark control records, followed by their kind and version.
+    const CONTROL_PREFIX: u32 = u32::MAX;
+    const CONTROL_LEN: usize = Self::PREFIX_LEN + 1 + 8;
+
+    fn encode(&self, buf: &mut Vec<u8>, event: &event::Persisted<Id, Evt>) -> anyhow::Result<()>
+    where
+        Id: Clone,
+        Evt: Clone,
+    {
+        let record = self.serde.serialize(event.clone())?;
+        let record_len = u32::try_from(record.len())
+            .ok()
+            .filter(|len| *len != Self::CONTROL_PREFIX)
+            .ok_or_else(|| anyhow::anyhow!("domain event record too large to persist"))?;
+
+        buf.extend_from_slice(&record_len.to_le_bytes());
+        buf.extend_from_slice(&record);
+
+        Ok(())
+    }
+
+    fn write(&mut self, events: &[event::Persisted<Id, Evt>]) -> anyhow::Result<()>
+    where
+        Id: Clone,
+        Evt: Clone,
+    {
+        let mut buf = Vec::new();
+
+        for event in events {
+            self.encode(&mut buf, event)?;
+        }
+
+        self.write_all(&buf)
+    }
+
+    fn write_control(
+        &mut self,
+        control: LogControl,
+        head: &event::Persisted<Id, Evt>,
+    ) -> anyhow::Result<()>
+    where
+        Id: Clone,
+        Evt: Clone,
+    {
+        let (kind, version) = match control {
+            LogControl::TruncateBefore(version) => (0_u8, version),
+            LogControl::DeleteStream => (1_u8, 0),
+        };
+
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&Self::CONTROL_PREFIX.to_le_bytes());
+        buf.push(kind);
+        buf.extend_from_slice(&version.to_le_bytes());
+        self.encode(&mut buf, head)?;
+
+        self.write_all(&buf)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> anyhow::Result<()> {
+        let result = self
+            .file
+            .write_all(buf)
+            .and_then(|()| self.file.sync_data());
+
+        if let Err(err) = result {
+            // Drop any partially-written record, so that the next appends
+            // are not written after a corrupted one.
+            self.file.set_len(self.len)?;
+            return Err(anyhow::anyhow!(
+                "failed to write to the event store log: {err}"
+            ));
+        }
+
+        self.len += buf.len() as u64;
+
+        Ok(())
+    }
+}
+
+impl<Id, Evt> InMemoryBackend<Id, Evt>
+where
+    Evt: message::Message,
+{
+    fn with_capacity(capacity: Capacity) -> Self {
         Self {
             event_streams: HashMap::default(),
+            heads: HashMap::default(),
+            capacity,
+            total_events: 0,
+            last_used: Mutex::default(),
+            log: None,
+        }
+    }
+}
+
+impl<Id, Evt> InMemoryBackend<Id, Evt>
+where
+    Id: Clone + Eq + Hash,
+    Evt: message::Message,
+{
+    fn touch(&self, id: &Id) {
+        if self.capacity.eviction != Eviction::LeastRecentlyUsed {
+            return;
+        }
+
+        let mut last_used = self
+            .last_used
+            .lock()
+            .expect("acquire lock on event streams usage");
+
+        last_used.0 += 1;
+        let tick = last_used.0;
+        last_used.1.insert(id.clone(), tick);
+    }
+
+    /// Returns the ids of the Event Streams to evict so that the new Domain Events,
+    /// by Event Stream id, fit in the [Capacity], without changing the backend.
+    ///
+    /// The Event Streams being appended to are never evicted: if the new Domain Events
+    /// do not fit otherwise, the whole append fails.
+    fn plan_eviction(
+        &self,
+        new_events: &HashMap<Id, usize>,
+    ) -> Result<Vec<Id>, CapacityExceededError> {
+        if let Some(limit) = self.capacity.max_events_per_stream {
+            for (id, events_len) in new_events {
+                let stream_events = self.event_streams.get(id).map_or(0, Vec::len);
+
+                if stream_events + events_len > limit {
+                    return Err(CapacityExceededError { limit });
+                }
+            }
+        }
+
+        let Some(limit) = self.capacity.max_total_events else {
+            return Ok(Vec::new());
+        };
+
+        let new_events_len: usize = new_events.values().sum();
+        let mut total_events = self.total_events;
+
+        if total_events + new_events_len <= limit {
+            return Ok(Vec::new());
+        }
+
+        if self.capacity.eviction == Eviction::Error {
+            return Err(CapacityExceededError { limit });
+        }
+
+        let mut candidates: Vec<(u64, Id)> = self
+            .last_used
+            .lock()
+            .expect("acquire lock on event streams usage")
+            .1
+            .iter()
+            .filter(|(stream_id, _)| !new_events.contains_key(*stream_id))
+            .map(|(stream_id, tick)| (*tick, stream_id.clone()))
+            .collect();
+
+        candidates.sort_unstable_by_key(|(tick, _)| *tick);
+
+        let mut candidates = candidates.into_iter();
+        let mut evicted_ids = Vec::new();
+
+        while total_events + new_events_len > limit {
+            let (_, evicted_id) = candidates.next().ok_or(CapacityExceededError { limit })?;
+
+            total_events -= self.event_streams.get(&evicted_id).map_or(0, Vec::len);
+            evicted_ids.push(evicted_id);
+        }
+
+        Ok(evicted_ids)
+    }
+
+    /// Truncates or deletes the Event Stream, as described by the [`LogControl`],
+    /// returning the number of removed Domain Events.
+    fn truncate(&mut self, id: &Id, control: LogControl) -> usize {
+        let removed = match control {
+            LogControl::TruncateBefore(version) => {
+                self.event_streams.get_mut(id).map_or(0, |events| {
+                    let truncated = events.partition_point(|evt| evt.version < version);
+                    events.drain(..truncated);
+                    truncated
+                })
+            },
+            LogControl::DeleteStream => {
+                self.heads.remove(id);
+                self.last_used
+                    .get_mut()
+                    .expect("acquire lock on event streams usage")
+                    .1
+                    .remove(id);
+
+                self.event_streams
+                    .remove(id)
+                    .map_or(0, |events| events.len())
+            },
+        };
+
+        self.total_events -= removed;
+        removed
+    }
+
+    /// Writes the truncation or deletion of the Event Stream to the [Log], if any.
+    fn log_control(&mut self, id: &Id, control: LogControl) -> anyhow::Result<()>
+    where
+        Evt: Clone,
+    {
+        match (self.log.as_mut(), self.heads.get(id)) {
+            (Some(log), Some(head)) => log.write_control(control, head),
+            _ => Ok(()),
+        }
+    }
+
+    fn evict(&mut self, evicted_ids: Vec<Id>) {
+        let last_used = &mut self
+            .last_used
+            .get_mut()
+            .expect("acquire lock on event streams usage")
+            .1;
+
+        for evicted_id in evicted_ids {
+            last_used.remove(&evicted_id);
+            self.heads.remove(&evicted_id);
+
+            if let Some(events) = self.event_streams.remove(&evicted_id) {
+                self.total_events -= events.len();
+            }
         }
     }
 }
 
 /// In-memory implementation of [`event::Store`] trait,
 /// backed by a thread-safe [`std::collections::HashMap`].
+///
+/// Use [`InMemory::with_capacity`] to limit the number of Domain Events it can hold.
 #[derive(Debug, Clone)]
 pub struct InMemory<Id, Evt>
 where
@@ -110,17 +643,136 @@ where
     backend: Arc<RwLock<InMemoryBackend<Id, Evt>>>,
 }
 
-impl<Id, Evt> Default for InMemory<Id, Evt>
+impl<Id, Evt> InMemory<Id, Evt>
 where
     Evt: message::Message,
 {
-    fn default() -> Self {
+    /// Creates a new [`InMemory`] Event Store with the specified [Capacity] limits.
+    #[must_use]
+    pub fn with_capacity(capacity: Capacity) -> Self {
         Self {
-            backend: Arc::default(),
+            backend: Arc::new(RwLock::new(InMemoryBackend::with_capacity(capacity))),
         }
     }
 }
 
+impl<Id, Evt> InMemory<Id, Evt>
+where
+    Id: Clone + Eq + Hash,
+    Evt: message::Message,
+{
+    /// Opens an [`InMemory`] Event Store persisted to the append-only file
+    /// at the specified path, creating the file if missing.
+    ///
+    /// All the Domain Events found in the file are loaded in memory on startup,
+    /// and every successful append is written to the file, using the specified [Serde],
+    /// before being visible to readers. Truncations and deletions through [Truncator]
+    /// are written to the file as well. An incomplete record at the end of the file,
+    /// e.g. left by a crash during an append, is discarded.
+    ///
+    /// The Event Store has no [Capacity] limits, since evicted Domain Events
+    /// would be restored on the next startup anyway.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the file could not be opened or read,
+    /// or if a Domain Event in it could not be deserialized.
+    pub fn open<S>(path: impl AsRef<Path>, serde: S) -> anyhow::Result<Self>
+    where
+        Evt: Clone,
+        S: Serde<event::Persisted<Id, Evt>> + 'static,
+    {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+
+        let mut backend = InMemoryBackend::with_capacity(Capacity::default());
+        let mut offset = 0;
+
+        while let Some(prefix) = data.get(offset..offset + Log::<Id, Evt>::PREFIX_LEN) {
+            let mut control = None;
+            let mut start = offset;
+
+            if u32::from_le_bytes(prefix.try_into()?) == Log::<Id, Evt>::CONTROL_PREFIX {
+                let Some(header) = data.get(offset..offset + Log::<Id, Evt>::CONTROL_LEN) else {
+                    break;
+                };
+
+                let version =
+                    u64::from_le_bytes(header[Log::<Id, Evt>::PREFIX_LEN + 1..].try_into()?);
+
+                control = Some(match header[Log::<Id, Evt>::PREFIX_LEN] {
+                    0 => LogControl::TruncateBefore(version),
+                    1 => LogControl::DeleteStream,
+                    kind => {
+                        anyhow::bail!("unknown control record kind in the event store log: {kind}")
+                    },
+                });
+
+                start += Log::<Id, Evt>::CONTROL_LEN;
+            }
+
+            let Some(prefix) = data.get(start..start + Log::<Id, Evt>::PREFIX_LEN) else {
+                break;
+            };
+
+            let record_len = u32::from_le_bytes(prefix.try_into()?) as usize;
+            let start = start + Log::<Id, Evt>::PREFIX_LEN;
+
+            let Some(record) = data.get(start..start + record_len) else {
+                break;
+            };
+
+            let event = serde.deserialize(record)?;
+            offset = start + record_len;
+
+            match control {
+                None => {
+                    backend.heads.insert(event.stream_id.clone(), event.clone());
+                    backend
+                        .event_streams
+                        .entry(event.stream_id.clone())
+                        .or_default()
+                        .push(event);
+
+                    backend.total_events += 1;
+                },
+                Some(control) => {
+                    backend.truncate(&event.stream_id, control);
+                },
+            }
+        }
+
+        let len = offset as u64;
+        file.set_len(len)?;
+
+        backend.log = Some(Log {
+            file,
+            len,
+            serde: Box::new(serde),
+        });
+
+        Ok(Self {
+            backend: Arc::new(RwLock::new(backend)),
+        })
+    }
+}
+
+impl<Id, Evt> Default for InMemory<Id, Evt>
+where
+    Evt: message::Message,
+{
+    fn default() -> Self {
+        Self::with_capacity(Capacity::default())
+    }
+}
+
+#[async_trait]
 impl<Id, Evt> Streamer<Id, Evt> for InMemory<Id, Evt>
 where
     Id: Clone + Eq + Hash + Send + Sync,
@@ -128,12 +780,20 @@ where
 {
     type Error = Infallible;
 
-    fn stream(&self, id: &Id, select: event::VersionSelect) -> event::Stream<Id, Evt, Self::Error> {
+    fn stream(
+        &self,
+        id: &Id,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, Id, Evt, Self::Error> {
         let backend = self
             .backend
             .read()
             .expect("acquire read lock on event store backend");
 
+        if backend.event_streams.contains_key(id) {
+            backend.touch(id);
+        }
+
         let events = backend
             .event_streams
             .get(id)
@@ -147,6 +807,50 @@ where
 
         iter(events).map(Ok).boxed()
     }
+
+    fn read_from<'a>(
+        &'a self,
+        id: &Id,
+        from_version: version::Version,
+        batch_size: usize,
+    ) -> event::Stream<'a, Id, Evt, Self::Error>
+    where
+        Id: 'a,
+        Evt: 'a,
+    {
+        assert!(batch_size > 0, "batch size must be greater than zero");
+
+        let id = id.clone();
+
+        paged(from_version, batch_size, move |from_version| {
+            let backend = self
+                .backend
+                .read()
+                .expect("acquire read lock on event store backend");
+
+            // Only the Domain Events of the page are cloned.
+            let page = backend
+                .event_streams
+                .get(&id)
+                .map(|events| {
+                    let start = events.partition_point(|evt| evt.version < from_version);
+
+                    events[start..].iter().take(batch_size).cloned().collect()
+                })
+                .unwrap_or_default();
+
+            future::ready(Ok(page))
+        })
+    }
+
+    async fn head_version(&self, id: &Id) -> Result<Option<version::Version>, Self::Error> {
+        let backend = self
+            .backend
+            .read()
+            .expect("acquire read lock on event store backend");
+
+        Ok(backend.heads.get(id).map(|evt| evt.version))
+    }
 }
 
 #[async_trait]
@@ -161,49 +865,238 @@ where
         version_check: version::Check,
         events: Vec<event::Envelope<Evt>>,
     ) -> Result<version::Version, AppendError> {
+        let new_versions = self
+            .append_all(vec![StreamWrite {
+                id,
+                version_check,
+                events,
+            }])
+            .await?;
+
+        Ok(new_versions[0])
+    }
+}
+
+#[async_trait]
+impl<Id, Evt> MultiAppender<Id, Evt> for InMemory<Id, Evt>
+where
+    Id: Clone + Eq + Hash + Send + Sync,
+    Evt: message::Message + Clone + Send + Sync,
+{
+    async fn append_all(
+        &self,
+        writes: Vec<StreamWrite<Id, Evt>>,
+    ) -> Result<Vec<version::Version>, AppendError> {
         let mut backend = self
             .backend
             .write()
             .expect("acquire write lock on event store backend");
 
-        let last_event_stream_version = backend
-            .event_streams
-            .get(&id)
-            .and_then(|events| events.last())
-            .map(|event| event.version)
-            .unwrap_or_default();
-
-        if let version::Check::MustBe(expected) = version_check {
-            if last_event_stream_version != expected {
-                return Err(AppendError::Conflict(version::ConflictError {
-                    expected,
-                    actual: last_event_stream_version,
-                }));
+        // All the version checks are performed before changing the backend,
+        // which is locked for the whole operation.
+        let mut stream_versions: HashMap<Id, version::Version> = HashMap::new();
+        let mut new_events: HashMap<Id, usize> = HashMap::new();
+        let mut new_versions = Vec::with_capacity(writes.len());
+        let mut persisted_events: Vec<event::Persisted<Id, Evt>> = Vec::new();
+
+        for write in writes {
+            let last_event_stream_version =
+                *stream_versions.entry(write.id.clone()).or_insert_with(|| {
+                    backend
+                        .heads
+                        .get(&write.id)
+                        .map(|event| event.version)
+                        .unwrap_or_default()
+                });
+
+            if let version::Check::MustBe(expected) = write.version_check {
+                if last_event_stream_version != expected.version() {
+                    return Err(AppendError::Conflict(version::ConflictError {
+                        expected: expected.version(),
+                        actual: last_event_stream_version,
+                    }));
+                }
+            }
+
+            let events_len = write.events.len();
+
+            persisted_events.extend(write.events.into_iter().enumerate().map(|(i, event)| {
+                event::Persisted {
+                    stream_id: write.id.clone(),
+                    version: last_event_stream_version + (i as u64) + 1,
+                    event,
+                }
+            }));
+
+            let new_last_event_stream_version = if events_len == 0 {
+                0
+            } else {
+                last_event_stream_version + events_len as u64
+            };
+
+            if events_len > 0 {
+                stream_versions.insert(write.id.clone(), new_last_event_stream_version);
             }
+
+            *new_events.entry(write.id).or_default() += events_len;
+            new_versions.push(new_last_event_stream_version);
         }
 
-        let mut persisted_events: Vec<event::Persisted<Id, Evt>> = events
-            .into_iter()
-            .enumerate()
-            .map(|(i, event)| event::Persisted {
-                stream_id: id.clone(),
-                version: last_event_stream_version + (i as u64) + 1,
-                event,
-            })
-            .collect();
+        // Evictions are applied only once the whole append is known to succeed.
+        let evicted_ids = backend
+            .plan_eviction(&new_events)
+            .map_err(anyhow::Error::from)?;
+
+        if let Some(log) = backend.log.as_mut() {
+            log.write(&persisted_events)
+                .map_err(AppendError::Internal)?;
+        }
 
-        let new_last_event_stream_version = persisted_events
-            .last()
-            .map(|evt| evt.version)
-            .unwrap_or_default();
+        backend.evict(evicted_ids);
 
-        backend
+        backend.total_events += persisted_events.len();
+
+        for id in new_events.keys() {
+            backend.touch(id);
+        }
+
+        for persisted_event in persisted_events {
+            backend
+                .event_streams
+                .entry(persisted_event.stream_id.clone())
+                .or_default()
+                .push(persisted_event);
+        }
+
+        for id in new_events.keys() {
+            if let Some(head) = backend
+                .event_streams
+                .get(id)
+                .and_then(|events| events.last())
+            {
+                let head = head.clone();
+                backend.heads.insert(id.clone(), head);
+            }
+        }
+
+        Ok(new_versions)
+    }
+}
+
+#[cfg(feature = "correlation")]
+#[async_trait]
+impl<Id, Evt> crate::correlation::WorkflowQuery<Id, Evt> for InMemory<Id, Evt>
+where
+    Id: Clone + Eq + Hash + Send + Sync,
+    Evt: message::Message + Clone + Send + Sync,
+{
+    type Error = Infallible;
+
+    async fn correlated_events(
+        &self,
+        correlation_id: &str,
+    ) -> Result<Vec<event::Persisted<Id, Evt>>, Self::Error> {
+        let backend = self
+            .backend
+            .read()
+            .expect("acquire read lock on event store backend");
+
+        Ok(backend
             .event_streams
-            .entry(id)
-            .and_modify(|events| events.append(&mut persisted_events))
-            .or_insert_with(|| persisted_events);
+            .values()
+            .flatten()
+            .filter(|evt| {
+                evt.event
+                    .metadata
+                    .get(crate::aggregate::timeline::CORRELATION_ID_KEY)
+                    .is_some_and(|id| id == correlation_id)
+            })
+            .cloned()
+            .collect())
+    }
+}
 
-        Ok(new_last_event_stream_version)
+/// Truncations and deletions are written to the file of an [`InMemory`] Event Store
+/// opened with [`InMemory::open`] before being applied, so that they survive restarts.
+#[async_trait]
+impl<Id, Evt> Truncator<Id> for InMemory<Id, Evt>
+where
+    Id: Clone + Eq + Hash + Send + Sync,
+    Evt: message::Message + Clone + Send + Sync,
+{
+    type Error = anyhow::Error;
+
+    async fn truncate_before(
+        &self,
+        id: &Id,
+        version: version::Version,
+    ) -> Result<u64, Self::Error> {
+        let mut backend = self
+            .backend
+            .write()
+            .expect("acquire write lock on event store backend");
+
+        let truncated = backend.event_streams.get(id).map_or(0, |events| {
+            events.partition_point(|evt| evt.version < version)
+        });
+
+        if truncated == 0 {
+            return Ok(0);
+        }
+
+        let control = LogControl::TruncateBefore(version);
+        backend.log_control(id, control)?;
+
+        Ok(backend.truncate(id, control) as u64)
+    }
+
+    async fn delete_stream(&self, id: &Id) -> Result<u64, Self::Error> {
+        let mut backend = self
+            .backend
+            .write()
+            .expect("acquire write lock on event store backend");
+
+        if !backend.heads.contains_key(id) {
+            return Ok(0);
+        }
+
+        backend.log_control(id, LogControl::DeleteStream)?;
+
+        Ok(backend.truncate(id, LogControl::DeleteStream) as u64)
+    }
+}
+
+/// Evicted Domain Events are removed from memory only: an [`InMemory`] Event Store
+/// opened with [`InMemory::open`] restores them from its file on the next startup.
+#[async_trait]
+impl<Id, Evt> event::tiered::Evictor<Id> for InMemory<Id, Evt>
+where
+    Id: Clone + Eq + Hash + Send + Sync,
+    Evt: message::Message + Send + Sync,
+{
+    type Error = Infallible;
+
+    async fn evict(&self, id: &Id, up_to: version::Version) -> Result<(), Self::Error> {
+        let mut backend = self
+            .backend
+            .write()
+            .expect("acquire write lock on event store backend");
+
+        let Some(events) = backend.event_streams.get_mut(id) else {
+            return Ok(());
+        };
+
+        // Always keep the last Domain Event, as required by the Evictor contract.
+        let evicted = events
+            .iter()
+            .take(events.len().saturating_sub(1))
+            .take_while(|evt| evt.version <= up_to)
+            .count();
+
+        events.drain(..evicted);
+        backend.total_events -= evicted;
+
+        Ok(())
     }
 }
 
@@ -258,6 +1151,7 @@ where
     }
 }
 
+#[async_trait]
 impl<T, StreamId, Event> Streamer<StreamId, Event> for Tracking<T, StreamId, Event>
 where
     T: Store<StreamId, Event> + Send + Sync,
@@ -270,9 +1164,27 @@ where
         &self,
         id: &StreamId,
         select: event::VersionSelect,
-    ) -> event::Stream<StreamId, Event, Self::Error> {
+    ) -> event::Stream<'_, StreamId, Event, Self::Error> {
         self.store.stream(id, select)
     }
+
+    fn read_from<'a>(
+        &'a self,
+        id: &StreamId,
+        from_version: version::Version,
+        batch_size: usize,
+    ) -> event::Stream<'a, StreamId, Event, Self::Error>
+    where
+        StreamId: Clone + 'a,
+        Event: 'a,
+        Self::Error: 'a,
+    {
+        self.store.read_from(id, from_version, batch_size)
+    }
+
+    async fn head_version(&self, id: &StreamId) -> Result<Option<version::Version>, Self::Error> {
+        self.store.head_version(id).await
+    }
 }
 
 #[async_trait]
@@ -330,6 +1242,102 @@ where
             events: Arc::default(),
         }
     }
+
+    /// Returns a [`Deduplicated`][event::deduplication::Deduplicated] instance that decorates
+    /// the original [`event::Store`] instance this method has been called on,
+    /// remembering deduplication IDs for the specified `retention` window.
+    fn with_deduplication(
+        self,
+        retention: Duration,
+    ) -> event::deduplication::Deduplicated<Self, StreamId, Event>
+    where
+        StreamId: Eq + Hash,
+    {
+        event::deduplication::Deduplicated::new(self, retention)
+    }
+
+    /// Returns a [`Validated`][event::validation::Validated] instance that decorates
+    /// the original [`event::Store`] instance this method has been called on,
+    /// to be configured with a maximum payload size and validation hooks.
+    fn with_validation(self) -> event::validation::Validated<Self, StreamId, Event> {
+        event::validation::Validated::new(self)
+    }
+
+    /// Returns a [`Metered`][event::quota::Metered] instance that decorates
+    /// the original [`event::Store`] instance this method has been called on,
+    /// accounting the appended Domain Events to the account returned by `account_of`.
+    fn with_metering<F>(self, account_of: F) -> event::quota::Metered<Self, StreamId, Event>
+    where
+        F: Fn(&StreamId) -> String + Send + Sync + 'static,
+    {
+        event::quota::Metered::new(self, account_of)
+    }
+
+    /// Returns a [`ReplicaRouted`][event::replica::ReplicaRouted] instance that decorates
+    /// the original [`event::Store`] instance this method has been called on,
+    /// routing the reads to the specified `replica` Event [Streamer].
+    fn with_read_replica<R>(self, replica: R) -> event::replica::ReplicaRouted<Self, R>
+    where
+        R: Streamer<StreamId, Event>,
+    {
+        event::replica::ReplicaRouted::new(self, replica)
+    }
+
+    /// Returns a [`CachingProxy`][event::proxy::CachingProxy] instance that decorates
+    /// the original [`event::Store`] instance this method has been called on,
+    /// caching at most `capacity` Event Streams locally.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if `capacity` is zero.
+    fn with_caching_proxy(
+        self,
+        capacity: usize,
+    ) -> event::proxy::CachingProxy<Self, StreamId, Event>
+    where
+        StreamId: Eq + Hash,
+    {
+        event::proxy::CachingProxy::new(self, capacity)
+    }
+
+    /// Returns a [`Tapped`][event::tap::Tapped] instance that decorates
+    /// the original [`event::Store`] instance this method has been called on,
+    /// mirroring the newly-appended Domain Events to the specified [Sink][event::tap::Sink].
+    fn with_tap<S>(self, sink: S) -> event::tap::Tapped<Self, StreamId, Event>
+    where
+        S: event::tap::Sink<StreamId, Event> + 'static,
+    {
+        event::tap::Tapped::new(self, sink)
+    }
+
+    /// Returns a [`Throttled`][event::throttle::Throttled] instance that decorates
+    /// the original [`event::Store`] instance this method has been called on,
+    /// allowing at most `max_in_flight` concurrent appends per Event Stream
+    /// and queueing at most `max_queued` more.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if `max_in_flight` is zero.
+    #[cfg(feature = "throttling")]
+    fn with_throttling(
+        self,
+        max_in_flight: usize,
+        max_queued: usize,
+    ) -> event::throttle::Throttled<Self, StreamId, Event>
+    where
+        StreamId: Eq + Hash,
+    {
+        event::throttle::Throttled::new(self, max_in_flight, max_queued)
+    }
+
+    /// Returns a [`Stamped`][crate::correlation::Stamped] instance that decorates
+    /// the original [`event::Store`] instance this method has been called on,
+    /// adding the correlation and causation metadata of the Domain Command
+    /// being handled to the appended Domain Events.
+    #[cfg(feature = "correlation")]
+    fn with_correlation(self) -> crate::correlation::Stamped<Self> {
+        crate::correlation::Stamped::new(self)
+    }
 }
 
 impl<T, StreamId, Event> EventStoreExt<StreamId, Event> for T
@@ -343,8 +1351,8 @@ where
 #[allow(clippy::semicolon_if_nothing_returned)] // False positives :shrugs:
 #[cfg(test)]
 mod test {
-    use futures::TryStreamExt;
-    use lazy_static::lazy_static;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::LazyLock;
 
     use super::*;
     use crate::event;
@@ -354,20 +1362,20 @@ mod test {
 
     const STREAM_ID: &str = "stream:test";
 
-    lazy_static! {
-        static ref EVENTS: Vec<event::Envelope<StringMessage>> = vec![
+    static EVENTS: LazyLock<Vec<event::Envelope<StringMessage>>> = LazyLock::new(|| {
+        vec![
             event::Envelope::from(StringMessage("event-1")),
             event::Envelope::from(StringMessage("event-2")),
             event::Envelope::from(StringMessage("event-3")),
-        ];
-    }
+        ]
+    });
 
     #[tokio::test]
     async fn it_works() {
         let event_store = InMemory::<&'static str, StringMessage>::default();
 
         let new_event_stream_version = event_store
-            .append(STREAM_ID, version::Check::MustBe(0), EVENTS.clone())
+            .append(STREAM_ID, version::Check::must_be(0), EVENTS.clone())
             .await
             .expect("append should not fail");
 
@@ -400,7 +1408,7 @@ mod test {
         let tracking_event_store = event_store.with_recorded_events_tracking();
 
         tracking_event_store
-            .append(STREAM_ID, version::Check::MustBe(0), EVENTS.clone())
+            .append(STREAM_ID, version::Check::must_be(0), EVENTS.clone())
             .await
             .expect("append should not fail");
 
@@ -418,7 +1426,7 @@ mod test {
         let event_store = InMemory::<&'static str, StringMessage>::default();
 
         let append_error = event_store
-            .append(STREAM_ID, version::Check::MustBe(3), EVENTS.clone())
+            .append(STREAM_ID, version::Check::must_be(3), EVENTS.clone())
             .await
             .expect_err("the event stream version should be zero");
 
@@ -434,4 +1442,610 @@ mod test {
 
         panic!("expected conflict error, received: {append_error}")
     }
+
+    #[tokio::test]
+    async fn empty_version_check_fails_if_the_event_stream_exists() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+
+        event_store
+            .append(STREAM_ID, version::Check::empty(), EVENTS.clone())
+            .await
+            .expect("the event stream should be created");
+
+        let append_error = event_store
+            .append(STREAM_ID, version::Check::empty(), EVENTS.clone())
+            .await
+            .expect_err("the event stream should already exist");
+
+        assert!(matches!(
+            append_error,
+            AppendError::Conflict(version::ConflictError {
+                expected: 0,
+                actual: 3,
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn conflicting_events_can_be_streamed_after_a_version_conflict() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+
+        event_store
+            .append(STREAM_ID, version::Check::must_be(0), EVENTS.clone())
+            .await
+            .expect("append should not fail");
+
+        let append_error = event_store
+            .append(STREAM_ID, version::Check::must_be(1), EVENTS.clone())
+            .await
+            .expect_err("the event stream version should be 3");
+
+        let AppendError::Conflict(conflict) = append_error else {
+            panic!("expected conflict error, received: {append_error}");
+        };
+
+        assert_eq!(
+            version::ConflictError {
+                expected: 1,
+                actual: 3,
+            },
+            conflict
+        );
+
+        let conflicting_events: Vec<_> = event_store
+            .stream_conflicting(&STREAM_ID, conflict)
+            .try_collect()
+            .await
+            .expect("opening an event stream should not fail");
+
+        let expected_events = EVENTS
+            .iter()
+            .cloned()
+            .enumerate()
+            .skip(1)
+            .map(|(i, event)| event::Persisted {
+                stream_id: STREAM_ID,
+                version: (i as Version) + 1,
+                event,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(expected_events, conflicting_events);
+    }
+
+    #[tokio::test]
+    async fn head_version_and_existence_can_be_checked_without_streaming() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+        let tracking_event_store = event_store.clone().with_recorded_events_tracking();
+
+        assert_eq!(None, event_store.head_version(&STREAM_ID).await.unwrap());
+        assert!(!event_store.stream_exists(&STREAM_ID).await.unwrap());
+
+        tracking_event_store
+            .append(STREAM_ID, version::Check::must_be(0), EVENTS.clone())
+            .await
+            .expect("append should not fail");
+
+        let expected_version = Some(EVENTS.len() as Version);
+
+        assert_eq!(
+            expected_version,
+            event_store.head_version(&STREAM_ID).await.unwrap()
+        );
+        assert_eq!(
+            expected_version,
+            tracking_event_store.head_version(&STREAM_ID).await.unwrap()
+        );
+        assert!(tracking_event_store
+            .stream_exists(&STREAM_ID)
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn multiple_event_streams_are_appended_atomically() {
+        const RESERVATION_STREAM_ID: &str = "reservation:test";
+
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+
+        let new_versions = event_store
+            .append_all(vec![
+                StreamWrite {
+                    id: STREAM_ID,
+                    version_check: version::Check::empty(),
+                    events: EVENTS.clone(),
+                },
+                StreamWrite {
+                    id: RESERVATION_STREAM_ID,
+                    version_check: version::Check::empty(),
+                    events: vec![event::Envelope::from(StringMessage("reserved"))],
+                },
+            ])
+            .await
+            .expect("append should not fail");
+
+        assert_eq!(vec![3, 1], new_versions);
+
+        let err = event_store
+            .append_all(vec![
+                StreamWrite {
+                    id: STREAM_ID,
+                    version_check: version::Check::must_be(3),
+                    events: EVENTS.clone(),
+                },
+                StreamWrite {
+                    id: RESERVATION_STREAM_ID,
+                    version_check: version::Check::empty(),
+                    events: vec![event::Envelope::from(StringMessage("reserved"))],
+                },
+            ])
+            .await
+            .expect_err("the reservation stream already exists");
+
+        assert!(matches!(
+            err,
+            AppendError::Conflict(version::ConflictError {
+                expected: 0,
+                actual: 1,
+            })
+        ));
+
+        // No Domain Event has been appended to the first Event Stream.
+        assert_eq!(Some(3), event_store.head_version(&STREAM_ID).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn event_streams_can_be_read_in_pages() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+        let tracking_event_store = event_store.clone().with_recorded_events_tracking();
+
+        event_store
+            .append(STREAM_ID, version::Check::must_be(0), EVENTS.clone())
+            .await
+            .expect("append should not fail");
+
+        for batch_size in [1, 2, 3, 4] {
+            let versions: Vec<_> = event_store
+                .read_from(&STREAM_ID, 2, batch_size)
+                .map_ok(|evt| evt.version)
+                .try_collect()
+                .await
+                .expect("reading an event stream should not fail");
+
+            assert_eq!(vec![2, 3], versions);
+
+            // The default implementation, used by the decorator.
+            let versions: Vec<_> = tracking_event_store
+                .read_from(&STREAM_ID, 1, batch_size)
+                .map_ok(|evt| evt.version)
+                .try_collect()
+                .await
+                .expect("reading an event stream should not fail");
+
+            assert_eq!(vec![1, 2, 3], versions);
+        }
+    }
+
+    #[tokio::test]
+    async fn event_streams_can_be_truncated_and_deleted() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+
+        event_store
+            .append(STREAM_ID, version::Check::must_be(0), EVENTS.clone())
+            .await
+            .expect("append should not fail");
+
+        assert_eq!(2, event_store.truncate_before(&STREAM_ID, 3).await.unwrap());
+
+        let versions: Vec<_> = event_store
+            .stream(&STREAM_ID, event::VersionSelect::All)
+            .map_ok(|evt| evt.version)
+            .try_collect()
+            .await
+            .expect("opening an event stream should not fail");
+
+        assert_eq!(vec![3], versions);
+        assert_eq!(Some(3), event_store.head_version(&STREAM_ID).await.unwrap());
+
+        assert_eq!(1, event_store.delete_stream(&STREAM_ID).await.unwrap());
+        assert_eq!(0, event_store.delete_stream(&STREAM_ID).await.unwrap());
+        assert_eq!(None, event_store.head_version(&STREAM_ID).await.unwrap());
+
+        event_store
+            .append(STREAM_ID, version::Check::empty(), EVENTS.clone())
+            .await
+            .expect("a deleted event stream should be created again");
+    }
+
+    #[tokio::test]
+    async fn truncations_keep_the_event_stream_version_without_its_domain_events() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+
+        event_store
+            .append(STREAM_ID, version::Check::must_be(0), EVENTS.clone())
+            .await
+            .expect("append should not fail");
+
+        assert_eq!(3, event_store.truncate_before(&STREAM_ID, 4).await.unwrap());
+        assert_eq!(0, event_store.truncate_before(&STREAM_ID, 4).await.unwrap());
+
+        let events: Vec<_> = event_store
+            .stream(&STREAM_ID, event::VersionSelect::All)
+            .try_collect()
+            .await
+            .expect("opening an event stream should not fail");
+
+        assert!(events.is_empty());
+        assert_eq!(Some(3), event_store.head_version(&STREAM_ID).await.unwrap());
+
+        let new_version = event_store
+            .append(STREAM_ID, version::Check::must_be(3), EVENTS.clone())
+            .await
+            .expect("the version check should use the truncated event stream version");
+
+        assert_eq!(6, new_version);
+    }
+
+    #[tokio::test]
+    async fn appends_exceeding_the_capacity_fail() {
+        let event_store = InMemory::<&'static str, StringMessage>::with_capacity(Capacity {
+            max_events_per_stream: Some(4),
+            max_total_events: Some(6),
+            eviction: Eviction::Error,
+        });
+
+        event_store
+            .append(STREAM_ID, version::Check::Any, EVENTS.clone())
+            .await
+            .expect("append should not fail");
+
+        let append_error = event_store
+            .append(STREAM_ID, version::Check::Any, EVENTS.clone())
+            .await
+            .expect_err("the event stream should be full");
+
+        assert!(append_error.to_string().contains("limit: 4 events"));
+
+        event_store
+            .append("stream:other", version::Check::Any, EVENTS.clone())
+            .await
+            .expect("append should not fail");
+
+        let append_error = event_store
+            .append("stream:another", version::Check::Any, EVENTS.clone())
+            .await
+            .expect_err("the event store should be full");
+
+        assert!(append_error.to_string().contains("limit: 6 events"));
+    }
+
+    #[tokio::test]
+    async fn least_recently_used_event_streams_are_evicted() {
+        let event_store = InMemory::<&'static str, StringMessage>::with_capacity(Capacity {
+            max_events_per_stream: None,
+            max_total_events: Some(6),
+            eviction: Eviction::LeastRecentlyUsed,
+        });
+
+        for id in [STREAM_ID, "stream:other"] {
+            event_store
+                .append(id, version::Check::Any, EVENTS.clone())
+                .await
+                .expect("append should not fail");
+        }
+
+        // Reading the first Event Stream makes the other one the least recently used.
+        let _ = event_store.stream(&STREAM_ID, event::VersionSelect::All);
+
+        event_store
+            .append("stream:another", version::Check::Any, EVENTS.clone())
+            .await
+            .expect("append should evict the least recently used event stream");
+
+        for (id, expected_len) in [(STREAM_ID, 3), ("stream:other", 0), ("stream:another", 3)] {
+            let events: Vec<_> = event_store
+                .stream(&id, event::VersionSelect::All)
+                .try_collect()
+                .await
+                .expect("opening an event stream should not fail");
+
+            assert_eq!(expected_len, events.len());
+        }
+    }
+
+    #[tokio::test]
+    async fn multi_stream_appends_never_evict_the_event_streams_being_appended_to() {
+        let event_store = InMemory::<&'static str, StringMessage>::with_capacity(Capacity {
+            max_events_per_stream: None,
+            max_total_events: Some(9),
+            eviction: Eviction::LeastRecentlyUsed,
+        });
+
+        for id in [STREAM_ID, "stream:other", "stream:another"] {
+            event_store
+                .append(id, version::Check::Any, EVENTS.clone())
+                .await
+                .expect("append should not fail");
+        }
+
+        let write = |id| StreamWrite {
+            id,
+            version_check: version::Check::must_be(3),
+            events: EVENTS.clone(),
+        };
+
+        // Evicting the only Event Stream outside of the batch is not enough.
+        event_store
+            .append_all(vec![write(STREAM_ID), write("stream:other")])
+            .await
+            .expect_err("the batch should not fit in the event store");
+
+        for id in [STREAM_ID, "stream:other", "stream:another"] {
+            assert_eq!(Some(3), event_store.head_version(&id).await.unwrap());
+        }
+
+        let new_versions = event_store
+            .append_all(vec![write(STREAM_ID)])
+            .await
+            .expect("the least recently used event stream should be evicted");
+
+        assert_eq!(vec![6], new_versions);
+
+        let versions: Vec<_> = event_store
+            .stream(&STREAM_ID, event::VersionSelect::All)
+            .map_ok(|event| event.version)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(vec![1, 2, 3, 4, 5, 6], versions);
+        assert_eq!(
+            None,
+            event_store.head_version(&"stream:other").await.unwrap()
+        );
+        assert_eq!(
+            Some(3),
+            event_store.head_version(&"stream:another").await.unwrap()
+        );
+    }
+
+    #[cfg(feature = "serde-json")]
+    #[tokio::test]
+    async fn file_persisted_event_store_recovers_domain_events_on_open() {
+        #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Counted(u32);
+
+        impl message::Message for Counted {
+            fn name(&self) -> &'static str {
+                "counted"
+            }
+        }
+
+        let path = std::env::temp_dir().join(format!(
+            "eventually-in-memory-{}.log",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .expect("system time should be after the unix epoch")
+                .as_nanos()
+        ));
+
+        let open = || {
+            InMemory::<String, Counted>::open(&path, crate::serde::Json::default())
+                .expect("the event store should be opened")
+        };
+
+        open()
+            .append(
+                "stream:test".to_owned(),
+                version::Check::must_be(0),
+                vec![Counted(1).into(), Counted(2).into()],
+            )
+            .await
+            .expect("append should not fail");
+
+        // Simulate a crash in the middle of writing a record.
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(&[42, 0, 0, 0, b'{']))
+            .expect("the log file should be writable");
+
+        let event_store = open();
+
+        event_store
+            .append(
+                "stream:test".to_owned(),
+                version::Check::must_be(2),
+                vec![Counted(3).into()],
+            )
+            .await
+            .expect("append should not fail after recovery");
+
+        let events: Vec<_> = open()
+            .stream(&"stream:test".to_owned(), event::VersionSelect::All)
+            .map_ok(|persisted| persisted.event.message)
+            .try_collect()
+            .await
+            .expect("opening an event stream should not fail");
+
+        std::fs::remove_file(&path).expect("the log file should be removed");
+
+        assert_eq!(vec![Counted(1), Counted(2), Counted(3)], events);
+    }
+
+    #[cfg(feature = "serde-json")]
+    #[tokio::test]
+    async fn file_persisted_event_store_recovers_truncations_on_open() {
+        #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Counted(u32);
+
+        impl message::Message for Counted {
+            fn name(&self) -> &'static str {
+                "counted"
+            }
+        }
+
+        let path = std::env::temp_dir().join(format!(
+            "eventually-in-memory-truncations-{}.log",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .expect("system time should be after the unix epoch")
+                .as_nanos()
+        ));
+
+        let open = || {
+            InMemory::<String, Counted>::open(&path, crate::serde::Json::default())
+                .expect("the event store should be opened")
+        };
+
+        let truncated_id = "stream:truncated".to_owned();
+        let deleted_id = "stream:deleted".to_owned();
+
+        let event_store = open();
+
+        for id in [&truncated_id, &deleted_id] {
+            event_store
+                .append(
+                    id.clone(),
+                    version::Check::must_be(0),
+                    vec![Counted(1).into(), Counted(2).into(), Counted(3).into()],
+                )
+                .await
+                .expect("append should not fail");
+        }
+
+        assert_eq!(
+            2,
+            event_store.truncate_before(&truncated_id, 3).await.unwrap()
+        );
+        assert_eq!(3, event_store.delete_stream(&deleted_id).await.unwrap());
+
+        event_store
+            .append(
+                deleted_id.clone(),
+                version::Check::must_be(0),
+                vec![Counted(4).into()],
+            )
+            .await
+            .expect("a deleted event stream should be created again");
+
+        assert_eq!(
+            1,
+            event_store.truncate_before(&truncated_id, 4).await.unwrap()
+        );
+
+        let event_store = open();
+
+        let stream = |id: &String| {
+            event_store
+                .stream(id, event::VersionSelect::All)
+                .map_ok(|persisted| (persisted.version, persisted.event.message))
+                .try_collect::<Vec<_>>()
+        };
+
+        let truncated_events = stream(&truncated_id).await.unwrap();
+        let deleted_events = stream(&deleted_id).await.unwrap();
+        let truncated_head_version = event_store.head_version(&truncated_id).await.unwrap();
+
+        std::fs::remove_file(&path).expect("the log file should be removed");
+
+        assert!(truncated_events.is_empty());
+        assert_eq!(Some(3), truncated_head_version);
+        assert_eq!(vec![(1, Counted(4))], deleted_events);
+    }
+
+    /// Counts the calls to [`Streamer::read_from`] of the wrapped `InMemory` Event Store,
+    /// to check that decorators forward paged reads instead of using the default implementation.
+    #[derive(Clone, Default)]
+    struct CountingPagedReads {
+        store: InMemory<&'static str, StringMessage>,
+        paged_reads: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Streamer<&'static str, StringMessage> for CountingPagedReads {
+        type Error = Infallible;
+
+        fn stream(
+            &self,
+            id: &&'static str,
+            select: event::VersionSelect,
+        ) -> event::Stream<'_, &'static str, StringMessage, Self::Error> {
+            self.store.stream(id, select)
+        }
+
+        fn read_from<'a>(
+            &'a self,
+            id: &&'static str,
+            from_version: Version,
+            batch_size: usize,
+        ) -> event::Stream<'a, &'static str, StringMessage, Self::Error>
+        where
+            Self::Error: 'a,
+        {
+            self.paged_reads.fetch_add(1, Ordering::SeqCst);
+
+            self.store.read_from(id, from_version, batch_size)
+        }
+    }
+
+    #[async_trait]
+    impl Appender<&'static str, StringMessage> for CountingPagedReads {
+        async fn append(
+            &self,
+            id: &'static str,
+            version_check: version::Check,
+            events: Vec<event::Envelope<StringMessage>>,
+        ) -> Result<Version, AppendError> {
+            self.store.append(id, version_check, events).await
+        }
+    }
+
+    #[tokio::test]
+    async fn decorators_forward_paged_reads_to_the_decorated_event_store() {
+        async fn read_versions(
+            event_store: &impl Streamer<&'static str, StringMessage>,
+        ) -> Vec<Version> {
+            event_store
+                .read_from(&STREAM_ID, 2, 1)
+                .map_ok(|evt| evt.version)
+                .try_collect()
+                .await
+                .unwrap_or_default()
+        }
+
+        let event_store = CountingPagedReads::default();
+
+        event_store
+            .append(STREAM_ID, version::Check::must_be(0), EVENTS.clone())
+            .await
+            .expect("append should not fail");
+
+        let decorated_versions = vec![
+            read_versions(&event_store.clone().with_recorded_events_tracking()).await,
+            read_versions(&event::replica::ReadOnly::new(event_store.clone())).await,
+            read_versions(&event::replica::ReplicaRouted::new(
+                event_store.clone(),
+                event_store.clone(),
+            ))
+            .await,
+            read_versions(&event::validation::Validated::new(event_store.clone())).await,
+            read_versions(&event::deduplication::Deduplicated::new(
+                event_store.clone(),
+                Duration::from_secs(1),
+            ))
+            .await,
+            read_versions(&event::proxy::CachingProxy::new(event_store.clone(), 1)).await,
+            read_versions(&event::federation::Federated::new(event_store.clone())).await,
+        ];
+
+        for versions in &decorated_versions {
+            assert_eq!(&vec![2, 3], versions);
+        }
+
+        assert_eq!(
+            decorated_versions.len(),
+            event_store.paged_reads.load(Ordering::SeqCst)
+        );
+    }
 }
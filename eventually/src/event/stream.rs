@@ -0,0 +1,351 @@
+//! Contains the [`EventStreamExt`] extension trait, which provides domain-aware
+//! combinators for [Event Stream][event::Stream]s, so that consumers can compose
+//! processing pipelines instead of hand-rolling loops over the Domain Events.
+
+use futures::future;
+use futures::stream::{BoxStream, StreamExt, TryChunksError, TryStream, TryStreamExt};
+
+use crate::{event, message, version};
+
+/// Error returned by the [`EventStreamExt::decode`] combinator.
+#[derive(Debug, thiserror::Error)]
+pub enum DecodeError<S, D> {
+    /// Error returned by the underlying [Event Stream][event::Stream].
+    #[error("failed to stream domain event: {0}")]
+    Stream(#[source] S),
+    /// Error returned when the Domain Event could not be converted into the target type.
+    #[error("failed to decode domain event: {0}")]
+    Decode(#[source] D),
+}
+
+/// Extension trait for any [`TryStream`] of [Persisted][event::Persisted] Domain Events,
+/// such as the [Event Stream][event::Stream]s returned by an [Event Store][event::Store].
+pub trait EventStreamExt<Id, Evt, Err>:
+    TryStream<Ok = event::Persisted<Id, Evt>, Error = Err> + Send + Sized
+where
+    Evt: message::Message,
+{
+    /// Converts each Domain Event in the stream into a different type,
+    /// using its [`TryFrom`] implementation.
+    ///
+    /// Useful to narrow down a stream of Domain Events to the subset of Domain Events
+    /// a consumer is interested in.
+    fn decode<'a, E>(self) -> event::Stream<'a, Id, E, DecodeError<Err, E::Error>>
+    where
+        Self: 'a,
+        Id: Send + 'a,
+        Evt: Send + 'a,
+        Err: Send + 'a,
+        E: message::Message + TryFrom<Evt> + Send + 'a,
+        E::Error: Send + 'a,
+    {
+        self.map_err(DecodeError::Stream)
+            .and_then(|persisted| {
+                future::ready(
+                    E::try_from(persisted.event.message)
+                        .map(|message| event::Persisted {
+                            stream_id: persisted.stream_id,
+                            version: persisted.version,
+                            event: event::Envelope {
+                                message,
+                                metadata: persisted.event.metadata,
+                            },
+                        })
+                        .map_err(DecodeError::Decode),
+                )
+            })
+            .boxed()
+    }
+
+    /// Keeps only the Domain Events belonging to the specified category of Event Streams,
+    /// i.e. whose Event Stream id starts with `category`.
+    ///
+    /// The category is matched as a prefix, like the category subscriptions of the
+    /// Event Store implementations: include the separator used by the
+    /// [`StreamNameStrategy`][crate::aggregate::stream_name::StreamNameStrategy],
+    /// e.g. `"Order-"`, to avoid matching other categories sharing the same prefix.
+    fn by_category<'a>(self, category: impl Into<String>) -> event::Stream<'a, Id, Evt, Err>
+    where
+        Self: 'a,
+        Id: AsRef<str> + Send + 'a,
+        Evt: Send + 'a,
+        Err: Send + 'a,
+    {
+        let category = category.into();
+
+        self.try_filter(move |persisted| {
+            future::ready(persisted.stream_id.as_ref().starts_with(&category))
+        })
+        .into_stream()
+        .boxed()
+    }
+
+    /// Ends the stream once the Domain Event at the specified version of the
+    /// Event Stream has been returned, e.g. the head version returned by
+    /// [`Streamer::head_version`][event::store::Streamer::head_version].
+    ///
+    /// Useful to catch up with a live subscription to a single Event Stream,
+    /// which would otherwise never end. The stream ends immediately if
+    /// `head_version` is `0`, i.e. the Event Stream is empty.
+    fn until_caught_up<'a>(self, head_version: version::Version) -> event::Stream<'a, Id, Evt, Err>
+    where
+        Self: 'a,
+        Id: Send + 'a,
+        Evt: Send + 'a,
+        Err: Send + 'a,
+    {
+        // The underlying stream must not be polled once caught up,
+        // as a live subscription would wait for the next Domain Event.
+        futures::stream::unfold(
+            (self.into_stream().boxed(), head_version == 0),
+            move |(mut stream, caught_up)| async move {
+                if caught_up {
+                    return None;
+                }
+
+                let result = stream.next().await?;
+                let caught_up =
+                    matches!(&result, Ok(persisted) if persisted.version >= head_version);
+
+                Some((result, (stream, caught_up)))
+            },
+        )
+        .boxed()
+    }
+
+    /// Groups the Domain Events in the stream in batches of at most `size` elements.
+    ///
+    /// The last batch may contain less than `size` elements.
+    /// If the underlying stream fails, the error is returned and the Domain Events
+    /// collected in the current batch so far are discarded.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if `size` is zero.
+    fn batched<'a>(self, size: usize) -> BoxStream<'a, Result<Vec<event::Persisted<Id, Evt>>, Err>>
+    where
+        Self: 'a,
+        Id: Send + 'a,
+        Evt: Send + 'a,
+        Err: Send + 'a,
+    {
+        self.try_chunks(size)
+            .map_err(|TryChunksError(_, err)| err)
+            .boxed()
+    }
+
+    /// Calls the provided `checkpoint` function every `every` Domain Events
+    /// flowing through the stream, with the last of those Domain Events.
+    ///
+    /// Useful to periodically persist the position reached by a consumer.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if `every` is zero.
+    fn checkpoint_every<'a, F>(
+        self,
+        every: usize,
+        mut checkpoint: F,
+    ) -> event::Stream<'a, Id, Evt, Err>
+    where
+        Self: 'a,
+        Id: Send + 'a,
+        Evt: Send + 'a,
+        Err: Send + 'a,
+        F: FnMut(&event::Persisted<Id, Evt>) + Send + 'a,
+    {
+        assert!(every > 0, "checkpoint interval must be greater than zero");
+
+        let mut count = 0;
+
+        self.inspect_ok(move |persisted| {
+            count += 1;
+
+            if count % every == 0 {
+                checkpoint(persisted);
+            }
+        })
+        .into_stream()
+        .boxed()
+    }
+}
+
+impl<S, Id, Evt, Err> EventStreamExt<Id, Evt, Err> for S
+where
+    S: TryStream<Ok = event::Persisted<Id, Evt>, Error = Err> + Send,
+    Evt: message::Message,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::event::store::{Appender, InMemory, Streamer};
+    use crate::message::tests::StringMessage;
+    use crate::version;
+
+    const STREAM_ID: &str = "stream:test";
+
+    #[derive(Debug, PartialEq)]
+    struct EvenMessage(&'static str);
+
+    impl message::Message for EvenMessage {
+        fn name(&self) -> &'static str {
+            "even_message"
+        }
+    }
+
+    impl TryFrom<StringMessage> for EvenMessage {
+        type Error = &'static str;
+
+        fn try_from(value: StringMessage) -> Result<Self, Self::Error> {
+            match value.0 {
+                "event-2" | "event-4" => Ok(EvenMessage(value.0)),
+                _ => Err("odd message"),
+            }
+        }
+    }
+
+    async fn event_store() -> InMemory<&'static str, StringMessage> {
+        let event_store = InMemory::default();
+
+        event_store
+            .append(
+                STREAM_ID,
+                version::Check::must_be(0),
+                ["event-1", "event-2", "event-3", "event-4", "event-5"]
+                    .into_iter()
+                    .map(|msg| event::Envelope::from(StringMessage(msg)))
+                    .collect(),
+            )
+            .await
+            .expect("append should not fail");
+
+        event_store
+    }
+
+    #[tokio::test]
+    async fn batched_groups_domain_events() {
+        let event_store = event_store().await;
+
+        let batches: Vec<Vec<version::Version>> = event_store
+            .stream(&STREAM_ID, event::VersionSelect::All)
+            .batched(2)
+            .map_ok(|batch| batch.into_iter().map(|evt| evt.version).collect())
+            .try_collect()
+            .await
+            .expect("streaming should not fail");
+
+        assert_eq!(vec![vec![1, 2], vec![3, 4], vec![5]], batches);
+    }
+
+    #[tokio::test]
+    async fn checkpoint_every_is_called_periodically() {
+        let event_store = event_store().await;
+        let checkpoints = Arc::new(Mutex::new(Vec::new()));
+        let recorded_checkpoints = checkpoints.clone();
+
+        let count = event_store
+            .stream(&STREAM_ID, event::VersionSelect::All)
+            .checkpoint_every(2, move |evt| {
+                recorded_checkpoints.lock().unwrap().push(evt.version);
+            })
+            .try_fold(0, |count, _| future::ready(Ok(count + 1)))
+            .await
+            .expect("streaming should not fail");
+
+        assert_eq!(5, count);
+        assert_eq!(vec![2, 4], *checkpoints.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn by_category_keeps_the_domain_events_of_the_category() {
+        let event_store = event_store().await;
+
+        for id in ["order-1", "orders-1", "order-2"] {
+            event_store
+                .append(
+                    id,
+                    version::Check::must_be(0),
+                    vec![event::Envelope::from(StringMessage("event"))],
+                )
+                .await
+                .expect("append should not fail");
+        }
+
+        let stream_ids: Vec<_> =
+            futures::stream::iter(["order-1", STREAM_ID, "orders-1", "order-2"])
+                .flat_map(|id| event_store.stream(&id, event::VersionSelect::All))
+                .by_category("order-")
+                .map_ok(|evt| evt.stream_id)
+                .try_collect()
+                .await
+                .expect("streaming should not fail");
+
+        assert_eq!(vec!["order-1", "order-2"], stream_ids);
+    }
+
+    #[tokio::test]
+    async fn until_caught_up_ends_live_streams_at_the_head_version() {
+        let event_store = event_store().await;
+
+        let head_version = event_store
+            .head_version(&STREAM_ID)
+            .await
+            .expect("head version should be returned")
+            .unwrap_or_default();
+
+        // Simulates a live subscription, which never ends after the last Domain Event.
+        let subscription = || {
+            event_store
+                .stream(&STREAM_ID, event::VersionSelect::All)
+                .chain(futures::stream::pending())
+        };
+
+        let versions: Vec<_> = subscription()
+            .until_caught_up(head_version)
+            .map_ok(|evt| evt.version)
+            .try_collect()
+            .await
+            .expect("streaming should not fail");
+
+        assert_eq!(vec![1, 2, 3, 4, 5], versions);
+
+        let versions: Vec<_> = subscription()
+            .until_caught_up(0)
+            .try_collect()
+            .await
+            .expect("streaming should not fail");
+
+        assert!(versions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn decode_converts_domain_events() {
+        let event_store = event_store().await;
+
+        let decoded: Vec<_> = event_store
+            .stream(&STREAM_ID, event::VersionSelect::From(2))
+            .decode::<EvenMessage>()
+            .take(2)
+            .collect()
+            .await;
+
+        assert!(matches!(
+            decoded.as_slice(),
+            [
+                Ok(event::Persisted {
+                    version: 2,
+                    event: event::Envelope {
+                        message: EvenMessage("event-2"),
+                        ..
+                    },
+                    ..
+                }),
+                Err(DecodeError::Decode("odd message"))
+            ]
+        ));
+    }
+}
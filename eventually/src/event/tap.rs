@@ -0,0 +1,245 @@
+//! Contains the [Tapped] [`event::Store`] decorator, which mirrors a sample
+//! of the newly-appended Domain Events to a debugging [Sink], e.g. a log, a channel or a file.
+//!
+//! Unlike a consumer, a tap has no checkpoint and cannot fail the append:
+//! it is meant to troubleshoot live systems without affecting them.
+
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+
+use async_trait::async_trait;
+
+use crate::event::store::{AppendError, Appender, Store, Streamer};
+use crate::{event, message, version};
+
+/// A destination for the Domain Events mirrored by a [Tapped] Event Store.
+///
+/// Sinks are called inline with the append operation, so they should be fast
+/// and must never block.
+pub trait Sink<StreamId, Event>: Send + Sync
+where
+    Event: message::Message,
+{
+    /// Receives a copy of a newly-appended Domain Event.
+    fn send(&self, event: event::Persisted<StreamId, Event>);
+}
+
+impl<StreamId, Event, F> Sink<StreamId, Event> for F
+where
+    Event: message::Message,
+    F: Send + Sync + Fn(event::Persisted<StreamId, Event>),
+{
+    fn send(&self, event: event::Persisted<StreamId, Event>) {
+        self(event);
+    }
+}
+
+impl<StreamId, Event> Sink<StreamId, Event> for mpsc::Sender<event::Persisted<StreamId, Event>>
+where
+    StreamId: Send,
+    Event: message::Message + Send,
+{
+    fn send(&self, event: event::Persisted<StreamId, Event>) {
+        // A disconnected receiver only means nobody is listening anymore.
+        let _ = mpsc::Sender::send(self, event);
+    }
+}
+
+type Filter<StreamId, Event> =
+    Arc<dyn Fn(&event::Persisted<StreamId, Event>) -> bool + Send + Sync>;
+
+/// Decorator type for an [`event::Store`] implementation that mirrors
+/// the newly-appended Domain Events to a [Sink].
+///
+/// By default all Domain Events are mirrored: use [`Tapped::sample_every`]
+/// and [`Tapped::filter`] to reduce the number of mirrored Domain Events.
+#[derive(Clone)]
+pub struct Tapped<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync,
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    store: T,
+    sink: Arc<dyn Sink<StreamId, Event>>,
+    sample_every: usize,
+    filter: Option<Filter<StreamId, Event>>,
+    seen: Arc<AtomicUsize>,
+}
+
+impl<T, StreamId, Event> Debug for Tapped<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync + Debug,
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tapped")
+            .field("store", &self.store)
+            .field("sample_every", &self.sample_every)
+            .field("filtered", &self.filter.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, StreamId, Event> Tapped<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync,
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    /// Creates a new [Tapped] decorator over the specified [`event::Store`],
+    /// mirroring all the newly-appended Domain Events to the [Sink].
+    pub fn new<S>(store: T, sink: S) -> Self
+    where
+        S: Sink<StreamId, Event> + 'static,
+    {
+        Self {
+            store,
+            sink: Arc::new(sink),
+            sample_every: 1,
+            filter: None,
+            seen: Arc::default(),
+        }
+    }
+
+    /// Mirrors only one Domain Event every `n`, among the ones accepted by the
+    /// [filter][Tapped::filter], if any.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if `n` is zero.
+    #[must_use]
+    pub fn sample_every(mut self, n: usize) -> Self {
+        assert!(n > 0, "the sampling rate must be greater than zero");
+        self.sample_every = n;
+        self
+    }
+
+    /// Mirrors only the Domain Events for which the provided function returns `true`.
+    #[must_use]
+    pub fn filter<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&event::Persisted<StreamId, Event>) -> bool + Send + Sync + 'static,
+    {
+        self.filter = Some(Arc::new(f));
+        self
+    }
+
+    fn mirror(&self, event: event::Persisted<StreamId, Event>) {
+        if self.filter.as_ref().is_some_and(|filter| !filter(&event)) {
+            return;
+        }
+
+        if self.seen.fetch_add(1, Ordering::Relaxed) % self.sample_every == 0 {
+            self.sink.send(event);
+        }
+    }
+}
+
+#[async_trait]
+impl<T, StreamId, Event> Streamer<StreamId, Event> for Tapped<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync,
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    type Error = <T as Streamer<StreamId, Event>>::Error;
+
+    fn stream(
+        &self,
+        id: &StreamId,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, StreamId, Event, Self::Error> {
+        self.store.stream(id, select)
+    }
+
+    fn read_from<'a>(
+        &'a self,
+        id: &StreamId,
+        from_version: version::Version,
+        batch_size: usize,
+    ) -> event::Stream<'a, StreamId, Event, Self::Error>
+    where
+        StreamId: Clone + 'a,
+        Event: 'a,
+        Self::Error: 'a,
+    {
+        self.store.read_from(id, from_version, batch_size)
+    }
+
+    async fn head_version(&self, id: &StreamId) -> Result<Option<version::Version>, Self::Error> {
+        self.store.head_version(id).await
+    }
+}
+
+#[async_trait]
+impl<T, StreamId, Event> Appender<StreamId, Event> for Tapped<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync,
+    StreamId: Clone + Send + Sync,
+    Event: message::Message + Clone + Send + Sync,
+{
+    async fn append(
+        &self,
+        id: StreamId,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Event>>,
+    ) -> Result<version::Version, AppendError> {
+        let new_version = self
+            .store
+            .append(id.clone(), version_check, events.clone())
+            .await?;
+
+        let previous_version = new_version - events.len() as version::Version;
+
+        for (i, event) in events.into_iter().enumerate() {
+            self.mirror(event::Persisted {
+                stream_id: id.clone(),
+                version: previous_version + i as version::Version + 1,
+                event,
+            });
+        }
+
+        Ok(new_version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::store::InMemory;
+    use crate::message::tests::StringMessage;
+
+    #[tokio::test]
+    async fn tapped_event_store_mirrors_a_sample_of_the_appended_events() {
+        let (tx, rx) = mpsc::channel();
+        let event_store = Tapped::new(InMemory::<&'static str, StringMessage>::default(), tx)
+            .filter(|evt| evt.stream_id != "stream:ignored")
+            .sample_every(2);
+
+        for id in ["stream:test", "stream:ignored"] {
+            event_store
+                .append(
+                    id,
+                    version::Check::Any,
+                    ["event-1", "event-2", "event-3"]
+                        .into_iter()
+                        .map(|msg| event::Envelope::from(StringMessage(msg)))
+                        .collect(),
+                )
+                .await
+                .expect("append should not fail");
+        }
+
+        drop(event_store);
+
+        let mirrored: Vec<_> = rx
+            .into_iter()
+            .map(|evt| (evt.stream_id, evt.version))
+            .collect();
+
+        assert_eq!(vec![("stream:test", 1), ("stream:test", 3)], mirrored);
+    }
+}
@@ -0,0 +1,476 @@
+//! Contains the [Throttled] [`event::Store`] decorator, which limits the number
+//! of concurrent, in-flight appends to the same Event Stream, queueing the excess
+//! up to a bounded depth.
+//!
+//! This prevents a single, pathological Event Stream (e.g. a very hot Aggregate)
+//! from starving the resources of the underlying Event Store, such as its connection pool.
+//!
+//! It also contains the [`CategoryLimited`] [Consumer] decorator, which limits the number
+//! of Domain Events consumed concurrently per category, so that expensive categories
+//! (e.g. search indexing) can be throttled independently of cheap ones.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use tokio::sync::Semaphore;
+
+use crate::event::consumer::Consumer;
+use crate::event::store::{AppendError, Appender, Store, Streamer};
+use crate::{event, message, version};
+
+/// Error returned by the [Throttled] Event Store when an append cannot be queued,
+/// as the maximum number of appends to the same Event Stream are already waiting.
+///
+/// The error is returned as an [`AppendError::Internal`], and can be recovered
+/// by downcasting the inner [`anyhow::Error`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error(
+    "too many appends to the same event stream, in flight: {max_in_flight}, queued: {max_queued}"
+)]
+pub struct ThrottledError {
+    /// The maximum number of in-flight appends per Event Stream.
+    pub max_in_flight: usize,
+    /// The maximum number of queued appends per Event Stream.
+    pub max_queued: usize,
+}
+
+#[derive(Debug)]
+struct Slot {
+    in_flight: Semaphore,
+    queued: AtomicUsize,
+}
+
+/// Decrements the queued appends counter of a [Slot], even if the append is cancelled.
+struct QueuedGuard<'a>(&'a AtomicUsize);
+
+impl Drop for QueuedGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Decorator type for an [`event::Store`] implementation that allows at most
+/// `max_in_flight` concurrent appends to the same Event Stream, and queues
+/// at most `max_queued` more appends, in FIFO order.
+///
+/// Appends exceeding the queue depth fail immediately with a [`ThrottledError`].
+/// Appends to different Event Streams never wait on each other.
+#[derive(Debug, Clone)]
+pub struct Throttled<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync,
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    store: T,
+    max_in_flight: usize,
+    max_queued: usize,
+    slots: Arc<Mutex<HashMap<StreamId, Arc<Slot>>>>,
+    event: std::marker::PhantomData<Event>,
+}
+
+impl<T, StreamId, Event> Throttled<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync,
+    StreamId: Clone + Eq + Hash + Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    /// Creates a new [Throttled] decorator over the specified [`event::Store`].
+    ///
+    /// # Panics
+    ///
+    /// The method panics if `max_in_flight` is zero.
+    pub fn new(store: T, max_in_flight: usize, max_queued: usize) -> Self {
+        assert!(
+            max_in_flight > 0,
+            "at least one in-flight append must be allowed"
+        );
+
+        Self {
+            store,
+            max_in_flight,
+            max_queued,
+            slots: Arc::default(),
+            event: std::marker::PhantomData,
+        }
+    }
+
+    fn slot(&self, id: StreamId) -> SlotGuard<'_, StreamId> {
+        let slot = self
+            .slots
+            .lock()
+            .expect("acquire lock on event stream slots")
+            .entry(id.clone())
+            .or_insert_with(|| {
+                Arc::new(Slot {
+                    in_flight: Semaphore::new(self.max_in_flight),
+                    queued: AtomicUsize::new(0),
+                })
+            })
+            .clone();
+
+        SlotGuard {
+            slots: &self.slots,
+            id,
+            slot,
+        }
+    }
+}
+
+/// Holds the [Slot] of an Event Stream during an append, and removes it
+/// from the [Throttled] Event Store once no other append is using it.
+struct SlotGuard<'a, StreamId>
+where
+    StreamId: Eq + Hash,
+{
+    slots: &'a Mutex<HashMap<StreamId, Arc<Slot>>>,
+    id: StreamId,
+    slot: Arc<Slot>,
+}
+
+impl<StreamId> Drop for SlotGuard<'_, StreamId>
+where
+    StreamId: Eq + Hash,
+{
+    fn drop(&mut self) {
+        let mut slots = self
+            .slots
+            .lock()
+            .expect("acquire lock on event stream slots");
+
+        // Only the map and this guard hold the slot: nobody else is using it.
+        if Arc::strong_count(&self.slot) == 2 {
+            slots.remove(&self.id);
+        }
+    }
+}
+
+#[async_trait]
+impl<T, StreamId, Event> Streamer<StreamId, Event> for Throttled<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync,
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    type Error = <T as Streamer<StreamId, Event>>::Error;
+
+    fn stream(
+        &self,
+        id: &StreamId,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, StreamId, Event, Self::Error> {
+        self.store.stream(id, select)
+    }
+
+    fn read_from<'a>(
+        &'a self,
+        id: &StreamId,
+        from_version: version::Version,
+        batch_size: usize,
+    ) -> event::Stream<'a, StreamId, Event, Self::Error>
+    where
+        StreamId: Clone + 'a,
+        Event: 'a,
+        Self::Error: 'a,
+    {
+        self.store.read_from(id, from_version, batch_size)
+    }
+
+    async fn head_version(&self, id: &StreamId) -> Result<Option<version::Version>, Self::Error> {
+        self.store.head_version(id).await
+    }
+}
+
+#[async_trait]
+impl<T, StreamId, Event> Appender<StreamId, Event> for Throttled<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync,
+    StreamId: Clone + Eq + Hash + Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    async fn append(
+        &self,
+        id: StreamId,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Event>>,
+    ) -> Result<version::Version, AppendError> {
+        let guard = self.slot(id.clone());
+        let slot = &guard.slot;
+
+        let _permit = if let Ok(permit) = slot.in_flight.try_acquire() {
+            permit
+        } else {
+            if slot.queued.fetch_add(1, Ordering::SeqCst) >= self.max_queued {
+                slot.queued.fetch_sub(1, Ordering::SeqCst);
+
+                return Err(anyhow::Error::from(ThrottledError {
+                    max_in_flight: self.max_in_flight,
+                    max_queued: self.max_queued,
+                })
+                .into());
+            }
+
+            let _queued = QueuedGuard(&slot.queued);
+
+            slot.in_flight
+                .acquire()
+                .await
+                .expect("event stream semaphore is never closed")
+        };
+
+        self.store.append(id, version_check, events).await
+    }
+}
+
+/// Decorator type for a [Consumer] that allows at most a configured number of
+/// Domain Events of the same category to be consumed concurrently.
+///
+/// The category of each Domain Event is returned by the `categorize` function,
+/// e.g. the category of its Event Stream: categories without a configured limit
+/// are not throttled.
+///
+/// Use it together with [`Reliable::with_concurrency`][crate::event::consumer::Reliable::with_concurrency],
+/// which limits the concurrency of the whole [Consumer].
+#[derive(Debug, Clone)]
+pub struct CategoryLimited<C, F> {
+    consumer: C,
+    categorize: F,
+    limits: HashMap<String, Arc<Semaphore>>,
+}
+
+impl<C, F> CategoryLimited<C, F> {
+    /// Creates a new [`CategoryLimited`] decorator over the specified [Consumer],
+    /// using the `categorize` function to return the category of each Domain Event.
+    pub fn new(consumer: C, categorize: F) -> Self {
+        Self {
+            consumer,
+            categorize,
+            limits: HashMap::default(),
+        }
+    }
+
+    /// Allows at most `max_in_flight` Domain Events of the specified category
+    /// to be consumed concurrently.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if `max_in_flight` is zero.
+    #[must_use]
+    pub fn limit(mut self, category: impl Into<String>, max_in_flight: usize) -> Self {
+        assert!(
+            max_in_flight > 0,
+            "at least one in-flight domain event must be allowed"
+        );
+
+        self.limits
+            .insert(category.into(), Arc::new(Semaphore::new(max_in_flight)));
+
+        self
+    }
+}
+
+#[async_trait]
+impl<C, F, StreamId, Event> Consumer<StreamId, Event> for CategoryLimited<C, F>
+where
+    C: Consumer<StreamId, Event>,
+    F: Fn(&event::Persisted<StreamId, Event>) -> String + Send + Sync,
+    StreamId: Send + 'static,
+    Event: message::Message + Send + 'static,
+{
+    type Error = C::Error;
+
+    async fn consume(&self, event: event::Persisted<StreamId, Event>) -> Result<(), Self::Error> {
+        let _permit = match self.limits.get(&(self.categorize)(&event)) {
+            Some(semaphore) => Some(
+                semaphore
+                    .acquire()
+                    .await
+                    .expect("category semaphore is never closed"),
+            ),
+            None => None,
+        };
+
+        self.consumer.consume(event).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::store::InMemory;
+    use crate::message::tests::StringMessage;
+
+    const SLOW_STREAM_ID: &str = "stream:slow";
+
+    /// Event Store that blocks appends to [`SLOW_STREAM_ID`] until the gate is opened.
+    #[derive(Clone)]
+    struct Gated {
+        store: InMemory<&'static str, StringMessage>,
+        gate: Arc<Semaphore>,
+    }
+
+    #[async_trait]
+    impl Streamer<&'static str, StringMessage> for Gated {
+        type Error = std::convert::Infallible;
+
+        fn stream(
+            &self,
+            id: &&'static str,
+            select: event::VersionSelect,
+        ) -> event::Stream<'_, &'static str, StringMessage, Self::Error> {
+            self.store.stream(id, select)
+        }
+    }
+
+    #[async_trait]
+    impl Appender<&'static str, StringMessage> for Gated {
+        async fn append(
+            &self,
+            id: &'static str,
+            version_check: version::Check,
+            events: Vec<event::Envelope<StringMessage>>,
+        ) -> Result<version::Version, AppendError> {
+            if id == SLOW_STREAM_ID {
+                let _permit = self.gate.acquire().await.expect("gate is never closed");
+            }
+
+            self.store.append(id, version_check, events).await
+        }
+    }
+
+    fn events() -> Vec<event::Envelope<StringMessage>> {
+        vec![event::Envelope::from(StringMessage("event"))]
+    }
+
+    #[tokio::test]
+    async fn appends_exceeding_the_queue_depth_are_rejected() {
+        let gate = Arc::new(Semaphore::new(0));
+        let event_store = Arc::new(Throttled::new(
+            Gated {
+                store: InMemory::default(),
+                gate: gate.clone(),
+            },
+            1,
+            0,
+        ));
+
+        let in_flight = tokio::spawn({
+            let event_store = event_store.clone();
+            async move {
+                event_store
+                    .append(SLOW_STREAM_ID, version::Check::Any, events())
+                    .await
+            }
+        });
+
+        tokio::task::yield_now().await;
+
+        let err = event_store
+            .append(SLOW_STREAM_ID, version::Check::Any, events())
+            .await
+            .expect_err("the append should be throttled");
+
+        let AppendError::Internal(err) = err else {
+            panic!("unexpected append error: {err}");
+        };
+
+        assert_eq!(
+            Some(&ThrottledError {
+                max_in_flight: 1,
+                max_queued: 0
+            }),
+            err.downcast_ref()
+        );
+
+        event_store
+            .append("stream:other", version::Check::Any, events())
+            .await
+            .expect("appends to other event streams should not be throttled");
+
+        gate.add_permits(1);
+
+        let version = in_flight
+            .await
+            .expect("the task should not panic")
+            .expect("the in-flight append should not fail");
+
+        assert_eq!(1, version);
+        assert!(event_store.slots.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn category_limited_consumer_throttles_only_the_limited_categories() {
+        use futures::TryStreamExt;
+
+        use crate::event::consumer::Reliable;
+
+        #[derive(Default)]
+        struct InFlight {
+            current: AtomicUsize,
+            max: AtomicUsize,
+        }
+
+        let search = Arc::new(InFlight::default());
+        let other = Arc::new(InFlight::default());
+
+        let consumer = {
+            let (search, other) = (search.clone(), other.clone());
+
+            move |event: event::Persisted<&'static str, StringMessage>| {
+                let in_flight = if event.stream_id.starts_with("search:") {
+                    search.clone()
+                } else {
+                    other.clone()
+                };
+
+                async move {
+                    let current = in_flight.current.fetch_add(1, Ordering::SeqCst) + 1;
+                    in_flight.max.fetch_max(current, Ordering::SeqCst);
+                    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                    in_flight.current.fetch_sub(1, Ordering::SeqCst);
+
+                    Ok::<_, std::convert::Infallible>(())
+                }
+            }
+        };
+
+        let limited = CategoryLimited::new(
+            consumer,
+            |event: &event::Persisted<&'static str, StringMessage>| {
+                event
+                    .stream_id
+                    .split(':')
+                    .next()
+                    .unwrap_or_default()
+                    .to_owned()
+            },
+        )
+        .limit("search", 1);
+
+        let events = [
+            "search:1", "search:2", "order:1", "order:2", "search:3", "order:3",
+        ]
+        .into_iter()
+        .map(|stream_id| {
+            Ok::<_, std::convert::Infallible>(event::Persisted {
+                stream_id,
+                version: 1,
+                event: event::Envelope::from(StringMessage("event")),
+            })
+        });
+
+        let handled: Vec<_> = Reliable::new(limited, 1)
+            .with_concurrency(6)
+            .consume(futures::stream::iter(events))
+            .try_collect()
+            .await
+            .expect("all domain events should be consumed");
+
+        assert_eq!(6, handled.len());
+        assert_eq!(1, search.max.load(Ordering::SeqCst));
+        assert_eq!(3, other.max.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,435 @@
+//! Contains the [Tiered] [`event::Store`] combinator, which appends Domain Events
+//! to a fast _hot_ Event Store, and migrates the older ones to a cheaper _cold_
+//! Event Store, stitching the reads across both.
+//!
+//! Useful to keep the working set of the hot Event Store small, while Aggregates
+//! with long histories can still be rehydrated from the full Event Stream.
+
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use futures::future::ready;
+use futures::stream::{iter, once, StreamExt, TryStreamExt};
+
+use crate::event::store::{paged, AppendError, Appender, Store, Streamer};
+use crate::{event, message, version};
+
+/// Interface used by the [Tiered] Event Store to remove the Domain Events
+/// migrated to the cold Event Store from the hot Event Store.
+#[async_trait]
+pub trait Evictor<StreamId>: Send + Sync
+where
+    StreamId: Send + Sync,
+{
+    /// The error type returned when the Domain Events could not be evicted.
+    type Error: Send + Sync;
+
+    /// Removes the Domain Events of the Event Stream up to the specified
+    /// [Version][version::Version] included.
+    ///
+    /// Implementations must always keep the last Domain Event of the Event Stream,
+    /// so that the version checks of the next appends keep working.
+    async fn evict(&self, id: &StreamId, up_to: version::Version) -> Result<(), Self::Error>;
+}
+
+/// Error returned by the [Tiered] Event Store when reading an Event Stream.
+#[derive(Debug, thiserror::Error)]
+pub enum ReadError<H, C> {
+    /// Error returned by the hot Event Store.
+    #[error("failed to read from the hot event store: {0}")]
+    Hot(#[source] H),
+    /// Error returned by the cold Event Store.
+    #[error("failed to read from the cold event store: {0}")]
+    Cold(#[source] C),
+}
+
+/// Error returned by [`Tiered::migrate`].
+#[derive(Debug, thiserror::Error)]
+pub enum MigrateError {
+    /// Error returned when the Domain Events could not be read from,
+    /// or evicted from, the hot Event Store.
+    #[error("failed to migrate from the hot event store: {0}")]
+    Hot(#[source] anyhow::Error),
+    /// Error returned when the Domain Events could not be appended
+    /// to the cold Event Store.
+    #[error("failed to migrate to the cold event store: {0}")]
+    Cold(#[source] anyhow::Error),
+}
+
+/// [`event::Store`] combinator that appends new Domain Events to the `hot`
+/// Event Store, and keeps only the last `hot_window` Domain Events of each
+/// Event Stream in it, once they have been migrated to the `cold` Event Store
+/// with [`Tiered::migrate`].
+///
+/// Domain Events are copied to the cold Event Store before being evicted from
+/// the hot one, so reads never miss a Domain Event while a migration is in progress.
+#[derive(Clone)]
+pub struct Tiered<H, C, StreamId> {
+    hot: H,
+    cold: C,
+    hot_window: u64,
+    pending: Arc<Mutex<HashSet<StreamId>>>,
+}
+
+impl<H, C, StreamId> Debug for Tiered<H, C, StreamId>
+where
+    H: Debug,
+    C: Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tiered")
+            .field("hot", &self.hot)
+            .field("cold", &self.cold)
+            .field("hot_window", &self.hot_window)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<H, C, StreamId> Tiered<H, C, StreamId>
+where
+    StreamId: Clone + Eq + Hash,
+{
+    /// Creates a new [Tiered] Event Store, keeping at most the last `hot_window`
+    /// Domain Events of each Event Stream in the `hot` Event Store after a migration.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if `hot_window` is zero.
+    pub fn new(hot: H, cold: C, hot_window: u64) -> Self {
+        assert!(hot_window > 0, "hot window must be greater than zero");
+
+        Self {
+            hot,
+            cold,
+            hot_window,
+            pending: Arc::default(),
+        }
+    }
+
+    /// Migrates the Domain Events older than the hot window of the specified
+    /// Event Stream from the hot Event Store to the cold one.
+    ///
+    /// Returns the number of migrated Domain Events.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if either Event Store fails: the migration
+    /// can be safely retried, since it resumes from the head of the cold Event Stream.
+    pub async fn migrate<Event>(&self, id: &StreamId) -> Result<usize, MigrateError>
+    where
+        H: Store<StreamId, Event> + Evictor<StreamId>,
+        C: Store<StreamId, Event>,
+        StreamId: Send + Sync + 'static,
+        Event: message::Message + Send + Sync + 'static,
+        <H as Streamer<StreamId, Event>>::Error: std::error::Error + Send + Sync + 'static,
+        <H as Evictor<StreamId>>::Error: std::error::Error + Send + Sync + 'static,
+        C::Error: std::error::Error + Send + Sync + 'static,
+    {
+        let Some(hot_head) = self
+            .hot
+            .head_version(id)
+            .await
+            .map_err(|err| MigrateError::Hot(err.into()))?
+        else {
+            return Ok(0);
+        };
+
+        let up_to = hot_head.saturating_sub(self.hot_window);
+
+        let cold_head = self
+            .cold
+            .head_version(id)
+            .await
+            .map_err(|err| MigrateError::Cold(err.into()))?
+            .unwrap_or_default();
+
+        let events: Vec<_> = self
+            .hot
+            .stream(id, event::VersionSelect::From(cold_head + 1))
+            .try_take_while(|evt| ready(Ok(evt.version <= up_to)))
+            .map_ok(|evt| evt.event)
+            .try_collect()
+            .await
+            .map_err(|err| MigrateError::Hot(err.into()))?;
+
+        let migrated = events.len();
+
+        if !events.is_empty() {
+            self.cold
+                .append(id.clone(), version::Check::must_be(cold_head), events)
+                .await
+                .map_err(|err| MigrateError::Cold(err.into()))?;
+        }
+
+        if up_to > 0 {
+            self.hot
+                .evict(id, up_to)
+                .await
+                .map_err(|err| MigrateError::Hot(err.into()))?;
+        }
+
+        Ok(migrated)
+    }
+
+    /// Migrates all the Event Streams appended through this instance
+    /// since the last call, using [`Tiered::migrate`].
+    ///
+    /// Meant to be called periodically by a background task.
+    /// Returns the total number of migrated Domain Events.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the migration of an Event Stream fails:
+    /// the Event Streams that have not been migrated are retried on the next call.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if the lock on the pending Event Streams has been poisoned.
+    pub async fn migrate_pending<Event>(&self) -> Result<usize, MigrateError>
+    where
+        H: Store<StreamId, Event> + Evictor<StreamId>,
+        C: Store<StreamId, Event>,
+        StreamId: Send + Sync + 'static,
+        Event: message::Message + Send + Sync + 'static,
+        <H as Streamer<StreamId, Event>>::Error: std::error::Error + Send + Sync + 'static,
+        <H as Evictor<StreamId>>::Error: std::error::Error + Send + Sync + 'static,
+        C::Error: std::error::Error + Send + Sync + 'static,
+    {
+        let pending: Vec<_> = self
+            .pending
+            .lock()
+            .expect("acquire lock on pending event streams")
+            .drain()
+            .collect();
+
+        let mut migrated = 0;
+
+        for (i, id) in pending.iter().enumerate() {
+            match self.migrate(id).await {
+                Ok(n) => migrated += n,
+                Err(err) => {
+                    self.pending
+                        .lock()
+                        .expect("acquire lock on pending event streams")
+                        .extend(pending[i..].iter().cloned());
+
+                    return Err(err);
+                },
+            }
+        }
+
+        Ok(migrated)
+    }
+}
+
+#[async_trait]
+impl<H, C, StreamId, Event> Streamer<StreamId, Event> for Tiered<H, C, StreamId>
+where
+    H: Streamer<StreamId, Event>,
+    C: Streamer<StreamId, Event>,
+    StreamId: Clone + Send + Sync + 'static,
+    Event: message::Message + Send + Sync + 'static,
+{
+    type Error = ReadError<H::Error, C::Error>;
+
+    fn stream(
+        &self,
+        id: &StreamId,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, StreamId, Event, Self::Error> {
+        let id = id.clone();
+
+        once(async move {
+            // The hot Event Stream is read first: a concurrent migration copies
+            // the Domain Events to the cold Event Store before evicting them,
+            // so the cold Event Stream read afterwards has no gaps.
+            let hot_events: Vec<_> = self
+                .hot
+                .stream(&id, select)
+                .try_collect()
+                .await
+                .map_err(ReadError::Hot)?;
+
+            let first_hot_version = hot_events.first().map(|evt| evt.version);
+
+            let cold_events = self
+                .cold
+                .stream(&id, select)
+                .map_err(ReadError::Cold)
+                .try_take_while(move |evt| {
+                    ready(Ok(first_hot_version.is_none_or(|v| evt.version < v)))
+                });
+
+            Ok(cold_events.chain(iter(hot_events).map(Ok)))
+        })
+        .try_flatten()
+        .boxed()
+    }
+
+    fn read_from<'a>(
+        &'a self,
+        id: &StreamId,
+        from_version: version::Version,
+        batch_size: usize,
+    ) -> event::Stream<'a, StreamId, Event, Self::Error>
+    where
+        StreamId: 'a,
+        Event: 'a,
+        Self::Error: 'a,
+    {
+        assert!(batch_size > 0, "batch size must be greater than zero");
+
+        let id = id.clone();
+
+        paged(from_version, batch_size, move |from_version| {
+            let id = id.clone();
+
+            async move {
+                // As in `stream`, the hot page is read first, so that the cold page
+                // read afterwards covers any Domain Event migrated in the meantime.
+                let hot_page: Vec<_> = self
+                    .hot
+                    .read_from(&id, from_version, batch_size)
+                    .take(batch_size)
+                    .try_collect()
+                    .await
+                    .map_err(ReadError::Hot)?;
+
+                let first_hot_version = hot_page.first().map(|evt| evt.version);
+
+                if first_hot_version == Some(from_version) {
+                    return Ok(hot_page);
+                }
+
+                let mut page: Vec<_> = self
+                    .cold
+                    .read_from(&id, from_version, batch_size)
+                    .take(batch_size)
+                    .map_err(ReadError::Cold)
+                    .try_take_while(move |evt| {
+                        ready(Ok(first_hot_version.is_none_or(|v| evt.version < v)))
+                    })
+                    .try_collect()
+                    .await?;
+
+                // The page is completed with the hot Domain Events, so that a short
+                // cold page does not end the paged read early.
+                page.extend(hot_page);
+                page.truncate(batch_size);
+
+                Ok(page)
+            }
+        })
+    }
+
+    async fn head_version(&self, id: &StreamId) -> Result<Option<version::Version>, Self::Error> {
+        // The hot Event Store always keeps the last Domain Event of an Event Stream.
+        match self.hot.head_version(id).await.map_err(ReadError::Hot)? {
+            Some(version) => Ok(Some(version)),
+            None => self.cold.head_version(id).await.map_err(ReadError::Cold),
+        }
+    }
+}
+
+#[async_trait]
+impl<H, C, StreamId, Event> Appender<StreamId, Event> for Tiered<H, C, StreamId>
+where
+    H: Appender<StreamId, Event>,
+    C: Send + Sync,
+    StreamId: Clone + Eq + Hash + Send + Sync + 'static,
+    Event: message::Message + Send + Sync + 'static,
+{
+    async fn append(
+        &self,
+        id: StreamId,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Event>>,
+    ) -> Result<version::Version, AppendError> {
+        let new_version = self.hot.append(id.clone(), version_check, events).await?;
+
+        self.pending
+            .lock()
+            .expect("acquire lock on pending event streams")
+            .insert(id);
+
+        Ok(new_version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::store::InMemory;
+    use crate::message::tests::StringMessage;
+
+    const STREAM_ID: &str = "stream:test";
+
+    #[tokio::test]
+    async fn tiered_event_store_migrates_old_events_and_stitches_reads() {
+        let hot = InMemory::<&'static str, StringMessage>::default();
+        let cold = InMemory::<&'static str, StringMessage>::default();
+        let event_store = Tiered::new(hot.clone(), cold.clone(), 2);
+
+        for (i, message) in ["event-1", "event-2", "event-3", "event-4", "event-5"]
+            .into_iter()
+            .enumerate()
+        {
+            event_store
+                .append(
+                    STREAM_ID,
+                    version::Check::must_be(i as u64),
+                    vec![StringMessage(message).into()],
+                )
+                .await
+                .expect("append should not fail");
+        }
+
+        assert_eq!(3, event_store.migrate_pending().await.unwrap());
+        // Nothing left to migrate.
+        assert_eq!(0, event_store.migrate(&STREAM_ID).await.unwrap());
+
+        let hot_versions: Vec<_> = hot
+            .stream(&STREAM_ID, event::VersionSelect::All)
+            .map_ok(|evt| evt.version)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(vec![4, 5], hot_versions);
+        assert_eq!(Some(3), cold.head_version(&STREAM_ID).await.unwrap());
+
+        event_store
+            .append(
+                STREAM_ID,
+                version::Check::must_be(5),
+                vec![StringMessage("event-6").into()],
+            )
+            .await
+            .expect("version checks should use the hot event store");
+
+        let versions: Vec<_> = event_store
+            .stream(&STREAM_ID, event::VersionSelect::From(2))
+            .map_ok(|evt| evt.version)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(vec![2, 3, 4, 5, 6], versions);
+        assert_eq!(Some(6), event_store.head_version(&STREAM_ID).await.unwrap());
+
+        // Paged reads are stitched across both tiers too, whatever the page boundaries.
+        for batch_size in 1..=7 {
+            let versions: Vec<_> = event_store
+                .read_from(&STREAM_ID, 2, batch_size)
+                .map_ok(|evt| evt.version)
+                .try_collect()
+                .await
+                .unwrap();
+
+            assert_eq!(vec![2, 3, 4, 5, 6], versions, "batch size {batch_size}");
+        }
+    }
+}
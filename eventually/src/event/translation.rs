@@ -0,0 +1,288 @@
+//! Contains the [Translator] [Consumer], an anti-corruption layer that translates
+//! the Domain Events of a bounded context into the [Command][command::Envelope]s
+//! or Domain Events of another bounded context, and publishes them to a [Target].
+//!
+//! The [Translator] keeps its own checkpoint per source Event Stream, so that
+//! redelivered Domain Events are not translated and published twice.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+
+use crate::event::consumer::Consumer;
+use crate::event::store::{AppendError, Store};
+use crate::{command, event, message, version};
+
+/// The destination of the messages produced by a [Translator].
+#[async_trait]
+pub trait Target<Out>: Send + Sync {
+    /// The error type returned when a message could not be published.
+    type Error: Send + Sync;
+
+    /// Publishes a translated message to the target bounded context.
+    async fn publish(&self, message: Out) -> Result<(), Self::Error>;
+}
+
+/// [Target] that dispatches the translated [Command][command::Envelope]s
+/// to a Command [Handler][command::Handler].
+#[derive(Debug, Clone)]
+pub struct Commands<H>(pub H);
+
+#[async_trait]
+impl<H, Cmd> Target<command::Envelope<Cmd>> for Commands<H>
+where
+    H: command::Handler<Cmd>,
+    Cmd: message::Message + Send + Sync + 'static,
+{
+    type Error = H::Error;
+
+    async fn publish(&self, message: command::Envelope<Cmd>) -> Result<(), Self::Error> {
+        self.0.handle(message).await
+    }
+}
+
+/// [Target] that appends the translated Domain Events to an Event Stream
+/// of an [`event::Store`], with no version check.
+#[derive(Debug, Clone)]
+pub struct Events<S>(pub S);
+
+#[async_trait]
+impl<S, StreamId, Event> Target<(StreamId, event::Envelope<Event>)> for Events<S>
+where
+    S: Store<StreamId, Event>,
+    StreamId: Send + Sync + 'static,
+    Event: message::Message + Send + Sync + 'static,
+{
+    type Error = AppendError;
+
+    async fn publish(
+        &self,
+        message: (StreamId, event::Envelope<Event>),
+    ) -> Result<(), Self::Error> {
+        let (stream_id, event) = message;
+
+        self.0
+            .append(stream_id, version::Check::Any, vec![event])
+            .await
+            .map(|_| ())
+    }
+}
+
+type Translate<StreamId, Event, Out> =
+    Box<dyn Fn(&event::Persisted<StreamId, Event>) -> Vec<Out> + Send + Sync>;
+
+/// [Consumer] that translates each source Domain Event into zero or more messages
+/// of the target bounded context, using a user-defined function, and publishes them
+/// to a [Target].
+///
+/// Domain Events at or below the checkpoint of their Event Stream are skipped.
+/// The checkpoint of an Event Stream advances only after all the translated messages
+/// have been published, so a failed Domain Event is translated again when redelivered.
+pub struct Translator<T, StreamId, Event, Out>
+where
+    Event: message::Message,
+{
+    target: T,
+    translate: Translate<StreamId, Event, Out>,
+    checkpoints: Arc<Mutex<HashMap<StreamId, version::Version>>>,
+}
+
+impl<T, StreamId, Event, Out> Debug for Translator<T, StreamId, Event, Out>
+where
+    T: Debug,
+    StreamId: Debug,
+    Event: message::Message,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Translator")
+            .field("target", &self.target)
+            .field("checkpoints", &self.checkpoints)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, StreamId, Event, Out> Translator<T, StreamId, Event, Out>
+where
+    T: Target<Out>,
+    StreamId: Clone + Eq + Hash + Send + Sync,
+    Event: message::Message,
+{
+    /// Creates a new [Translator] publishing to the specified [Target]
+    /// the messages returned by the `translate` function.
+    pub fn new<F>(target: T, translate: F) -> Self
+    where
+        F: Fn(&event::Persisted<StreamId, Event>) -> Vec<Out> + Send + Sync + 'static,
+    {
+        Self {
+            target,
+            translate: Box::new(translate),
+            checkpoints: Arc::default(),
+        }
+    }
+
+    /// Restores the checkpoint of the specified source Event Stream,
+    /// e.g. as previously returned by [`Translator::checkpoints`].
+    ///
+    /// # Panics
+    ///
+    /// The method panics if the lock on the checkpoints has been poisoned.
+    #[must_use]
+    pub fn with_checkpoint(self, stream_id: StreamId, version: version::Version) -> Self {
+        self.checkpoints
+            .lock()
+            .expect("acquire lock on translator checkpoints")
+            .insert(stream_id, version);
+        self
+    }
+
+    /// Returns the version of the last Domain Event translated for each source
+    /// Event Stream, to be persisted and restored with [`Translator::with_checkpoint`].
+    ///
+    /// # Panics
+    ///
+    /// The method panics if the lock on the checkpoints has been poisoned.
+    #[must_use]
+    pub fn checkpoints(&self) -> HashMap<StreamId, version::Version> {
+        self.checkpoints
+            .lock()
+            .expect("acquire lock on translator checkpoints")
+            .clone()
+    }
+
+    fn checkpoint(&self, stream_id: &StreamId) -> Option<version::Version> {
+        self.checkpoints
+            .lock()
+            .expect("acquire lock on translator checkpoints")
+            .get(stream_id)
+            .copied()
+    }
+}
+
+#[async_trait]
+impl<T, StreamId, Event, Out> Consumer<StreamId, Event> for Translator<T, StreamId, Event, Out>
+where
+    T: Target<Out>,
+    StreamId: Clone + Eq + Hash + Send + Sync + 'static,
+    Event: message::Message + Send + Sync + 'static,
+    Out: Send + 'static,
+{
+    type Error = T::Error;
+
+    async fn consume(&self, event: event::Persisted<StreamId, Event>) -> Result<(), Self::Error> {
+        if self
+            .checkpoint(&event.stream_id)
+            .is_some_and(|checkpoint| event.version <= checkpoint)
+        {
+            return Ok(());
+        }
+
+        for message in (self.translate)(&event) {
+            self.target.publish(message).await?;
+        }
+
+        let mut checkpoints = self
+            .checkpoints
+            .lock()
+            .expect("acquire lock on translator checkpoints");
+
+        let checkpoint = checkpoints.entry(event.stream_id).or_default();
+        *checkpoint = (*checkpoint).max(event.version);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::event::store::{InMemory, Streamer};
+    use crate::message::tests::StringMessage;
+
+    fn persisted(
+        version: version::Version,
+        message: &'static str,
+    ) -> event::Persisted<&'static str, StringMessage> {
+        event::Persisted {
+            stream_id: "order:1",
+            version,
+            event: event::Envelope::from(StringMessage(message)),
+        }
+    }
+
+    #[tokio::test]
+    async fn translator_dispatches_commands_at_most_once_per_domain_event() {
+        let dispatched = Arc::new(AtomicUsize::default());
+
+        let translator = Translator::new(
+            Commands({
+                let dispatched = dispatched.clone();
+
+                move |_: command::Envelope<StringMessage>| {
+                    let dispatched = dispatched.clone();
+
+                    async move {
+                        dispatched.fetch_add(1, Ordering::SeqCst);
+                        Ok::<_, std::convert::Infallible>(())
+                    }
+                }
+            }),
+            |event: &event::Persisted<&'static str, StringMessage>| match event.event.message.0 {
+                "order-placed" => vec![command::Envelope::from(StringMessage("reserve-stock"))],
+                _ => Vec::new(),
+            },
+        );
+
+        for event in [
+            persisted(1, "order-placed"),
+            persisted(2, "order-shipped"),
+            // Redelivery of an already translated Domain Event.
+            persisted(1, "order-placed"),
+        ] {
+            translator
+                .consume(event)
+                .await
+                .expect("the domain event should be translated");
+        }
+
+        assert_eq!(1, dispatched.load(Ordering::SeqCst));
+        assert_eq!(HashMap::from([("order:1", 2)]), translator.checkpoints());
+    }
+
+    #[tokio::test]
+    async fn translator_appends_domain_events_to_the_target_context() {
+        let target = InMemory::<String, StringMessage>::default();
+
+        let translator = Translator::new(
+            Events(target.clone()),
+            |event: &event::Persisted<&'static str, StringMessage>| {
+                vec![(
+                    format!("shipment:{}", event.stream_id),
+                    event::Envelope::from(StringMessage("shipment-requested")),
+                )]
+            },
+        )
+        .with_checkpoint("order:1", 1);
+
+        for event in [persisted(1, "order-placed"), persisted(2, "order-paid")] {
+            translator
+                .consume(event)
+                .await
+                .expect("the domain event should be translated");
+        }
+
+        let events: Vec<_> = target
+            .stream(&"shipment:order:1".to_owned(), event::VersionSelect::All)
+            .try_collect()
+            .await
+            .expect("opening an event stream should not fail");
+
+        assert_eq!(1, events.len());
+    }
+}
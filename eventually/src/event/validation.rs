@@ -0,0 +1,326 @@
+//! Contains the [Validated] [`event::Store`] decorator, which checks the Domain Events
+//! before appending them, enforcing a maximum payload size and running
+//! the configured [Validator]s (e.g. schema or forbidden-fields checks).
+//!
+//! Invalid Domain Events are rejected at append time with a [`ValidationError`],
+//! rather than being discovered when reading the Event Stream back.
+
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::event::store::{AppendError, Appender, Store, Streamer};
+use crate::{event, message, serde, version};
+
+/// Error returned by the [Validated] Event Store when some of the Domain Events
+/// to append are invalid.
+///
+/// The error is returned as an [`AppendError::Internal`], and can be recovered
+/// by downcasting the inner [`anyhow::Error`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ValidationError {
+    /// Error returned when the serialized payload of a Domain Event
+    /// exceeds the configured maximum size.
+    #[error("domain event #{index} payload is {size} bytes, exceeding the limit of {limit} bytes")]
+    PayloadTooLarge {
+        /// The position of the Domain Event in the appended batch.
+        index: usize,
+        /// The size of the serialized payload, in bytes.
+        size: usize,
+        /// The maximum payload size allowed, in bytes.
+        limit: usize,
+    },
+    /// Error returned when a Domain Event has been rejected by a [Validator].
+    #[error("domain event #{index} is invalid: {reason}")]
+    Invalid {
+        /// The position of the Domain Event in the appended batch.
+        index: usize,
+        /// The reason returned by the [Validator].
+        reason: String,
+    },
+}
+
+/// A check run on each Domain Event before it is appended to an Event Stream.
+pub trait Validator<StreamId, Event>: Send + Sync
+where
+    Event: message::Message,
+{
+    /// Validates a Domain Event to be appended to the specified Event Stream,
+    /// returning the reason of the failure if the Domain Event is invalid.
+    ///
+    /// # Errors
+    ///
+    /// The reason why the Domain Event is invalid.
+    fn validate(&self, stream_id: &StreamId, event: &event::Envelope<Event>) -> Result<(), String>;
+}
+
+impl<StreamId, Event, F> Validator<StreamId, Event> for F
+where
+    Event: message::Message,
+    F: Send + Sync + Fn(&StreamId, &event::Envelope<Event>) -> Result<(), String>,
+{
+    fn validate(&self, stream_id: &StreamId, event: &event::Envelope<Event>) -> Result<(), String> {
+        self(stream_id, event)
+    }
+}
+
+type PayloadLimit<Event> = (Arc<dyn serde::Serializer<Event>>, usize);
+
+/// Decorator type for an [`event::Store`] implementation that validates
+/// all the Domain Events of a batch before appending them.
+///
+/// If any of the Domain Events is invalid, the whole batch is rejected.
+#[derive(Clone)]
+pub struct Validated<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync,
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    store: T,
+    max_payload_size: Option<PayloadLimit<Event>>,
+    validators: Vec<Arc<dyn Validator<StreamId, Event>>>,
+}
+
+impl<T, StreamId, Event> Debug for Validated<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync + Debug,
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Validated")
+            .field("store", &self.store)
+            .field(
+                "max_payload_size",
+                &self.max_payload_size.as_ref().map(|(_, limit)| limit),
+            )
+            .field("validators", &self.validators.len())
+            .finish()
+    }
+}
+
+impl<T, StreamId, Event> Validated<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync,
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    /// Creates a new [Validated] decorator over the specified [`event::Store`],
+    /// with no payload size limit nor [Validator]s.
+    pub fn new(store: T) -> Self {
+        Self {
+            store,
+            max_payload_size: None,
+            validators: Vec::new(),
+        }
+    }
+
+    /// Rejects the Domain Events which payload, serialized using the specified
+    /// [Serializer][serde::Serializer], is larger than `limit` bytes.
+    ///
+    /// Use the same [Serializer][serde::Serializer] used by the underlying Event Store.
+    #[must_use]
+    pub fn with_max_payload_size<S>(mut self, serializer: S, limit: usize) -> Self
+    where
+        S: serde::Serializer<Event> + 'static,
+    {
+        self.max_payload_size = Some((Arc::new(serializer), limit));
+        self
+    }
+
+    /// Adds a [Validator] to run on each Domain Event before appending it.
+    ///
+    /// Validators are run in the same order they have been added.
+    #[must_use]
+    pub fn with_validator<V>(mut self, validator: V) -> Self
+    where
+        V: Validator<StreamId, Event> + 'static,
+    {
+        self.validators.push(Arc::new(validator));
+        self
+    }
+}
+
+impl<T, StreamId, Event> Validated<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync,
+    StreamId: Send + Sync,
+    Event: message::Message + Clone + Send + Sync,
+{
+    fn validate(
+        &self,
+        stream_id: &StreamId,
+        events: &[event::Envelope<Event>],
+    ) -> Result<(), AppendError> {
+        for (index, event) in events.iter().enumerate() {
+            if let Some((serializer, limit)) = &self.max_payload_size {
+                let size = serializer
+                    .serialize(event.message.clone())
+                    .map_err(AppendError::Internal)?
+                    .len();
+
+                if size > *limit {
+                    return Err(anyhow::Error::from(ValidationError::PayloadTooLarge {
+                        index,
+                        size,
+                        limit: *limit,
+                    })
+                    .into());
+                }
+            }
+
+            for validator in &self.validators {
+                validator.validate(stream_id, event).map_err(|reason| {
+                    anyhow::Error::from(ValidationError::Invalid { index, reason })
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<T, StreamId, Event> Streamer<StreamId, Event> for Validated<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync,
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    type Error = <T as Streamer<StreamId, Event>>::Error;
+
+    fn stream(
+        &self,
+        id: &StreamId,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, StreamId, Event, Self::Error> {
+        self.store.stream(id, select)
+    }
+
+    fn read_from<'a>(
+        &'a self,
+        id: &StreamId,
+        from_version: version::Version,
+        batch_size: usize,
+    ) -> event::Stream<'a, StreamId, Event, Self::Error>
+    where
+        StreamId: Clone + 'a,
+        Event: 'a,
+        Self::Error: 'a,
+    {
+        self.store.read_from(id, from_version, batch_size)
+    }
+
+    async fn head_version(&self, id: &StreamId) -> Result<Option<version::Version>, Self::Error> {
+        self.store.head_version(id).await
+    }
+}
+
+#[async_trait]
+impl<T, StreamId, Event> Appender<StreamId, Event> for Validated<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync,
+    StreamId: Send + Sync,
+    Event: message::Message + Clone + Send + Sync,
+{
+    async fn append(
+        &self,
+        id: StreamId,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Event>>,
+    ) -> Result<version::Version, AppendError> {
+        self.validate(&id, &events)?;
+        self.store.append(id, version_check, events).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::event::store::InMemory;
+    use crate::message::tests::StringMessage;
+
+    const STREAM_ID: &str = "stream:test";
+
+    struct Utf8Length;
+
+    impl serde::Serializer<StringMessage> for Utf8Length {
+        fn serialize(&self, value: StringMessage) -> anyhow::Result<Vec<u8>> {
+            Ok(value.0.as_bytes().to_vec())
+        }
+    }
+
+    fn validation_error(err: &AppendError) -> &ValidationError {
+        let AppendError::Internal(err) = err else {
+            panic!("unexpected append error: {err}");
+        };
+
+        err.downcast_ref()
+            .expect("error should be a validation error")
+    }
+
+    #[tokio::test]
+    async fn invalid_domain_events_are_rejected_before_appending() {
+        let event_store = Validated::new(InMemory::<&'static str, StringMessage>::default())
+            .with_max_payload_size(Utf8Length, 8)
+            .with_validator(|_: &&'static str, evt: &event::Envelope<StringMessage>| {
+                if evt.message.0.contains("password") {
+                    return Err("forbidden field: password".to_owned());
+                }
+
+                Ok(())
+            });
+
+        let append = |events: &[&'static str]| {
+            event_store.append(
+                STREAM_ID,
+                version::Check::Any,
+                events
+                    .iter()
+                    .map(|msg| event::Envelope::from(StringMessage(msg)))
+                    .collect(),
+            )
+        };
+
+        let err = append(&["event-1", "event-too-large"])
+            .await
+            .expect_err("append should fail");
+
+        assert_eq!(
+            &ValidationError::PayloadTooLarge {
+                index: 1,
+                size: 15,
+                limit: 8
+            },
+            validation_error(&err)
+        );
+
+        let err = append(&["password"]).await.expect_err("append should fail");
+
+        assert_eq!(
+            &ValidationError::Invalid {
+                index: 0,
+                reason: "forbidden field: password".to_owned()
+            },
+            validation_error(&err)
+        );
+
+        let version = append(&["event-1", "event-2"])
+            .await
+            .expect("append should not fail");
+
+        assert_eq!(2, version);
+
+        let events: Vec<_> = event_store
+            .stream(&STREAM_ID, event::VersionSelect::All)
+            .try_collect()
+            .await
+            .expect("streaming should not fail");
+
+        assert_eq!(2, events.len());
+    }
+}
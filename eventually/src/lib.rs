@@ -6,7 +6,15 @@
 #![deny(clippy::all, clippy::pedantic, clippy::cargo)]
 
 pub mod aggregate;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod catalog;
+#[cfg(feature = "chaos")]
+pub mod chaos;
 pub mod command;
+#[cfg(feature = "correlation")]
+pub mod correlation;
+pub mod error;
 pub mod event;
 pub mod message;
 pub mod query;
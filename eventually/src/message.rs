@@ -16,10 +16,41 @@ pub trait Message {
     fn name(&self) -> &'static str;
 }
 
+/// Implemented by [Message] types made of a closed set of variants, such as
+/// Domain Event enums, to list the stable names of all their variants.
+///
+/// Useful to check whether a persisted [Message] name is known to the application,
+/// e.g. before attempting to deserialize its payload.
+///
+/// Check out the `Event` derive macro in `eventually-macros` to implement this trait automatically.
+pub trait Registry: Message {
+    /// The names of all the [Message] variants of this type.
+    const NAMES: &'static [&'static str];
+
+    /// Returns true if the specified name belongs to one of the variants of this type.
+    #[must_use]
+    fn is_registered(name: &str) -> bool {
+        Self::NAMES.contains(&name)
+    }
+}
+
 /// Optional metadata to attach to an [Envelope] to provide additional context
 /// to the [Message] carried out.
 pub type Metadata = HashMap<String, String>;
 
+/// The [Metadata] key used to record the name of the [Message] that caused
+/// another one, e.g. the Domain Command that caused a Domain Event.
+///
+/// Use [`Envelope::caused_by`] to set it.
+pub const CAUSED_BY_KEY: &str = "Caused-By";
+
+/// The [Metadata] key used to record the RFC 3339 timestamp of when
+/// a Domain Event has been recorded.
+///
+/// Event Store implementations set it automatically on append,
+/// overriding any value set by the caller.
+pub const RECORDED_AT_KEY: &str = "Recorded-At";
+
 /// Represents a [Message] packaged for persistance and/or processing by other
 /// parts of the system.
 ///
@@ -45,6 +76,29 @@ where
         self.metadata.insert(key, value);
         self
     }
+
+    /// Adds all the specified entries in the [Envelope]'s [Metadata],
+    /// overriding the values of any existing keys.
+    #[must_use]
+    pub fn with_metadata_entries<K, V>(mut self, entries: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.metadata.extend(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into())),
+        );
+
+        self
+    }
+
+    /// Records the [Message] that caused this one, through the [`CAUSED_BY_KEY`] [Metadata].
+    #[must_use]
+    pub fn caused_by(self, cause: &impl Message) -> Self {
+        self.with_metadata(CAUSED_BY_KEY.to_owned(), cause.name().to_owned())
+    }
 }
 
 impl<T> From<T> for Envelope<T>
@@ -98,5 +152,10 @@ pub(crate) mod tests {
 
         // Metadata does not affect equality of message.
         assert_eq!(message, new_message);
+
+        let new_message = new_message.with_metadata_entries([("hello_world", "override")]);
+
+        assert_eq!(2, new_message.metadata.len());
+        assert_eq!("override", new_message.metadata["hello_world"]);
     }
 }
@@ -3,9 +3,13 @@
 //! different formats like JSON, Protobuf, etc.
 
 use std::fmt::Display;
+#[cfg(feature = "serde-compression")]
+use std::io::{Read, Write};
 use std::marker::PhantomData;
 
 use anyhow::anyhow;
+#[cfg(feature = "serde-compression")]
+use flate2::{read::GzDecoder, write::GzEncoder};
 #[cfg(feature = "serde-prost")]
 use prost::bytes::Bytes;
 #[cfg(feature = "serde-json")]
@@ -84,7 +88,7 @@ where
         self.serde.serialize(
             value
                 .try_into()
-                .map_err(|err| anyhow!("failed to convert type values: {}", err))?,
+                .map_err(|err| anyhow!("failed to convert type values: {err}"))?,
         )
     }
 }
@@ -100,7 +104,7 @@ where
         let inn = self.serde.deserialize(data)?;
 
         inn.try_into()
-            .map_err(|err| anyhow!("failed to convert type values: {}", err))
+            .map_err(|err| anyhow!("failed to convert type values: {err}"))
     }
 }
 
@@ -132,7 +136,7 @@ where
 {
     fn serialize(&self, value: T) -> anyhow::Result<Vec<u8>> {
         serde_json::to_vec(&value)
-            .map_err(|err| anyhow!("failed to serialize value to json: {}", err))
+            .map_err(|err| anyhow!("failed to serialize value to json: {err}"))
     }
 }
 
@@ -144,7 +148,7 @@ where
 {
     fn deserialize(&self, data: &[u8]) -> anyhow::Result<T> {
         serde_json::from_slice(data)
-            .map_err(|err| anyhow!("failed to deserialize value from json: {}", err))
+            .map_err(|err| anyhow!("failed to deserialize value from json: {err}"))
     }
 }
 
@@ -175,7 +179,7 @@ where
         let buf = Bytes::copy_from_slice(data);
 
         T::decode(buf)
-            .map_err(|err| anyhow!("failed to deserialize protobuf message into value: {}", err))
+            .map_err(|err| anyhow!("failed to deserialize protobuf message into value: {err}"))
     }
 }
 
@@ -212,3 +216,161 @@ where
         Json::<T>::default().deserialize(data)
     }
 }
+
+/// Compression algorithm used by the [Compressed] serde.
+#[cfg(feature = "serde-compression")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Compression {
+    /// Stores the serialized data as-is.
+    None,
+    /// Compresses the serialized data using gzip.
+    #[default]
+    Gzip,
+}
+
+#[cfg(feature = "serde-compression")]
+impl Compression {
+    fn tag(self) -> u8 {
+        match self {
+            Compression::None => 0x00,
+            Compression::Gzip => 0x01,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0x00 => Some(Compression::None),
+            0x01 => Some(Compression::Gzip),
+            _ => None,
+        }
+    }
+}
+
+/// Implementation of [Serde] traits that transparently compresses the data
+/// serialized by the inner [Serde], e.g. to reduce the storage cost of large
+/// Aggregate states.
+///
+/// The [Compression] algorithm used is recorded in a one-byte header of each record,
+/// so that records written with different algorithms, or uncompressed because smaller
+/// than the configured minimum size, can all be deserialized.
+///
+/// Records without a known header are deserialized as-is by the inner [Serde]:
+/// this allows to read data written before enabling compression, as long as
+/// the wire format of the inner [Serde] never starts with a `0x00` or `0x01` byte,
+/// which is the case for both JSON and Protobuf.
+#[cfg(feature = "serde-compression")]
+#[derive(Debug, Clone, Copy)]
+pub struct Compressed<S> {
+    serde: S,
+    compression: Compression,
+    min_size: usize,
+}
+
+#[cfg(feature = "serde-compression")]
+impl<S> Compressed<S> {
+    /// Creates a new [Compressed] serde, using the specified [Compression] algorithm.
+    pub fn new(serde: S, compression: Compression) -> Self {
+        Self {
+            serde,
+            compression,
+            min_size: 0,
+        }
+    }
+
+    /// Specifies the minimum size in bytes of the serialized data to compress:
+    /// smaller data is stored uncompressed. Defaults to 0.
+    #[must_use]
+    pub fn with_min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        self
+    }
+}
+
+#[cfg(feature = "serde-compression")]
+impl<S, T> Serializer<T> for Compressed<S>
+where
+    S: Serializer<T>,
+{
+    fn serialize(&self, value: T) -> anyhow::Result<Vec<u8>> {
+        let data = self.serde.serialize(value)?;
+
+        let compression = if data.len() < self.min_size {
+            Compression::None
+        } else {
+            self.compression
+        };
+
+        let mut out = vec![compression.tag()];
+
+        match compression {
+            Compression::None => out.extend_from_slice(&data),
+            Compression::Gzip => {
+                let mut encoder = GzEncoder::new(out, flate2::Compression::default());
+                encoder
+                    .write_all(&data)
+                    .map_err(|err| anyhow!("failed to compress serialized value: {err}"))?;
+
+                out = encoder
+                    .finish()
+                    .map_err(|err| anyhow!("failed to compress serialized value: {err}"))?;
+            },
+        }
+
+        Ok(out)
+    }
+}
+
+#[cfg(feature = "serde-compression")]
+impl<S, T> Deserializer<T> for Compressed<S>
+where
+    S: Deserializer<T>,
+{
+    fn deserialize(&self, data: &[u8]) -> anyhow::Result<T> {
+        let Some((compression, payload)) = data
+            .split_first()
+            .and_then(|(tag, payload)| Compression::from_tag(*tag).map(|c| (c, payload)))
+        else {
+            return self.serde.deserialize(data);
+        };
+
+        match compression {
+            Compression::None => self.serde.deserialize(payload),
+            Compression::Gzip => {
+                let mut decompressed = Vec::new();
+
+                GzDecoder::new(payload)
+                    .read_to_end(&mut decompressed)
+                    .map_err(|err| anyhow!("failed to decompress serialized value: {err}"))?;
+
+                self.serde.deserialize(&decompressed)
+            },
+        }
+    }
+}
+
+#[cfg(all(test, feature = "serde-compression", feature = "serde-json"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compressed_serde_roundtrips_values() {
+        let value = vec!["a large aggregate state".to_owned(); 64];
+        let json = Json::<Vec<String>>::default();
+        let uncompressed = json.serialize(value.clone()).unwrap();
+
+        let serde = Compressed::new(json, Compression::Gzip);
+        let compressed = serde.serialize(value.clone()).unwrap();
+
+        assert_eq!(Compression::Gzip.tag(), compressed[0]);
+        assert!(compressed.len() < uncompressed.len());
+        assert_eq!(value, serde.deserialize(&compressed).unwrap());
+
+        // Small values and legacy, uncompressed records are also supported.
+        let serde = serde.with_min_size(uncompressed.len() + 1);
+        let small = serde.serialize(value.clone()).unwrap();
+
+        assert_eq!(Compression::None.tag(), small[0]);
+        assert_eq!(value, serde.deserialize(&small).unwrap());
+        assert_eq!(value, serde.deserialize(&uncompressed).unwrap());
+    }
+}
@@ -3,9 +3,10 @@
 
 use std::fmt::Debug;
 use std::marker::PhantomData;
+use std::time::Instant;
 
 use async_trait::async_trait;
-use tracing::instrument;
+use tracing::{field, instrument, Span};
 
 use crate::aggregate::Aggregate;
 use crate::version::{self, Version};
@@ -13,6 +14,14 @@ use crate::{aggregate, event, message};
 
 /// [`aggregate::Repository`] type wrapper that provides instrumentation
 /// features through the `tracing` crate.
+///
+/// Besides the arguments and results of each operation, the spans record:
+/// * `elapsed_ms`: the time taken by the inner [`aggregate::Repository`],
+/// * `version`: the [Version] of the [`aggregate::Root`] loaded or saved, which
+///   for Event-sourced Repositories is the number of Domain Events used to rehydrate it,
+/// * `events`: the number of new Domain Events saved, for [`Saver::save`][aggregate::repository::Saver::save].
+/// * `cache_hit`: whether the [`aggregate::Root`] has been loaded from the cache,
+///   if the inner [`aggregate::Repository`] is [`Cached`][aggregate::cache::Cached].
 #[derive(Debug, Clone)]
 pub struct InstrumentedAggregateRepository<T, Inner>
 where
@@ -34,9 +43,50 @@ where
     Inner: aggregate::Repository<T>,
 {
     #[allow(clippy::blocks_in_conditions)] // NOTE(ar3s3ru): seems to be a false positive.
-    #[instrument(name = "aggregate::repository::Getter.get", ret, err, skip(self))]
+    #[instrument(
+        name = "aggregate::repository::Getter.get",
+        ret,
+        err,
+        skip(self),
+        fields(elapsed_ms = field::Empty, version = field::Empty, cache_hit = field::Empty)
+    )]
     async fn get(&self, id: &T::Id) -> Result<aggregate::Root<T>, aggregate::repository::GetError> {
-        self.inner.get(id).await
+        let start = Instant::now();
+        let result = self.inner.get(id).await;
+        let span = Span::current();
+
+        span.record("elapsed_ms", elapsed_ms(start));
+
+        if let Ok(root) = &result {
+            span.record("version", root.version());
+        }
+
+        result
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // NOTE(ar3s3ru): seems to be a false positive.
+    #[instrument(
+        name = "aggregate::repository::Getter.get_including_tombstoned",
+        ret,
+        err,
+        skip(self),
+        fields(elapsed_ms = field::Empty, version = field::Empty, cache_hit = field::Empty)
+    )]
+    async fn get_including_tombstoned(
+        &self,
+        id: &T::Id,
+    ) -> Result<aggregate::Root<T>, aggregate::repository::GetError> {
+        let start = Instant::now();
+        let result = self.inner.get_including_tombstoned(id).await;
+        let span = Span::current();
+
+        span.record("elapsed_ms", elapsed_ms(start));
+
+        if let Ok(root) = &result {
+            span.record("version", root.version());
+        }
+
+        result
     }
 }
 
@@ -49,15 +99,30 @@ where
     Inner: aggregate::Repository<T>,
 {
     #[allow(clippy::blocks_in_conditions)] // NOTE(ar3s3ru): seems to be a false positive.
-    #[instrument(name = "aggregate::repository::Saver.save", ret, err, skip(self))]
+    #[instrument(
+        name = "aggregate::repository::Saver.save",
+        ret,
+        err,
+        skip(self),
+        fields(elapsed_ms = field::Empty, version = root.version(), events = root.uncommitted_events_len())
+    )]
     async fn save(
         &self,
         root: &mut aggregate::Root<T>,
     ) -> Result<(), aggregate::repository::SaveError> {
-        self.inner.save(root).await
+        let start = Instant::now();
+        let result = self.inner.save(root).await;
+
+        Span::current().record("elapsed_ms", elapsed_ms(start));
+
+        result
     }
 }
 
+fn elapsed_ms(start: Instant) -> u64 {
+    u64::try_from(start.elapsed().as_millis()).unwrap_or(u64::MAX)
+}
+
 /// Extension trait for any [`aggregate::Repository`] type to provide
 /// instrumentation features through the `tracing` crate.
 pub trait AggregateRepositoryExt<T>: aggregate::Repository<T> + Sized
@@ -98,6 +163,7 @@ where
     event: PhantomData<Event>,
 }
 
+#[async_trait]
 impl<T, StreamId, Event> event::store::Streamer<StreamId, Event>
     for InstrumentedEventStore<T, StreamId, Event>
 where
@@ -112,9 +178,30 @@ where
         &self,
         id: &StreamId,
         select: event::VersionSelect,
-    ) -> event::Stream<StreamId, Event, Self::Error> {
+    ) -> event::Stream<'_, StreamId, Event, Self::Error> {
         self.store.stream(id, select)
     }
+
+    #[instrument(name = "event::Store.read_from", skip(self))]
+    fn read_from<'a>(
+        &'a self,
+        id: &StreamId,
+        from_version: Version,
+        batch_size: usize,
+    ) -> event::Stream<'a, StreamId, Event, Self::Error>
+    where
+        StreamId: Clone + 'a,
+        Event: 'a,
+        Self::Error: 'a,
+    {
+        self.store.read_from(id, from_version, batch_size)
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // NOTE(ar3s3ru): seems to be a false positive.
+    #[instrument(name = "event::Store.head_version", skip(self))]
+    async fn head_version(&self, id: &StreamId) -> Result<Option<Version>, Self::Error> {
+        self.store.head_version(id).await
+    }
 }
 
 #[async_trait]
@@ -1,30 +1,134 @@
 //! Contains the types necessary for Optimistic Locking through versioning.
 
+use std::hash::{Hash, Hasher};
+
+use serde::{Deserialize, Serialize};
+
 /// A version used for Optimistic Locking.
 ///
 /// Used by the [`aggregate::Root`][crate::aggregate::Root] to avoid concurrency issues,
 /// and [`event::Store`][crate::event::Store] to implement stream-local ordering to the messages.
 pub type Version = u64;
 
+/// Describes where the [Version] carried by an [`ExpectedVersion`] comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Provenance {
+    /// The [Version] has been loaded from the store, e.g. through
+    /// [`aggregate::Root::version`][crate::aggregate::Root::version] or
+    /// [`Streamer::head_version`][crate::event::store::Streamer::head_version].
+    Loaded,
+    /// The [Version] has been specified by the caller, e.g. `0` to create a new Event Stream.
+    Specified,
+}
+
+/// The [Version] a resource is expected to have before an operation mutates it,
+/// used as the optimistic concurrency token in [`Check::MustBe`].
+///
+/// Unlike a raw [Version], an [`ExpectedVersion`] can only be created by stating
+/// its [Provenance], which is also reported in logs and traces: this makes it harder
+/// to pass an unrelated number (e.g. the number of new Domain Events) by mistake.
+///
+/// The [Provenance] is declared by the caller and never verified, and it does not
+/// change the check performed: two [`ExpectedVersion`]s are equal, and hash the same,
+/// whenever they expect the same [Version], regardless of their [Provenance].
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ExpectedVersion {
+    version: Version,
+    provenance: Provenance,
+}
+
+impl PartialEq for ExpectedVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.version == other.version
+    }
+}
+
+impl Eq for ExpectedVersion {}
+
+impl Hash for ExpectedVersion {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.version.hash(state);
+    }
+}
+
+impl ExpectedVersion {
+    /// Creates a new [`ExpectedVersion`] from a [Version] that has been loaded from the store.
+    ///
+    /// The [Version] is not checked against the store: it is up to the caller
+    /// to pass a [Version] that has actually been loaded.
+    #[must_use]
+    pub fn loaded(version: Version) -> Self {
+        Self {
+            version,
+            provenance: Provenance::Loaded,
+        }
+    }
+
+    /// Creates a new [`ExpectedVersion`] from a [Version] specified by the caller.
+    #[must_use]
+    pub fn specified(version: Version) -> Self {
+        Self {
+            version,
+            provenance: Provenance::Specified,
+        }
+    }
+
+    /// Returns the expected [Version].
+    #[must_use]
+    pub fn version(self) -> Version {
+        self.version
+    }
+
+    /// Returns the [Provenance] of the expected [Version].
+    #[must_use]
+    pub fn provenance(self) -> Provenance {
+        self.provenance
+    }
+}
+
 /// Used to set a specific expectation during an operation
 /// that mutates some sort of resource (e.g. an [Event Stream][crate::event::Stream])
 /// that supports versioning.
 ///
 /// It allows for optimistic locking, avoiding data races
 /// when modifying the same resource at the same time.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Check {
     /// Disables any kind of optimistic locking check, allowing
     /// for any [Version] to be used compared to the new one.
     Any,
     /// Expects that the previous [Version] used for the operation
     /// must have the value specified.
-    MustBe(Version),
+    MustBe(ExpectedVersion),
+}
+
+impl Check {
+    /// Returns a [`Check::MustBe`] expecting the specified [Version],
+    /// that has been loaded from the store.
+    ///
+    /// It performs the same check as [`Check::must_be`]: see [`ExpectedVersion`].
+    #[must_use]
+    pub fn loaded(version: Version) -> Self {
+        Check::MustBe(ExpectedVersion::loaded(version))
+    }
+
+    /// Returns a [`Check::MustBe`] expecting the [Version] specified by the caller.
+    #[must_use]
+    pub fn must_be(version: Version) -> Self {
+        Check::MustBe(ExpectedVersion::specified(version))
+    }
+
+    /// Returns a [`Check::MustBe`] expecting the resource not to exist yet,
+    /// e.g. to create a new Event Stream. It is the same as `Check::must_be(0)`.
+    #[must_use]
+    pub fn empty() -> Self {
+        Check::must_be(0)
+    }
 }
 
 /// This error is returned by a function when a version conflict error has
 /// been detected.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, thiserror::Error)]
 #[error("conflict error detected, expected version was: {expected}, found: {actual}")]
 pub struct ConflictError {
     /// The [Version] value that was expected when calling the function that failed.
@@ -33,3 +137,43 @@ pub struct ConflictError {
     /// The actual [Version] value, which mismatch caused this error.
     pub actual: Version,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_types_can_be_serialized_and_deserialized() {
+        let check = Check::must_be(3);
+        let conflict = ConflictError {
+            expected: 3,
+            actual: 5,
+        };
+
+        let serialized = serde_json::to_string(&(check, conflict)).expect("serialization works");
+        let deserialized: (Check, ConflictError) =
+            serde_json::from_str(&serialized).expect("deserialization works");
+
+        assert_eq!((check, conflict), deserialized);
+    }
+
+    #[test]
+    fn checks_with_the_same_expected_version_are_equal_regardless_of_provenance() {
+        let hash = |check: Check| {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            check.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        assert_eq!(Check::loaded(3), Check::must_be(3));
+        assert_eq!(hash(Check::loaded(3)), hash(Check::must_be(3)));
+        assert_ne!(Check::loaded(3), Check::loaded(4));
+        assert_ne!(Check::Any, Check::must_be(0));
+
+        let Check::MustBe(expected) = Check::loaded(3) else {
+            panic!("check should expect a version");
+        };
+
+        assert_eq!(Provenance::Loaded, expected.provenance());
+    }
+}